@@ -26,11 +26,12 @@ async fn main() {
         // init logger
         env_logger::Builder::new().filter_level(args.verbose.log_level_filter()).init();
     }
-    if !args.verbose.is_present() {
+    if !args.verbose.is_present() && !args.command.json_output() {
         banner();
     }
     if let Err(err) = run(args.command, args.verbose).await {
-        eprintln!("{}", err.to_string().red())
+        eprintln!("{}", err.to_string().red());
+        std::process::exit(1);
     }
 }
 
@@ -0,0 +1,146 @@
+use crate::utils::{print_output, remark, success};
+use clap::Parser;
+use soldeer_core::{
+    Result,
+    audit::{
+        AdvisoryMatch, Finding, Severity, advisory_db_path, audit, read_advisory_db,
+        sync_advisory_db,
+    },
+    config::Paths,
+    errors::AuditError,
+    lock::read_lockfile,
+};
+use std::path::PathBuf;
+
+/// Check the locked dependencies against an advisory database
+#[derive(Debug, Clone, Default, Parser, bon::Builder)]
+#[allow(clippy::duplicated_attributes)]
+#[builder(on(String, into), on(PathBuf, into))]
+#[clap(
+    long_about = "Check the locked dependencies against an advisory database
+
+Each entry of the lockfile is matched against a local database of security advisories: by name and version for dependencies from the registry or a URL, and by git URL and commit for git dependencies. The database is a TOML or JSON file described in the USAGE.md guide. It's stored in ~/.soldeer/advisory-db.json by default (can be changed with the SOLDEER_ADVISORY_DB environment variable) and can be synced from a file or URL.
+
+The command exits with an error if any advisory with the `--fail-on` severity or higher affects the dependencies. Advisories which could not be evaluated against a dependency (e.g. because its version is not a semver version) also make the command fail, unless they are ignored.
+
+Examples:
+- Sync the database and audit: soldeer audit --sync https://example.com/advisories.toml
+- Audit with the local database: soldeer audit
+- Only fail for serious issues: soldeer audit --fail-on high
+- Ignore an advisory: soldeer audit --ignore SOL-2024-0001",
+    after_help = "For more information, read the README.md"
+)]
+#[non_exhaustive]
+pub struct Audit {
+    /// Sync the local advisory database from a file or URL before auditing
+    #[arg(long, value_name = "FILE|URL")]
+    pub sync: Option<String>,
+
+    /// Use this advisory database file instead of the local copy
+    #[arg(long, value_name = "FILE", conflicts_with = "sync")]
+    pub db: Option<PathBuf>,
+
+    /// Exit with an error if an advisory with this severity or higher is found or could not be
+    /// evaluated
+    ///
+    /// One of low, medium, high or critical.
+    #[arg(long, value_name = "SEVERITY", default_value_t = Severity::Low)]
+    #[builder(default)]
+    pub fail_on: Severity,
+
+    /// Ignore the advisory with this ID (can be repeated)
+    #[arg(long = "ignore", value_name = "ID")]
+    #[builder(default)]
+    pub ignore: Vec<String>,
+
+    /// Output the result as JSON
+    #[cfg(feature = "serde")]
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub json: bool,
+}
+
+pub(crate) async fn audit_command(paths: &Paths, cmd: Audit) -> Result<()> {
+    let db = match (&cmd.sync, &cmd.db) {
+        (Some(source), _) => {
+            let db = sync_advisory_db(source, advisory_db_path()?).await?;
+            success!(format!("Synced {} advisories from {source}", db.advisories.len()));
+            db
+        }
+        (None, Some(path)) => read_advisory_db(path)?,
+        (None, None) => read_advisory_db(advisory_db_path()?)?,
+    };
+    let lockfile = read_lockfile(&paths.lock)?;
+    success!("Done reading lockfile");
+
+    let findings = audit(&db, &lockfile.entries, &cmd.ignore);
+
+    #[cfg(feature = "serde")]
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&findings).expect("findings should be serializable")
+        );
+    } else if !findings.is_empty() {
+        print_output(format_findings(&findings));
+    }
+    #[cfg(not(feature = "serde"))]
+    if !findings.is_empty() {
+        print_output(format_findings(&findings));
+    }
+
+    let failing = |status| {
+        findings.iter().filter(|f| f.status == status && f.advisory.severity >= cmd.fail_on).count()
+    };
+    let affected = failing(AdvisoryMatch::Affected);
+    if affected > 0 {
+        return Err(AuditError::Vulnerable { count: affected, severity: cmd.fail_on }.into());
+    }
+    let unknown = failing(AdvisoryMatch::Unknown);
+    if unknown > 0 {
+        return Err(AuditError::Unevaluated { count: unknown, severity: cmd.fail_on }.into());
+    }
+    if findings.is_empty() {
+        success!(format!("No advisory affects the {} locked dependencies", lockfile.entries.len()));
+    } else {
+        remark!(format!(
+            "{} advisories found, none with severity {} or higher",
+            findings.len(),
+            cmd.fail_on
+        ));
+    }
+    Ok(())
+}
+
+/// Format the findings with a header line per advisory, followed by its details.
+fn format_findings(findings: &[Finding]) -> String {
+    let mut lines = Vec::new();
+    for finding in findings {
+        let advisory = &finding.advisory;
+        lines.push(format!(
+            "{} ({}) {} {}: {}",
+            advisory.id, advisory.severity, finding.name, finding.version, advisory.title
+        ));
+        if finding.status == AdvisoryMatch::Unknown {
+            lines.push(
+                "  could not evaluate: the locked version can't be compared with the affected versions"
+                    .to_string(),
+            );
+        }
+        if !advisory.affected.is_empty() {
+            lines.push(format!("  affected: {}", advisory.affected.join(" || ")));
+        }
+        if !advisory.revs.is_empty() {
+            lines.push(format!("  affected commits: {}", advisory.revs.join(", ")));
+        }
+        if advisory.fixed.is_empty() {
+            lines.push("  fixed: no fixed version".to_string());
+        } else {
+            lines.push(format!("  fixed: {}", advisory.fixed.join(", ")));
+        }
+        if let Some(url) = &advisory.url {
+            lines.push(format!("  {url}"));
+        }
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,116 @@
+use crate::utils::{print_output, remark, success};
+use clap::{Parser, Subcommand};
+use soldeer_core::{
+    Result,
+    cache::{CacheEntry, cache_dir, clean_cache, list_cache, prune_cache},
+};
+use std::time::Duration;
+
+/// Manage the global package cache
+#[derive(Debug, Clone, Parser, bon::Builder)]
+#[clap(
+    long_about = "Manage the global package cache
+
+Archives downloaded by Soldeer are stored in a cache folder shared by all projects (~/.soldeer/cache by default, can be changed with the SOLDEER_CACHE_DIR environment variable). They are re-used when installing a dependency whose checksum is in the lockfile.
+
+Examples:
+- List cached archives: soldeer cache list
+- Remove all cached archives: soldeer cache clean
+- Remove archives not used in the last 7 days: soldeer cache prune --max-age 7",
+    after_help = "For more information, read the README.md"
+)]
+#[non_exhaustive]
+pub struct Cache {
+    #[command(subcommand)]
+    pub command: CacheCommand,
+}
+
+/// The cache subcommands
+#[derive(Debug, Clone, Subcommand)]
+#[non_exhaustive]
+pub enum CacheCommand {
+    /// List the archives in the cache
+    List,
+
+    /// Remove all archives from the cache
+    Clean,
+
+    /// Remove the archives which were not used recently, as well as corrupted archives
+    Prune(Prune),
+}
+
+/// Remove the archives which were not used recently
+#[derive(Debug, Clone, Parser, bon::Builder)]
+#[non_exhaustive]
+pub struct Prune {
+    /// Remove the archives which were not used for this number of days
+    #[arg(long, value_name = "DAYS", default_value_t = 30)]
+    #[builder(default = 30)]
+    pub max_age: u64,
+}
+
+pub(crate) fn cache_command(cmd: Cache) -> Result<()> {
+    let dir = cache_dir()?;
+    match cmd.command {
+        CacheCommand::List => {
+            let entries = list_cache()?;
+            if entries.is_empty() {
+                remark!(format!("The cache at {} is empty", dir.display()));
+                return Ok(());
+            }
+            print_output(format_entries(&entries));
+            let total: u64 = entries.iter().map(|e| e.size).sum();
+            success!(format!(
+                "{} archives ({}) in {}",
+                entries.len(),
+                format_size(total),
+                dir.display()
+            ));
+        }
+        CacheCommand::Clean => {
+            let removed = clean_cache()?;
+            success!(format!(
+                "Removed {} archives ({}) from the cache",
+                removed.len(),
+                format_size(removed.iter().map(|e| e.size).sum())
+            ));
+        }
+        CacheCommand::Prune(prune) => {
+            let removed = prune_cache(Duration::from_secs(prune.max_age * 24 * 60 * 60))?;
+            success!(format!(
+                "Removed {} archives ({}) from the cache",
+                removed.len(),
+                format_size(removed.iter().map(|e| e.size).sum())
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Format the cache entries with one line per archive.
+fn format_entries(entries: &[CacheEntry]) -> String {
+    entries
+        .iter()
+        .map(|e| {
+            format!(
+                "{}  {:>10}  {}",
+                e.checksum,
+                format_size(e.size),
+                e.last_used.format("%Y-%m-%d %H:%M")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format a size in bytes for humans.
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 { format!("{size} B") } else { format!("{value:.1} {}", UNITS[unit]) }
+}
@@ -1,14 +1,14 @@
 use crate::{
     ConfigLocation,
-    utils::{Progress, remark, success},
+    utils::{Progress, offline_from_env, remark, success, vendor_dir_from_env},
 };
 use clap::Parser;
 use soldeer_core::{
     Result,
     config::{Paths, add_to_config, read_soldeer_config, update_config_libs},
-    install::{InstallProgress, ensure_dependencies_dir, install_dependency},
+    install::{InstallOptions, InstallProgress, ensure_dependencies_dir, install_dependency},
     lock::add_to_lockfile,
-    registry::get_latest_version,
+    registry::{Registries, get_latest_version},
     remappings::{RemappingsAction, edit_remappings},
     utils::remove_forge_lib,
 };
@@ -42,15 +42,31 @@ pub(crate) async fn init_command(paths: &Paths, cmd: Init) -> Result<()> {
     let config = read_soldeer_config(&paths.config)?;
     success!("Done reading config");
     ensure_dependencies_dir(&paths.dependencies)?;
-    let dependency = get_latest_version("forge-std").await?;
+    let registries = Registries::default();
+    let options = InstallOptions::builder()
+        .link_mode(config.link_mode)
+        .offline(offline_from_env())
+        .maybe_vendor_dir(vendor_dir_from_env())
+        .build();
+    let dependency =
+        get_latest_version(registries.default_registry(), "forge-std", options.offline).await?;
     let (progress, monitor) = InstallProgress::new();
     let bars = Progress::new(format!("Installing {dependency}"), 1, monitor);
     bars.start_all();
-    let lock = install_dependency(&dependency, None, &paths.dependencies, None, false, progress)
-        .await
-        .inspect_err(|e| {
-            bars.set_error(e);
-        })?;
+    let lock = install_dependency(
+        &dependency,
+        None,
+        &paths.dependencies,
+        None,
+        &registries,
+        false,
+        &options,
+        progress,
+    )
+    .await
+    .inspect_err(|e| {
+        bars.set_error(e);
+    })?;
     bars.stop_all();
     add_to_config(&dependency, &paths.config)?;
     let foundry_config = paths.root.join("foundry.toml");
@@ -1,7 +1,7 @@
-use super::validate_dependency;
+use super::{validate_dependency, validate_subdir};
 use crate::{
     ConfigLocation,
-    utils::{Progress, remark, success, warning},
+    utils::{Progress, offline_from_env, remark, success, vendor_dir_from_env, warning},
 };
 use clap::Parser;
 use soldeer_core::{
@@ -11,16 +11,18 @@ use soldeer_core::{
         read_soldeer_config,
     },
     errors::{InstallError, LockError},
-    install::{InstallProgress, ensure_dependencies_dir, install_dependencies, install_dependency},
+    install::{InstallOptions, InstallProgress, ensure_dependencies_dir, install_dependency},
     lock::{add_to_lockfile, generate_lockfile_contents, read_lockfile},
     remappings::{RemappingsAction, edit_remappings},
+    resolver::{check_lockfile_sync, install_locked, install_resolved, resolve_transitive},
+    workspace::load_workspace,
 };
-use std::fs;
+use std::{fs, path::PathBuf};
 
 /// Install a dependency
 #[derive(Debug, Clone, Default, Parser, bon::Builder)]
 #[allow(clippy::duplicated_attributes)]
-#[builder(on(String, into), on(ConfigLocation, into))]
+#[builder(on(String, into), on(ConfigLocation, into), on(PathBuf, into))]
 #[clap(
     long_about = "Install a dependency
 
@@ -28,12 +30,18 @@ If used with arguments, a dependency will be added to the configuration. When us
 
 Examples:
 - Install all: soldeer install
+- Install all, failing if the lockfile is out of sync: soldeer install --locked
+- Install all without network access: soldeer install --offline
+- Install all from a vendor folder: soldeer install --offline --vendor-dir vendor
 - Add from registry: soldeer install lib_name~2.3.0
+- Add from another registry: soldeer install lib_name~2.3.0 --registry internal
 - Add with custom URL: soldeer install lib_name~2.3.0 --url https://foo.bar/lib.zip
 - Add with git: soldeer install lib_name~2.3.0 --git git@github.com:foo/bar.git
 - Add with git (commit): soldeer install lib_name~2.3.0 --git git@github.com:foo/bar.git --rev 05f218fb6617932e56bf5388c3b389c3028a7b73
 - Add with git (tag): soldeer install lib_name~2.3.0 --git git@github.com:foo/bar.git --tag v2.3.0
-- Add with git (branch): soldeer install lib_name~2.3.0 --git git@github.com:foo/bar.git --branch feature/baz",
+- Add with git (branch): soldeer install lib_name~2.3.0 --git git@github.com:foo/bar.git --branch feature/baz
+- Add with git (subdirectory only): soldeer install lib_name~2.3.0 --git git@github.com:foo/bar.git --subdir packages/contracts
+- Add from a local folder: soldeer install lib_name~2.3.0 --path ../lib_name",
     after_help = "For more information, read the README.md"
 )]
 #[non_exhaustive]
@@ -44,18 +52,31 @@ pub struct Install {
     #[arg(value_parser = validate_dependency, value_name = "DEPENDENCY~VERSION")]
     pub dependency: Option<String>,
 
-    /// The URL to the dependency zip file.
+    /// The URL to the dependency archive (zip file or tarball).
     ///
     /// Example: https://my-domain/dep.zip
-    #[arg(long = "url", requires = "dependency", conflicts_with = "git_url")]
+    #[arg(long = "url", requires = "dependency", conflicts_with_all = ["git_url", "path"])]
     pub zip_url: Option<String>,
 
     /// The URL to the dependency repository.
     ///
     /// Example: git@github.com:foo/bar.git
-    #[arg(long = "git", requires = "dependency", conflicts_with = "zip_url")]
+    #[arg(long = "git", requires = "dependency", conflicts_with_all = ["zip_url", "path"])]
     pub git_url: Option<String>,
 
+    /// The path to a local folder containing the dependency, relative to the project root.
+    ///
+    /// Example: ../my-lib
+    #[arg(long, requires = "dependency", conflicts_with_all = ["zip_url", "git_url"])]
+    pub path: Option<String>,
+
+    /// The name of the registry to download the dependency from, as declared in the
+    /// `[registries]` table of the config file.
+    ///
+    /// Example: internal
+    #[arg(long, requires = "dependency", conflicts_with_all = ["zip_url", "git_url", "path"])]
+    pub registry: Option<String>,
+
     /// A Git commit hash
     #[arg(long, group = "identifier", requires = "git_url")]
     pub rev: Option<String>,
@@ -68,6 +89,14 @@ pub struct Install {
     #[arg(long, group = "identifier", requires = "git_url")]
     pub branch: Option<String>,
 
+    /// A subdirectory of the Git repository.
+    ///
+    /// Only this subdirectory is checked out (sparse checkout) and the remappings point into it.
+    ///
+    /// Example: packages/contracts
+    #[arg(long, value_parser = validate_subdir, requires = "git_url")]
+    pub subdir: Option<String>,
+
     /// If set, this command will delete the existing remappings and re-create them
     #[arg(short = 'g', long, default_value_t = false)]
     #[builder(default)]
@@ -84,6 +113,34 @@ pub struct Install {
     #[builder(default)]
     pub clean: bool,
 
+    /// Fail if the lockfile is missing or out of sync with the config file, instead of updating it
+    ///
+    /// Dependencies are installed exactly as described by the lockfile, without resolving any
+    /// version.
+    #[arg(long, default_value_t = false, conflicts_with = "dependency")]
+    #[builder(default)]
+    pub locked: bool,
+
+    /// Same as `--locked`, but also never contact the registry.
+    #[arg(long, default_value_t = false, conflicts_with = "dependency")]
+    #[builder(default)]
+    pub frozen: bool,
+
+    /// Never use the network: dependencies are installed from the global cache or from the
+    /// dependencies folder.
+    ///
+    /// This can also be enabled with the `SOLDEER_OFFLINE=1` environment variable.
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub offline: bool,
+
+    /// Install the locked dependencies from a folder created with `soldeer vendor`.
+    ///
+    /// Archives and git repositories found in the folder are used instead of the cache or the
+    /// network. This can also be set with the `SOLDEER_VENDOR_DIR` environment variable.
+    #[arg(long, value_name = "DIR")]
+    pub vendor_dir: Option<PathBuf>,
+
     /// Specify the config location without prompting.
     ///
     /// This prevents prompting the user if the automatic detection can't determine the config
@@ -100,17 +157,45 @@ pub(crate) async fn install_command(paths: &Paths, cmd: Install) -> Result<()> {
     if cmd.recursive_deps {
         config.recursive_deps = true;
     }
+    let registries = paths.registries()?;
+    let options = InstallOptions::builder()
+        .link_mode(config.link_mode)
+        .offline(cmd.offline || offline_from_env())
+        .maybe_vendor_dir(cmd.vendor_dir.clone().or_else(vendor_dir_from_env))
+        .trust_policy(paths.trust_policy()?)
+        .policy(config.policy.clone())
+        .build();
     success!("Done reading config");
-    ensure_dependencies_dir(&paths.dependencies)?;
-    let (dependencies, warnings) = read_config_deps(&paths.config)?;
+    // when installing all dependencies of a workspace project, the whole workspace is installed
+    let workspace = if cmd.dependency.is_none() { load_workspace(paths)? } else { None };
+    let (dependencies, warnings) = match &workspace {
+        Some(workspace) => {
+            success!(format!("Found workspace with {} members", workspace.members.len()));
+            workspace.dependencies()?
+        }
+        None => read_config_deps(&paths.config)?,
+    };
     for w in warnings {
         warning!(format!("Config warning: {w}"));
     }
 
     match &cmd.dependency {
         None => {
+            config.policy.check_dependencies(&dependencies)?;
             let lockfile = read_lockfile(&paths.lock)?;
             success!("Done reading lockfile");
+            let locked = cmd.locked || cmd.frozen;
+            if locked {
+                if lockfile.raw.is_empty() {
+                    return Err(InstallError::LockfileMissing.into());
+                }
+                let drift = check_lockfile_sync(&dependencies, &lockfile.entries);
+                if !drift.is_empty() {
+                    return Err(InstallError::LockfileOutOfSync(drift).into());
+                }
+                success!("Lockfile is in sync with the dependencies");
+            }
+            ensure_dependencies_dir(&paths.dependencies)?;
             if cmd.clean {
                 remark!("Flag `--clean` was set, re-installing all dependencies");
                 fs::remove_dir_all(&paths.dependencies).map_err(|e| InstallError::IOError {
@@ -123,24 +208,45 @@ pub(crate) async fn install_command(paths: &Paths, cmd: Install) -> Result<()> {
             let (progress, monitor) = InstallProgress::new();
             let bars = Progress::new("Installing dependencies", dependencies.len(), monitor);
             bars.start_all();
-            let new_locks = install_dependencies(
-                &dependencies,
-                &lockfile.entries,
-                &paths.dependencies,
-                config.recursive_deps,
-                progress,
-            )
-            .await?;
+            // in locked mode, the lockfile is installed as-is so that no version is resolved again
+            let new_locks = if locked {
+                install_locked(
+                    &dependencies,
+                    &lockfile.entries,
+                    &paths.dependencies,
+                    &registries,
+                    !cmd.frozen,
+                    config.recursive_deps,
+                    &options,
+                    progress,
+                )
+                .await?
+            } else {
+                install_resolved(
+                    &dependencies,
+                    &lockfile.entries,
+                    &paths.dependencies,
+                    &registries,
+                    config.recursive_deps,
+                    &options,
+                    progress,
+                )
+                .await?
+            };
             bars.stop_all();
             let new_lockfile_content = generate_lockfile_contents(new_locks);
-            if !lockfile.raw.is_empty() && new_lockfile_content != lockfile.raw {
+            // in locked mode, the entries are identical and only the formatting could differ
+            if !locked && !lockfile.raw.is_empty() && new_lockfile_content != lockfile.raw {
                 warning!(
                     "Warning: the lock file is out of sync with the dependencies. Consider running `soldeer update` to re-generate the lockfile."
                 );
             } else if lockfile.raw.is_empty() {
                 fs::write(&paths.lock, new_lockfile_content).map_err(LockError::IOError)?;
             }
-            edit_remappings(&RemappingsAction::Update, &config, paths)?;
+            match &workspace {
+                Some(workspace) => workspace.edit_remappings(cmd.regenerate_remappings)?,
+                None => edit_remappings(&RemappingsAction::Update, &config, paths)?,
+            }
             success!("Updated remappings");
         }
         Some(dependency) => {
@@ -151,9 +257,19 @@ pub(crate) async fn install_command(paths: &Paths, cmd: Install) -> Result<()> {
                 (None, None, None) => None,
                 _ => unreachable!("clap should prevent this"),
             };
-            let url =
-                cmd.zip_url.as_ref().map(UrlType::http).or(cmd.git_url.as_ref().map(UrlType::git));
+            let url = cmd
+                .zip_url
+                .as_ref()
+                .map(UrlType::http)
+                .or(cmd.git_url.as_ref().map(UrlType::git))
+                .or(cmd.path.as_ref().map(UrlType::path));
             let mut dep = Dependency::from_name_version(dependency, url, identifier)?;
+            if let Some(http_dep) = dep.as_http_mut() {
+                http_dep.registry.clone_from(&cmd.registry);
+            }
+            if let Some(git_dep) = dep.as_git_mut() {
+                git_dep.subdir.clone_from(&cmd.subdir);
+            }
             if dependencies
                 .iter()
                 .any(|d| d.name() == dep.name() && d.version_req() == dep.version_req())
@@ -165,12 +281,16 @@ pub(crate) async fn install_command(paths: &Paths, cmd: Install) -> Result<()> {
                         .regenerate_remappings(cmd.regenerate_remappings)
                         .recursive_deps(cmd.recursive_deps)
                         .clean(cmd.clean)
+                        .offline(cmd.offline)
+                        .maybe_vendor_dir(cmd.vendor_dir.clone())
                         .maybe_config_location(cmd.config_location)
                         .build(),
                 ))
                 .await?;
                 return Ok(());
             }
+            config.policy.check_dependencies([&dep])?;
+            ensure_dependencies_dir(&paths.dependencies)?;
             let (progress, monitor) = InstallProgress::new();
             let bars = Progress::new(format!("Installing {dep}"), 1, monitor);
             bars.start_all();
@@ -179,10 +299,28 @@ pub(crate) async fn install_command(paths: &Paths, cmd: Install) -> Result<()> {
                 None,
                 &paths.dependencies,
                 None,
+                &registries,
                 config.recursive_deps,
-                progress,
+                &options,
+                progress.clone(),
             )
             .await?;
+            let new_locks = if config.recursive_deps {
+                let lockfile = read_lockfile(&paths.lock)?;
+                resolve_transitive(
+                    std::slice::from_ref(&dep),
+                    vec![lock.clone()],
+                    &lockfile.entries,
+                    &[],
+                    &paths.dependencies,
+                    &registries,
+                    &options,
+                    progress,
+                )
+                .await?
+            } else {
+                vec![lock.clone()]
+            };
             bars.stop_all();
             // for git deps, we need to add the commit hash before adding them to the
             // config, unless a branch/tag was specified
@@ -195,7 +333,9 @@ pub(crate) async fn install_command(paths: &Paths, cmd: Install) -> Result<()> {
             }
             add_to_config(&dep, &paths.config)?;
             success!("Dependency added to config");
-            add_to_lockfile(lock, &paths.lock)?;
+            for lock in new_locks {
+                add_to_lockfile(lock, &paths.lock)?;
+            }
             success!("Dependency added to lockfile");
             edit_remappings(&RemappingsAction::Add(dep), &config, paths)?;
             success!("Dependency added to remappings");
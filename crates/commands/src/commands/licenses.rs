@@ -0,0 +1,105 @@
+use crate::utils::{self, print_output, remark, success};
+use clap::Parser;
+use soldeer_core::{
+    Result,
+    config::{Paths, read_soldeer_config},
+    errors::{PolicyError, PolicyViolation},
+    license::{PackageLicenses, scan_dependencies},
+    lock::read_lockfile,
+};
+
+/// List the licenses of the installed dependencies
+#[derive(Debug, Clone, Default, Parser, bon::Builder)]
+#[builder(on(String, into))]
+#[clap(
+    long_about = "List the licenses of the installed dependencies
+
+The license of each dependency of the lockfile is read from its license files (LICENSE, COPYING, etc.) and from the `SPDX-License-Identifier` headers of its Solidity files. The licenses are checked against the `allowed_licenses` and `denied_licenses` of the `[soldeer.policy]` config section, and the command exits with an error if any of them is violated.",
+    after_help = "For more information, read the README.md"
+)]
+#[non_exhaustive]
+pub struct Licenses {
+    /// Output the result as JSON
+    #[cfg(feature = "serde")]
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub json: bool,
+}
+
+pub(crate) async fn licenses_command(paths: &Paths, cmd: Licenses) -> Result<()> {
+    let config = read_soldeer_config(&paths.config)?;
+    let lockfile = read_lockfile(&paths.lock)?;
+    success!("Done reading config and lockfile");
+
+    let results = scan_dependencies(&lockfile.entries, &paths.dependencies, &config.policy);
+
+    #[cfg(feature = "serde")]
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&results).expect("results should be serializable")
+        );
+    } else {
+        print_output(format_table(&results));
+    }
+    #[cfg(not(feature = "serde"))]
+    {
+        let _ = cmd;
+        print_output(format_table(&results));
+    }
+
+    let missing = results.iter().filter(|r| !r.installed).count();
+    if missing > 0 {
+        remark!(format!("{missing} dependencies are not installed and were not scanned"));
+    }
+    let violations: Vec<_> = results.into_iter().flat_map(|r| r.violations).collect();
+    if !violations.is_empty() {
+        return Err(PolicyError::Violations(violations).into());
+    }
+    success!("All licenses are allowed by the policy");
+    Ok(())
+}
+
+/// Format the results as a table with one row per dependency.
+fn format_table(results: &[PackageLicenses]) -> String {
+    let rows: Vec<[String; 5]> = results
+        .iter()
+        .map(|r| {
+            [
+                r.name.clone(),
+                r.version.clone(),
+                r.license.clone().unwrap_or_else(|| "-".to_string()),
+                format_declarations(r),
+                match (r.installed, r.violations.len()) {
+                    (false, _) => "missing".to_string(),
+                    (true, 0) => "ok".to_string(),
+                    (true, _) => {
+                        r.violations.iter().map(format_violation).collect::<Vec<_>>().join(", ")
+                    }
+                },
+            ]
+        })
+        .collect();
+    utils::format_table(["Package", "Version", "License", "Declared in", "Policy"], &rows)
+}
+
+/// Format where the licenses of a dependency are declared: its license files and the number of
+/// Solidity files with an SPDX header for each license.
+fn format_declarations(result: &PackageLicenses) -> String {
+    let mut declarations: Vec<_> = result.scan.license_files.keys().cloned().collect();
+    declarations.extend(
+        result.scan.spdx_headers.iter().map(|(license, count)| format!("{count} .sol ({license})")),
+    );
+    if declarations.is_empty() { "-".to_string() } else { declarations.join(", ") }
+}
+
+/// Format a violation of the policy without the name of the dependency, which is already displayed
+/// in the table.
+fn format_violation(violation: &PolicyViolation) -> String {
+    match violation {
+        PolicyViolation::LicenseNotAllowed { license, .. } => format!("not allowed ({license})"),
+        PolicyViolation::LicenseDenied { license, .. } => format!("denied ({license})"),
+        PolicyViolation::UnknownLicense { .. } => "unknown license".to_string(),
+        violation => violation.to_string(),
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{
+    commands::outdated::short_rev,
+    utils::{self, print_output, success},
+};
+use clap::Parser;
+use path_slash::PathBufExt as _;
+use soldeer_core::{
+    Result,
+    config::{Paths, read_config_deps},
+    graph::PackageSource,
+    install::{DependencyInfo, DependencyStatus, list_dependencies},
+    lock::read_lockfile,
+    utils::relative_path,
+};
+
+/// List the dependencies of the project
+#[derive(Debug, Clone, Default, Parser, bon::Builder)]
+#[builder(on(String, into))]
+#[clap(
+    long_about = "List the dependencies of the project
+
+Shows the dependencies from the config file and the transitive dependencies from the lockfile, with their version requirement, locked version, source, install path and status (installed, missing or failing the integrity check).",
+    after_help = "For more information, read the README.md"
+)]
+#[non_exhaustive]
+pub struct List {
+    /// Output the result as JSON
+    #[cfg(feature = "serde")]
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub json: bool,
+}
+
+pub(crate) async fn list_command(paths: &Paths, cmd: List) -> Result<()> {
+    let (dependencies, _) = read_config_deps(&paths.config)?;
+    let lockfile = read_lockfile(&paths.lock)?;
+    success!("Done reading config and lockfile");
+
+    let results = list_dependencies(&dependencies, &lockfile.entries, &paths.dependencies).await?;
+
+    #[cfg(feature = "serde")]
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&results).expect("results should be serializable")
+        );
+    } else {
+        print_output(format_table(&results, paths));
+    }
+    #[cfg(not(feature = "serde"))]
+    {
+        let _ = cmd;
+        print_output(format_table(&results, paths));
+    }
+    Ok(())
+}
+
+/// Format the results as a table with one row per dependency.
+fn format_table(results: &[DependencyInfo], paths: &Paths) -> String {
+    let rows: Vec<[String; 6]> = results
+        .iter()
+        .map(|r| {
+            [
+                r.name.clone(),
+                r.version_req.clone().unwrap_or_else(|| "-".to_string()),
+                r.locked_version.as_deref().map_or_else(|| "-".to_string(), short_rev),
+                format_source(r),
+                r.install_path.as_ref().map_or_else(
+                    || "-".to_string(),
+                    |p| relative_path(&paths.root, p).to_slash_lossy().to_string(),
+                ),
+                match r.status {
+                    DependencyStatus::Missing => "missing",
+                    DependencyStatus::FailedIntegrity => "modified",
+                    DependencyStatus::Installed => "installed",
+                }
+                .to_string(),
+            ]
+        })
+        .collect();
+    utils::format_table(["Package", "Required", "Locked", "Source", "Path", "Status"], &rows)
+}
+
+/// Format the source of a dependency.
+///
+/// Registry packages are not shown with their download URL, and git dependencies are shown with
+/// their locked commit.
+fn format_source(info: &DependencyInfo) -> String {
+    match (info.source, &info.url, &info.rev) {
+        (None, _, _) => "-".to_string(),
+        (Some(PackageSource::Git), Some(url), Some(rev)) => format!("{url}#{}", short_rev(rev)),
+        (Some(PackageSource::Http | PackageSource::Path), Some(url), _) => url.clone(),
+        (Some(source), _, _) => source.to_string(),
+    }
+}
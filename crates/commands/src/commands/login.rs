@@ -1,4 +1,4 @@
-use crate::utils::{info, remark, step, success, warning};
+use crate::utils::{get_registry, info, remark, step, success, warning};
 use clap::Parser;
 use email_address_parser::{EmailAddress, ParsingOptions};
 use path_slash::PathBufExt as _;
@@ -29,15 +29,27 @@ pub struct Login {
     /// Login with a token created via soldeer.xyz.
     #[arg(long)]
     pub token: Option<String>,
+
+    /// The name of the registry to log into, as declared in the `[registries]` table of the
+    /// config file.
+    ///
+    /// Defaults to the soldeer.xyz registry.
+    #[arg(long)]
+    pub registry: Option<String>,
 }
 
 pub(crate) async fn login_command(cmd: Login) -> Result<()> {
-    remark!("If you do not have an account, please visit soldeer.xyz to create one.");
+    let registry = get_registry(cmd.registry.as_deref())?;
+    if registry.is_default() {
+        remark!("If you do not have an account, please visit soldeer.xyz to create one.");
+    } else {
+        step!(format!("Registry: {} ({})", registry.name, registry.url));
+    }
 
     if let Some(token) = cmd.token {
         let token = token.trim();
-        let username = check_token(token).await?;
-        let token_path = save_token(token)?;
+        let username = check_token(&registry, token).await?;
+        let token_path = save_token(&registry, token)?;
         info!(format!(
             "Token is valid for user {username} and was saved in: {}",
             PathBuf::from_slash_lossy(&token_path).to_string_lossy() /* normalize separators */
@@ -86,7 +98,7 @@ pub(crate) async fn login_command(cmd: Login) -> Result<()> {
         }
     };
 
-    let token_path = execute_login(&Credentials { email, password }).await?;
+    let token_path = execute_login(&registry, &Credentials { email, password }).await?;
     success!("Login successful");
     info!(format!(
         "Token saved in: {}",
@@ -1,14 +1,25 @@
 pub use clap::{Parser, Subcommand};
 use clap_verbosity_flag::{LogLevel, VerbosityFilter};
 use derive_more::derive::From;
+use soldeer_core::config::normalize_subdir;
 
+pub mod audit;
+pub mod cache;
 pub mod clean;
 pub mod init;
 pub mod install;
+pub mod licenses;
+pub mod list;
 pub mod login;
+pub mod outdated;
 pub mod push;
+pub mod sbom;
+pub mod tree;
 pub mod uninstall;
 pub mod update;
+pub mod vendor;
+pub mod verify;
+pub mod why;
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct CustomLevel;
@@ -62,13 +73,46 @@ pub enum Command {
     Init(init::Init),
     Install(install::Install),
     Update(update::Update),
+    Outdated(outdated::Outdated),
+    List(list::List),
+    Tree(tree::Tree),
+    Verify(verify::Verify),
+    Audit(audit::Audit),
+    Sbom(sbom::Sbom),
+    Licenses(licenses::Licenses),
+    Vendor(vendor::Vendor),
+    Why(why::Why),
     Login(login::Login),
     Push(push::Push),
     Uninstall(uninstall::Uninstall),
     Clean(clean::Clean),
+    Cache(cache::Cache),
     Version(Version),
 }
 
+impl Command {
+    /// Whether the command outputs machine-readable data (JSON or DOT).
+    ///
+    /// In that case, nothing else should be printed to stdout.
+    pub fn json_output(&self) -> bool {
+        match self {
+            #[cfg(feature = "serde")]
+            Self::Outdated(cmd) => cmd.json,
+            #[cfg(feature = "serde")]
+            Self::List(cmd) => cmd.json,
+            #[cfg(feature = "serde")]
+            Self::Verify(cmd) => cmd.json,
+            #[cfg(feature = "serde")]
+            Self::Audit(cmd) => cmd.json,
+            #[cfg(feature = "serde")]
+            Self::Licenses(cmd) => cmd.json,
+            Self::Tree(cmd) => cmd.format != tree::TreeFormat::Text,
+            Self::Sbom(cmd) => cmd.output.is_none(),
+            _ => false,
+        }
+    }
+}
+
 /// Display the version of Soldeer
 #[derive(Debug, Clone, Default, Parser)]
 #[non_exhaustive]
@@ -80,3 +124,9 @@ fn validate_dependency(dep: &str) -> std::result::Result<String, String> {
     }
     Ok(dep.to_string())
 }
+
+fn validate_subdir(subdir: &str) -> std::result::Result<String, String> {
+    normalize_subdir(subdir).ok_or_else(|| {
+        "The subdirectory should be a relative path inside the repository".to_string()
+    })
+}
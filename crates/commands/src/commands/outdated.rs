@@ -0,0 +1,89 @@
+use crate::utils::{self, offline_from_env, print_output, success};
+use clap::Parser;
+use soldeer_core::{
+    Result,
+    config::{Paths, read_config_deps},
+    errors::UpdateError,
+    lock::read_lockfile,
+    update::{OutdatedDependency, check_outdated},
+};
+
+/// Check which dependencies have newer versions available
+#[derive(Debug, Clone, Default, Parser, bon::Builder)]
+#[builder(on(String, into))]
+#[clap(
+    long_about = "Check which dependencies have newer versions available
+
+Compares the versions in the lockfile with the newest versions allowed by the config file and the newest versions overall. The command exits with an error if any dependency is outdated.",
+    after_help = "For more information, read the README.md"
+)]
+#[non_exhaustive]
+pub struct Outdated {
+    /// Output the result as JSON
+    #[cfg(feature = "serde")]
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub json: bool,
+}
+
+pub(crate) async fn outdated_command(paths: &Paths, cmd: Outdated) -> Result<()> {
+    let (dependencies, _) = read_config_deps(&paths.config)?;
+    let registries = paths.registries()?;
+    let lockfile = read_lockfile(&paths.lock)?;
+    success!("Done reading config and lockfile");
+
+    let results =
+        check_outdated(&dependencies, &lockfile.entries, &registries, offline_from_env()).await?;
+
+    #[cfg(feature = "serde")]
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&results).expect("results should be serializable")
+        );
+    } else {
+        print_output(format_table(&results));
+    }
+    #[cfg(not(feature = "serde"))]
+    {
+        let _ = cmd;
+        print_output(format_table(&results));
+    }
+
+    let outdated: Vec<_> = results.into_iter().filter(|r| r.outdated).map(|r| r.name).collect();
+    if !outdated.is_empty() {
+        return Err(UpdateError::OutdatedDependencies(outdated).into());
+    }
+    success!("All dependencies are up-to-date");
+    Ok(())
+}
+
+/// Format the results as a table with one row per dependency.
+fn format_table(results: &[OutdatedDependency]) -> String {
+    let rows: Vec<[String; 4]> = results
+        .iter()
+        .map(|r| {
+            [
+                r.name.clone(),
+                short_rev(&r.current),
+                r.wanted.as_deref().map_or_else(|| "-".to_string(), short_rev),
+                r.latest.as_deref().map_or_else(|| "-".to_string(), short_rev),
+            ]
+        })
+        .collect();
+    utils::format_table(["Package", "Current", "Wanted", "Latest"], &rows)
+}
+
+/// Shorten git commit hashes to 7 characters for display.
+pub(crate) fn short_rev(version: &str) -> String {
+    if version.len() == 40 && version.chars().all(|c| c.is_ascii_hexdigit()) {
+        return version[..7].to_string();
+    }
+    // tags are displayed with their commit hash in parentheses
+    if let Some((tag, rev)) = version.split_once(" (") &&
+        let Some(rev) = rev.strip_suffix(')')
+    {
+        return format!("{tag} ({})", short_rev(rev));
+    }
+    version.to_string()
+}
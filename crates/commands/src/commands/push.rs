@@ -1,10 +1,11 @@
 use super::validate_dependency;
-use crate::utils::{info, remark, success, warning};
+use crate::utils::{get_registry, info, remark, success, warning};
 use clap::Parser;
 use soldeer_core::{
     Result,
     errors::PublishError,
-    push::{filter_ignored_files, push_version, validate_name, validate_version},
+    push::{check_license, filter_ignored_files, push_version, validate_name, validate_version},
+    signing::{SigningKey, signing_key_path},
     utils::{canonicalize_sync, check_dotfiles},
 };
 use std::{env, path::PathBuf, sync::atomic::Ordering};
@@ -23,8 +24,13 @@ Examples:
 - Current directory: soldeer push mypkg~0.1.0
 - Custom directory: soldeer push mypkg~0.1.0 /path/to/dep
 - Dry run: soldeer push mypkg~0.1.0 --dry-run
+- Signed package: soldeer push mypkg~0.1.0 --sign
 
-To ignore certain files, create a `.soldeerignore` file in the root of the project and add the files you want to ignore. The `.soldeerignore` uses the same syntax as `.gitignore`.",
+To ignore certain files, create a `.soldeerignore` file in the root of the project and add the files you want to ignore. The `.soldeerignore` uses the same syntax as `.gitignore`.
+
+The package must declare its license, with a LICENSE (or COPYING) file at its root or with `SPDX-License-Identifier` headers in its Solidity files.
+
+With `--sign`, the zip file is signed with the ed25519 key stored in `~/.soldeer/signing_key` (or the file in the `SOLDEER_SIGNING_KEY_FILE` environment variable), which is generated on first use. Share the public key with the users of your package so they can add it to the `[trust]` table of their config.",
     after_help = "For more information, read the README.md"
 )]
 #[non_exhaustive]
@@ -50,6 +56,20 @@ pub struct Push {
     #[arg(long, default_value_t = false)]
     #[builder(default)]
     pub skip_warnings: bool,
+
+    /// The name of the registry to push to, as declared in the `[registries]` table of the config
+    /// file.
+    ///
+    /// Defaults to the soldeer.xyz registry.
+    #[arg(long)]
+    pub registry: Option<String>,
+
+    /// Sign the package with your signing key, so that users can verify who published it.
+    ///
+    /// A new key is generated if none exists yet.
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub sign: bool,
 }
 
 pub(crate) async fn push_command(cmd: Push) -> Result<()> {
@@ -57,6 +77,11 @@ pub(crate) async fn push_command(cmd: Push) -> Result<()> {
     let path = canonicalize_sync(&path)?;
 
     let files_to_copy: Vec<PathBuf> = filter_ignored_files(&path);
+    let licenses = check_license(&path, &files_to_copy)?;
+    match licenses.license() {
+        Some(license) => info!(format!("Package license: {license}")),
+        None => remark!("The license of the package could not be identified"),
+    }
 
     // Check for sensitive files or directories
     if !cmd.dry_run &&
@@ -80,9 +105,30 @@ pub(crate) async fn push_command(cmd: Push) -> Result<()> {
 
     validate_name(dependency_name)?;
     validate_version(dependency_version)?;
-
-    if let Some(zip_path) =
-        push_version(dependency_name, dependency_version, path, &files_to_copy, cmd.dry_run).await?
+    let registry = get_registry(cmd.registry.as_deref())?;
+
+    let signing_key = if cmd.sign && !cmd.dry_run {
+        let path = signing_key_path()?;
+        let (key, generated) = SigningKey::load_or_generate(&path).map_err(PublishError::from)?;
+        if generated {
+            remark!(format!("Generated a new signing key at {}", path.to_string_lossy()));
+        }
+        info!(format!("Signing package with public key {}", key.public_key()));
+        Some(key)
+    } else {
+        None
+    };
+
+    if let Some(zip_path) = push_version(
+        &registry,
+        dependency_name,
+        dependency_version,
+        path,
+        &files_to_copy,
+        cmd.dry_run,
+        signing_key.as_ref(),
+    )
+    .await?
     {
         info!(format!("Zip file created at {}", zip_path.to_string_lossy()));
     } else {
@@ -0,0 +1,81 @@
+use crate::utils::{offline_from_env, project_name, success};
+use clap::{Parser, ValueEnum};
+use soldeer_core::{
+    Result,
+    config::{Paths, read_config_deps},
+    lock::read_lockfile,
+    sbom::{components, cyclonedx_document, spdx_document},
+};
+use std::{fs, path::PathBuf};
+
+/// Export a software bill of materials (SBOM) of the dependencies
+#[derive(Debug, Clone, Default, Parser, bon::Builder)]
+#[builder(on(PathBuf, into))]
+#[clap(
+    long_about = "Export a software bill of materials (SBOM) of the dependencies
+
+Each entry of the lockfile becomes a component of the SBOM, with its package URL (purl), download URL, checksum and integrity hashes, and license. The license is detected from the license files and the SPDX headers of the installed package, or retrieved from the registry for registry packages.
+
+Examples:
+- CycloneDX to stdout: soldeer sbom
+- SPDX to a file: soldeer sbom --format spdx --output sbom.spdx.json",
+    after_help = "For more information, read the README.md"
+)]
+#[non_exhaustive]
+pub struct Sbom {
+    /// The format of the SBOM
+    #[arg(long, value_enum, default_value_t = SbomFormat::default())]
+    #[builder(default)]
+    pub format: SbomFormat,
+
+    /// Write the SBOM to this file instead of stdout
+    #[arg(long, short, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+/// The format of the SBOM.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[non_exhaustive]
+pub enum SbomFormat {
+    /// A CycloneDX 1.5 JSON document
+    #[default]
+    #[value(name = "cyclonedx")]
+    CycloneDx,
+
+    /// An SPDX 2.3 JSON document
+    Spdx,
+}
+
+pub(crate) async fn sbom_command(paths: &Paths, cmd: Sbom) -> Result<()> {
+    let (dependencies, _) = read_config_deps(&paths.config)?;
+    let registries = paths.registries()?;
+    let lockfile = read_lockfile(&paths.lock)?;
+    success!("Done reading config and lockfile");
+
+    let components = components(
+        &dependencies,
+        &lockfile.entries,
+        &paths.dependencies,
+        &registries,
+        offline_from_env(),
+    )
+    .await;
+    let project = project_name(paths);
+    let document = match cmd.format {
+        SbomFormat::CycloneDx => cyclonedx_document(&project, &dependencies, &components),
+        SbomFormat::Spdx => spdx_document(&project, &dependencies, &components),
+    };
+    let document = format!("{document:#}");
+    match &cmd.output {
+        Some(path) => {
+            fs::write(path, document)?;
+            success!(format!(
+                "Wrote the SBOM with {} components to {}",
+                components.len(),
+                path.display()
+            ));
+        }
+        None => println!("{document}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,213 @@
+use crate::utils::{print_output, project_name, success};
+use clap::{Parser, ValueEnum};
+use path_slash::PathBufExt as _;
+use soldeer_core::{
+    Result,
+    config::{Paths, read_config_deps},
+    graph::{DependencyGraph, Edge, Package, build_graph},
+    lock::read_lockfile,
+    utils::relative_path,
+};
+use std::collections::HashSet;
+
+/// Display the dependency graph of the project
+#[derive(Debug, Clone, Default, Parser, bon::Builder)]
+#[builder(on(String, into))]
+#[clap(
+    long_about = "Display the dependency graph of the project
+
+Shows the dependencies of the project, their own dependencies, and the git submodules vendored inside of them, along with their resolved version, source and install path.
+
+Examples:
+- Whole graph: soldeer tree
+- Packages which pull in a dependency: soldeer tree --invert forge-std
+- Graphviz output: soldeer tree --format dot | dot -Tsvg > deps.svg",
+    after_help = "For more information, read the README.md"
+)]
+#[non_exhaustive]
+pub struct Tree {
+    /// Show the packages which depend on the given package instead of the dependencies
+    #[arg(long, short, value_name = "PACKAGE")]
+    pub invert: Option<String>,
+
+    /// The output format
+    #[arg(long, value_enum, default_value_t = TreeFormat::default())]
+    #[builder(default)]
+    pub format: TreeFormat,
+}
+
+/// The output format of the `tree` command.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[non_exhaustive]
+pub enum TreeFormat {
+    /// An indented tree
+    #[default]
+    Text,
+
+    /// A Graphviz graph
+    Dot,
+
+    /// The packages and edges of the graph as JSON
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+pub(crate) async fn tree_command(paths: &Paths, cmd: Tree) -> Result<()> {
+    let (dependencies, _) = read_config_deps(&paths.config)?;
+    let lockfile = read_lockfile(&paths.lock)?;
+    success!("Done reading config and lockfile");
+
+    let mut graph = build_graph(&dependencies, &lockfile.entries, &paths.dependencies).await?;
+    if let Some(name) = &cmd.invert {
+        graph = graph.dependents_of(name)?;
+    }
+    let project = project_name(paths);
+    match cmd.format {
+        TreeFormat::Text => match &cmd.invert {
+            Some(name) => print_output(format_inverted_tree(&graph, name, &project, paths)),
+            None => print_output(format_tree(&graph, &project, paths)),
+        },
+        TreeFormat::Dot => println!("{}", format_dot(&graph, &project)),
+        #[cfg(feature = "serde")]
+        TreeFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&graph).expect("graph should be serializable")
+        ),
+    }
+    Ok(())
+}
+
+/// Format a package for display in the tree, with its version, source and install path.
+fn format_package(package: &Package, paths: &Paths) -> String {
+    format!(
+        "{} {} ({}) {}",
+        package.name,
+        package.version,
+        package.source,
+        relative_path(&paths.root, &package.install_path).to_slash_lossy()
+    )
+}
+
+/// Format the graph as a tree, starting from the project.
+///
+/// Packages which appear several times in the tree are only expanded the first time, and marked
+/// with `(*)` afterwards.
+fn format_tree(graph: &DependencyGraph, project: &str, paths: &Paths) -> String {
+    let mut printer = TreePrinter::new(graph, project, paths, Direction::Dependencies);
+    printer.lines.push(project.to_string());
+    let roots: Vec<_> = graph.roots().collect();
+    printer.push_edges(&roots, "");
+    printer.lines.join("\n")
+}
+
+/// Format the graph as trees starting from the packages with the given name, and going up to the
+/// project through the packages which depend on them.
+fn format_inverted_tree(
+    graph: &DependencyGraph,
+    name: &str,
+    project: &str,
+    paths: &Paths,
+) -> String {
+    let mut printer = TreePrinter::new(graph, project, paths, Direction::Dependents);
+    for package in graph.packages.iter().filter(|p| p.name == name) {
+        printer.lines.push(format_package(package, paths));
+        printer.seen.insert(&package.id);
+        let parents: Vec<_> = graph.parents(&package.id).collect();
+        printer.push_edges(&parents, "");
+    }
+    printer.lines.join("\n")
+}
+
+/// Which side of the edges is displayed in the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Dependencies,
+    Dependents,
+}
+
+/// Helper to render the lines of a tree.
+struct TreePrinter<'a> {
+    graph: &'a DependencyGraph,
+    project: &'a str,
+    paths: &'a Paths,
+    direction: Direction,
+    seen: HashSet<&'a str>,
+    lines: Vec<String>,
+}
+
+impl<'a> TreePrinter<'a> {
+    fn new(
+        graph: &'a DependencyGraph,
+        project: &'a str,
+        paths: &'a Paths,
+        direction: Direction,
+    ) -> Self {
+        Self { graph, project, paths, direction, seen: HashSet::new(), lines: Vec::new() }
+    }
+
+    /// Add the lines for a list of edges to the tree, recursively.
+    fn push_edges(&mut self, edges: &[&'a Edge], indent: &str) {
+        for (i, edge) in edges.iter().enumerate() {
+            let (branch, next_indent) = if i == edges.len() - 1 {
+                ("└── ", format!("{indent}    "))
+            } else {
+                ("├── ", format!("{indent}│   "))
+            };
+            let id = match self.direction {
+                Direction::Dependencies => Some(edge.to.as_str()),
+                Direction::Dependents => edge.from.as_deref(),
+            };
+            let Some(package) = id.and_then(|id| self.graph.package(id)) else {
+                // the dependent is the project itself
+                self.lines.push(format!("{indent}{branch}{}", self.project));
+                continue;
+            };
+            let line = format_package(package, self.paths);
+            if !self.seen.insert(&package.id) {
+                self.lines.push(format!("{indent}{branch}{line} (*)"));
+                continue;
+            }
+            self.lines.push(format!("{indent}{branch}{line}"));
+            let next: Vec<_> = match self.direction {
+                Direction::Dependencies => self.graph.children(&package.id).collect(),
+                Direction::Dependents => self.graph.parents(&package.id).collect(),
+            };
+            self.push_edges(&next, &next_indent);
+        }
+    }
+}
+
+/// Format the graph in the Graphviz DOT language.
+fn format_dot(graph: &DependencyGraph, project: &str) -> String {
+    let mut lines = vec!["digraph dependencies {".to_string()];
+    // the project node has an identifier which can't be a package name
+    lines.push(format!("    \".\" [label=\"{}\", shape=box];", escape(project)));
+    for package in &graph.packages {
+        lines.push(format!(
+            "    \"{}\" [label=\"{} {}\\n({})\"];",
+            escape(&package.id),
+            escape(&package.name),
+            escape(&package.version),
+            package.source
+        ));
+    }
+    for edge in &graph.edges {
+        let from = edge.from.as_deref().unwrap_or(".");
+        match &edge.version_req {
+            Some(req) => lines.push(format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"];",
+                escape(from),
+                escape(&edge.to),
+                escape(req)
+            )),
+            None => lines.push(format!("    \"{}\" -> \"{}\";", escape(from), escape(&edge.to))),
+        }
+    }
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+/// Escape a string for use inside of a quoted DOT identifier.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
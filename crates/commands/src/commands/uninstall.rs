@@ -2,11 +2,15 @@ use crate::utils::success;
 use clap::Parser;
 use soldeer_core::{
     Result, SoldeerError,
-    config::{Paths, delete_from_config, read_soldeer_config},
+    config::{Paths, delete_from_config, read_config_deps, read_soldeer_config},
     download::delete_dependency_files_sync,
-    lock::remove_lock,
+    errors::{InstallError, LockError},
+    lock::{generate_lockfile_contents, read_lockfile, remove_lock},
     remappings::{RemappingsAction, edit_remappings},
+    resolver::reachable_entries,
+    workspace::load_workspace,
 };
+use std::fs;
 
 /// Uninstall a dependency
 #[derive(Debug, Clone, Parser, bon::Builder)]
@@ -29,6 +33,19 @@ pub(crate) fn uninstall_command(paths: &Paths, cmd: &Uninstall) -> Result<()> {
     edit_remappings(&RemappingsAction::Remove(dependency.clone()), &config, paths)?;
     success!("Dependency removed from remappings");
 
+    // in a workspace, the dependency is kept if other projects still require it
+    let workspace = load_workspace(paths)?;
+    if let Some(workspace) = &workspace {
+        let projects = workspace.projects_requiring(dependency.name())?;
+        if !projects.is_empty() {
+            success!(format!(
+                "Dependency is still required by {}, keeping it in the workspace",
+                projects.join(", ")
+            ));
+            return Ok(());
+        }
+    }
+
     // deleting the files
     delete_dependency_files_sync(&dependency, &paths.dependencies)
         .map_err(|e| SoldeerError::DownloadError { dep: dependency.to_string(), source: e })?;
@@ -36,5 +53,30 @@ pub(crate) fn uninstall_command(paths: &Paths, cmd: &Uninstall) -> Result<()> {
 
     remove_lock(&dependency, &paths.lock)?;
     success!("Dependency removed from lockfile");
+
+    // remove transitive dependencies which are not required anymore
+    let lockfile = read_lockfile(&paths.lock)?;
+    if lockfile.entries.is_empty() {
+        return Ok(());
+    }
+    let (dependencies, _) = match &workspace {
+        Some(workspace) => workspace.dependencies()?,
+        None => read_config_deps(&paths.config)?,
+    };
+    let (kept, removed): (Vec<_>, Vec<_>) = {
+        let reachable = reachable_entries(&dependencies, lockfile.entries.clone());
+        lockfile.entries.into_iter().partition(|e| reachable.contains(e))
+    };
+    if removed.is_empty() {
+        return Ok(());
+    }
+    for entry in &removed {
+        let path = entry.install_path(&paths.dependencies);
+        if path.exists() {
+            fs::remove_dir_all(&path).map_err(|e| InstallError::IOError { path, source: e })?;
+        }
+    }
+    fs::write(&paths.lock, generate_lockfile_contents(kept)).map_err(LockError::IOError)?;
+    success!(format!("Removed {} unused transitive dependencies", removed.len()));
     Ok(())
 }
@@ -1,16 +1,18 @@
 use crate::{
     ConfigLocation,
-    utils::{Progress, success, warning},
+    utils::{Progress, offline_from_env, success, vendor_dir_from_env, warning},
 };
 use clap::Parser;
 use soldeer_core::{
     Result,
-    config::{Paths, read_config_deps, read_soldeer_config},
-    errors::LockError,
-    install::{InstallProgress, ensure_dependencies_dir},
+    config::{Dependency, Paths, read_config_deps, read_soldeer_config},
+    errors::{ConfigError, InstallError, LockError},
+    install::{InstallOptions, InstallProgress, ensure_dependencies_dir, install_dependencies},
     lock::{generate_lockfile_contents, read_lockfile},
     remappings::{RemappingsAction, edit_remappings},
+    resolver::{reachable_entries, resolve_transitive},
     update::update_dependencies,
+    workspace::load_workspace,
 };
 use std::fs;
 
@@ -18,9 +20,31 @@ use std::fs;
 #[derive(Debug, Clone, Default, Parser, bon::Builder)]
 #[allow(clippy::duplicated_attributes)]
 #[builder(on(String, into), on(ConfigLocation, into))]
-#[clap(after_help = "For more information, read the README.md")]
+#[clap(
+    long_about = "Update dependencies by reading the config file
+
+If dependency names are provided, only those dependencies are updated. The other entries of the lockfile are left untouched.
+
+Examples:
+- Update all: soldeer update
+- Update some: soldeer update forge-std solady
+- Update all except some: soldeer update --except forge-std",
+    after_help = "For more information, read the README.md"
+)]
 #[non_exhaustive]
 pub struct Update {
+    /// The names of the dependencies to update.
+    ///
+    /// If not present, all dependencies are updated.
+    #[arg(value_name = "DEPENDENCY")]
+    #[builder(default)]
+    pub dependencies: Vec<String>,
+
+    /// The name of a dependency which should not be updated. Can be repeated.
+    #[arg(long, value_name = "DEPENDENCY", conflicts_with = "dependencies")]
+    #[builder(default)]
+    pub except: Vec<String>,
+
     /// If set, this command will delete the existing remappings and re-create them
     #[arg(short = 'g', long, default_value_t = false)]
     #[builder(default)]
@@ -40,9 +64,6 @@ pub struct Update {
     pub config_location: Option<ConfigLocation>,
 }
 
-// TODO: add a parameter for a dependency name, where we would only update that particular
-// dependency
-
 pub(crate) async fn update_command(paths: &Paths, cmd: Update) -> Result<()> {
     let mut config = read_soldeer_config(&paths.config)?;
     if cmd.regenerate_remappings {
@@ -51,33 +72,153 @@ pub(crate) async fn update_command(paths: &Paths, cmd: Update) -> Result<()> {
     if cmd.recursive_deps {
         config.recursive_deps = true;
     }
+    let registries = paths.registries()?;
+    let options = InstallOptions::builder()
+        .link_mode(config.link_mode)
+        .offline(offline_from_env())
+        .maybe_vendor_dir(vendor_dir_from_env())
+        .trust_policy(paths.trust_policy()?)
+        .policy(config.policy.clone())
+        .build();
     success!("Done reading config");
     ensure_dependencies_dir(&paths.dependencies)?;
-    let (dependencies, warnings) = read_config_deps(&paths.config)?;
+    let workspace = load_workspace(paths)?;
+    let (dependencies, warnings) = match &workspace {
+        Some(workspace) => {
+            success!(format!("Found workspace with {} members", workspace.members.len()));
+            workspace.dependencies()?
+        }
+        None => read_config_deps(&paths.config)?,
+    };
     for w in warnings {
         warning!(format!("Config warning: {w}"));
     }
+    config.policy.check_dependencies(&dependencies)?;
+
+    for name in cmd.dependencies.iter().chain(&cmd.except) {
+        if !dependencies.iter().any(|d| d.name() == name) {
+            return Err(ConfigError::MissingDependency(name.clone()).into());
+        }
+    }
+    let partial = !cmd.dependencies.is_empty() || !cmd.except.is_empty();
+    let selected: Vec<Dependency> = dependencies
+        .iter()
+        .filter(|d| {
+            (cmd.dependencies.is_empty() || cmd.dependencies.iter().any(|n| n == d.name())) &&
+                !cmd.except.iter().any(|n| n == d.name())
+        })
+        .cloned()
+        .collect();
 
     let lockfile = read_lockfile(&paths.lock)?;
     success!("Done reading lockfile");
     let (progress, monitor) = InstallProgress::new();
-    let bars = Progress::new("Updating dependencies", dependencies.len(), monitor);
+    let bars = Progress::new("Updating dependencies", selected.len(), monitor);
     bars.start_all();
-    let new_locks = update_dependencies(
-        &dependencies,
+    let mut new_locks = update_dependencies(
+        &selected,
         &lockfile.entries,
         &paths.dependencies,
+        &registries,
         config.recursive_deps,
-        progress,
+        &options,
+        progress.clone(),
     )
     .await?;
+    if config.recursive_deps {
+        // for a full update, transitive dependencies are resolved again without taking the
+        // lockfile into account
+        let locks = if partial { lockfile.entries.as_slice() } else { &[] };
+        // the other dependencies and all of their transitive dependencies are pinned to their lock
+        // entries, so that their requirements on shared transitive dependencies are taken into
+        // account and their entries are left untouched
+        let unselected: Vec<_> = dependencies
+            .iter()
+            .filter(|d| !selected.iter().any(|s| s.name() == d.name()))
+            .cloned()
+            .collect();
+        let pinned =
+            if partial { reachable_entries(&unselected, lockfile.entries.clone()) } else { vec![] };
+        if partial {
+            new_locks.extend(
+                install_dependencies(
+                    &unselected,
+                    locks,
+                    &paths.dependencies,
+                    &registries,
+                    config.recursive_deps,
+                    &options,
+                    progress.clone(),
+                )
+                .await?,
+            );
+        }
+        new_locks = resolve_transitive(
+            &dependencies,
+            new_locks,
+            locks,
+            &pinned,
+            &paths.dependencies,
+            &registries,
+            &options,
+            progress,
+        )
+        .await?;
+    }
     bars.stop_all();
 
-    let new_lockfile_content = generate_lockfile_contents(new_locks);
-    fs::write(&paths.lock, new_lockfile_content).map_err(LockError::IOError)?;
+    if !partial {
+        let new_lockfile_content = generate_lockfile_contents(new_locks);
+        fs::write(&paths.lock, new_lockfile_content).map_err(LockError::IOError)?;
+        success!("Updated lockfile");
+
+        match &workspace {
+            Some(workspace) => workspace.edit_remappings(cmd.regenerate_remappings)?,
+            None => edit_remappings(&RemappingsAction::Update, &config, paths)?,
+        }
+        success!("Updated remappings");
+        return Ok(());
+    }
+
+    // only the entries of the updated packages are replaced, the other ones are kept as is
+    let mut entries = lockfile.entries.clone();
+    let mut changed = Vec::new();
+    for lock in new_locks {
+        match entries.iter_mut().find(|e| e.name() == lock.name()) {
+            Some(entry) if *entry == lock => {}
+            Some(entry) => {
+                changed.push(lock.name().to_string());
+                // the folder of the previous version is not needed anymore
+                let path = entry.install_path(&paths.dependencies);
+                if path != lock.install_path(&paths.dependencies) && path.exists() {
+                    fs::remove_dir_all(&path)
+                        .map_err(|e| InstallError::IOError { path, source: e })?;
+                }
+                *entry = lock;
+            }
+            None => {
+                changed.push(lock.name().to_string());
+                entries.push(lock);
+            }
+        }
+    }
+    // transitive dependencies which are not required anymore are removed
+    let reachable = reachable_entries(&dependencies, entries.clone());
+    for entry in entries.iter().filter(|e| !reachable.contains(e)) {
+        let path = entry.install_path(&paths.dependencies);
+        if path.exists() {
+            fs::remove_dir_all(&path).map_err(|e| InstallError::IOError { path, source: e })?;
+        }
+    }
+    fs::write(&paths.lock, generate_lockfile_contents(reachable)).map_err(LockError::IOError)?;
     success!("Updated lockfile");
 
-    edit_remappings(&RemappingsAction::Update, &config, paths)?;
+    for dep in selected.iter().filter(|d| changed.iter().any(|n| n == d.name())) {
+        match &workspace {
+            Some(workspace) => workspace.refresh_remappings(dep)?,
+            None => edit_remappings(&RemappingsAction::Refresh(dep.clone()), &config, paths)?,
+        }
+    }
     success!("Updated remappings");
     Ok(())
 }
@@ -0,0 +1,39 @@
+use crate::utils::{offline_from_env, remark, success};
+use clap::Parser;
+use soldeer_core::{Result, config::Paths, lock::read_lockfile, vendor::vendor_dependencies};
+use std::{fs, path::PathBuf};
+
+/// Copy the artifacts of all locked dependencies into a folder, for installs without network access
+#[derive(Debug, Clone, Parser, bon::Builder)]
+#[builder(on(PathBuf, into))]
+#[clap(
+    long_about = "Copy the artifacts of all locked dependencies into a folder, for installs without network access
+
+The zip archives of HTTP and private dependencies are copied from the cache or downloaded, and git dependencies are saved as git bundles containing the locked commit. An `index.json` file lists the artifacts. Local path dependencies are not vendored.
+
+The folder can then be used to install the dependencies on another machine:
+- soldeer vendor vendor
+- soldeer install --offline --vendor-dir vendor",
+    after_help = "For more information, read the README.md"
+)]
+#[non_exhaustive]
+pub struct Vendor {
+    /// The folder where the artifacts are copied
+    pub dir: PathBuf,
+}
+
+pub(crate) async fn vendor_command(paths: &Paths, cmd: Vendor) -> Result<()> {
+    let registries = paths.registries()?;
+    let lockfile = read_lockfile(&paths.lock)?;
+    success!("Done reading lockfile");
+
+    fs::create_dir_all(&cmd.dir)?;
+    let index =
+        vendor_dependencies(&lockfile.entries, &cmd.dir, &registries, offline_from_env()).await?;
+    success!(format!("Vendored {} artifacts into {}", index.artifacts.len(), cmd.dir.display()));
+    remark!(format!(
+        "Install from this folder with `soldeer install --vendor-dir {}`",
+        cmd.dir.display()
+    ));
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use crate::utils::{offline_from_env, print_output, success};
+use clap::Parser;
+use soldeer_core::{
+    Result,
+    config::Paths,
+    errors::VerifyError,
+    install::DependencyStatus,
+    lock::read_lockfile,
+    manifest::ChangeKind,
+    verify::{VerifyReport, verify_dependencies},
+};
+
+/// Check the integrity of the installed dependencies without modifying them
+#[derive(Debug, Clone, Default, Parser, bon::Builder)]
+#[builder(on(String, into))]
+#[clap(
+    long_about = "Check the integrity of the installed dependencies without modifying them
+
+Each entry of the lockfile is checked against its install folder. For dependencies downloaded as a zip file, the files which differ from the published archive are listed (the archive is read from the cache or downloaded in memory). For git dependencies, the changes compared to the locked commit are shown. The command exits with an error if any dependency was modified.",
+    after_help = "For more information, read the README.md"
+)]
+#[non_exhaustive]
+pub struct Verify {
+    /// Output the result as JSON
+    #[cfg(feature = "serde")]
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub json: bool,
+}
+
+pub(crate) async fn verify_command(paths: &Paths, cmd: Verify) -> Result<()> {
+    let registries = paths.registries()?;
+    let lockfile = read_lockfile(&paths.lock)?;
+    success!("Done reading lockfile");
+
+    let reports = verify_dependencies(
+        &lockfile.entries,
+        &paths.dependencies,
+        &registries,
+        offline_from_env(),
+    )
+    .await?;
+
+    #[cfg(feature = "serde")]
+    if cmd.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&reports).expect("reports should be serializable")
+        );
+    } else {
+        print_output(format_reports(&reports));
+    }
+    #[cfg(not(feature = "serde"))]
+    {
+        let _ = cmd;
+        print_output(format_reports(&reports));
+    }
+
+    let tampered: Vec<_> = reports
+        .into_iter()
+        .filter(|r| r.status == DependencyStatus::FailedIntegrity)
+        .map(|r| r.name)
+        .collect();
+    if !tampered.is_empty() {
+        return Err(VerifyError::Tampered(tampered).into());
+    }
+    success!("No dependency was modified");
+    Ok(())
+}
+
+/// Format the reports with one line per dependency, followed by the list of changes if any.
+fn format_reports(reports: &[VerifyReport]) -> String {
+    let mut lines = Vec::new();
+    for report in reports {
+        let status = match report.status {
+            DependencyStatus::Missing => "missing",
+            DependencyStatus::FailedIntegrity => "modified",
+            DependencyStatus::Installed => "ok",
+        };
+        lines.push(format!("{} {}: {status}", report.name, report.version));
+        if let Some(changes) = &report.changes {
+            lines.extend(changes.iter().map(|c| {
+                let kind = match c.kind {
+                    ChangeKind::Modified => 'M',
+                    ChangeKind::Added => 'A',
+                    ChangeKind::Deleted => 'D',
+                };
+                format!("  {kind} {}", c.path)
+            }));
+        }
+        if let Some(stat) = &report.diff_stat {
+            lines.extend(stat.lines().map(|l| format!("  {}", l.trim())));
+        }
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,63 @@
+use crate::utils::{print_output, project_name, success};
+use clap::Parser;
+use soldeer_core::{
+    Result,
+    config::{Paths, read_config_deps},
+    graph::{DependencyGraph, Edge, build_graph},
+    lock::read_lockfile,
+};
+
+/// Explain why a package is installed
+#[derive(Debug, Clone, Parser, bon::Builder)]
+#[builder(on(String, into))]
+#[clap(
+    long_about = "Explain why a package is installed
+
+Prints every path from the dependencies declared in the config file down to the package, with the version requirement declared on each step.
+
+Example: soldeer why forge-std",
+    after_help = "For more information, read the README.md"
+)]
+#[non_exhaustive]
+pub struct Why {
+    /// The name of the package
+    #[arg(value_name = "PACKAGE")]
+    pub package: String,
+}
+
+pub(crate) async fn why_command(paths: &Paths, cmd: &Why) -> Result<()> {
+    let (dependencies, _) = read_config_deps(&paths.config)?;
+    let lockfile = read_lockfile(&paths.lock)?;
+    success!("Done reading config and lockfile");
+
+    let graph = build_graph(&dependencies, &lockfile.entries, &paths.dependencies).await?;
+    let chains = graph.paths_to(&cmd.package)?;
+    let project = project_name(paths);
+    let mut lines = Vec::with_capacity(chains.len() + 1);
+    lines.push(match chains.len() {
+        1 => format!("{} is required by 1 path:", cmd.package),
+        n => format!("{} is required by {n} paths:", cmd.package),
+    });
+    lines.extend(chains.iter().map(|chain| format_chain(&graph, &project, chain)));
+    print_output(lines.join("\n"));
+    Ok(())
+}
+
+/// Format a path of the dependency graph, starting from the project.
+///
+/// Each step shows the name of the package along with the version requirement declared by its
+/// parent, if known. The resolved version of the package is shown at the end.
+fn format_chain(graph: &DependencyGraph, project: &str, chain: &[&Edge]) -> String {
+    let mut out = project.to_string();
+    for edge in chain {
+        let name = graph.package(&edge.to).map_or(edge.to.as_str(), |p| p.name.as_str());
+        match &edge.version_req {
+            Some(req) => out.push_str(&format!(" → {name} ({req})")),
+            None => out.push_str(&format!(" → {name}")),
+        }
+    }
+    if let Some(package) = chain.last().and_then(|e| graph.package(&e.to)) {
+        out.push_str(&format!(" = {}", package.version));
+    }
+    out
+}
@@ -56,7 +56,8 @@ impl From<soldeer_core::config::ConfigLocation> for ConfigLocation {
 pub async fn run(command: Command, verbosity: Verbosity<CustomLevel>) -> Result<()> {
     if let Some(level) = verbosity.log_level() &&
         level <= Level::Error &&
-        env::var("RUST_LOG").is_err()
+        env::var("RUST_LOG").is_err() &&
+        !command.json_output()
     {
         // enable TUI if no `-v` flag and no RUST_LOG is provided
         TUI_ENABLED.store(true, Ordering::Relaxed);
@@ -105,6 +106,98 @@ pub async fn run(command: Command, verbosity: Verbosity<CustomLevel>) -> Result<
             })?;
             outro!("Done updating!");
         }
+        Command::Outdated(cmd) => {
+            intro!("🦌 Soldeer Outdated 🦌");
+            let root = Paths::get_root_path();
+            let paths =
+                Paths::with_root_and_config(&root, Some(get_config_location(&root, None)?))?;
+            commands::outdated::outdated_command(&paths, cmd).await.inspect_err(|_| {
+                outro_cancel!("Some dependencies are outdated or could not be checked");
+            })?;
+            outro!("Done checking dependencies!");
+        }
+        Command::List(cmd) => {
+            intro!("🦌 Soldeer List 🦌");
+            let root = Paths::get_root_path();
+            let paths =
+                Paths::with_root_and_config(&root, Some(get_config_location(&root, None)?))?;
+            commands::list::list_command(&paths, cmd).await.inspect_err(|_| {
+                outro_cancel!("An error occurred while listing dependencies");
+            })?;
+            outro!("Done!");
+        }
+        Command::Tree(cmd) => {
+            intro!("🦌 Soldeer Tree 🦌");
+            let root = Paths::get_root_path();
+            let paths =
+                Paths::with_root_and_config(&root, Some(get_config_location(&root, None)?))?;
+            commands::tree::tree_command(&paths, cmd).await.inspect_err(|_| {
+                outro_cancel!("An error occurred while reading the dependency graph");
+            })?;
+            outro!("Done!");
+        }
+        Command::Verify(cmd) => {
+            intro!("🦌 Soldeer Verify 🦌");
+            let root = Paths::get_root_path();
+            let paths =
+                Paths::with_root_and_config(&root, Some(get_config_location(&root, None)?))?;
+            commands::verify::verify_command(&paths, cmd).await.inspect_err(|_| {
+                outro_cancel!("Some dependencies were modified or could not be verified");
+            })?;
+            outro!("Done verifying dependencies!");
+        }
+        Command::Audit(cmd) => {
+            intro!("🦌 Soldeer Audit 🦌");
+            let root = Paths::get_root_path();
+            let paths =
+                Paths::with_root_and_config(&root, Some(get_config_location(&root, None)?))?;
+            commands::audit::audit_command(&paths, cmd).await.inspect_err(|_| {
+                outro_cancel!(
+                    "Some dependencies are affected by advisories or could not be audited"
+                );
+            })?;
+            outro!("Done auditing dependencies!");
+        }
+        Command::Sbom(cmd) => {
+            intro!("🦌 Soldeer SBOM 🦌");
+            let root = Paths::get_root_path();
+            let paths =
+                Paths::with_root_and_config(&root, Some(get_config_location(&root, None)?))?;
+            commands::sbom::sbom_command(&paths, cmd).await.inspect_err(|_| {
+                outro_cancel!("An error occurred while exporting the SBOM");
+            })?;
+            outro!("Done!");
+        }
+        Command::Licenses(cmd) => {
+            intro!("🦌 Soldeer Licenses 🦌");
+            let root = Paths::get_root_path();
+            let paths =
+                Paths::with_root_and_config(&root, Some(get_config_location(&root, None)?))?;
+            commands::licenses::licenses_command(&paths, cmd).await.inspect_err(|_| {
+                outro_cancel!("Some licenses are not allowed or could not be checked");
+            })?;
+            outro!("Done checking licenses!");
+        }
+        Command::Vendor(cmd) => {
+            intro!("🦌 Soldeer Vendor 🦌");
+            let root = Paths::get_root_path();
+            let paths =
+                Paths::with_root_and_config(&root, Some(get_config_location(&root, None)?))?;
+            commands::vendor::vendor_command(&paths, cmd).await.inspect_err(|_| {
+                outro_cancel!("An error occurred while vendoring dependencies");
+            })?;
+            outro!("Done vendoring dependencies!");
+        }
+        Command::Why(cmd) => {
+            intro!("🦌 Soldeer Why 🦌");
+            let root = Paths::get_root_path();
+            let paths =
+                Paths::with_root_and_config(&root, Some(get_config_location(&root, None)?))?;
+            commands::why::why_command(&paths, &cmd).await.inspect_err(|_| {
+                outro_cancel!("An error occurred while reading the dependency graph");
+            })?;
+            outro!("Done!");
+        }
         Command::Uninstall(cmd) => {
             intro!("🦌 Soldeer Uninstall 🦌");
             let root = Paths::get_root_path();
@@ -125,6 +218,13 @@ pub async fn run(command: Command, verbosity: Verbosity<CustomLevel>) -> Result<
             })?;
             outro!("Done cleaning!");
         }
+        Command::Cache(cmd) => {
+            intro!("🦌 Soldeer Cache 🦌");
+            commands::cache::cache_command(cmd).inspect_err(|_| {
+                outro_cancel!("An error occurred during the cache operation");
+            })?;
+            outro!("Done!");
+        }
         Command::Login(cmd) => {
             intro!("🦌 Soldeer Login 🦌");
             commands::login::login_command(cmd).await.inspect_err(|_| {
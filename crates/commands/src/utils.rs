@@ -1,10 +1,18 @@
 #![allow(unused_macros)]
 //! Utils for the commands crate
-use std::{fmt, path::Path};
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+};
 
 use crate::ConfigLocation;
 use cliclack::{MultiProgress, ProgressBar, multi_progress, progress_bar, select};
-use soldeer_core::{Result, config::detect_config_location, install::InstallMonitoring};
+use soldeer_core::{
+    Result,
+    config::{Paths, detect_config_location},
+    install::InstallMonitoring,
+    registry::{Registries, Registry},
+};
 
 /// Template for the progress bars.
 pub const PROGRESS_TEMPLATE: &str = "[{elapsed_precise}] {bar:30.magenta} ({pos}/{len}) {msg}";
@@ -130,6 +138,47 @@ impl Progress {
     }
 }
 
+/// Print the main output of a command.
+///
+/// When the TUI is enabled, the output is shown as a log message. Otherwise, it's printed to
+/// stdout.
+pub fn print_output(output: impl fmt::Display) {
+    if crate::TUI_ENABLED.load(std::sync::atomic::Ordering::Relaxed) {
+        cliclack::log::info(output).ok();
+    } else {
+        println!("{output}");
+    }
+}
+
+/// Format rows of cells as a table with aligned columns, preceded by a header row.
+pub fn format_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) -> String {
+    let header = header.map(ToString::to_string);
+    let mut widths = header.clone().map(|h| h.len());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    std::iter::once(&header)
+        .chain(rows)
+        .map(|row| {
+            row.iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Get the name of the project, which is the name of its root folder.
+pub fn project_name(paths: &Paths) -> String {
+    paths.root.file_name().map_or_else(|| ".".to_string(), |n| n.to_string_lossy().to_string())
+}
+
 /// Auto-detect config location or prompt the user for preference.
 pub fn get_config_location(
     root: impl AsRef<Path>,
@@ -144,6 +193,31 @@ pub fn get_config_location(
     })
 }
 
+/// Get a registry declared in the config file of the current project, or the default registry if
+/// no name is provided.
+///
+/// If the current folder is not a Soldeer project, only the default registry is available.
+pub fn get_registry(name: Option<&str>) -> Result<Registry> {
+    let root = Paths::get_root_path();
+    let registries = match detect_config_location(&root) {
+        Some(location) => Paths::with_root_and_config(&root, Some(location))?.registries()?,
+        None => Registries::default(),
+    };
+    Ok(registries.get(name)?.clone())
+}
+
+/// Whether the offline mode is enabled with the `SOLDEER_OFFLINE` environment variable.
+///
+/// The variable must be set to `1` or `true`.
+pub fn offline_from_env() -> bool {
+    env::var("SOLDEER_OFFLINE").is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+/// The vendor folder set with the `SOLDEER_VENDOR_DIR` environment variable, if any.
+pub fn vendor_dir_from_env() -> Option<PathBuf> {
+    env::var("SOLDEER_VENDOR_DIR").ok().filter(|d| !d.is_empty()).map(PathBuf::from)
+}
+
 /// Prompt the user for their desired config location in case it cannot be auto-detected.
 pub fn prompt_config_location() -> Result<ConfigLocation> {
     Ok(select("Select how you want to configure Soldeer")
@@ -0,0 +1,41 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+use soldeer_core::lock::SOLDEER_LOCK;
+use std::{fs, path::Path};
+
+/// A lockfile with dependencies from each kind of source.
+pub(crate) const LOCK: &str = r#"[[dependencies]]
+name = "@scope-lib"
+version = "1.2.0"
+url = "https://example.com/scope-lib.zip"
+checksum = "0a73dbe106f48179ea39b00d42e5d4dd96fdc6252caa3a89ce7efdaec0b94681"
+integrity = "03c628f3e9eae4db14fe14f9ab29e49a0107c47b8ee956e4cee57b616b493fc2"
+
+[[dependencies]]
+name = "mylib"
+version = "1.1.0"
+url = "https://example.com/mylib.zip"
+checksum = "94a73dbe106f48179ea39b00d42e5d4dd96fdc6252caa3a89ce7efdaec0b9468"
+integrity = "f3c628f3e9eae4db14fe14f9ab29e49a0107c47b8ee956e4cee57b616b493fc2"
+
+[[dependencies]]
+name = "other"
+version = "2.0.0"
+url = "https://example.com/other.zip"
+checksum = "0a73dbe106f48179ea39b00d42e5d4dd96fdc6252caa3a89ce7efdaec0b94681"
+integrity = "03c628f3e9eae4db14fe14f9ab29e49a0107c47b8ee956e4cee57b616b493fc2"
+
+[[dependencies]]
+name = "repo"
+version = "1.0.0"
+git = "https://github.com/org/repo.git"
+rev = "abcdef0123456789abcdef0123456789abcdef01"
+"#;
+
+/// Write a project with the given config file contents and the [`LOCK`] lockfile into `dir`.
+///
+/// The dependencies are not installed.
+pub(crate) fn setup_project(dir: &Path, config: &str) {
+    fs::write(dir.join("soldeer.toml"), config).unwrap();
+    fs::write(dir.join(SOLDEER_LOCK), LOCK).unwrap();
+}
@@ -0,0 +1,140 @@
+#![allow(clippy::unwrap_used)]
+use common::{LOCK, setup_project};
+use soldeer_commands::{Command, Verbosity, commands::audit::Audit, run};
+use soldeer_core::{SoldeerError, audit::Severity, errors::AuditError, lock::SOLDEER_LOCK};
+use std::fs;
+use temp_env::async_with_vars;
+use testdir::testdir;
+
+mod common;
+
+const ADVISORIES: &str = r#"[[advisories]]
+id = "SOL-1"
+package = "mylib"
+title = "Reentrancy"
+severity = "medium"
+affected = ["<1.2.0"]
+fixed = ["1.2.0"]
+
+[[advisories]]
+id = "SOL-2"
+git = "git@github.com:org/repo.git"
+revs = ["abcdef0"]
+title = "Broken"
+severity = "high"
+"#;
+
+#[tokio::test]
+async fn test_audit() {
+    let dir = testdir!();
+    setup_project(&dir, "[dependencies]\n");
+    let source = dir.join("advisories.toml");
+    fs::write(&source, ADVISORIES).unwrap();
+    let db = dir.join("advisory-db.json");
+    let vars = [
+        ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().to_string())),
+        ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().to_string())),
+        ("SOLDEER_ADVISORY_DB", Some(db.to_string_lossy().to_string())),
+    ];
+
+    // the local database must be synced first
+    let cmd: Command = Audit::default().into();
+    let res = async_with_vars(vars.clone(), run(cmd, Verbosity::default())).await;
+    assert!(
+        matches!(res, Err(SoldeerError::AuditError(AuditError::MissingDatabase(_)))),
+        "{res:?}"
+    );
+
+    let cmd: Command = Audit::builder().sync(source.to_string_lossy()).build().into();
+    let res = async_with_vars(vars.clone(), run(cmd, Verbosity::default())).await;
+    assert!(
+        matches!(
+            res,
+            Err(SoldeerError::AuditError(AuditError::Vulnerable {
+                count: 2,
+                severity: Severity::Low
+            }))
+        ),
+        "{res:?}"
+    );
+    assert!(db.is_file());
+
+    let cmd: Command = Audit::builder().fail_on(Severity::High).build().into();
+    let res = async_with_vars(vars.clone(), run(cmd, Verbosity::default())).await;
+    assert!(
+        matches!(res, Err(SoldeerError::AuditError(AuditError::Vulnerable { count: 1, .. }))),
+        "{res:?}"
+    );
+
+    let cmd: Command =
+        Audit::builder().fail_on(Severity::High).ignore(vec!["SOL-2".to_string()]).build().into();
+    let res = async_with_vars(vars, run(cmd, Verbosity::default())).await;
+    assert!(res.is_ok(), "{res:?}");
+}
+
+#[tokio::test]
+async fn test_audit_db_url() {
+    let dir = testdir!();
+    setup_project(&dir, "[dependencies]\n");
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/advisories.toml")
+        .with_body(ADVISORIES.replace("<1.2.0", "<1.1.0"))
+        .create_async()
+        .await;
+    let vars = [
+        ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().to_string())),
+        ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().to_string())),
+        ("SOLDEER_ADVISORY_DB", Some(dir.join("advisory-db.json").to_string_lossy().to_string())),
+    ];
+    let cmd: Command = Audit::builder()
+        .sync(format!("{}/advisories.toml", server.url()))
+        .fail_on(Severity::Critical)
+        .build()
+        .into();
+    let res = async_with_vars(vars, run(cmd, Verbosity::default())).await;
+    assert!(res.is_ok(), "{res:?}");
+}
+
+#[tokio::test]
+async fn test_audit_unevaluated() {
+    let dir = testdir!();
+    fs::write(dir.join("soldeer.toml"), "[dependencies]\n").unwrap();
+    fs::write(
+        dir.join(SOLDEER_LOCK),
+        LOCK.replace("version = \"1.1.0\"", "version = \"v1-final\""),
+    )
+    .unwrap();
+    let db = dir.join("advisories.toml");
+    fs::write(&db, ADVISORIES).unwrap();
+    let vars = [
+        ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().to_string())),
+        ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().to_string())),
+    ];
+
+    // the non-semver version can't be compared with the affected range of SOL-1
+    let cmd: Command = Audit::builder().db(&db).ignore(vec!["SOL-2".to_string()]).build().into();
+    let res = async_with_vars(vars.clone(), run(cmd, Verbosity::default())).await;
+    assert!(
+        matches!(res, Err(SoldeerError::AuditError(AuditError::Unevaluated { count: 1, .. }))),
+        "{res:?}"
+    );
+
+    let cmd: Command = Audit::builder()
+        .db(&db)
+        .ignore(vec!["SOL-1".to_string(), "SOL-2".to_string()])
+        .build()
+        .into();
+    let res = async_with_vars(vars.clone(), run(cmd, Verbosity::default())).await;
+    assert!(res.is_ok(), "{res:?}");
+
+    // SOL-1 has a medium severity
+    let cmd: Command = Audit::builder()
+        .db(&db)
+        .fail_on(Severity::High)
+        .ignore(vec!["SOL-2".to_string()])
+        .build()
+        .into();
+    let res = async_with_vars(vars, run(cmd, Verbosity::default())).await;
+    assert!(res.is_ok(), "{res:?}");
+}
@@ -0,0 +1,96 @@
+use soldeer_commands::{
+    Command, Verbosity,
+    commands::{
+        cache::{Cache, CacheCommand, Prune},
+        install::Install,
+    },
+    run,
+};
+use soldeer_core::{
+    cache::list_cache,
+    lock::{SOLDEER_LOCK, read_lockfile},
+    push::zip_file,
+};
+use std::fs;
+use temp_env::{async_with_vars, with_var};
+use testdir::testdir;
+
+#[tokio::test]
+async fn test_install_from_cache() {
+    let dir = testdir!();
+    let cache = dir.join("cache");
+    let pkg = dir.join("pkg");
+    fs::create_dir(&pkg).unwrap();
+    fs::write(pkg.join("Lib.sol"), "contract Lib {}").unwrap();
+    let zip_path = zip_file(&pkg, &[pkg.join("Lib.sol")], "pkg").unwrap();
+
+    let mut server = mockito::Server::new_async().await;
+    let download =
+        server.mock("GET", "/pkg.zip").with_body_from_file(zip_path).create_async().await;
+    let contents = format!(
+        r#"[dependencies]
+pkg = {{ version = "1.0.0", url = "{}/pkg.zip" }}
+"#,
+        server.url()
+    );
+    fs::write(dir.join("soldeer.toml"), contents).unwrap();
+
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().as_ref())),
+        ],
+        run(cmd.clone(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    let lock = read_lockfile(dir.join(SOLDEER_LOCK)).unwrap();
+    let checksum = &lock.entries[0].as_http().unwrap().checksum;
+    let entries = with_var("SOLDEER_CACHE_DIR", Some(&cache), list_cache).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(&entries[0].checksum, checksum);
+
+    // the archive can't be downloaded anymore, it must come from the cache
+    download.remove_async().await;
+    fs::remove_dir_all(dir.join("dependencies")).unwrap();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(dir.join("dependencies/pkg-1.0.0/Lib.sol").exists());
+    assert_eq!(read_lockfile(dir.join(SOLDEER_LOCK)).unwrap().entries, lock.entries);
+}
+
+#[tokio::test]
+async fn test_cache_commands() {
+    let dir = testdir!();
+    let cache = dir.join("cache");
+    fs::create_dir(&cache).unwrap();
+    let archive = dir.join("archive.zip");
+    fs::write(&archive, "foo").unwrap();
+    let checksum = soldeer_core::utils::hash_file(&archive).unwrap().0;
+    fs::copy(&archive, cache.join(format!("{checksum}.zip"))).unwrap();
+    fs::write(cache.join("1234.zip"), "corrupted").unwrap();
+
+    for (command, remaining) in [
+        (CacheCommand::List, 2),
+        (CacheCommand::Prune(Prune::builder().build()), 1),
+        (CacheCommand::Clean, 0),
+    ] {
+        let cmd: Command = Cache::builder().command(command).build().into();
+        let res = async_with_vars(
+            [("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().as_ref()))],
+            run(cmd, Verbosity::default()),
+        )
+        .await;
+        assert!(res.is_ok(), "{res:?}");
+        let entries = with_var("SOLDEER_CACHE_DIR", Some(&cache), list_cache).unwrap();
+        assert_eq!(entries.len(), remaining);
+    }
+}
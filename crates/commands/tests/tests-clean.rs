@@ -60,7 +60,10 @@ libs = ["dependencies"]
     fs::write(dir.join(config_filename), contents).unwrap();
     let cmd: Command = Install::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -76,7 +79,10 @@ async fn test_clean_basic() {
     assert!(dir.join("dependencies").exists());
     let cmd: Command = Clean::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -91,7 +97,10 @@ async fn test_clean_foundry_config() {
     check_artifacts_exist(&dir);
     let cmd: Command = Clean::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -108,7 +117,10 @@ async fn test_clean_no_artifacts() {
     // Run clean on empty project (no dependencies folder or lock file)
     let cmd: Command = Clean::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -123,7 +135,10 @@ async fn test_clean_restores_with_install() {
 
     let cmd: Command = Clean::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -133,7 +148,10 @@ async fn test_clean_restores_with_install() {
 
     let cmd: Command = Install::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -167,7 +185,10 @@ async fn test_clean_with_complex_file_structure() {
 
     let cmd: Command = Clean::builder().build().into();
     let res: Result<(), soldeer_core::SoldeerError> = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -189,7 +210,10 @@ async fn test_clean_permission_error() {
 
         let cmd: Command = Clean::builder().build().into();
         let res: Result<(), soldeer_core::SoldeerError> = async_with_vars(
-            [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+            [
+                ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+                ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ],
             run(cmd, Verbosity::default()),
         )
         .await;
@@ -207,7 +231,10 @@ async fn test_clean_permission_error() {
         // On non-Unix systems, just run a successful clean
         let cmd: Command = Clean::builder().build().into();
         let res = async_with_vars(
-            [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+            [
+                ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+                ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ],
             run(cmd, Verbosity::default()),
         )
         .await;
@@ -233,7 +260,10 @@ solady = "0.0.238"
 
     let cmd: Command = Install::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -242,7 +272,10 @@ solady = "0.0.238"
 
     let cmd: Command = Clean::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -262,7 +295,10 @@ async fn test_clean_multiple_times() {
 
     let cmd: Command = Clean::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -270,7 +306,10 @@ async fn test_clean_multiple_times() {
 
     let cmd: Command = Clean::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -278,7 +317,10 @@ async fn test_clean_multiple_times() {
 
     let cmd: Command = Clean::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -2,7 +2,7 @@ use soldeer_commands::{Command, Verbosity, commands::init::Init, run};
 use soldeer_core::{
     config::{ConfigLocation, read_config_deps},
     lock::{SOLDEER_LOCK, read_lockfile},
-    registry::get_latest_version,
+    registry::{Registry, get_latest_version},
     utils::run_git_command,
 };
 use std::fs;
@@ -22,7 +22,10 @@ async fn test_init_clean() {
     let cmd: Command =
         Init::builder().clean(true).config_location(ConfigLocation::Soldeer).build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -54,7 +57,10 @@ async fn test_init_no_clean() {
     fs::write(dir.join("soldeer.toml"), "[dependencies]\n").unwrap();
     let cmd: Command = Init::builder().config_location(ConfigLocation::Soldeer).build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -92,7 +98,10 @@ remappings_generate = false
     let cmd: Command =
         Init::builder().clean(true).config_location(ConfigLocation::Soldeer).build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -114,7 +123,10 @@ async fn test_init_no_gitignore() {
     let cmd: Command =
         Init::builder().clean(true).config_location(ConfigLocation::Soldeer).build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -129,13 +141,16 @@ async fn test_init_select_foundry_location() {
     let cmd: Command =
         Init::builder().clean(true).config_location(ConfigLocation::Foundry).build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
     assert!(res.is_ok(), "{res:?}");
 
-    let forge_std = get_latest_version("forge-std").await.unwrap();
+    let forge_std = get_latest_version(&Registry::soldeer(), "forge-std", false).await.unwrap();
     let config_path = dir.join("foundry.toml");
     assert!(config_path.exists());
 
@@ -162,13 +177,16 @@ async fn test_init_select_soldeer_location() {
     let cmd: Command =
         Init::builder().clean(true).config_location(ConfigLocation::Soldeer).build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
     assert!(res.is_ok(), "{res:?}");
 
-    let forge_std = get_latest_version("forge-std").await.unwrap();
+    let forge_std = get_latest_version(&Registry::soldeer(), "forge-std", false).await.unwrap();
     let config_path = dir.join("soldeer.toml");
     assert!(config_path.exists());
 
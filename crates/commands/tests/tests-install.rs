@@ -5,9 +5,11 @@ use soldeer_core::{
     SoldeerError,
     config::{ConfigLocation, read_config_deps},
     download::download_file,
-    errors::InstallError,
+    errors::{InstallError, PolicyError, PolicyViolation, SigningError, WorkspaceError},
     lock::{SOLDEER_LOCK, read_lockfile},
+    manifest::MANIFEST_DIR,
     push::zip_file,
+    signing::SigningKey,
     utils::hash_file,
 };
 use std::{
@@ -65,6 +67,17 @@ recursive_deps = true"#,
     zip_file(&root, &files, "test").unwrap() // zip is inside the `monorepo` folder
 }
 
+fn create_zip_with_config(testdir: &Path, name: &str, dependencies: &str) -> PathBuf {
+    let root = testdir.join(format!("pkg_{name}"));
+    fs::create_dir(&root).unwrap();
+    let mut files = Vec::new();
+    files.push(root.join("soldeer.toml"));
+    fs::write(files.last().unwrap(), format!("[dependencies]\n{dependencies}\n")).unwrap();
+    files.push(root.join("Lib.sol"));
+    fs::write(files.last().unwrap(), format!("contract {name} {{}}")).unwrap();
+    zip_file(&root, &files, name).unwrap()
+}
+
 fn create_zip_with_foundry_lock(testdir: &Path, branch: Option<&str>) -> PathBuf {
     let root = testdir.join("foundry_lock_project");
     fs::create_dir(&root).unwrap();
@@ -128,7 +141,10 @@ async fn test_install_registry_any_version() {
     fs::write(dir.join("soldeer.toml"), "[dependencies]\n").unwrap();
     let cmd: Command = Install::builder().dependency("@openzeppelin-contracts~5").build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -142,7 +158,10 @@ async fn test_install_registry_wildcard() {
     fs::write(dir.join("soldeer.toml"), "[dependencies]\n").unwrap();
     let cmd: Command = Install::builder().dependency("solady~*").build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -157,7 +176,10 @@ async fn test_install_registry_specific_version() {
     let cmd: Command =
         Install::builder().dependency("@openzeppelin-contracts~4.9.5").build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -174,7 +196,10 @@ async fn test_install_custom_http() {
         .build()
         .into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -198,7 +223,10 @@ async fn test_install_git_main() {
         .build()
         .into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -223,7 +251,10 @@ async fn test_install_git_commit() {
         .build()
         .into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -248,7 +279,10 @@ async fn test_install_git_tag() {
         .build()
         .into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -273,7 +307,10 @@ async fn test_install_git_branch() {
         .build()
         .into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -293,7 +330,10 @@ async fn test_install_foundry_config() {
     fs::write(dir.join("foundry.toml"), "[dependencies]\n").unwrap();
     let cmd: Command = Install::builder().dependency("@openzeppelin-contracts~5").build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -315,7 +355,10 @@ remappings_location = "config"
     fs::write(dir.join("foundry.toml"), contents).unwrap();
     let cmd: Command = Install::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -335,7 +378,10 @@ async fn test_install_missing_no_lock() {
     fs::write(dir.join("soldeer.toml"), contents).unwrap();
     let cmd: Command = Install::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -360,7 +406,10 @@ integrity = "f3c628f3e9eae4db14fe14f9ab29e49a0107c47b8ee956e4cee57b616b493fc2"
     fs::write(dir.join(SOLDEER_LOCK), lock).unwrap();
     let cmd: Command = Install::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -403,7 +452,10 @@ integrity = "f3c628f3e9eae4db14fe14f9ab29e49a0107c47b8ee956e4cee57b616b493fc2"
     fs::write(dir.join(SOLDEER_LOCK), lock).unwrap();
     let cmd: Command = Install::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd.clone(), Verbosity::default()),
     )
     .await;
@@ -412,7 +464,10 @@ integrity = "f3c628f3e9eae4db14fe14f9ab29e49a0107c47b8ee956e4cee57b616b493fc2"
 
     // second install
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -464,6 +519,7 @@ integrity = "f3c628f3e9eae4db14fe14f9ab29e49a0107c47b8ee956e4cee57b616b493fc2"
         [
             ("SOLDEER_API_URL", Some(server.url().as_str())),
             ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
         ],
         run(cmd.clone(), Verbosity::default()),
     )
@@ -475,6 +531,7 @@ integrity = "f3c628f3e9eae4db14fe14f9ab29e49a0107c47b8ee956e4cee57b616b493fc2"
         [
             ("SOLDEER_API_URL", Some(server.url().as_str())),
             ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
         ],
         run(cmd, Verbosity::default()),
     )
@@ -491,7 +548,10 @@ async fn test_install_add_existing_reinstall() {
     fs::write(dir.join("soldeer.toml"), contents).unwrap();
     let cmd: Command = Install::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -505,7 +565,10 @@ async fn test_install_add_existing_reinstall() {
     let cmd: Command =
         Install::builder().dependency("@openzeppelin-contracts~5.0.2").build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -526,7 +589,10 @@ async fn test_install_clean() {
     fs::write(test_path.join("foo.txt"), "test").unwrap();
     let cmd: Command = Install::builder().clean(true).build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -543,7 +609,10 @@ foo = { version = "0.1.0", git = "https://github.com/foundry-rs/forge-template.g
     fs::write(dir.join("soldeer.toml"), contents).unwrap();
     let cmd: Command = Install::builder().recursive_deps(true).build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -565,16 +634,22 @@ foo = { version = "0.1.0", git = "https://github.com/beeb/forge-template.git" }
     fs::write(dir.join("soldeer.toml"), contents).unwrap();
     let cmd: Command = Install::builder().recursive_deps(true).build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
     assert!(res.is_ok(), "{res:?}");
     let dep_path = dir.join("dependencies").join("foo-0.1.0");
     assert!(dep_path.exists());
-    let sub_dirs_path = dep_path.join("dependencies");
-    assert!(sub_dirs_path.exists());
-    assert!(sub_dirs_path.join("forge-std-1.9.7").join("src").exists());
+    // transitive dependencies are installed in the project's dependencies folder
+    assert!(dir.join("dependencies").join("forge-std-1.9.7").join("src").exists());
+    let lock = read_lockfile(dir.join(SOLDEER_LOCK)).unwrap();
+    assert_eq!(lock.entries.len(), 2);
+    let foo = lock.entries.iter().find(|e| e.name() == "foo").unwrap();
+    assert_eq!(foo.dependencies(), &["forge-std".to_string()]);
 }
 
 #[tokio::test]
@@ -586,7 +661,10 @@ async fn test_install_recursive_deps_nested() {
     fs::write(dir.join("soldeer.toml"), contents).unwrap();
     let cmd: Command = Install::builder().recursive_deps(true).build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -636,14 +714,188 @@ integrity = "e629088e5b74df78f116a24c328a64fd002b4e42449607b6ca78f9afb799374d"
 
     let cmd: Command = Install::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd.clone(), Verbosity::default()),
     )
     .await;
     assert!(res.is_ok(), "{res:?}");
 
     // check that we recursively installed all deps
-    assert!(dir.join("dependencies/mylib-1.0.0/contracts/dependencies/forge-std-1.11.0").is_dir());
+    assert!(dir.join("dependencies/forge-std-1.11.0").is_dir());
+    assert!(!dir.join("dependencies/mylib-1.0.0/contracts/dependencies").exists());
+}
+
+#[tokio::test]
+async fn test_install_recursive_transitive_lock() {
+    let dir = testdir!();
+    let mut server = mockito::Server::new_async().await;
+    let zip_b = create_zip_with_config(&dir, "b", "");
+    let zip_a = create_zip_with_config(
+        &dir,
+        "a",
+        &format!(r#"b = {{ version = "1.0.0", url = "{}/b.zip" }}"#, server.url()),
+    );
+    server.mock("GET", "/a.zip").with_body_from_file(zip_a).create_async().await;
+    server.mock("GET", "/b.zip").with_body_from_file(zip_b).create_async().await;
+
+    let contents = format!(
+        r#"[dependencies]
+a = {{ version = "1.0.0", url = "{}/a.zip" }}
+
+[soldeer]
+recursive_deps = true
+"#,
+        server.url()
+    );
+    fs::write(dir.join("soldeer.toml"), contents).unwrap();
+
+    let cmd: Command = Install::builder().build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd.clone(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(dir.join("dependencies/a-1.0.0").is_dir());
+    assert!(dir.join("dependencies/b-1.0.0").is_dir());
+    let lock = read_lockfile(dir.join(SOLDEER_LOCK)).unwrap();
+    assert_eq!(lock.entries.len(), 2);
+    assert_eq!(lock.entries[0].name(), "a");
+    assert_eq!(lock.entries[0].dependencies(), &["b".to_string()]);
+    assert_eq!(lock.entries[1].name(), "b");
+    assert!(lock.entries[1].dependencies().is_empty());
+
+    // a second install with the lockfile yields the same lockfile
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    assert_eq!(read_lockfile(dir.join(SOLDEER_LOCK)).unwrap().raw, lock.raw);
+}
+
+fn revision_response(server_url: &str, name: &str, versions: &[&str]) -> String {
+    let data: Vec<_> = versions
+        .iter()
+        .map(|v| {
+            format!(
+                r#"{{"created_at":"2024-08-06T17:31:25.751079Z","deleted":false,"id":"660132e6-4902-4804-8c4b-7cae0a648054","internal_name":"{name}/{v}.zip","project_id":"37adefe5-9bc6-4777-aaf2-e56277d1f30b","url":"{server_url}/{name}-{v}.zip","version":"{v}"}}"#
+            )
+        })
+        .collect();
+    format!(r#"{{"data":[{}],"status":"success"}}"#, data.join(","))
+}
+
+#[tokio::test]
+async fn test_install_recursive_unify_versions() {
+    let dir = testdir!();
+    let mut server = mockito::Server::new_async().await;
+    let zip_a = create_zip_with_config(&dir, "a", r#"c = "^1.0""#);
+    let zip_b = create_zip_with_config(&dir, "b", r#"c = "<1.2""#);
+    let zip_c = create_zip_with_config(&dir, "c", "");
+    server.mock("GET", "/a.zip").with_body_from_file(zip_a).create_async().await;
+    server.mock("GET", "/b.zip").with_body_from_file(zip_b).create_async().await;
+    server.mock("GET", "/c-1.1.0.zip").with_body_from_file(zip_c).create_async().await;
+    server
+        .mock("GET", "/api/v1/revision")
+        .match_query(Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(revision_response(&server.url(), "c", &["2.0.0", "1.2.0", "1.1.0", "1.0.0"]))
+        .create_async()
+        .await;
+    // only the version which satisfies all requirements can be downloaded
+    server
+        .mock("GET", "/api/v1/revision-cli")
+        .match_query(Matcher::UrlEncoded("revision".into(), "1.1.0".into()))
+        .with_header("content-type", "application/json")
+        .with_body(revision_response(&server.url(), "c", &["1.1.0"]))
+        .create_async()
+        .await;
+
+    let contents = format!(
+        r#"[dependencies]
+a = {{ version = "1.0.0", url = "{0}/a.zip" }}
+b = {{ version = "1.0.0", url = "{0}/b.zip" }}
+
+[soldeer]
+recursive_deps = true
+"#,
+        server.url()
+    );
+    fs::write(dir.join("soldeer.toml"), contents).unwrap();
+
+    let cmd: Command = Install::builder().build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(dir.join("dependencies/c-1.1.0").is_dir());
+    let lock = read_lockfile(dir.join(SOLDEER_LOCK)).unwrap();
+    let names: Vec<_> = lock.entries.iter().map(|e| e.name()).collect();
+    assert_eq!(names, vec!["a", "b", "c"]);
+    assert_eq!(lock.entries[2].version(), "1.1.0");
+}
+
+#[tokio::test]
+async fn test_install_recursive_version_conflict() {
+    let dir = testdir!();
+    let mut server = mockito::Server::new_async().await;
+    let zip_a = create_zip_with_config(&dir, "a", r#"c = "^1.0""#);
+    let zip_b = create_zip_with_config(&dir, "b", r#"c = "^2.0""#);
+    server.mock("GET", "/a.zip").with_body_from_file(zip_a).create_async().await;
+    server.mock("GET", "/b.zip").with_body_from_file(zip_b).create_async().await;
+    server
+        .mock("GET", "/api/v1/revision")
+        .match_query(Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(revision_response(&server.url(), "c", &["2.0.0", "1.0.0"]))
+        .create_async()
+        .await;
+
+    let contents = format!(
+        r#"[dependencies]
+a = {{ version = "1.0.0", url = "{0}/a.zip" }}
+b = {{ version = "1.0.0", url = "{0}/b.zip" }}
+
+[soldeer]
+recursive_deps = true
+"#,
+        server.url()
+    );
+    fs::write(dir.join("soldeer.toml"), contents).unwrap();
+
+    let cmd: Command = Install::builder().build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    let err = res.unwrap_err();
+    assert!(
+        matches!(&err, SoldeerError::InstallError(InstallError::VersionConflict { .. })),
+        "{err:?}"
+    );
+    assert!(err.to_string().contains("a -> c~^1.0 vs b -> c~^2.0"), "{err}");
 }
 
 #[tokio::test]
@@ -678,7 +930,10 @@ integrity = "e629088e5b74df78f116a24c328a64fd002b4e42449607b6ca78f9afb799374d"
 
     let cmd: Command = Install::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd.clone(), Verbosity::default()),
     )
     .await;
@@ -701,7 +956,10 @@ async fn test_install_regenerate_remappings() {
         .build()
         .into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -734,7 +992,10 @@ remappings_regenerate = true
     fs::write(dir.join("foundry.toml"), contents).unwrap();
     let cmd: Command = Install::builder().dependency("forge-std~1.8.1").build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -779,7 +1040,10 @@ remappings_location = "config"
     fs::write(dir.join("foundry.toml"), contents).unwrap();
     let cmd: Command = Install::builder().dependency("forge-std~1.8.1").build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd.clone(), Verbosity::default()),
     )
     .await;
@@ -821,7 +1085,10 @@ remappings_regenerate = true
     fs::write(dir.join("foundry.toml"), contents).unwrap();
     let cmd: Command = Install::builder().dependency("forge-std~1.8.1").build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd.clone(), Verbosity::default()),
     )
     .await;
@@ -846,7 +1113,10 @@ libs = ["lib"]
         .build()
         .into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -871,7 +1141,10 @@ async fn test_install_new_soldeer_no_soldeer_toml() {
         .build()
         .into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -895,7 +1168,10 @@ async fn test_install_new_soldeer_no_dependency_tag() {
         .build()
         .into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -941,7 +1217,10 @@ integrity = "placeholder"
 
     let cmd: Command = Install::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd.clone(), Verbosity::default()),
     )
     .await;
@@ -994,7 +1273,10 @@ integrity = "placeholder"
 
     let cmd: Command = Install::builder().build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd.clone(), Verbosity::default()),
     )
     .await;
@@ -1014,3 +1296,935 @@ integrity = "placeholder"
     let current_rev = String::from_utf8_lossy(&output.stdout).trim().to_string();
     assert_eq!(current_rev, "c29afdd40a82db50a3d3709d324416be50050e5e",);
 }
+
+#[tokio::test]
+async fn test_install_locked_missing_lockfile() {
+    let dir = testdir!();
+    fs::write(
+        dir.join("soldeer.toml"),
+        r#"[dependencies]
+a = { version = "1.0.0", url = "https://example.com/a.zip" }
+"#,
+    )
+    .unwrap();
+    let cmd: Command = Install::builder().locked(true).build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(res, Err(SoldeerError::InstallError(InstallError::LockfileMissing))),
+        "{res:?}"
+    );
+    assert!(!dir.join(SOLDEER_LOCK).exists());
+}
+
+#[tokio::test]
+async fn test_install_locked_out_of_sync() {
+    let dir = testdir!();
+    let mut server = mockito::Server::new_async().await;
+    let zip_a = create_zip_with_config(&dir, "a", "");
+    server.mock("GET", "/a.zip").with_body_from_file(zip_a).create_async().await;
+    let contents = format!(
+        r#"[dependencies]
+a = {{ version = "1.0.0", url = "{}/a.zip" }}
+"#,
+        server.url()
+    );
+    fs::write(dir.join("soldeer.toml"), &contents).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    let lock_contents = fs::read_to_string(dir.join(SOLDEER_LOCK)).unwrap();
+
+    // in sync
+    let cmd: Command = Install::builder().locked(true).build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd.clone(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    // change the version of `a` and add a new dependency
+    let contents = format!(
+        r#"[dependencies]
+a = {{ version = "2.0.0", url = "{0}/a.zip" }}
+b = {{ version = "1.0.0", url = "{0}/b.zip" }}
+"#,
+        server.url()
+    );
+    fs::write(dir.join("soldeer.toml"), &contents).unwrap();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    let Err(SoldeerError::InstallError(InstallError::LockfileOutOfSync(drift))) = res else {
+        panic!("expected out of sync error, got {res:?}");
+    };
+    assert_eq!(drift.len(), 2, "{drift:?}");
+    assert!(drift[0].to_string().starts_with("a is locked at 1.0.0"), "{}", drift[0]);
+    assert_eq!(drift[1].to_string(), "b is missing from the lockfile");
+    // neither the lockfile nor the dependencies folder were modified
+    assert_eq!(fs::read_to_string(dir.join(SOLDEER_LOCK)).unwrap(), lock_contents);
+    let installed: Vec<_> = fs::read_dir(dir.join("dependencies"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|name| name != MANIFEST_DIR)
+        .collect();
+    assert_eq!(installed, vec!["a-1.0.0"]);
+}
+
+#[tokio::test]
+async fn test_install_frozen() {
+    let dir = testdir!();
+    let mut server = mockito::Server::new_async().await;
+    let zip_a = create_zip_with_config(&dir, "a", r#"b = "1.0.0""#);
+    let zip_b = create_zip_with_config(&dir, "b", "");
+    server.mock("GET", "/a.zip").with_body_from_file(zip_a).create_async().await;
+    server.mock("GET", "/b-1.0.0.zip").with_body_from_file(zip_b).create_async().await;
+    let registry = [
+        server
+            .mock("GET", "/api/v1/revision")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(revision_response(&server.url(), "b", &["1.0.0"]))
+            .create_async()
+            .await,
+        server
+            .mock("GET", "/api/v1/revision-cli")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(revision_response(&server.url(), "b", &["1.0.0"]))
+            .create_async()
+            .await,
+    ];
+    let contents = format!(
+        r#"[dependencies]
+a = {{ version = "1.0.0", url = "{}/a.zip" }}
+
+[soldeer]
+recursive_deps = true
+"#,
+        server.url()
+    );
+    fs::write(dir.join("soldeer.toml"), contents).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    let lock_contents = fs::read_to_string(dir.join(SOLDEER_LOCK)).unwrap();
+
+    // any request to the registry would now fail
+    for mock in registry {
+        mock.remove_async().await;
+    }
+    fs::remove_dir_all(dir.join("dependencies")).unwrap();
+    let cmd: Command = Install::builder().frozen(true).build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(dir.join("dependencies/a-1.0.0").is_dir());
+    assert!(dir.join("dependencies/b-1.0.0").is_dir());
+    assert_eq!(fs::read_to_string(dir.join(SOLDEER_LOCK)).unwrap(), lock_contents);
+}
+
+#[tokio::test]
+async fn test_install_offline() {
+    let dir = testdir!();
+    let cache = dir.join("cache");
+    let mut server = mockito::Server::new_async().await;
+    let zip_a = create_zip_with_config(&dir, "a", "");
+    let mocks = [
+        server.mock("GET", "/a-1.1.0.zip").with_body_from_file(zip_a).create_async().await,
+        server
+            .mock("GET", "/api/v1/revision")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(revision_response(&server.url(), "a", &["2.0.0", "1.1.0", "1.0.0"]))
+            .create_async()
+            .await,
+        server
+            .mock("GET", "/api/v1/revision-cli")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(revision_response(&server.url(), "a", &["1.1.0"]))
+            .create_async()
+            .await,
+    ];
+    fs::write(dir.join("soldeer.toml"), "[dependencies]\na = \"^1.0\"\n").unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+            ("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    let lock_contents = fs::read_to_string(dir.join(SOLDEER_LOCK)).unwrap();
+
+    // without network, the version is resolved from the cached revisions and the archive is found
+    // in the cache even without a lockfile
+    for mock in mocks {
+        mock.remove_async().await;
+    }
+    fs::remove_dir_all(dir.join("dependencies")).unwrap();
+    fs::remove_file(dir.join(SOLDEER_LOCK)).unwrap();
+    let cmd: Command = Install::builder().offline(true).build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+            ("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(dir.join("dependencies/a-1.1.0/Lib.sol").exists());
+    assert_eq!(fs::read_to_string(dir.join(SOLDEER_LOCK)).unwrap(), lock_contents);
+}
+
+#[tokio::test]
+async fn test_install_offline_missing() {
+    let dir = testdir!();
+    let cache = dir.join("cache");
+    fs::write(
+        dir.join("soldeer.toml"),
+        r#"[dependencies]
+a = { version = "1.0.0", url = "https://example.com/a.zip" }
+"#,
+    )
+    .unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().as_ref())),
+            ("SOLDEER_OFFLINE", Some("1")),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(res, Err(SoldeerError::InstallError(InstallError::Offline(ref name))) if name == "a-1.0.0"),
+        "{res:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_install_link_modes() {
+    for link_mode in ["hardlink", "symlink"] {
+        let dir = testdir!().join(link_mode);
+        fs::create_dir_all(&dir).unwrap();
+        let cache = testdir!().join(format!("cache_{link_mode}"));
+        let mut server = mockito::Server::new_async().await;
+        let zip_a = create_zip_with_config(&dir, "a", "");
+        let mock = server
+            .mock("GET", "/a-1.0.0.zip")
+            .with_body_from_file(zip_a)
+            .expect(1)
+            .create_async()
+            .await;
+        fs::write(
+            dir.join("soldeer.toml"),
+            format!(
+                "[soldeer]\nlink_mode = \"{link_mode}\"\n\n[dependencies]\na = {{ version = \"1.0.0\", url = \"{}/a-1.0.0.zip\" }}\n",
+                server.url()
+            ),
+        )
+        .unwrap();
+        let install = || async {
+            let cmd: Command = Install::default().into();
+            async_with_vars(
+                [
+                    ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+                    ("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().as_ref())),
+                ],
+                run(cmd, Verbosity::default()),
+            )
+            .await
+        };
+        let res = install().await;
+        assert!(res.is_ok(), "{res:?}");
+        let path = dir.join("dependencies/a-1.0.0");
+        assert_eq!(fs::read_to_string(path.join("Lib.sol")).unwrap(), "contract a {}");
+        assert_eq!(fs::symlink_metadata(&path).unwrap().is_symlink(), link_mode == "symlink");
+        assert!(cache.join("store").is_dir());
+        assert_eq!(
+            fs::read_to_string(dir.join("remappings.txt")).unwrap(),
+            "a-1.0.0/=dependencies/a-1.0.0/\n"
+        );
+
+        // the integrity check of the linked folder succeeds, nothing is downloaded again
+        let res = install().await;
+        assert!(res.is_ok(), "{res:?}");
+        mock.assert_async().await;
+
+        // a modified file (which also modifies the store) is restored from the cached archive
+        fs::remove_file(path.join("Lib.sol")).unwrap();
+        fs::write(path.join("Lib.sol"), "modified").unwrap();
+        let res = install().await;
+        assert!(res.is_ok(), "{res:?}");
+        assert_eq!(fs::read_to_string(path.join("Lib.sol")).unwrap(), "contract a {}");
+        mock.assert_async().await;
+
+        // a file modified in place corrupts the store entry, which is replaced before linking
+        fs::write(path.join("Lib.sol"), "modified").unwrap();
+        let res = install().await;
+        assert!(res.is_ok(), "{res:?}");
+        assert_eq!(fs::read_to_string(path.join("Lib.sol")).unwrap(), "contract a {}");
+        mock.assert_async().await;
+    }
+}
+
+#[tokio::test]
+async fn test_install_workspace() {
+    let dir = testdir!();
+    let mut server = mockito::Server::new_async().await;
+    let zip_a = create_zip_with_config(&dir, "a", "");
+    let mock = server
+        .mock("GET", "/a-1.0.0.zip")
+        .with_body_from_file(zip_a)
+        .expect(1)
+        .create_async()
+        .await;
+    fs::write(dir.join("soldeer.toml"), "[workspace]\nmembers = [\"packages/*\"]\n").unwrap();
+    let dep_a = format!("a = {{ version = \"1.0.0\", url = \"{}/a-1.0.0.zip\" }}", server.url());
+    for (name, dependencies) in [
+        ("app", format!("{dep_a}\nlib = {{ version = \"1.0.0\", path = \"../lib\" }}")),
+        ("lib", dep_a),
+    ] {
+        let path = dir.join("packages").join(name);
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("soldeer.toml"), format!("[dependencies]\n{dependencies}\n")).unwrap();
+    }
+
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    mock.assert_async().await;
+    assert!(dir.join("dependencies/a-1.0.0/Lib.sol").is_file());
+    assert!(!dir.join("packages/app/dependencies").exists());
+    let lock = read_lockfile(dir.join(SOLDEER_LOCK)).unwrap();
+    assert_eq!(lock.entries.len(), 2);
+    // the path of the member is relative to the workspace root
+    assert_eq!(lock.entries.iter().find_map(|e| e.as_path()).unwrap().path, "packages/lib");
+    assert_eq!(
+        fs::read_to_string(dir.join("packages/app/remappings.txt")).unwrap(),
+        "a-1.0.0/=../../dependencies/a-1.0.0/\nlib-1.0.0/=../../dependencies/lib-1.0.0/\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("packages/lib/remappings.txt")).unwrap(),
+        "a-1.0.0/=../../dependencies/a-1.0.0/\n"
+    );
+    assert!(!dir.join("remappings.txt").exists());
+
+    // installing from a member installs the whole workspace
+    fs::remove_dir_all(dir.join("dependencies")).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.join("packages/lib").to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join("cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(dir.join("dependencies/a-1.0.0/Lib.sol").is_file());
+}
+
+#[tokio::test]
+async fn test_install_workspace_conflict() {
+    let dir = testdir!();
+    fs::write(dir.join("soldeer.toml"), "[workspace]\nmembers = [\"packages/*\"]\n").unwrap();
+    for (name, version) in [("app", "1.9.2"), ("lib", "1.9.3")] {
+        let path = dir.join("packages").join(name);
+        fs::create_dir_all(&path).unwrap();
+        fs::write(
+            path.join("soldeer.toml"),
+            format!("[dependencies]\nforge-std = \"{version}\"\n"),
+        )
+        .unwrap();
+    }
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(res, Err(SoldeerError::WorkspaceError(WorkspaceError::ConflictingRequirements { ref dependency, .. })) if dependency == "forge-std"),
+        "{res:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_install_path_dependency() {
+    let dir = testdir!();
+    let project = dir.join("project");
+    let lib = dir.join("my-lib");
+    fs::create_dir_all(&project).unwrap();
+    fs::create_dir_all(lib.join("src")).unwrap();
+    fs::write(lib.join("src/Lib.sol"), "contract Lib {}").unwrap();
+    fs::write(project.join("soldeer.toml"), "[dependencies]\n").unwrap();
+
+    let cmd: Command =
+        Install::builder().dependency("my-lib~1.0.0").path("../my-lib").build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(project.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    assert_eq!(
+        fs::read_to_string(project.join("soldeer.toml")).unwrap(),
+        "[dependencies]\nmy-lib = { version = \"1.0.0\", path = \"../my-lib\" }\n"
+    );
+    let path = project.join("dependencies/my-lib-1.0.0");
+    assert!(fs::symlink_metadata(&path).unwrap().is_symlink());
+    assert!(path.join("src/Lib.sol").is_file());
+    let lock = read_lockfile(project.join(SOLDEER_LOCK)).unwrap();
+    assert_eq!(lock.entries.first().unwrap().as_path().unwrap().path, "../my-lib");
+    assert_eq!(
+        fs::read_to_string(project.join("remappings.txt")).unwrap(),
+        "my-lib-1.0.0/=dependencies/my-lib-1.0.0/\n"
+    );
+
+    // changes to the folder are visible in the project
+    fs::write(lib.join("src/Other.sol"), "contract Other {}").unwrap();
+    assert!(path.join("src/Other.sol").is_file());
+
+    // a missing folder is reported
+    fs::remove_dir_all(&path).unwrap();
+    fs::rename(&lib, dir.join("moved")).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(project.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(res, Err(SoldeerError::InstallError(InstallError::PathNotFound { .. }))),
+        "{res:?}"
+    );
+    assert!(dir.join("moved/src/Lib.sol").is_file());
+}
+
+fn git(args: &[&str], dir: &Path) -> String {
+    let output = std::process::Command::new("git")
+        .args(["-c", "user.name=soldeer", "-c", "user.email=soldeer@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+#[tokio::test]
+async fn test_install_git_subdir() {
+    let dir = testdir!();
+    let repo = dir.join("monorepo");
+    fs::create_dir_all(repo.join("packages/core/src")).unwrap();
+    fs::create_dir_all(repo.join("packages/tests")).unwrap();
+    fs::write(repo.join("packages/core/src/Core.sol"), "contract Core {}").unwrap();
+    fs::write(repo.join("packages/tests/Big.t.sol"), "contract BigTest {}").unwrap();
+    git(&["init"], &repo);
+    git(&["add", "."], &repo);
+    git(&["commit", "-m", "first"], &repo);
+    let rev = git(&["rev-parse", "HEAD"], &repo);
+
+    let project = dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("soldeer.toml"), "[dependencies]\n").unwrap();
+    let install = |cmd: Command| {
+        async_with_vars(
+            [
+                ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().to_string())),
+                ("SOLDEER_CACHE_DIR", Some(dir.join("cache").to_string_lossy().to_string())),
+            ],
+            run(cmd, Verbosity::default()),
+        )
+    };
+
+    let cmd: Command = Install::builder()
+        .dependency("core~1.0.0")
+        .git_url(repo.to_string_lossy().replace('\\', "/"))
+        .subdir("packages/core")
+        .build()
+        .into();
+    let res = install(cmd).await;
+    assert!(res.is_ok(), "{res:?}");
+    let path = project.join("dependencies/core-1.0.0");
+    assert!(path.join("packages/core/src/Core.sol").is_file());
+    assert!(!path.join("packages/tests").exists());
+    let (deps, _) = read_config_deps(project.join("soldeer.toml")).unwrap();
+    assert_eq!(deps.first().unwrap().subdir(), Some("packages/core"));
+    let lock = read_lockfile(project.join(SOLDEER_LOCK)).unwrap();
+    let lock = lock.entries.first().unwrap().as_git().unwrap();
+    assert_eq!(lock.rev, rev);
+    assert_eq!(lock.subdir, Some("packages/core".to_string()));
+    assert_eq!(
+        fs::read_to_string(project.join("remappings.txt")).unwrap(),
+        "core-1.0.0/=dependencies/core-1.0.0/packages/core/\n"
+    );
+
+    // a full checkout doesn't match the lockfile and is replaced by the sparse checkout
+    fs::remove_dir_all(&path).unwrap();
+    git(&["clone", repo.to_string_lossy().as_ref(), path.to_string_lossy().as_ref()], &project);
+    let res = install(Install::default().into()).await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(path.join("packages/core/src/Core.sol").is_file());
+    assert!(!path.join("packages/tests").exists());
+    assert_eq!(
+        fs::read_to_string(project.join("remappings.txt")).unwrap(),
+        "core-1.0.0/=dependencies/core-1.0.0/packages/core/\n"
+    );
+}
+
+#[tokio::test]
+async fn test_install_http_tarball() {
+    let dir = testdir!();
+    // a tarball like the ones generated by GitHub, with a top-level folder
+    let mut builder = tar::Builder::new(Vec::new());
+    for (path, contents) in
+        [("lib-v1.0.0/src/Lib.sol", "contract Lib {}"), ("lib-v1.0.0/README.md", "# lib")]
+    {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, contents.as_bytes()).unwrap();
+    }
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    std::io::Write::write_all(&mut encoder, &builder.into_inner().unwrap()).unwrap();
+    let tarball = encoder.finish().unwrap();
+    let archive = dir.join("lib.tar.gz");
+    fs::write(&archive, &tarball).unwrap();
+    let checksum = hash_file(&archive).unwrap();
+
+    // the URL has no extension, the format is detected from the contents
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/archive/v1.0.0")
+        .with_header("content-type", "application/octet-stream")
+        .with_body(tarball)
+        .create_async()
+        .await;
+    fs::write(
+        dir.join("soldeer.toml"),
+        format!(
+            "[dependencies]\nlib = {{ version = \"1.0.0\", url = \"{}/archive/v1.0.0\" }}\n",
+            server.url()
+        ),
+    )
+    .unwrap();
+
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().to_string())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join("cache").to_string_lossy().to_string())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    let path = dir.join("dependencies/lib-1.0.0");
+    assert_eq!(fs::read_to_string(path.join("src/Lib.sol")).unwrap(), "contract Lib {}");
+    assert!(path.join("README.md").is_file());
+    let lock = read_lockfile(dir.join(SOLDEER_LOCK)).unwrap();
+    let lock = lock.entries.first().unwrap().as_http().unwrap();
+    assert_eq!(lock.checksum, checksum.to_string());
+    assert!(dir.join(format!("cache/{checksum}.tar.gz")).is_file());
+}
+
+#[tokio::test]
+async fn test_install_signed() {
+    let dir = testdir!();
+    let (key, _) = SigningKey::load_or_generate(dir.join("signing_key")).unwrap();
+    let zip = create_zip_with_config(&dir, "a", "");
+    let checksum = hash_file(&zip).unwrap();
+    let signature = key.sign(&checksum.0).unwrap();
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/a-1.0.0.zip").with_body_from_file(zip).create_async().await;
+    server
+        .mock("GET", "/api/v1/revision")
+        .match_query(Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(revision_response(&server.url(), "a", &["1.0.0"]))
+        .create_async()
+        .await;
+    let revision = server
+        .mock("GET", "/api/v1/revision-cli")
+        .match_query(Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(revision_response(&server.url(), "a", &["1.0.0"]).replace(
+            r#""version":"#,
+            &format!(
+                r#""public_key":"{}","signature":"{}","version":"#,
+                signature.public_key, signature.signature
+            ),
+        ))
+        .create_async()
+        .await;
+    fs::write(
+        dir.join("soldeer.toml"),
+        format!(
+            "[dependencies]\na = \"1.0.0\"\n\n[trust]\nrequire_signatures = true\nkeys = [\"{}\"]\n",
+            key.public_key()
+        ),
+    )
+    .unwrap();
+
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join("cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    let lock = read_lockfile(dir.join(SOLDEER_LOCK)).unwrap();
+    let lock = lock.entries.first().unwrap().as_http().unwrap();
+    assert_eq!(lock.signature.as_ref(), Some(&signature));
+
+    // the same package without a signature is rejected
+    revision.remove_async().await;
+    server
+        .mock("GET", "/api/v1/revision-cli")
+        .match_query(Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(revision_response(&server.url(), "a", &["1.0.0"]))
+        .create_async()
+        .await;
+    fs::remove_dir_all(dir.join("dependencies")).unwrap();
+    fs::remove_file(dir.join(SOLDEER_LOCK)).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join("cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(
+            res,
+            Err(SoldeerError::InstallError(InstallError::SigningError(SigningError::Unsigned(_))))
+        ),
+        "{res:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_install_signed_path_dependency() {
+    let dir = testdir!();
+    let project = dir.join("project");
+    let lib = dir.join("my-lib");
+    fs::create_dir_all(&project).unwrap();
+    fs::create_dir_all(lib.join("src")).unwrap();
+    fs::write(lib.join("src/Lib.sol"), "contract Lib {}").unwrap();
+    fs::write(
+        project.join("soldeer.toml"),
+        "[dependencies]\nmy-lib = { version = \"1.0.0\", path = \"../my-lib\" }\n\n[trust]\nrequire_signatures = true\n",
+    )
+    .unwrap();
+
+    // path dependencies can't be signed, they are installed with a warning
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(project.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(project.join("dependencies/my-lib-1.0.0/src/Lib.sol").is_file());
+}
+
+#[tokio::test]
+async fn test_install_policy() {
+    let dir = testdir!();
+    let contents = r#"[dependencies]
+a = { version = "1.0.0", url = "https://example.com/a.zip" }
+b = { version = "1.0.0", git = "https://gitlab.com/org/b.git", branch = "main" }
+c = "1.0.0"
+
+[soldeer.policy]
+allowed_url_hosts = []
+allowed_git_hosts = ["github.com"]
+require_git_rev = true
+banned = ["c"]
+"#;
+    fs::write(dir.join("soldeer.toml"), contents).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    // all violations are reported before anything is downloaded
+    let Err(SoldeerError::PolicyError(PolicyError::Violations(violations))) = res else {
+        panic!("{res:?}");
+    };
+    assert!(matches!(
+        &violations[..],
+        [
+            PolicyViolation::UrlHostNotAllowed { .. },
+            PolicyViolation::GitHostNotAllowed { .. },
+            PolicyViolation::GitNotPinned { .. },
+            PolicyViolation::Banned { .. }
+        ]
+    ));
+    assert!(!dir.join("dependencies/c-1.0.0").exists());
+
+    // versions from the registry must be old enough
+    let mut server = mockito::Server::new_async().await;
+    let zip = create_zip_with_config(&dir, "c", "");
+    server.mock("GET", "/c-1.0.0.zip").with_body_from_file(zip).create_async().await;
+    server
+        .mock("GET", "/api/v1/revision")
+        .match_query(Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(revision_response(&server.url(), "c", &["1.0.0"]))
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/api/v1/revision-cli")
+        .match_query(Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(revision_response(&server.url(), "c", &["1.0.0"]))
+        .create_async()
+        .await;
+    let contents =
+        "[dependencies]\nc = \"1.0.0\"\n\n[soldeer.policy]\nmin_package_age_days = 100000\n";
+    fs::write(dir.join("soldeer.toml"), contents).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(
+            &res,
+            Err(SoldeerError::InstallError(InstallError::PolicyError(PolicyError::Violations(v))))
+                if matches!(&v[..], [PolicyViolation::TooRecent { .. }])
+        ),
+        "{res:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_install_license_policy() {
+    let dir = testdir!();
+    let mut server = mockito::Server::new_async().await;
+    let zip = create_zip_with_config(&dir, "c", "");
+    server.mock("GET", "/c-1.0.0.zip").with_body_from_file(zip).create_async().await;
+    let root = dir.join("pkg_d");
+    fs::create_dir(&root).unwrap();
+    let files = vec![root.join("LICENSE"), root.join("Lib.sol")];
+    fs::write(&files[0], "MIT License\n\nPermission is hereby granted, free of charge").unwrap();
+    fs::write(&files[1], "// SPDX-License-Identifier: BUSL-1.1\ncontract d {}").unwrap();
+    let zip = zip_file(&root, &files, "d").unwrap();
+    server.mock("GET", "/d-1.0.0.zip").with_body_from_file(zip).create_async().await;
+    for name in ["c", "d"] {
+        for path in ["/api/v1/revision", "/api/v1/revision-cli"] {
+            server
+                .mock("GET", path)
+                .match_query(Matcher::UrlEncoded("project_name".into(), name.into()))
+                .with_header("content-type", "application/json")
+                .with_body(revision_response(&server.url(), name, &["1.0.0"]))
+                .create_async()
+                .await;
+        }
+    }
+
+    // packages without license are rejected if licenses are allowed explicitly
+    let contents =
+        "[dependencies]\nc = \"1.0.0\"\n\n[soldeer.policy]\nallowed_licenses = [\"MIT\"]\n";
+    fs::write(dir.join("soldeer.toml"), contents).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(
+            &res,
+            Err(SoldeerError::InstallError(InstallError::PolicyError(PolicyError::Violations(v))))
+                if matches!(&v[..], [PolicyViolation::UnknownLicense { .. }])
+        ),
+        "{res:?}"
+    );
+    assert!(!dir.join("dependencies/c-1.0.0").exists());
+
+    // all the licenses found in the package must be allowed
+    let contents =
+        "[dependencies]\nd = \"1.0.0\"\n\n[soldeer.policy]\ndenied_licenses = [\"BUSL-1.1\"]\n";
+    fs::write(dir.join("soldeer.toml"), contents).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(
+            &res,
+            Err(SoldeerError::InstallError(InstallError::PolicyError(PolicyError::Violations(v))))
+                if matches!(&v[..], [PolicyViolation::LicenseDenied { license, .. }] if license == "BUSL-1.1")
+        ),
+        "{res:?}"
+    );
+    assert!(!dir.join("dependencies/d-1.0.0").exists());
+
+    let contents = "[dependencies]\nd = \"1.0.0\"\n\n[soldeer.policy]\nallowed_licenses = [\"MIT\", \"BUSL-1.1\"]\n";
+    fs::write(dir.join("soldeer.toml"), contents).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(dir.join("dependencies/d-1.0.0/Lib.sol").exists());
+
+    // dependencies which are already installed are checked too, but their folder is kept
+    let contents =
+        "[dependencies]\nd = \"1.0.0\"\n\n[soldeer.policy]\ndenied_licenses = [\"BUSL-1.1\"]\n";
+    fs::write(dir.join("soldeer.toml"), contents).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(
+            &res,
+            Err(SoldeerError::InstallError(InstallError::PolicyError(PolicyError::Violations(v))))
+                if matches!(&v[..], [PolicyViolation::LicenseDenied { license, .. }] if license == "BUSL-1.1")
+        ),
+        "{res:?}"
+    );
+    assert!(dir.join("dependencies/d-1.0.0/Lib.sol").exists());
+}
+
+#[tokio::test]
+async fn test_install_license_policy_path() {
+    let dir = testdir!();
+    let project = dir.join("project");
+    fs::create_dir(&project).unwrap();
+    let lib = dir.join("my-lib");
+    fs::create_dir(&lib).unwrap();
+    fs::write(lib.join("Lib.sol"), "// SPDX-License-Identifier: BUSL-1.1\ncontract Lib {}")
+        .unwrap();
+    let contents = "[dependencies]\nmy-lib = { version = \"1.0.0\", path = \"../my-lib\" }\n\n[soldeer.policy]\ndenied_licenses = [\"BUSL-1.1\"]\n";
+    fs::write(project.join("soldeer.toml"), contents).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(
+            &res,
+            Err(SoldeerError::InstallError(InstallError::PolicyError(PolicyError::Violations(v))))
+                if matches!(&v[..], [PolicyViolation::LicenseDenied { license, .. }] if license == "BUSL-1.1")
+        ),
+        "{res:?}"
+    );
+    assert!(fs::symlink_metadata(project.join("dependencies/my-lib-1.0.0")).is_err());
+    // the source folder of the path dependency is left untouched
+    assert!(lib.join("Lib.sol").exists());
+}
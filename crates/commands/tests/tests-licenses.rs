@@ -0,0 +1,85 @@
+#![allow(clippy::unwrap_used)]
+use common::setup_project;
+use soldeer_commands::{Command, Verbosity, commands::licenses::Licenses, run};
+use soldeer_core::{
+    SoldeerError,
+    errors::{PolicyError, PolicyViolation},
+};
+use std::{fs, path::Path};
+use temp_env::async_with_vars;
+use testdir::testdir;
+
+mod common;
+
+fn setup_installed_project(dir: &Path, policy: &str) {
+    setup_project(dir, &format!("[dependencies]\n\n[soldeer.policy]\n{policy}"));
+    let mylib = dir.join("dependencies/mylib-1.1.0");
+    fs::create_dir_all(mylib.join("src")).unwrap();
+    fs::write(mylib.join("LICENSE"), "MIT License\n\nPermission is hereby granted, free of charge")
+        .unwrap();
+    fs::write(mylib.join("src/Lib.sol"), "// SPDX-License-Identifier: MIT\ncontract Lib {}")
+        .unwrap();
+    let other = dir.join("dependencies/other-2.0.0");
+    fs::create_dir_all(other.join("src")).unwrap();
+    fs::write(other.join("src/A.sol"), "// SPDX-License-Identifier: MIT\ncontract A {}").unwrap();
+    fs::write(other.join("src/B.sol"), "// SPDX-License-Identifier: GPL-3.0-only\ncontract B {}")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_licenses() {
+    let dir = testdir!();
+    setup_installed_project(&dir, "");
+    let cmd: Command = Licenses::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().to_string())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().to_string())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+}
+
+#[tokio::test]
+async fn test_licenses_policy() {
+    let dir = testdir!();
+    setup_installed_project(&dir, "allowed_licenses = [\"MIT\", \"Apache-2.0\"]\n");
+    let cmd: Command = Licenses::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().to_string())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().to_string())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    // dependencies which are not installed are not reported
+    let Err(SoldeerError::PolicyError(PolicyError::Violations(violations))) = res else {
+        panic!("{res:?}");
+    };
+    assert_eq!(
+        violations,
+        vec![PolicyViolation::LicenseNotAllowed {
+            dependency: "other~2.0.0".to_string(),
+            license: "GPL-3.0-only".to_string()
+        }]
+    );
+
+    setup_installed_project(&dir, "denied_licenses = [\"GPL-3.0-only\", \"MIT\"]\n");
+    let cmd: Command = Licenses::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().to_string())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().to_string())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    let Err(SoldeerError::PolicyError(PolicyError::Violations(violations))) = res else {
+        panic!("{res:?}");
+    };
+    assert_eq!(violations.len(), 3);
+    assert!(violations.iter().all(|v| matches!(v, PolicyViolation::LicenseDenied { .. })));
+}
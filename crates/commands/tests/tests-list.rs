@@ -0,0 +1,44 @@
+#![allow(clippy::unwrap_used)]
+use soldeer_commands::{
+    Command, Verbosity,
+    commands::{install::Install, list::List},
+    run,
+};
+use std::fs;
+use temp_env::async_with_vars;
+use testdir::testdir;
+
+#[tokio::test]
+async fn test_list() {
+    let dir = testdir!();
+    let project = dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::create_dir_all(dir.join("my-lib/src")).unwrap();
+    fs::write(dir.join("my-lib/src/Lib.sol"), "contract Lib {}").unwrap();
+    let config = "[dependencies]\nmy-lib = { version = \"1.0.0\", path = \"../my-lib\" }\n";
+    fs::write(project.join("soldeer.toml"), config).unwrap();
+
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(project.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    // dependencies which are not installed are listed too
+    fs::write(project.join("soldeer.toml"), format!("{config}forge-std = \"1.9.2\"\n")).unwrap();
+    let cmd: Command = List::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(project.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+}
@@ -0,0 +1,122 @@
+use mockito::Matcher;
+use soldeer_commands::{
+    Command, Verbosity,
+    commands::{install::Install, outdated::Outdated},
+    run,
+};
+use soldeer_core::{SoldeerError, errors::UpdateError, push::zip_file};
+use std::{fs, path::PathBuf};
+use temp_env::async_with_vars;
+use testdir::testdir;
+
+#[allow(clippy::unwrap_used)]
+async fn setup(config: &str) -> (PathBuf, mockito::ServerGuard) {
+    let dir = testdir!();
+    let pkg = dir.join("pkg");
+    fs::create_dir(&pkg).unwrap();
+    fs::write(pkg.join("Lib.sol"), "contract Lib {}").unwrap();
+    let zip_path = zip_file(&pkg, &[pkg.join("Lib.sol")], "pkg").unwrap();
+
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/pkg.zip").with_body_from_file(zip_path).create_async().await;
+    let url = server.url();
+    let revision = |version: &str| {
+        format!(
+            r#"{{"created_at":"2024-08-06T17:31:25.751079Z","deleted":false,"id":"660132e6-4902-4804-8c4b-7cae0a648054","internal_name":"pkg.zip","project_id":"37adefe5-9bc6-4777-aaf2-e56277d1f30b","url":"{url}/pkg.zip","version":"{version}"}}"#
+        )
+    };
+    let data = format!(
+        r#"{{"data":[{},{},{}],"status":"success"}}"#,
+        revision("2.0.0"),
+        revision("1.1.0"),
+        revision("1.0.0")
+    );
+    server
+        .mock("GET", "/api/v1/revision")
+        .match_query(Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(data)
+        .create_async()
+        .await;
+    let data = format!(r#"{{"data":[{}],"status":"success"}}"#, revision("1.0.0"));
+    server
+        .mock("GET", "/api/v1/revision-cli")
+        .match_query(Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(data)
+        .create_async()
+        .await;
+
+    fs::write(dir.join("soldeer.toml"), config).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    (dir, server)
+}
+
+#[tokio::test]
+async fn test_outdated() {
+    let (dir, server) = setup(
+        r#"[dependencies]
+a = "1.0.0"
+b = "1.0.0"
+"#,
+    )
+    .await;
+    // allow newer versions for `a` only, `b` is still outdated compared to the latest version
+    fs::write(
+        dir.join("soldeer.toml"),
+        r#"[dependencies]
+a = "^1.0"
+b = "1.0.0"
+"#,
+    )
+    .unwrap();
+    let cmd: Command = Outdated::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(
+            res,
+            Err(SoldeerError::UpdateError(UpdateError::OutdatedDependencies(ref names)))
+                if names == &["a".to_string(), "b".to_string()]
+        ),
+        "{res:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_outdated_up_to_date() {
+    let (dir, server) = setup(
+        r#"[dependencies]
+a = "2.0.0"
+"#,
+    )
+    .await;
+    let cmd: Command = Outdated::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+}
@@ -14,6 +14,11 @@ fn setup_project(dotfile: bool) -> (PathBuf, PathBuf) {
     let project_path = dir.join("mypkg");
     fs::create_dir(&project_path).unwrap();
     fs::write(project_path.join("foundry.toml"), "[dependencies]\n").unwrap();
+    fs::write(
+        project_path.join("LICENSE"),
+        "MIT License\n\nPermission is hereby granted, free of charge",
+    )
+    .unwrap();
     if dotfile {
         fs::write(project_path.join(".env"), "super-secret-stuff").unwrap();
     }
@@ -81,7 +86,8 @@ async fn test_push_other_dir_success() {
     fs::write(&login_file, "jwt_token_example").unwrap();
     let project_path = dir.join("mypkg");
     fs::create_dir(&project_path).unwrap();
-    fs::write(project_path.join("test.sol"), "contract Foo {}\n").unwrap();
+    fs::write(project_path.join("test.sol"), "// SPDX-License-Identifier: MIT\ncontract Foo {}\n")
+        .unwrap();
 
     let (server, mock) = mock_api_server(None).await;
 
@@ -265,3 +271,44 @@ async fn test_push_skip_warnings() {
     assert!(res.is_ok(), "{res:?}");
     mock.expect(1);
 }
+
+#[tokio::test]
+async fn test_push_no_license() {
+    let (login_file, project_path) = setup_project(false);
+    fs::remove_file(project_path.join("LICENSE")).unwrap();
+    fs::write(project_path.join("test.sol"), "contract Foo {}\n").unwrap();
+
+    let (server, mock) = mock_api_server(None).await;
+
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project_path.to_string_lossy().to_string())),
+            ("SOLDEER_API_URL", Some(server.url())),
+            ("SOLDEER_LOGIN_FILE", Some(login_file.to_string_lossy().to_string())),
+        ],
+        run(
+            Push::builder().dependency("mypkg~0.1.0").path(&project_path).build().into(),
+            Verbosity::default(),
+        ),
+    )
+    .await;
+    assert!(matches!(res, Err(SoldeerError::PublishError(PublishError::NoLicense))), "{res:?}");
+    mock.expect(0);
+
+    // a license file which is ignored doesn't count
+    fs::write(project_path.join("LICENSE"), "MIT").unwrap();
+    fs::write(project_path.join(".soldeerignore"), "LICENSE\n").unwrap();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project_path.to_string_lossy().to_string())),
+            ("SOLDEER_API_URL", Some(server.url())),
+            ("SOLDEER_LOGIN_FILE", Some(login_file.to_string_lossy().to_string())),
+        ],
+        run(
+            Push::builder().dependency("mypkg~0.1.0").path(&project_path).build().into(),
+            Verbosity::default(),
+        ),
+    )
+    .await;
+    assert!(matches!(res, Err(SoldeerError::PublishError(PublishError::NoLicense))), "{res:?}");
+}
@@ -0,0 +1,84 @@
+#![allow(clippy::unwrap_used)]
+use common::setup_project;
+use mockito::Matcher;
+use soldeer_commands::{
+    Command, Verbosity,
+    commands::sbom::{Sbom, SbomFormat},
+    run,
+};
+use std::{fs, path::Path};
+use temp_env::async_with_vars;
+use testdir::testdir;
+
+mod common;
+
+const CONFIG: &str = r#"[dependencies]
+"@scope-lib" = "1.2.0"
+mylib = { version = "1.1.0", url = "https://example.com/mylib.zip" }
+repo = { version = "1.0.0", git = "https://github.com/org/repo.git", rev = "abcdef0123456789abcdef0123456789abcdef01" }
+"#;
+
+fn setup_installed_project(dir: &Path) {
+    setup_project(dir, CONFIG);
+    let deps = dir.join("dependencies");
+    fs::create_dir_all(deps.join("mylib-1.1.0")).unwrap();
+    fs::write(
+        deps.join("mylib-1.1.0").join("LICENSE"),
+        "MIT License\n\nPermission is hereby granted, free of charge, to any person",
+    )
+    .unwrap();
+    fs::create_dir_all(deps.join("repo-1.0.0")).unwrap();
+}
+
+#[tokio::test]
+async fn test_sbom() {
+    let dir = testdir!();
+    setup_installed_project(&dir);
+    let mut server = mockito::Server::new_async().await;
+    let project = server
+        .mock("GET", "/api/v2/project")
+        .match_query(Matcher::UrlEncoded("project_name".into(), "@scope-lib".into()))
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"data":[{"id":"37adefe5-9bc6-4777-aaf2-e56277d1f30b","name":"@scope-lib","description":"","github_url":"","created_by":"96228bb5-f777-4c19-ba72-363d14b8beed","license":"Apache-2.0"}],"status":"success"}"#)
+        .create_async()
+        .await;
+    let vars = [
+        ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().to_string())),
+        ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().to_string())),
+        ("SOLDEER_API_URL", Some(server.url())),
+    ];
+
+    let output = dir.join("sbom.cdx.json");
+    let cmd: Command = Sbom::builder().output(&output).build().into();
+    let res = async_with_vars(vars.clone(), run(cmd, Verbosity::default())).await;
+    assert!(res.is_ok(), "{res:?}");
+    project.assert_async().await;
+    let bom = fs::read_to_string(&output).unwrap();
+    assert!(bom.contains(r#""bomFormat": "CycloneDX""#));
+    assert!(bom.contains(r#""purl": "pkg:soldeer/%40scope-lib@1.2.0""#));
+    assert!(
+        bom.contains(r#""purl": "pkg:github/org/repo@abcdef0123456789abcdef0123456789abcdef01""#)
+    );
+    assert!(bom.contains(r#""url": "https://example.com/mylib.zip""#));
+    assert!(bom.contains(
+        r#""content": "94a73dbe106f48179ea39b00d42e5d4dd96fdc6252caa3a89ce7efdaec0b9468""#
+    ));
+    assert!(bom.contains(
+        r#""value": "f3c628f3e9eae4db14fe14f9ab29e49a0107c47b8ee956e4cee57b616b493fc2""#
+    ));
+    assert!(bom.contains(r#""expression": "Apache-2.0""#));
+    assert!(bom.contains(r#""expression": "MIT""#));
+
+    let output = dir.join("sbom.spdx.json");
+    let cmd: Command = Sbom::builder().format(SbomFormat::Spdx).output(&output).build().into();
+    let res = async_with_vars(vars, run(cmd, Verbosity::default())).await;
+    assert!(res.is_ok(), "{res:?}");
+    let spdx = fs::read_to_string(&output).unwrap();
+    assert!(spdx.contains(r#""spdxVersion": "SPDX-2.3""#));
+    assert!(spdx.contains(r#""referenceLocator": "pkg:soldeer/%40scope-lib@1.2.0""#));
+    assert!(spdx.contains(
+        r#""downloadLocation": "git+https://github.com/org/repo.git@abcdef0123456789abcdef0123456789abcdef01""#
+    ));
+    assert!(spdx.contains(r#""licenseDeclared": "MIT""#));
+    assert!(spdx.contains(r#""licenseDeclared": "NOASSERTION""#));
+}
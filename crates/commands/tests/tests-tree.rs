@@ -0,0 +1,101 @@
+#![allow(clippy::unwrap_used)]
+use soldeer_commands::{
+    Command, Verbosity,
+    commands::{
+        install::Install,
+        tree::{Tree, TreeFormat},
+    },
+    run,
+};
+use soldeer_core::{SoldeerError, errors::GraphError, lock::read_lockfile};
+use std::{fs, path::PathBuf};
+use temp_env::async_with_vars;
+use testdir::testdir;
+
+async fn setup() -> PathBuf {
+    let dir = testdir!();
+    let project = dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(
+        project.join("soldeer.toml"),
+        r#"[soldeer]
+recursive_deps = true
+
+[dependencies]
+my-lib = { version = "1.0.0", path = "../my-lib" }
+"#,
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join("my-lib/src")).unwrap();
+    fs::write(dir.join("my-lib/src/Lib.sol"), "contract Lib {}").unwrap();
+    fs::write(
+        dir.join("my-lib/soldeer.toml"),
+        "[dependencies]\nother = { version = \"2.0.0\", path = \"../other\" }\n",
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join("other/src")).unwrap();
+    fs::write(dir.join("other/src/Other.sol"), "contract Other {}").unwrap();
+
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(project.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    let lock = read_lockfile(project.join("soldeer.lock")).unwrap();
+    assert_eq!(lock.entries.len(), 2);
+    project
+}
+
+#[tokio::test]
+async fn test_tree() {
+    let project = setup().await;
+    for format in [TreeFormat::Text, TreeFormat::Dot] {
+        let cmd: Command = Tree::builder().format(format).build().into();
+        let res = async_with_vars(
+            [
+                ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+                (
+                    "SOLDEER_CACHE_DIR",
+                    Some(project.join(".soldeer-cache").to_string_lossy().as_ref()),
+                ),
+            ],
+            run(cmd, Verbosity::default()),
+        )
+        .await;
+        assert!(res.is_ok(), "{res:?}");
+    }
+}
+
+#[tokio::test]
+async fn test_tree_invert() {
+    let project = setup().await;
+    let cmd: Command = Tree::builder().invert("other").build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(project.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let cmd: Command = Tree::builder().invert("foo").build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(project.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(res, Err(SoldeerError::GraphError(GraphError::PackageNotFound(_)))),
+        "{res:?}"
+    );
+}
@@ -6,6 +6,7 @@ use soldeer_commands::{
 use soldeer_core::{
     config::read_config_deps,
     lock::{SOLDEER_LOCK, read_lockfile},
+    push::zip_file,
 };
 use std::{fs, path::PathBuf};
 use temp_env::async_with_vars;
@@ -32,7 +33,10 @@ remappings_location = "config"
     fs::write(dir.join(config_filename), contents).unwrap();
     let cmd: Command = Install::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -45,7 +49,10 @@ async fn test_uninstall_one() {
     let dir = setup("soldeer.toml").await;
     let cmd: Command = Uninstall::builder().dependency("solady").build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -64,14 +71,20 @@ async fn test_uninstall_all() {
     let dir = setup("soldeer.toml").await;
     let cmd: Command = Uninstall::builder().dependency("solady").build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
     assert!(res.is_ok(), "{res:?}");
     let cmd: Command = Uninstall::builder().dependency("@openzeppelin-contracts").build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -89,7 +102,10 @@ async fn test_uninstall_foundry_config() {
     let dir = setup("foundry.toml").await;
     let cmd: Command = Uninstall::builder().dependency("solady").build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -99,3 +115,109 @@ async fn test_uninstall_foundry_config() {
     let config = fs::read_to_string(dir.join("foundry.toml")).unwrap();
     assert!(!config.contains("solady"));
 }
+
+#[tokio::test]
+#[allow(clippy::unwrap_used)]
+async fn test_uninstall_workspace() {
+    let dir = testdir!();
+    let pkg = dir.join("pkg_a");
+    fs::create_dir(&pkg).unwrap();
+    fs::write(pkg.join("Lib.sol"), "contract a {}").unwrap();
+    let zip_a = zip_file(&pkg, &[pkg.join("Lib.sol")], "a").unwrap();
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/a-1.0.0.zip").with_body_from_file(zip_a).create_async().await;
+    fs::write(dir.join("soldeer.toml"), "[workspace]\nmembers = [\"packages/*\"]\n").unwrap();
+    for name in ["app", "lib"] {
+        let path = dir.join("packages").join(name);
+        fs::create_dir_all(&path).unwrap();
+        fs::write(
+            path.join("soldeer.toml"),
+            format!(
+                "[dependencies]\na = {{ version = \"1.0.0\", url = \"{}/a-1.0.0.zip\" }}\n",
+                server.url()
+            ),
+        )
+        .unwrap();
+    }
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    // the dependency is still required by the other member
+    let uninstall = |member: &str| {
+        let cmd: Command = Uninstall::builder().dependency("a").build().into();
+        async_with_vars(
+            [
+                (
+                    "SOLDEER_PROJECT_ROOT",
+                    Some(dir.join("packages").join(member).to_string_lossy().to_string()),
+                ),
+                (
+                    "SOLDEER_CACHE_DIR",
+                    Some(dir.join(".soldeer-cache").to_string_lossy().to_string()),
+                ),
+            ],
+            run(cmd, Verbosity::default()),
+        )
+    };
+    let res = uninstall("app").await;
+    assert!(res.is_ok(), "{res:?}");
+    let (deps, _) = read_config_deps(dir.join("packages/app/soldeer.toml")).unwrap();
+    assert!(deps.is_empty());
+    assert!(dir.join("dependencies/a-1.0.0").exists());
+    let lock = read_lockfile(dir.join(SOLDEER_LOCK)).unwrap();
+    assert_eq!(lock.entries.len(), 1);
+
+    let res = uninstall("lib").await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(!dir.join("dependencies/a-1.0.0").exists());
+    let lock = read_lockfile(dir.join(SOLDEER_LOCK)).unwrap();
+    assert!(lock.entries.is_empty());
+}
+
+#[tokio::test]
+#[allow(clippy::unwrap_used)]
+async fn test_uninstall_path_dependency() {
+    let dir = testdir!();
+    let project = dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::create_dir_all(dir.join("my-lib")).unwrap();
+    fs::write(dir.join("my-lib/Lib.sol"), "contract Lib {}").unwrap();
+    fs::write(
+        project.join("soldeer.toml"),
+        "[dependencies]\nmy-lib = { version = \"1.0.0\", path = \"../my-lib\" }\n",
+    )
+    .unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(project.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(project.join("dependencies/my-lib-1.0.0/Lib.sol").is_file());
+
+    let cmd: Command = Uninstall::builder().dependency("my-lib").build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(project.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(!project.join("dependencies/my-lib-1.0.0").exists());
+    // the linked folder is left untouched
+    assert!(dir.join("my-lib/Lib.sol").is_file());
+}
@@ -1,11 +1,15 @@
+use mockito::Matcher;
 use soldeer_commands::{
     Command, Verbosity,
     commands::{install::Install, update::Update},
     run,
 };
 use soldeer_core::{
+    SoldeerError,
     config::ConfigLocation,
+    errors::{ConfigError, InstallError},
     lock::{SOLDEER_LOCK, read_lockfile},
+    push::zip_file,
 };
 use std::{fs, path::PathBuf};
 use temp_env::async_with_vars;
@@ -32,7 +36,10 @@ remappings_location = "config"
     fs::write(dir.join(config_filename), &contents).unwrap();
     let cmd: Command = Install::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -50,7 +57,10 @@ async fn test_update_existing() {
     let dir = setup("soldeer.toml").await;
     let cmd: Command = Update::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -68,7 +78,10 @@ async fn test_update_foundry_config() {
     let dir = setup("foundry.toml").await;
     let cmd: Command = Update::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -88,7 +101,10 @@ forge-std = "1"
     fs::write(dir.join("soldeer.toml"), contents).unwrap();
     let cmd: Command = Update::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -106,7 +122,10 @@ async fn test_update_custom_remappings() {
         .unwrap();
     let cmd: Command = Update::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -134,7 +153,10 @@ rev = "78c2f6a1a54db26bab6c3f501854a1564eb3707f"
     fs::write(dir.join(SOLDEER_LOCK), lockfile).unwrap();
     let cmd: Command = Install::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -143,7 +165,10 @@ rev = "78c2f6a1a54db26bab6c3f501854a1564eb3707f"
     // update to latest commit in "main" branch
     let cmd: Command = Update::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -172,7 +197,10 @@ rev = "78c2f6a1a54db26bab6c3f501854a1564eb3707f"
     fs::write(dir.join(SOLDEER_LOCK), lockfile).unwrap();
     let cmd: Command = Install::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -181,7 +209,10 @@ rev = "78c2f6a1a54db26bab6c3f501854a1564eb3707f"
     // update to latest commit in "dev" branch
     let cmd: Command = Update::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -215,7 +246,10 @@ remappings_location = "config"
 
     let cmd: Command = Update::default().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -235,7 +269,10 @@ async fn test_install_new_foundry_no_foundry_toml() {
 
     let cmd: Command = Update::builder().config_location(ConfigLocation::Foundry).build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -259,7 +296,10 @@ async fn test_install_new_soldeer_no_soldeer_toml() {
 
     let cmd: Command = Update::builder().config_location(ConfigLocation::Soldeer).build().into();
     let res = async_with_vars(
-        [("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
         run(cmd, Verbosity::default()),
     )
     .await;
@@ -268,3 +308,255 @@ async fn test_install_new_soldeer_no_soldeer_toml() {
     let content = "[dependencies]\n";
     assert_eq!(config, content);
 }
+
+#[allow(clippy::unwrap_used)]
+async fn setup_registry() -> (PathBuf, mockito::ServerGuard) {
+    let dir = testdir!();
+    let pkg = dir.join("pkg");
+    fs::create_dir(&pkg).unwrap();
+    fs::write(pkg.join("Lib.sol"), "contract Lib {}").unwrap();
+    let zip_path = zip_file(&pkg, &[pkg.join("Lib.sol")], "pkg").unwrap();
+
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/pkg.zip").with_body_from_file(zip_path).create_async().await;
+    let url = server.url();
+    let revision = |version: &str| {
+        format!(
+            r#"{{"created_at":"2024-08-06T17:31:25.751079Z","deleted":false,"id":"660132e6-4902-4804-8c4b-7cae0a648054","internal_name":"pkg.zip","project_id":"37adefe5-9bc6-4777-aaf2-e56277d1f30b","url":"{url}/pkg.zip","version":"{version}"}}"#
+        )
+    };
+    let data =
+        format!(r#"{{"data":[{},{}],"status":"success"}}"#, revision("1.1.0"), revision("1.0.0"));
+    server
+        .mock("GET", "/api/v1/revision")
+        .match_query(Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(data)
+        .create_async()
+        .await;
+    let data = format!(r#"{{"data":[{}],"status":"success"}}"#, revision("1.0.0"));
+    server
+        .mock("GET", "/api/v1/revision-cli")
+        .match_query(Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(data)
+        .create_async()
+        .await;
+
+    // install both packages at version 1.0.0
+    fs::write(
+        dir.join("soldeer.toml"),
+        r#"[dependencies]
+a = "1.0.0"
+b = "1.0.0"
+"#,
+    )
+    .unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    // allow newer versions (making the current installs outdated)
+    fs::write(
+        dir.join("soldeer.toml"),
+        r#"[dependencies]
+a = "^1.0"
+b = "^1.0"
+"#,
+    )
+    .unwrap();
+    (dir, server)
+}
+
+#[tokio::test]
+#[allow(clippy::unwrap_used)]
+async fn test_update_selected() {
+    let (dir, server) = setup_registry().await;
+    let lock_before = fs::read_to_string(dir.join(SOLDEER_LOCK)).unwrap();
+    let remappings_before = fs::read_to_string(dir.join("remappings.txt")).unwrap();
+    let cmd: Command = Update::builder().dependencies(vec!["a".to_string()]).build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    let lockfile = read_lockfile(dir.join(SOLDEER_LOCK)).unwrap();
+    assert_eq!(lockfile.entries[0].version(), "1.1.0");
+    assert_eq!(lockfile.entries[1].version(), "1.0.0");
+    // the entry for `b` is unchanged
+    let b_entry = |lock: &str| lock[lock.find("name = \"b\"").unwrap()..].to_string();
+    assert_eq!(b_entry(&lockfile.raw), b_entry(&lock_before));
+    assert!(dir.join("dependencies/a-1.1.0").is_dir());
+    assert!(dir.join("dependencies/b-1.0.0").is_dir());
+    let remappings = fs::read_to_string(dir.join("remappings.txt")).unwrap();
+    assert_eq!(
+        remappings,
+        remappings_before.replace("dependencies/a-1.0.0/", "dependencies/a-1.1.0/")
+    );
+}
+
+#[tokio::test]
+#[allow(clippy::unwrap_used)]
+async fn test_update_except() {
+    let (dir, server) = setup_registry().await;
+    let cmd: Command = Update::builder().except(vec!["a".to_string()]).build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    let lockfile = read_lockfile(dir.join(SOLDEER_LOCK)).unwrap();
+    assert_eq!(lockfile.entries[0].version(), "1.0.0");
+    assert_eq!(lockfile.entries[1].version(), "1.1.0");
+    assert!(dir.join("dependencies/a-1.0.0").is_dir());
+    assert!(dir.join("dependencies/b-1.1.0").is_dir());
+}
+
+#[tokio::test]
+async fn test_update_unknown_dependency() {
+    let (dir, server) = setup_registry().await;
+    let cmd: Command = Update::builder().dependencies(vec!["foo".to_string()]).build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().as_ref())),
+            ("SOLDEER_API_URL", Some(&server.url())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(res, Err(SoldeerError::ConfigError(ConfigError::MissingDependency(ref name))) if name == "foo"),
+        "{res:?}"
+    );
+}
+
+#[allow(clippy::unwrap_used)]
+fn create_zip_with_config(testdir: &std::path::Path, name: &str, dependencies: &str) -> PathBuf {
+    let root = testdir.join(format!("pkg_{name}"));
+    fs::create_dir(&root).unwrap();
+    let files = vec![root.join("soldeer.toml"), root.join("Lib.sol")];
+    fs::write(&files[0], format!("[dependencies]\n{dependencies}\n")).unwrap();
+    fs::write(&files[1], "contract Lib {}").unwrap();
+    zip_file(&root, &files, name).unwrap()
+}
+
+#[tokio::test]
+#[allow(clippy::unwrap_used)]
+async fn test_update_selected_shared_transitive() {
+    let dir = testdir!();
+    let mut server = mockito::Server::new_async().await;
+    let url = server.url();
+    let zip_a1 = create_zip_with_config(&dir, "a1", r#"c = "1.0.0""#);
+    let zip_a2 = create_zip_with_config(&dir, "a2", r#"c = "^1.0""#);
+    let zip_a3 = create_zip_with_config(&dir, "a3", r#"c = "^1.1""#);
+    let zip_b = create_zip_with_config(&dir, "b", r#"c = "<1.2""#);
+    let zip_c = create_zip_with_config(&dir, "c", "");
+    server.mock("GET", "/a1.zip").with_body_from_file(zip_a1).create_async().await;
+    server.mock("GET", "/a2.zip").with_body_from_file(zip_a2).create_async().await;
+    server.mock("GET", "/a3.zip").with_body_from_file(zip_a3).create_async().await;
+    server.mock("GET", "/b.zip").with_body_from_file(zip_b).create_async().await;
+    let revision = |v: &str| {
+        format!(
+            r#"{{"created_at":"2024-08-06T17:31:25.751079Z","deleted":false,"id":"660132e6-4902-4804-8c4b-7cae0a648054","internal_name":"c/{v}.zip","project_id":"37adefe5-9bc6-4777-aaf2-e56277d1f30b","url":"{url}/c-{v}.zip","version":"{v}"}}"#
+        )
+    };
+    server
+        .mock("GET", "/api/v1/revision")
+        .match_query(Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(format!(
+            r#"{{"data":[{},{},{}],"status":"success"}}"#,
+            revision("1.2.0"),
+            revision("1.1.0"),
+            revision("1.0.0")
+        ))
+        .create_async()
+        .await;
+    for version in ["1.0.0", "1.1.0", "1.2.0"] {
+        server
+            .mock("GET", format!("/c-{version}.zip").as_str())
+            .with_body_from_file(&zip_c)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/api/v1/revision-cli")
+            .match_query(Matcher::UrlEncoded("revision".into(), version.into()))
+            .with_header("content-type", "application/json")
+            .with_body(format!(r#"{{"data":[{}],"status":"success"}}"#, revision(version)))
+            .create_async()
+            .await;
+    }
+    let config = |a: &str| {
+        format!(
+            r#"[dependencies]
+a = {{ version = "{a}", url = "{url}/a{a}.zip" }}
+b = {{ version = "1", url = "{url}/b.zip" }}
+
+[soldeer]
+recursive_deps = true
+"#
+        )
+    };
+    let vars = [
+        ("SOLDEER_PROJECT_ROOT", Some(dir.to_string_lossy().to_string())),
+        ("SOLDEER_CACHE_DIR", Some(dir.join(".soldeer-cache").to_string_lossy().to_string())),
+        ("SOLDEER_API_URL", Some(url.clone())),
+    ];
+    fs::write(dir.join("soldeer.toml"), config("1")).unwrap();
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(vars.clone(), run(cmd, Verbosity::default())).await;
+    assert!(res.is_ok(), "{res:?}");
+    let lock_before = fs::read_to_string(dir.join(SOLDEER_LOCK)).unwrap();
+    assert!(dir.join("dependencies/c-1.0.0").is_dir());
+
+    let entry = |lock: &str, name: &str| {
+        let start = lock.find(&format!("name = \"{name}\"")).unwrap();
+        lock[start..start + lock[start..].find("\n\n").unwrap_or(lock.len() - start)].to_string()
+    };
+
+    // the entries of `b` and of its dependency `c` are unchanged
+    fs::write(dir.join("soldeer.toml"), config("2")).unwrap();
+    let cmd: Command = Update::builder().dependencies(vec!["a".to_string()]).build().into();
+    let res = async_with_vars(vars.clone(), run(cmd, Verbosity::default())).await;
+    assert!(res.is_ok(), "{res:?}");
+    let lockfile = read_lockfile(dir.join(SOLDEER_LOCK)).unwrap();
+    let versions: Vec<_> = lockfile.entries.iter().map(|e| (e.name(), e.version())).collect();
+    assert_eq!(versions, vec![("a", "2"), ("b", "1"), ("c", "1.0.0")]);
+    for name in ["b", "c"] {
+        assert_eq!(entry(&lockfile.raw, name), entry(&lock_before, name));
+    }
+
+    // the new version of `a` requires `c ^1.1`, but `c` is pinned by `b`
+    let lock_before = lockfile.raw;
+    fs::write(dir.join("soldeer.toml"), config("3")).unwrap();
+    let cmd: Command = Update::builder().dependencies(vec!["a".to_string()]).build().into();
+    let res = async_with_vars(vars, run(cmd, Verbosity::default())).await;
+    assert!(
+        matches!(
+            &res,
+            Err(SoldeerError::InstallError(InstallError::VersionConflict { dependency, .. }))
+                if dependency == "c"
+        ),
+        "{res:?}"
+    );
+    assert_eq!(fs::read_to_string(dir.join(SOLDEER_LOCK)).unwrap(), lock_before);
+    assert!(dir.join("dependencies/c-1.0.0").is_dir());
+}
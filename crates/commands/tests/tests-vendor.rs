@@ -0,0 +1,97 @@
+#![allow(clippy::unwrap_used)]
+use soldeer_commands::{
+    Command, Verbosity,
+    commands::{install::Install, vendor::Vendor},
+    run,
+};
+use soldeer_core::{config::read_config_deps, push::zip_file, vendor::VendorIndex};
+use std::{fs, path::Path, process};
+use temp_env::async_with_vars;
+use testdir::testdir;
+
+fn git(args: &[&str], dir: &Path) -> String {
+    let output = process::Command::new("git")
+        .args(["-c", "user.name=soldeer", "-c", "user.email=soldeer@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+#[tokio::test]
+async fn test_vendor_and_install() {
+    let dir = testdir!();
+    let cache = dir.join("cache");
+
+    // an HTTP dependency served by a mock server
+    let pkg = dir.join("pkg");
+    fs::create_dir_all(&pkg).unwrap();
+    fs::write(pkg.join("Lib.sol"), "contract Lib {}").unwrap();
+    let zip_path = zip_file(&pkg, &[pkg.join("Lib.sol")], "pkg").unwrap();
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("GET", "/pkg.zip").with_body_from_file(zip_path).create_async().await;
+
+    // a git dependency in a local repository
+    let repo = dir.join("repo");
+    fs::create_dir_all(&repo).unwrap();
+    git(&["init"], &repo);
+    fs::write(repo.join("Other.sol"), "contract Other {}").unwrap();
+    git(&["add", "."], &repo);
+    git(&["commit", "-m", "first"], &repo);
+    let rev = git(&["rev-parse", "HEAD"], &repo);
+    fs::write(repo.join("Other.sol"), "contract OtherV2 {}").unwrap();
+    git(&["commit", "-am", "second"], &repo);
+
+    let project = dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(
+        project.join("soldeer.toml"),
+        format!(
+            "[dependencies]\nlib = {{ version = \"1.0.0\", url = \"{}/pkg.zip\" }}\nother = {{ version = \"1.0.0\", git = \"{}\", rev = \"{rev}\" }}\n",
+            server.url(),
+            repo.to_string_lossy().replace('\\', "/")
+        ),
+    )
+    .unwrap();
+    assert_eq!(read_config_deps(project.join("soldeer.toml")).unwrap().0.len(), 2);
+
+    let env = [
+        ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().to_string())),
+        ("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().to_string())),
+    ];
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(env.clone(), run(cmd, Verbosity::default())).await;
+    assert!(res.is_ok(), "{res:?}");
+    let lock_contents = fs::read_to_string(project.join("soldeer.lock")).unwrap();
+
+    let vendor = dir.join("vendor");
+    let cmd: Command = Vendor::builder().dir(&vendor).build().into();
+    let res = async_with_vars(env.clone(), run(cmd, Verbosity::default())).await;
+    assert!(res.is_ok(), "{res:?}");
+    let index = VendorIndex::read(&vendor).unwrap();
+    assert_eq!(index.artifacts.len(), 2);
+    for artifact in &index.artifacts {
+        assert!(vendor.join(&artifact.path).is_file(), "{artifact:?}");
+    }
+
+    // remove every other source of the artifacts
+    mock.remove_async().await;
+    fs::remove_dir_all(&cache).unwrap();
+    fs::remove_dir_all(&repo).unwrap();
+    fs::remove_dir_all(project.join("dependencies")).unwrap();
+
+    let cmd: Command = Install::builder().offline(true).vendor_dir(&vendor).build().into();
+    let res = async_with_vars(env, run(cmd, Verbosity::default())).await;
+    assert!(res.is_ok(), "{res:?}");
+    assert_eq!(
+        fs::read_to_string(project.join("dependencies/lib-1.0.0/Lib.sol")).unwrap(),
+        "contract Lib {}"
+    );
+    assert_eq!(
+        fs::read_to_string(project.join("dependencies/other-1.0.0/Other.sol")).unwrap(),
+        "contract Other {}"
+    );
+    assert_eq!(fs::read_to_string(project.join("soldeer.lock")).unwrap(), lock_contents);
+}
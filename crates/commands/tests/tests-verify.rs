@@ -0,0 +1,56 @@
+#![allow(clippy::unwrap_used)]
+use soldeer_commands::{
+    Command, Verbosity,
+    commands::{install::Install, verify::Verify},
+    run,
+};
+use soldeer_core::{SoldeerError, errors::VerifyError, push::zip_file};
+use std::fs;
+use temp_env::async_with_vars;
+use testdir::testdir;
+
+#[tokio::test]
+async fn test_verify() {
+    let dir = testdir!();
+    let pkg = dir.join("pkg");
+    fs::create_dir_all(pkg.join("src")).unwrap();
+    fs::write(pkg.join("src/Lib.sol"), "contract Lib {}").unwrap();
+    fs::write(pkg.join("README.md"), "# Lib").unwrap();
+    let zip_path =
+        zip_file(&pkg, &[pkg.join("README.md"), pkg.join("src/Lib.sol")], "pkg").unwrap();
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/pkg.zip").with_body_from_file(zip_path).create_async().await;
+
+    let project = dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(
+        project.join("soldeer.toml"),
+        format!(
+            "[dependencies]\nlib = {{ version = \"1.0.0\", url = \"{}/pkg.zip\" }}\n",
+            server.url()
+        ),
+    )
+    .unwrap();
+    let cache = dir.join("cache");
+    let vars = [
+        ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().to_string())),
+        ("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().to_string())),
+    ];
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(vars.clone(), run(cmd, Verbosity::default())).await;
+    assert!(res.is_ok(), "{res:?}");
+    // the manifest is written outside of the dependency folder
+    assert!(project.join("dependencies/.soldeer-manifests/lib-1.0.0.json").is_file());
+
+    let cmd: Command = Verify::default().into();
+    let res = async_with_vars(vars.clone(), run(cmd, Verbosity::default())).await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let file = project.join("dependencies/lib-1.0.0/src/Lib.sol");
+    fs::write(&file, "contract Evil {}").unwrap();
+    let cmd: Command = Verify::default().into();
+    let res = async_with_vars(vars, run(cmd, Verbosity::default())).await;
+    assert!(matches!(res, Err(SoldeerError::VerifyError(VerifyError::Tampered(_)))), "{res:?}");
+    // the file was not restored
+    assert_eq!(fs::read_to_string(&file).unwrap(), "contract Evil {}");
+}
@@ -0,0 +1,71 @@
+#![allow(clippy::unwrap_used)]
+use soldeer_commands::{
+    Command, Verbosity,
+    commands::{install::Install, why::Why},
+    run,
+};
+use soldeer_core::{SoldeerError, errors::GraphError};
+use std::fs;
+use temp_env::async_with_vars;
+use testdir::testdir;
+
+#[tokio::test]
+async fn test_why() {
+    let dir = testdir!();
+    let project = dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(
+        project.join("soldeer.toml"),
+        r#"[soldeer]
+recursive_deps = true
+
+[dependencies]
+my-lib = { version = "1.0.0", path = "../my-lib" }
+other = { version = "2.0.0", path = "../other" }
+"#,
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join("my-lib/src")).unwrap();
+    fs::write(
+        dir.join("my-lib/soldeer.toml"),
+        "[dependencies]\nother = { version = \"2.0.0\", path = \"../other\" }\n",
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join("other/src")).unwrap();
+
+    let cmd: Command = Install::default().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(project.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let cmd: Command = Why::builder().package("other").build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(project.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let cmd: Command = Why::builder().package("foo").build().into();
+    let res = async_with_vars(
+        [
+            ("SOLDEER_PROJECT_ROOT", Some(project.to_string_lossy().as_ref())),
+            ("SOLDEER_CACHE_DIR", Some(project.join(".soldeer-cache").to_string_lossy().as_ref())),
+        ],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(
+        matches!(res, Err(SoldeerError::GraphError(GraphError::PackageNotFound(_)))),
+        "{res:?}"
+    );
+}
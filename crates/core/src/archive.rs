@@ -0,0 +1,438 @@
+//! Archive formats of HTTP dependencies.
+//!
+//! Packages from the registry are zip files, but dependencies with a custom URL can also point to
+//! tarballs (e.g. GitHub release assets or `codeload.github.com` links). The following formats are
+//! supported:
+//!
+//! - zip
+//! - tar compressed with gzip (`.tar.gz`)
+//! - tar compressed with xz (`.tar.xz`)
+//! - tar compressed with zstd (`.tar.zst`)
+//!
+//! The format of an archive is detected from its first bytes, so that the name of the file doesn't
+//! matter. For all formats, the top-level folder of the archive is stripped during extraction if
+//! all the entries are inside of it (and there are at least two entries).
+//!
+//! Tarballs are decompressed on the fly while reading their entries, and reading fails once more
+//! than [`MAX_TAR_SIZE`] bytes were decompressed. Only the permission bits of the file modes are
+//! kept.
+use crate::errors::ArchiveError;
+use flate2::read::MultiGzDecoder;
+use log::{debug, trace};
+use std::{
+    fs,
+    io::{self, Cursor, Read},
+    path::{Component, Path},
+};
+
+pub type Result<T> = std::result::Result<T, ArchiveError>;
+
+/// The maximum size of a decompressed tarball (512 MiB), to protect against decompression bombs.
+pub const MAX_TAR_SIZE: u64 = 512 * 1024 * 1024;
+
+/// The format of an archive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ArchiveFormat {
+    /// A zip file.
+    #[default]
+    Zip,
+
+    /// A tar file compressed with gzip.
+    TarGz,
+
+    /// A tar file compressed with xz.
+    TarXz,
+
+    /// A tar file compressed with zstd.
+    TarZst,
+}
+
+impl ArchiveFormat {
+    /// All the supported formats.
+    pub const ALL: [Self; 4] = [Self::Zip, Self::TarGz, Self::TarXz, Self::TarZst];
+
+    /// The file extension for the format (without leading dot).
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::TarGz => "tar.gz",
+            Self::TarXz => "tar.xz",
+            Self::TarZst => "tar.zst",
+        }
+    }
+
+    /// Detect the format of an archive from its first bytes.
+    pub fn from_magic(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else if bytes.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::TarGz)
+        } else if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Self::TarXz)
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Self::TarZst)
+        } else {
+            None
+        }
+    }
+
+    /// Get the format corresponding to the `Content-Type` header of an HTTP response.
+    ///
+    /// Generic types like `application/octet-stream` don't correspond to any format.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+        match mime.as_str() {
+            "application/zip" | "application/x-zip-compressed" => Some(Self::Zip),
+            "application/gzip" | "application/x-gzip" | "application/x-gtar" => Some(Self::TarGz),
+            "application/x-xz" => Some(Self::TarXz),
+            "application/zstd" | "application/x-zstd" => Some(Self::TarZst),
+            _ => None,
+        }
+    }
+
+    /// Get the format corresponding to the extension of a file name or URL path.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar.xz") || name.ends_with(".txz") {
+            Some(Self::TarXz)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Some(Self::TarZst)
+        } else {
+            None
+        }
+    }
+
+    /// Detect the format of an archive file from its first bytes.
+    pub fn detect_file(path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        let mut magic = Vec::with_capacity(6);
+        fs::File::open(path)?.take(6).read_to_end(&mut magic)?;
+        Ok(Self::from_magic(&magic))
+    }
+}
+
+/// A regular file inside of an archive.
+pub struct ArchiveFile<'a> {
+    /// The path of the file, relative to the root of the archive (after stripping the top-level
+    /// folder if needed), with forward slashes.
+    pub path: String,
+
+    /// The unix permissions of the file (without the file type and special bits), if available.
+    pub mode: Option<u32>,
+
+    /// A reader for the contents of the file.
+    pub reader: &'a mut dyn Read,
+}
+
+/// Call a function for each regular file of an archive.
+///
+/// Other kinds of entries (directories, links, etc.) and entries with a path that would be
+/// outside of the archive root are skipped.
+pub fn visit_files(
+    bytes: &[u8],
+    mut f: impl FnMut(ArchiveFile<'_>) -> io::Result<()>,
+) -> Result<()> {
+    let format = ArchiveFormat::from_magic(bytes).ok_or(ArchiveError::UnsupportedFormat)?;
+    debug!(format:?; "reading archive");
+    if format == ArchiveFormat::Zip {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+        let names: Vec<String> = archive.file_names().map(ToString::to_string).collect();
+        let strip = toplevel(&names);
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if !file.is_file() {
+                continue;
+            }
+            let Some(name) = file.enclosed_name() else {
+                continue;
+            };
+            let path = strip_toplevel(&name.to_string_lossy().replace('\\', "/"), strip.as_deref());
+            let mode = file.unix_mode().map(|m| m & 0o777);
+            f(ArchiveFile { path, mode, reader: &mut file })?;
+        }
+        return Ok(());
+    }
+    // the entries are streamed twice, the first time to find the top-level folder
+    let mut names = Vec::new();
+    for entry in tar::Archive::new(decompress(bytes, format)?).entries()? {
+        let entry = entry?;
+        let kind = entry.header().entry_type();
+        if (kind.is_file() || kind.is_dir()) &&
+            let Some(name) = enclosed_name(&entry.path()?)
+        {
+            names.push(name);
+        }
+    }
+    let strip = toplevel(&names);
+    for entry in tar::Archive::new(decompress(bytes, format)?).entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            trace!(path:? = entry.path().ok(); "skipping archive entry which is not a file");
+            continue;
+        }
+        let Some(name) = enclosed_name(&entry.path()?) else {
+            continue;
+        };
+        let path = strip_toplevel(&name, strip.as_deref());
+        let mode = entry.header().mode().ok().map(|m| m & 0o777);
+        f(ArchiveFile { path, mode, reader: &mut entry })?;
+    }
+    Ok(())
+}
+
+/// Extract an archive into a folder.
+///
+/// The folder is created if it doesn't exist.
+pub fn extract(bytes: &[u8], into: impl AsRef<Path>) -> Result<()> {
+    let into = into.as_ref();
+    if ArchiveFormat::from_magic(bytes) == Some(ArchiveFormat::Zip) {
+        #[allow(deprecated)] // until we can get rid of zip_extract
+        zip_extract::extract(Cursor::new(bytes), into, true)?;
+        return Ok(());
+    }
+    fs::create_dir_all(into)?;
+    visit_files(bytes, |file| {
+        let path = into.join(&file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(file.reader, &mut fs::File::create(&path)?)?;
+        #[cfg(unix)]
+        if let Some(mode) = file.mode {
+            use std::os::unix::fs::PermissionsExt as _;
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+        }
+        Ok(())
+    })
+}
+
+/// Get a reader which decompresses a tarball, up to [`MAX_TAR_SIZE`] bytes.
+fn decompress(bytes: &[u8], format: ArchiveFormat) -> Result<SizeLimit<Box<dyn Read + '_>>> {
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::Zip => unreachable!("zip files are not tarballs"),
+        ArchiveFormat::TarGz => Box::new(MultiGzDecoder::new(bytes)),
+        ArchiveFormat::TarXz => {
+            // lzma-rs can only decompress into a writer
+            let mut tar = SizeLimit::new(Vec::new());
+            lzma_rs::xz_decompress(&mut &bytes[..], &mut tar)
+                .map_err(|e| ArchiveError::DecompressError(e.to_string()))?;
+            Box::new(Cursor::new(tar.inner))
+        }
+        ArchiveFormat::TarZst => Box::new(
+            ruzstd::decoding::StreamingDecoder::new(bytes)
+                .map_err(|e| ArchiveError::DecompressError(e.to_string()))?,
+        ),
+    };
+    Ok(SizeLimit::new(reader))
+}
+
+/// A reader or writer which fails after [`MAX_TAR_SIZE`] bytes.
+struct SizeLimit<T> {
+    inner: T,
+    remaining: u64,
+}
+
+impl<T> SizeLimit<T> {
+    fn new(inner: T) -> Self {
+        Self { inner, remaining: MAX_TAR_SIZE }
+    }
+
+    fn consume(&mut self, len: usize) -> io::Result<()> {
+        self.remaining = self.remaining.checked_sub(len as u64).ok_or_else(|| {
+            io::Error::other(format!(
+                "decompressed archive is larger than the maximum of {MAX_TAR_SIZE} bytes"
+            ))
+        })?;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for SizeLimit<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.consume(len)?;
+        Ok(len)
+    }
+}
+
+impl<W: io::Write> io::Write for SizeLimit<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.consume(buf.len())?;
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Normalize the path of a tar entry, returning `None` if it would be outside of the archive
+/// root.
+fn enclosed_name(path: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// Get the top-level folder which contains all the entries of an archive, if there is one.
+///
+/// Archives with less than two entries are never stripped.
+fn toplevel(names: &[String]) -> Option<String> {
+    let top = names.first().and_then(|n| n.split('/').next())?;
+    (names.len() >= 2 &&
+        names
+            .iter()
+            .all(|n| n.strip_prefix(top).is_some_and(|r| r.is_empty() || r.starts_with('/'))))
+    .then(|| top.to_string())
+}
+
+/// Remove the top-level folder from the path of an entry.
+fn strip_toplevel(name: &str, toplevel: Option<&str>) -> String {
+    match toplevel {
+        Some(top) => name.strip_prefix(&format!("{top}/")).unwrap_or(name).to_string(),
+        None => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::push::zip_file;
+    use flate2::{Compression, write::GzEncoder};
+    use testdir::testdir;
+
+    fn tarball(files: &[(&str, &str)]) -> Vec<u8> {
+        tarball_with_mode(files, 0o644)
+    }
+
+    fn tarball_with_mode(files: &[(&str, &str)], mode: u32) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(mode);
+            header.set_cksum();
+            builder.append_data(&mut header, path, contents.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        io::Write::write_all(&mut encoder, bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_from_magic() {
+        assert_eq!(ArchiveFormat::from_magic(b"PK\x03\x04rest"), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::from_magic(&gzip(b"foo")), Some(ArchiveFormat::TarGz));
+        assert_eq!(
+            ArchiveFormat::from_magic(&[0xfd, b'7', b'z', b'X', b'Z', 0x00, 0x00]),
+            Some(ArchiveFormat::TarXz)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(&[0x28, 0xb5, 0x2f, 0xfd, 0x00]),
+            Some(ArchiveFormat::TarZst)
+        );
+        assert_eq!(ArchiveFormat::from_magic(b"<html>"), None);
+    }
+
+    #[test]
+    fn test_from_content_type_and_name() {
+        assert_eq!(
+            ArchiveFormat::from_content_type("application/x-gzip; charset=binary"),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(ArchiveFormat::from_content_type("application/octet-stream"), None);
+        assert_eq!(ArchiveFormat::from_file_name("/v1.0.0/lib.tgz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_file_name("lib.tar.zst"), Some(ArchiveFormat::TarZst));
+        assert_eq!(ArchiveFormat::from_file_name("/refs/tags/v1.0.0"), None);
+    }
+
+    #[test]
+    fn test_extract_tar_gz_strip_toplevel() {
+        let dir = testdir!();
+        let bytes = gzip(&tarball(&[
+            ("repo-v1/src/Lib.sol", "contract Lib {}"),
+            ("repo-v1/README.md", "readme"),
+        ]));
+        let res = extract(&bytes, dir.join("out"));
+        assert!(res.is_ok(), "{res:?}");
+        assert_eq!(fs::read_to_string(dir.join("out/src/Lib.sol")).unwrap(), "contract Lib {}");
+        assert!(dir.join("out/README.md").is_file());
+
+        // no common top-level folder
+        let bytes = gzip(&tarball(&[("src/Lib.sol", "contract Lib {}"), ("README.md", "readme")]));
+        let res = extract(&bytes, dir.join("out2"));
+        assert!(res.is_ok(), "{res:?}");
+        assert!(dir.join("out2/src/Lib.sol").is_file());
+    }
+
+    #[test]
+    fn test_visit_files_mode() {
+        let bytes = gzip(&tarball_with_mode(&[("Lib.sol", "contract Lib {}")], 0o104755));
+        let mut modes = Vec::new();
+        visit_files(&bytes, |file| {
+            modes.push(file.mode);
+            Ok(())
+        })
+        .unwrap();
+        // the setuid bit is dropped
+        assert_eq!(modes, vec![Some(0o755)]);
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let mut reader = SizeLimit { inner: &b"0123456789"[..], remaining: 4 };
+        let mut buf = Vec::new();
+        let res = reader.read_to_end(&mut buf);
+        assert!(res.is_err(), "{res:?}");
+
+        let mut writer = SizeLimit { inner: Vec::new(), remaining: 4 };
+        assert!(io::Write::write_all(&mut writer, b"0123").is_ok());
+        assert!(io::Write::write_all(&mut writer, b"4").is_err());
+    }
+
+    #[test]
+    fn test_visit_files_same_for_zip_and_tar() {
+        let dir = testdir!();
+        fs::create_dir_all(dir.join("pkg/src")).unwrap();
+        fs::write(dir.join("pkg/src/Lib.sol"), "contract Lib {}").unwrap();
+        fs::write(dir.join("pkg/README.md"), "readme").unwrap();
+        let zip_path = zip_file(
+            dir.join("pkg"),
+            &[dir.join("pkg/src/Lib.sol"), dir.join("pkg/README.md")],
+            dir.join("pkg.zip"),
+        )
+        .unwrap();
+        let tar_gz =
+            gzip(&tarball(&[("pkg/src/Lib.sol", "contract Lib {}"), ("pkg/README.md", "readme")]));
+
+        let list = |bytes: &[u8]| {
+            let mut files = Vec::new();
+            visit_files(bytes, |file| {
+                let mut contents = String::new();
+                file.reader.read_to_string(&mut contents)?;
+                files.push((file.path, contents));
+                Ok(())
+            })
+            .unwrap();
+            files.sort();
+            files
+        };
+        assert_eq!(list(&fs::read(zip_path).unwrap()), list(&tar_gz));
+        assert!(matches!(visit_files(b"<html>", |_| Ok(())), Err(ArchiveError::UnsupportedFormat)));
+    }
+}
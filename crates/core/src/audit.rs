@@ -0,0 +1,486 @@
+//! Check the locked dependencies against an advisory database.
+//!
+//! The advisory database lists known vulnerabilities in packages, similarly to the RustSec
+//! database for Rust crates. It's a TOML (or JSON, with the same structure) file containing a list
+//! of advisories:
+//!
+//! ```toml
+//! [[advisories]]
+//! id = "SOL-2024-0001"
+//! package = "openzeppelin-contracts" # the name of the package in the registry
+//! title = "Reentrancy in Foo"
+//! description = "A longer description of the issue" # optional
+//! severity = "high" # one of low, medium, high or critical
+//! affected = [">=4.0.0, <4.9.3", "=5.0.0"] # version requirements, all versions if empty
+//! fixed = ["4.9.3", "5.0.1"] # optional
+//! url = "https://example.com/advisory" # optional
+//!
+//! [[advisories]]
+//! id = "SOL-2024-0002"
+//! git = "https://github.com/org/repo" # a git dependency
+//! revs = ["0123456789abcdef0123456789abcdef01234567"] # affected commits, all commits if empty
+//! title = "Bar is broken"
+//! severity = "critical"
+//! ```
+//!
+//! Advisories with a `package` are matched against the name and version of the lock entries of
+//! HTTP dependencies, while advisories with a `git` URL are matched against the URL and commit of
+//! git dependencies. Like in the RustSec database, pre-releases are affected if they are inside of
+//! an affected range (e.g. `1.1.0-rc.1` is affected by `>=1.0.0, <1.2.0`). If the locked version
+//! is not a semver version, the advisory can't be evaluated and is reported as such.
+//!
+//! The database is synced from a file or URL into a local copy (see [`advisory_db_path`]) with
+//! [`sync_advisory_db`].
+use crate::{errors::AuditError, lock::LockEntry, registry::parse_version_req};
+use log::{debug, warn};
+use reqwest::Client;
+use semver::{Comparator, Op, Prerelease, Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+pub type Result<T> = std::result::Result<T, AuditError>;
+
+/// The severity of an advisory.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Low => write!(f, "low"),
+            Self::Medium => write!(f, "medium"),
+            Self::High => write!(f, "high"),
+            Self::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = AuditError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "low" => Ok(Self::Low),
+            "medium" => Ok(Self::Medium),
+            "high" => Ok(Self::High),
+            "critical" => Ok(Self::Critical),
+            _ => Err(AuditError::UnknownSeverity(s.to_string())),
+        }
+    }
+}
+
+/// An advisory for a vulnerable package.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, bon::Builder)]
+#[builder(on(String, into))]
+#[non_exhaustive]
+pub struct Advisory {
+    /// The unique ID of the advisory.
+    pub id: String,
+
+    /// The name of the affected package in the registry.
+    pub package: Option<String>,
+
+    /// The URL of the affected git repository.
+    pub git: Option<String>,
+
+    /// A short summary of the issue.
+    pub title: String,
+
+    /// A description of the issue.
+    pub description: Option<String>,
+
+    /// The severity of the issue.
+    pub severity: Severity,
+
+    /// The version requirements matching the affected versions of the package.
+    ///
+    /// If empty, all versions are affected.
+    #[serde(default)]
+    #[builder(default)]
+    pub affected: Vec<String>,
+
+    /// The versions which fix the issue.
+    #[serde(default)]
+    #[builder(default)]
+    pub fixed: Vec<String>,
+
+    /// The affected commits of the git repository.
+    ///
+    /// If empty, all commits are affected.
+    #[serde(default)]
+    #[builder(default)]
+    pub revs: Vec<String>,
+
+    /// A link to more information about the issue.
+    pub url: Option<String>,
+}
+
+/// Whether an advisory affects a lock entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AdvisoryMatch {
+    /// The locked version or commit is affected.
+    Affected,
+
+    /// The locked version or commit is not affected.
+    NotAffected,
+
+    /// The advisory targets the dependency, but the locked version could not be compared with
+    /// the affected ranges (e.g. because it's not a semver version).
+    Unknown,
+}
+
+impl Advisory {
+    /// Check whether the advisory affects a lock entry.
+    pub fn affects(&self, entry: &LockEntry) -> AdvisoryMatch {
+        match entry {
+            LockEntry::Http(_) | LockEntry::Private(_) => {
+                if self.package.as_deref() != Some(entry.name()) {
+                    return AdvisoryMatch::NotAffected;
+                }
+                if self.affected.is_empty() {
+                    return AdvisoryMatch::Affected;
+                }
+                let Ok(version) = Version::parse(entry.version()) else {
+                    debug!(dep = entry.name(), version = entry.version(); "version is not semver, cannot match advisory ranges");
+                    return AdvisoryMatch::Unknown;
+                };
+                let ranges: Vec<_> = self.affected.iter().map(|r| parse_version_req(r)).collect();
+                if ranges.iter().flatten().any(|req| matches_with_prerelease(req, &version)) {
+                    AdvisoryMatch::Affected
+                } else if ranges.iter().any(Option::is_none) {
+                    debug!(id = self.id; "advisory has an invalid version range");
+                    AdvisoryMatch::Unknown
+                } else {
+                    AdvisoryMatch::NotAffected
+                }
+            }
+            LockEntry::Git(lock) => {
+                let Some(git) = &self.git else {
+                    return AdvisoryMatch::NotAffected;
+                };
+                if normalize_git_url(git) == normalize_git_url(&lock.git) &&
+                    (self.revs.is_empty() ||
+                        self.revs
+                            .iter()
+                            .any(|r| lock.rev.starts_with(r) || r.starts_with(&lock.rev)))
+                {
+                    AdvisoryMatch::Affected
+                } else {
+                    AdvisoryMatch::NotAffected
+                }
+            }
+            LockEntry::Path(_) => AdvisoryMatch::NotAffected,
+        }
+    }
+}
+
+/// Check whether a version matches a requirement, including pre-releases.
+///
+/// Semver requirements only match a pre-release if one of their comparators has a pre-release for
+/// the same version. Adding a comparator which is always true for the pre-release (`>=x.y.z-0`)
+/// lifts this restriction, so that the pre-release is compared with the bounds of the range.
+fn matches_with_prerelease(req: &VersionReq, version: &Version) -> bool {
+    if version.pre.is_empty() {
+        return req.matches(version);
+    }
+    let mut req = req.clone();
+    req.comparators.push(Comparator {
+        op: Op::GreaterEq,
+        major: version.major,
+        minor: Some(version.minor),
+        patch: Some(version.patch),
+        pre: Prerelease::new("0").expect("0 should be a valid pre-release"),
+    });
+    req.matches(version)
+}
+
+/// The advisory database.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct AdvisoryDatabase {
+    /// The advisories.
+    #[serde(default)]
+    pub advisories: Vec<Advisory>,
+}
+
+impl AdvisoryDatabase {
+    /// Parse an advisory database in TOML or JSON format, and validate its advisories.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let db: Self = if contents.trim_start().starts_with('{') {
+            serde_json::from_str(contents).map_err(|e| AuditError::ParseError(e.to_string()))?
+        } else {
+            toml_edit::de::from_str(contents).map_err(|e| AuditError::ParseError(e.to_string()))?
+        };
+        for advisory in &db.advisories {
+            if advisory.package.is_none() && advisory.git.is_none() {
+                return Err(AuditError::MissingTarget(advisory.id.clone()));
+            }
+            if let Some(range) = advisory.affected.iter().find(|r| parse_version_req(r).is_none()) {
+                return Err(AuditError::InvalidRange {
+                    id: advisory.id.clone(),
+                    range: range.clone(),
+                });
+            }
+        }
+        Ok(db)
+    }
+}
+
+/// An advisory which affects a locked dependency.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct Finding {
+    /// The name of the dependency.
+    pub name: String,
+
+    /// The locked version of the dependency.
+    pub version: String,
+
+    /// The advisory.
+    pub advisory: Advisory,
+
+    /// Whether the dependency is affected, or the advisory could not be evaluated.
+    pub status: AdvisoryMatch,
+}
+
+/// Get the path of the local copy of the advisory database.
+///
+/// The file is `~/.soldeer/advisory-db.json`, or `.soldeer/advisory-db.json` in the current
+/// directory if the home cannot be found. The path can be overridden by setting the
+/// `SOLDEER_ADVISORY_DB` environment variable.
+pub fn advisory_db_path() -> io::Result<PathBuf> {
+    if let Ok(path) = env::var("SOLDEER_ADVISORY_DB") &&
+        !path.is_empty()
+    {
+        debug!("using advisory database defined in environment variable");
+        return Ok(path.into());
+    }
+    let dir = home::home_dir().unwrap_or(env::current_dir()?);
+    Ok(dir.join(".soldeer").join("advisory-db.json"))
+}
+
+/// Read an advisory database from a file in TOML or JSON format.
+pub fn read_advisory_db(path: impl AsRef<Path>) -> Result<AdvisoryDatabase> {
+    let path = path.as_ref();
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(AuditError::MissingDatabase(path.to_path_buf()));
+        }
+        Err(e) => return Err(AuditError::IOError { path: path.to_path_buf(), source: e }),
+    };
+    let db = AdvisoryDatabase::parse(&contents)?;
+    debug!(path:?, count = db.advisories.len(); "read advisory database");
+    Ok(db)
+}
+
+/// Sync the advisory database from a file or an HTTP(S) URL into the local copy at `path`.
+///
+/// The source is validated before the local copy is replaced.
+pub async fn sync_advisory_db(source: &str, path: impl AsRef<Path>) -> Result<AdvisoryDatabase> {
+    let path = path.as_ref();
+    let contents = if source.starts_with("http://") || source.starts_with("https://") {
+        debug!(url = source; "downloading advisory database");
+        Client::new().get(source).send().await?.error_for_status()?.text().await?
+    } else {
+        fs::read_to_string(source)
+            .map_err(|e| AuditError::IOError { path: source.into(), source: e })?
+    };
+    let db = AdvisoryDatabase::parse(&contents)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AuditError::IOError { path: parent.to_path_buf(), source: e })?;
+    }
+    let json = serde_json::to_string_pretty(&db).expect("database should be serializable");
+    fs::write(path, json)
+        .map_err(|e| AuditError::IOError { path: path.to_path_buf(), source: e })?;
+    debug!(path:?, count = db.advisories.len(); "synced advisory database");
+    Ok(db)
+}
+
+/// Find the advisories which affect the lock entries.
+///
+/// Advisories which could not be evaluated against a lock entry are also returned, with the
+/// [`AdvisoryMatch::Unknown`] status. Advisories whose ID is in the `ignore` list are skipped.
+pub fn audit(db: &AdvisoryDatabase, entries: &[LockEntry], ignore: &[String]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for entry in entries {
+        for advisory in &db.advisories {
+            if ignore.contains(&advisory.id) {
+                continue;
+            }
+            let status = advisory.affects(entry);
+            match status {
+                AdvisoryMatch::NotAffected => continue,
+                AdvisoryMatch::Affected => {
+                    debug!(dep = entry.name(), version = entry.version(), id = advisory.id; "dependency is affected by advisory");
+                }
+                AdvisoryMatch::Unknown => {
+                    warn!(dep = entry.name(), version = entry.version(), id = advisory.id; "could not evaluate advisory against the locked version");
+                }
+            }
+            findings.push(Finding {
+                name: entry.name().to_string(),
+                version: entry.version().to_string(),
+                advisory: advisory.clone(),
+                status,
+            });
+        }
+    }
+    findings
+}
+
+/// Normalize a git URL so that the HTTPS and SSH URLs of a repository are equal.
+pub(crate) fn normalize_git_url(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/').trim_end_matches(".git").to_lowercase();
+    let url = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+    let url = url.split_once('@').map_or(url, |(_, rest)| rest);
+    url.replacen(':', "/", 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock::{GitLockEntry, HttpLockEntry};
+    use testdir::testdir;
+
+    const DB: &str = r#"[[advisories]]
+id = "SOL-1"
+package = "lib"
+title = "Reentrancy"
+severity = "high"
+affected = [">=1.0.0, <1.2.0", "2.0.0"]
+fixed = ["1.2.0", "2.0.1"]
+
+[[advisories]]
+id = "SOL-2"
+git = "https://github.com/org/repo"
+revs = ["abcdef"]
+title = "Broken"
+severity = "critical"
+"#;
+
+    fn http_entry(name: &str, version: &str) -> LockEntry {
+        HttpLockEntry::builder()
+            .name(name)
+            .version(version)
+            .url("https://example.com/lib.zip")
+            .checksum("")
+            .integrity("")
+            .build()
+            .into()
+    }
+
+    #[test]
+    fn test_parse_database() {
+        let db = AdvisoryDatabase::parse(DB).unwrap();
+        assert_eq!(db.advisories.len(), 2);
+        assert_eq!(db.advisories[0].severity, Severity::High);
+        let json = serde_json::to_string(&db).unwrap();
+        assert_eq!(AdvisoryDatabase::parse(&json).unwrap(), db);
+
+        let res = AdvisoryDatabase::parse(&DB.replace("2.0.0\"]", "foo\"]"));
+        assert!(matches!(res, Err(AuditError::InvalidRange { .. })), "{res:?}");
+        let res = AdvisoryDatabase::parse(&DB.replace("package = \"lib\"\n", ""));
+        assert!(matches!(res, Err(AuditError::MissingTarget(_))), "{res:?}");
+        assert!(Severity::Medium < Severity::High);
+        assert_eq!("Critical".parse::<Severity>().unwrap(), Severity::Critical);
+    }
+
+    #[test]
+    fn test_audit() {
+        let db = AdvisoryDatabase::parse(DB).unwrap();
+        let entries = vec![
+            http_entry("lib", "1.1.0"),
+            http_entry("lib", "1.2.0"),
+            http_entry("lib", "2.0.0"),
+            http_entry("other", "1.1.0"),
+            GitLockEntry::builder()
+                .name("repo")
+                .version("1.0.0")
+                .git("git@github.com:org/repo.git")
+                .rev("abcdef0123456789")
+                .build()
+                .into(),
+        ];
+        let findings = audit(&db, &entries, &[]);
+        let found: Vec<_> =
+            findings.iter().map(|f| (f.advisory.id.as_str(), f.version.as_str())).collect();
+        assert_eq!(found, vec![("SOL-1", "1.1.0"), ("SOL-1", "2.0.0"), ("SOL-2", "1.0.0")]);
+        assert!(findings.iter().all(|f| f.status == AdvisoryMatch::Affected));
+
+        let findings = audit(&db, &entries, &["SOL-1".to_string()]);
+        assert_eq!(findings.len(), 1);
+    }
+
+    #[test]
+    fn test_affects_prerelease() {
+        let db = AdvisoryDatabase::parse(DB).unwrap();
+        let advisory = &db.advisories[0];
+        for (version, expected) in [
+            ("1.1.0-rc.1", AdvisoryMatch::Affected),
+            ("1.0.0-beta", AdvisoryMatch::NotAffected),
+            ("1.2.0-rc.1", AdvisoryMatch::Affected),
+            ("2.0.0-rc.1", AdvisoryMatch::NotAffected),
+            ("1.3.0-rc.1", AdvisoryMatch::NotAffected),
+        ] {
+            assert_eq!(advisory.affects(&http_entry("lib", version)), expected, "{version}");
+        }
+    }
+
+    #[test]
+    fn test_affects_unknown() {
+        let db = AdvisoryDatabase::parse(DB).unwrap();
+        let entries = vec![http_entry("lib", "v1-final"), http_entry("other", "v1-final")];
+        assert_eq!(db.advisories[0].affects(&entries[0]), AdvisoryMatch::Unknown);
+        let findings = audit(&db, &entries, &[]);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].status, AdvisoryMatch::Unknown);
+        assert!(audit(&db, &entries, &["SOL-1".to_string()]).is_empty());
+
+        // an advisory with an invalid range which doesn't match otherwise can't be evaluated
+        let advisory = Advisory::builder()
+            .id("SOL-3")
+            .package("lib")
+            .title("Invalid")
+            .severity(Severity::Low)
+            .affected(vec!["foo".to_string(), "<1.0.0".to_string()])
+            .build();
+        assert_eq!(advisory.affects(&http_entry("lib", "1.0.0")), AdvisoryMatch::Unknown);
+        assert_eq!(advisory.affects(&http_entry("lib", "0.9.0")), AdvisoryMatch::Affected);
+    }
+
+    #[tokio::test]
+    async fn test_sync_database() {
+        let dir = testdir!();
+        let source = dir.join("advisories.toml");
+        fs::write(&source, DB).unwrap();
+        let path = dir.join("db/advisory-db.json");
+        let res = read_advisory_db(&path);
+        assert!(matches!(res, Err(AuditError::MissingDatabase(_))), "{res:?}");
+
+        let db = sync_advisory_db(&source.to_string_lossy(), &path).await.unwrap();
+        assert_eq!(read_advisory_db(&path).unwrap(), db);
+
+        // an invalid source doesn't replace the local copy
+        fs::write(&source, "[[advisories]]\nid = 1").unwrap();
+        assert!(sync_advisory_db(&source.to_string_lossy(), &path).await.is_err());
+        assert_eq!(read_advisory_db(&path).unwrap(), db);
+    }
+}
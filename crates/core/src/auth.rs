@@ -1,5 +1,8 @@
 //! Registry authentication
-use crate::{errors::AuthError, registry::api_url, utils::login_file_path};
+use crate::{
+    errors::AuthError,
+    registry::{Registry, RegistryAuth},
+};
 use log::{debug, info, warn};
 use reqwest::{
     Client, StatusCode,
@@ -25,16 +28,31 @@ pub struct LoginResponse {
     pub token: String,
 }
 
-/// Get the JWT token from the environment or from the login file
+/// Get the JWT token for a registry from its auth source
 ///
-/// Precedence is given to the `SOLDEER_API_TOKEN` environment variable.
-pub fn get_token() -> Result<String> {
-    if let Ok(token) = env::var("SOLDEER_API_TOKEN") &&
-        !token.is_empty()
-    {
-        return Ok(token)
-    }
-    let token_path = login_file_path()?;
+/// If the auth source has both an environment variable and a file, precedence is given to the
+/// environment variable.
+pub fn get_token(registry: &Registry) -> Result<String> {
+    let token_path = match &registry.auth {
+        RegistryAuth::EnvOrFile { var, file } => {
+            if let Ok(token) = env::var(var) &&
+                !token.is_empty()
+            {
+                debug!(registry = registry.name, var; "token retrieved from env");
+                return Ok(token);
+            }
+            file.clone()
+        }
+        RegistryAuth::Env(var) => {
+            return env::var(var)
+                .ok()
+                .filter(|t| !t.is_empty())
+                .ok_or(AuthError::MissingToken)
+                .inspect(|_| debug!(registry = registry.name, var; "token retrieved from env"));
+        }
+        RegistryAuth::File(path) => path.clone(),
+        RegistryAuth::None => return Err(AuthError::MissingToken),
+    };
     let jwt =
         fs::read_to_string(&token_path).map_err(|_| AuthError::MissingToken)?.trim().to_string();
     if jwt.is_empty() {
@@ -45,10 +63,29 @@ pub fn get_token() -> Result<String> {
     Ok(jwt)
 }
 
+/// Get the path of the file where the token of a registry is saved
+///
+/// Tokens provided through an environment variable can't be saved. The parent folder of the file is
+/// created if needed.
+fn token_file_path(registry: &Registry) -> Result<PathBuf> {
+    let path = match &registry.auth {
+        RegistryAuth::EnvOrFile { file, .. } | RegistryAuth::File(file) => file.clone(),
+        RegistryAuth::Env(_) | RegistryAuth::None => {
+            return Err(AuthError::TokenNotWritable(registry.name.clone()));
+        }
+    };
+    if let Some(parent) = path.parent() &&
+        !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(path)
+}
+
 /// Get a header map with the bearer token set up if it exists
-pub fn get_auth_headers() -> Result<HeaderMap> {
+pub fn get_auth_headers(registry: &Registry) -> Result<HeaderMap> {
     let mut headers: HeaderMap = HeaderMap::new();
-    let Ok(token) = get_token() else {
+    let Ok(token) = get_token(registry) else {
         return Ok(headers);
     };
     let header_value =
@@ -57,17 +94,17 @@ pub fn get_auth_headers() -> Result<HeaderMap> {
     Ok(headers)
 }
 
-/// Save an access token in the login file
-pub fn save_token(token: &str) -> Result<PathBuf> {
-    let token_path = login_file_path()?;
+/// Save an access token in the token file of a registry
+pub fn save_token(registry: &Registry, token: &str) -> Result<PathBuf> {
+    let token_path = token_file_path(registry)?;
     fs::write(&token_path, token)?;
     Ok(token_path)
 }
 
 /// Retrieve user profile for the token to check its validity, returning the username
-pub async fn check_token(token: &str) -> Result<String> {
+pub async fn check_token(registry: &Registry, token: &str) -> Result<String> {
     let client = Client::new();
-    let url = api_url("v1", "auth/validate-cli-token", &[]);
+    let url = registry.api_url("v1", "auth/validate-cli-token", &[]);
     let mut headers: HeaderMap = HeaderMap::new();
     let header_value =
         HeaderValue::from_str(&format!("Bearer {token}")).map_err(|_| AuthError::InvalidToken)?;
@@ -95,14 +132,14 @@ pub async fn check_token(token: &str) -> Result<String> {
     }
 }
 
-/// Execute the login request and store the JWT token in the login file
-pub async fn execute_login(login: &Credentials) -> Result<PathBuf> {
+/// Execute the login request and store the JWT token in the token file of the registry
+pub async fn execute_login(registry: &Registry, login: &Credentials) -> Result<PathBuf> {
     warn!(
         "the option to login via email and password will be removed in a future version of Soldeer. Please update your usage by either using `soldeer login --token [YOUR CLI TOKEN]` or passing the `SOLDEER_API_TOKEN` environment variable to the `push` command."
     );
 
-    let token_path = login_file_path()?;
-    let url = api_url("v1", "auth/login", &[]);
+    let token_path = token_file_path(registry)?;
+    let url = registry.api_url("v1", "auth/login", &[]);
     let client = Client::new();
     let res = client.post(url).json(login).send().await?;
     match res.status() {
@@ -123,9 +160,14 @@ pub async fn execute_login(login: &Credentials) -> Result<PathBuf> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use temp_env::{async_with_vars, with_var};
+    use crate::registry::DEFAULT_REGISTRY;
+    use temp_env::with_var;
     use testdir::testdir;
 
+    fn registry(url: &str, auth: RegistryAuth) -> Registry {
+        Registry::new(DEFAULT_REGISTRY, url.parse().unwrap(), auth)
+    }
+
     #[tokio::test]
     async fn test_login_success() {
         let mut server = mockito::Server::new_async().await;
@@ -138,15 +180,9 @@ mod tests {
             .await;
 
         let test_file = testdir!().join("test_save_jwt");
-        let res = async_with_vars(
-            [
-                ("SOLDEER_API_URL", Some(server.url())),
-                ("SOLDEER_LOGIN_FILE", Some(test_file.to_string_lossy().to_string())),
-            ],
-            execute_login(&Credentials {
-                email: "test@test.com".to_string(),
-                password: "1234".to_string(),
-            }),
+        let res = execute_login(
+            &registry(&server.url(), RegistryAuth::File(test_file.clone())),
+            &Credentials { email: "test@test.com".to_string(), password: "1234".to_string() },
         )
         .await;
         assert!(res.is_ok(), "{res:?}");
@@ -166,15 +202,9 @@ mod tests {
             .await;
 
         let test_file = testdir!().join("test_save_jwt");
-        let res = async_with_vars(
-            [
-                ("SOLDEER_API_URL", Some(server.url())),
-                ("SOLDEER_LOGIN_FILE", Some(test_file.to_string_lossy().to_string())),
-            ],
-            execute_login(&Credentials {
-                email: "test@test.com".to_string(),
-                password: "1234".to_string(),
-            }),
+        let res = execute_login(
+            &registry(&server.url(), RegistryAuth::File(test_file.clone())),
+            &Credentials { email: "test@test.com".to_string(), password: "1234".to_string() },
         )
         .await;
         assert!(matches!(res, Err(AuthError::InvalidCredentials)), "{res:?}");
@@ -192,15 +222,9 @@ mod tests {
             .await;
 
         let test_file = testdir!().join("test_save_jwt");
-        let res = async_with_vars(
-            [
-                ("SOLDEER_API_URL", Some(server.url())),
-                ("SOLDEER_LOGIN_FILE", Some(test_file.to_string_lossy().to_string())),
-            ],
-            execute_login(&Credentials {
-                email: "test@test.com".to_string(),
-                password: "1234".to_string(),
-            }),
+        let res = execute_login(
+            &registry(&server.url(), RegistryAuth::File(test_file.clone())),
+            &Credentials { email: "test@test.com".to_string(), password: "1234".to_string() },
         )
         .await;
         assert!(matches!(res, Err(AuthError::HttpError(_))), "{res:?}");
@@ -219,9 +243,7 @@ mod tests {
             .create_async()
             .await;
 
-        let res =
-            async_with_vars([("SOLDEER_API_URL", Some(server.url()))], check_token("eyJ0..."))
-                .await;
+        let res = check_token(&registry(&server.url(), RegistryAuth::None), "eyJ0...").await;
         assert!(res.is_ok(), "{res:?}");
         assert_eq!(res.unwrap(), "test");
     }
@@ -237,15 +259,56 @@ mod tests {
             .create_async()
             .await;
 
-        let res =
-            async_with_vars([("SOLDEER_API_URL", Some(server.url()))], check_token("foobar")).await;
+        let res = check_token(&registry(&server.url(), RegistryAuth::None), "foobar").await;
         assert!(res.is_err(), "{res:?}");
     }
 
     #[test]
     fn test_get_token_env() {
-        let res = with_var("SOLDEER_API_TOKEN", Some("test"), get_token);
+        let registry = Registry::soldeer();
+        let res = with_var("SOLDEER_API_TOKEN", Some("test"), || get_token(&registry));
         assert!(res.is_ok(), "{res:?}");
         assert_eq!(res.unwrap(), "test");
     }
+
+    #[test]
+    fn test_get_token_custom_env() {
+        let registry =
+            registry("https://registry.example.com", RegistryAuth::Env("MY_TOKEN".to_string()));
+        let res = with_var("MY_TOKEN", Some("test"), || get_token(&registry));
+        assert!(res.is_ok(), "{res:?}");
+        assert_eq!(res.unwrap(), "test");
+        let res = with_var("MY_TOKEN", None::<&str>, || get_token(&registry));
+        assert!(matches!(res, Err(AuthError::MissingToken)), "{res:?}");
+        let res = save_token(&registry, "test");
+        assert!(matches!(res, Err(AuthError::TokenNotWritable(_))), "{res:?}");
+    }
+
+    #[test]
+    fn test_save_token_custom_file() {
+        let token_file = testdir!().join("token");
+        let registry =
+            registry("https://registry.example.com", RegistryAuth::File(token_file.clone()));
+        let res = save_token(&registry, "test");
+        assert!(res.is_ok(), "{res:?}");
+        assert_eq!(fs::read_to_string(&token_file).unwrap(), "test");
+        let res = get_token(&registry);
+        assert!(res.is_ok(), "{res:?}");
+        assert_eq!(res.unwrap(), "test");
+    }
+
+    #[test]
+    fn test_get_token_env_or_file() {
+        let token_file = testdir!().join("login");
+        let registry = registry(
+            "https://registry.example.com",
+            RegistryAuth::EnvOrFile { var: "MY_TOKEN".to_string(), file: token_file },
+        );
+        let res = save_token(&registry, "from_file");
+        assert!(res.is_ok(), "{res:?}");
+        let res = with_var("MY_TOKEN", None::<&str>, || get_token(&registry));
+        assert_eq!(res.unwrap(), "from_file");
+        let res = with_var("MY_TOKEN", Some("from_env"), || get_token(&registry));
+        assert_eq!(res.unwrap(), "from_env");
+    }
 }
@@ -0,0 +1,479 @@
+//! Global package cache.
+//!
+//! Archives downloaded from the registry or from a custom URL are stored in a global cache folder
+//! which is shared by all projects (`~/.soldeer/cache` by default). The files are named after the
+//! SHA256 checksum of their contents and the extension of their format (e.g. `<checksum>.zip` or
+//! `<checksum>.tar.gz`), the checksum being the `checksum` field of the lockfile entries. When
+//! installing a dependency with a known checksum, the archive is copied from the cache instead of
+//! being downloaded again.
+//!
+//! The modification time of each cached file is updated whenever it's used, so that unused files
+//! can be pruned.
+//!
+//! The cache also contains an index of the URLs from which the archives were downloaded (in the
+//! `urls` subfolder), and the last revision listing retrieved from the registry for each package
+//! (in the `revisions` subfolder). They are used in offline mode to resolve versions and find
+//! archives without contacting the registry.
+//!
+//! Finally, the `store` subfolder contains the packages which were extracted for a
+//! [`crate::config::LinkMode`] other than `copy`.
+use crate::{
+    archive::ArchiveFormat,
+    errors::CacheError,
+    utils::{hash_content, hash_file, sanitize_filename},
+};
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+pub type Result<T> = std::result::Result<T, CacheError>;
+
+/// The subfolder of the cache which contains the URL index.
+const URLS_DIR: &str = "urls";
+
+/// The subfolder of the cache which contains the revision listings.
+const REVISIONS_DIR: &str = "revisions";
+
+/// The subfolder of the cache which contains the extracted package store (see [`crate::store`]).
+pub(crate) const STORE_DIR: &str = "store";
+
+/// A file of the global package cache.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, bon::Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct CacheEntry {
+    /// The SHA256 checksum of the archive, which is also its file name without the extension.
+    pub checksum: String,
+
+    /// The path to the archive.
+    pub path: PathBuf,
+
+    /// The size of the archive in bytes.
+    pub size: u64,
+
+    /// The last time the archive was added to the cache or used for an install.
+    pub last_used: DateTime<Utc>,
+}
+
+/// Get the path to the global cache folder.
+///
+/// The folder is `~/.soldeer/cache`, or `.soldeer/cache` in the current directory if the home
+/// cannot be found. It can be overridden by setting the `SOLDEER_CACHE_DIR` environment variable.
+///
+/// The folder is not created by this function.
+pub fn cache_dir() -> Result<PathBuf> {
+    if let Ok(dir) = env::var("SOLDEER_CACHE_DIR") &&
+        !dir.is_empty()
+    {
+        debug!("using cache folder defined in environment variable");
+        return Ok(dir.into());
+    }
+    let dir = match home::home_dir() {
+        Some(dir) => dir,
+        None => {
+            env::current_dir().map_err(|e| CacheError::IOError { path: ".".into(), source: e })?
+        }
+    };
+    Ok(dir.join(".soldeer").join("cache"))
+}
+
+/// Get the path of a cached archive from its checksum, if it exists in the cache.
+///
+/// The integrity of the file is checked with [`hash_file`]. If the contents don't match the
+/// checksum, the file is removed from the cache and `None` is returned.
+pub fn get_cached_archive(checksum: &str) -> Result<Option<PathBuf>> {
+    let Some(path) = find_archive(&cache_dir()?, checksum) else {
+        debug!(checksum; "archive not found in cache");
+        return Ok(None);
+    };
+    let actual =
+        hash_file(&path).map_err(|e| CacheError::IOError { path: path.clone(), source: e })?;
+    if actual.0 != checksum {
+        warn!(path:?, actual:% = actual; "cached archive is corrupted, removing it");
+        fs::remove_file(&path)
+            .map_err(|e| CacheError::IOError { path: path.clone(), source: e })?;
+        return Ok(None);
+    }
+    touch(&path)?;
+    debug!(path:?; "found archive in cache");
+    Ok(Some(path))
+}
+
+/// Copy an archive into the cache.
+///
+/// The checksum must be the SHA256 hash of the archive's contents. The file is first copied to a
+/// temporary file and then renamed, so that concurrent installs never see a partial archive.
+pub fn add_to_cache(archive: impl AsRef<Path>, checksum: &str) -> Result<PathBuf> {
+    let dir = cache_dir()?;
+    fs::create_dir_all(&dir).map_err(|e| CacheError::IOError { path: dir.clone(), source: e })?;
+    if let Some(path) = find_archive(&dir, checksum) {
+        touch(&path)?;
+        return Ok(path);
+    }
+    let format = ArchiveFormat::detect_file(archive.as_ref())
+        .map_err(|e| CacheError::IOError { path: archive.as_ref().to_path_buf(), source: e })?
+        .unwrap_or_default();
+    let path = dir.join(format!("{checksum}.{}", format.extension()));
+    let tmp_path = dir.join(format!("{checksum}.{}.tmp", uuid::Uuid::new_v4()));
+    fs::copy(archive.as_ref(), &tmp_path)
+        .map_err(|e| CacheError::IOError { path: tmp_path.clone(), source: e })?;
+    fs::rename(&tmp_path, &path)
+        .map_err(|e| CacheError::IOError { path: path.clone(), source: e })?;
+    debug!(path:?; "added archive to cache");
+    Ok(path)
+}
+
+/// Record the URL from which an archive was downloaded.
+///
+/// This allows to find the archive by URL in offline mode, when its checksum is not known yet.
+pub fn record_url(url: &str, checksum: &str) -> Result<()> {
+    let path = url_index_path(url)?;
+    write_atomic(&path, checksum)?;
+    debug!(url, checksum; "recorded archive URL in cache");
+    Ok(())
+}
+
+/// Get the path and checksum of a cached archive from the URL it was downloaded from.
+///
+/// The integrity of the archive is checked like for [`get_cached_archive`].
+pub fn get_cached_archive_by_url(url: &str) -> Result<Option<(PathBuf, String)>> {
+    let path = url_index_path(url)?;
+    if !path.is_file() {
+        debug!(url; "URL not found in cache index");
+        return Ok(None);
+    }
+    let checksum = fs::read_to_string(&path)
+        .map_err(|e| CacheError::IOError { path: path.clone(), source: e })?
+        .trim()
+        .to_string();
+    Ok(get_cached_archive(&checksum)?.map(|archive| (archive, checksum)))
+}
+
+/// Save the revision listing of a package, as returned by the registry.
+///
+/// Listings are stored separately for each registry, identified by its host (e.g.
+/// `api.soldeer.xyz`).
+pub fn cache_revisions(registry: &str, name: &str, listing: &str) -> Result<()> {
+    let path = revisions_path(registry, name)?;
+    write_atomic(&path, listing)?;
+    debug!(name; "saved revision listing in cache");
+    Ok(())
+}
+
+/// Get the last revision listing of a package which was retrieved from the registry, if any.
+pub fn get_cached_revisions(registry: &str, name: &str) -> Result<Option<String>> {
+    let path = revisions_path(registry, name)?;
+    if !path.is_file() {
+        debug!(name; "revision listing not found in cache");
+        return Ok(None);
+    }
+    fs::read_to_string(&path).map(Some).map_err(|e| CacheError::IOError { path, source: e })
+}
+
+/// List the archives in the cache, sorted by checksum.
+pub fn list_cache() -> Result<Vec<CacheEntry>> {
+    let dir = cache_dir()?;
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for entry in
+        fs::read_dir(&dir).map_err(|e| CacheError::IOError { path: dir.clone(), source: e })?
+    {
+        let entry = entry.map_err(|e| CacheError::IOError { path: dir.clone(), source: e })?;
+        let path = entry.path();
+        let Some(checksum) = path.file_name().and_then(|n| archive_checksum(&n.to_string_lossy()))
+        else {
+            continue;
+        };
+        let metadata =
+            entry.metadata().map_err(|e| CacheError::IOError { path: path.clone(), source: e })?;
+        let modified = metadata
+            .modified()
+            .map_err(|e| CacheError::IOError { path: path.clone(), source: e })?;
+        entries.push(
+            CacheEntry::builder()
+                .checksum(checksum)
+                .path(path)
+                .size(metadata.len())
+                .last_used(modified.into())
+                .build(),
+        );
+    }
+    entries.sort_unstable_by(|a, b| a.checksum.cmp(&b.checksum));
+    Ok(entries)
+}
+
+/// Remove all archives, URL index entries, revision listings and extracted packages from the cache.
+///
+/// Dependencies which are symlinked to the store need to be installed again afterwards.
+///
+/// The removed archives are returned.
+pub fn clean_cache() -> Result<Vec<CacheEntry>> {
+    let entries = list_cache()?;
+    for entry in &entries {
+        remove_entry(entry)?;
+    }
+    remove_temp_files()?;
+    let dir = cache_dir()?;
+    for subfolder in [URLS_DIR, REVISIONS_DIR, STORE_DIR] {
+        let path = dir.join(subfolder);
+        if path.is_dir() {
+            fs::remove_dir_all(&path).map_err(|e| CacheError::IOError { path, source: e })?;
+        }
+    }
+    Ok(entries)
+}
+
+/// Remove the archives which were not used for the given duration, as well as corrupted archives.
+///
+/// The removed entries are returned.
+pub fn prune_cache(max_age: Duration) -> Result<Vec<CacheEntry>> {
+    let threshold: DateTime<Utc> = (SystemTime::now() - max_age).into();
+    let mut removed = Vec::new();
+    for entry in list_cache()? {
+        let corrupted = hash_file(&entry.path)
+            .map_err(|e| CacheError::IOError { path: entry.path.clone(), source: e })?
+            .0 !=
+            entry.checksum;
+        if corrupted || entry.last_used < threshold {
+            debug!(path:? = entry.path, corrupted; "pruning cached archive");
+            remove_entry(&entry)?;
+            removed.push(entry);
+        }
+    }
+    remove_temp_files()?;
+    remove_dangling_urls()?;
+    Ok(removed)
+}
+
+/// Remove a cached archive.
+fn remove_entry(entry: &CacheEntry) -> Result<()> {
+    fs::remove_file(&entry.path)
+        .map_err(|e| CacheError::IOError { path: entry.path.clone(), source: e })
+}
+
+/// Remove leftover temporary files from interrupted copies.
+fn remove_temp_files() -> Result<()> {
+    let dir = cache_dir()?;
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in
+        fs::read_dir(&dir).map_err(|e| CacheError::IOError { path: dir.clone(), source: e })?
+    {
+        let path = entry.map_err(|e| CacheError::IOError { path: dir.clone(), source: e })?.path();
+        if path.extension().is_some_and(|ext| ext == "tmp") {
+            fs::remove_file(&path)
+                .map_err(|e| CacheError::IOError { path: path.clone(), source: e })?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove the URL index entries which point to archives that are not in the cache anymore.
+fn remove_dangling_urls() -> Result<()> {
+    let dir = cache_dir()?;
+    let urls = dir.join(URLS_DIR);
+    if !urls.is_dir() {
+        return Ok(());
+    }
+    for entry in
+        fs::read_dir(&urls).map_err(|e| CacheError::IOError { path: urls.clone(), source: e })?
+    {
+        let path = entry.map_err(|e| CacheError::IOError { path: urls.clone(), source: e })?.path();
+        let checksum = fs::read_to_string(&path)
+            .map_err(|e| CacheError::IOError { path: path.clone(), source: e })?;
+        if find_archive(&dir, checksum.trim()).is_none() {
+            fs::remove_file(&path).map_err(|e| CacheError::IOError { path, source: e })?;
+        }
+    }
+    Ok(())
+}
+
+/// Find the archive with the given checksum in the cache folder, whatever its format.
+pub(crate) fn find_archive(dir: &Path, checksum: &str) -> Option<PathBuf> {
+    ArchiveFormat::ALL
+        .iter()
+        .map(|format| dir.join(format!("{checksum}.{}", format.extension())))
+        .find(|path| path.is_file())
+}
+
+/// Get the checksum of a cached archive from its file name.
+///
+/// Returns `None` if the file is not an archive (e.g. a temporary file).
+fn archive_checksum(file_name: &str) -> Option<String> {
+    ArchiveFormat::ALL.iter().find_map(|format| {
+        file_name
+            .strip_suffix(&format!(".{}", format.extension()))
+            .filter(|checksum| !checksum.is_empty() && !checksum.contains('.'))
+            .map(ToString::to_string)
+    })
+}
+
+/// The path of the URL index entry for a URL.
+///
+/// Since URLs can't be used as filenames, the file is named after the hash of the URL.
+fn url_index_path(url: &str) -> Result<PathBuf> {
+    let hash = const_hex::encode(hash_content(&mut url.as_bytes()));
+    Ok(cache_dir()?.join(URLS_DIR).join(hash))
+}
+
+/// The path of the cached revision listing for a package of a registry.
+fn revisions_path(registry: &str, name: &str) -> Result<PathBuf> {
+    Ok(cache_dir()?
+        .join(REVISIONS_DIR)
+        .join(sanitize_filename(registry))
+        .join(sanitize_filename(&format!("{name}.json"))))
+}
+
+/// Write a file by writing a temporary file first and renaming it, creating the parent folder if
+/// needed.
+fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let dir = path.parent().expect("cache files should have a parent folder");
+    fs::create_dir_all(dir)
+        .map_err(|e| CacheError::IOError { path: dir.to_path_buf(), source: e })?;
+    let tmp_path = dir.join(format!("{}.tmp", uuid::Uuid::new_v4()));
+    fs::write(&tmp_path, contents)
+        .map_err(|e| CacheError::IOError { path: tmp_path.clone(), source: e })?;
+    fs::rename(&tmp_path, path)
+        .map_err(|e| CacheError::IOError { path: path.to_path_buf(), source: e })
+}
+
+/// Mark a cached archive as used now.
+fn touch(path: &Path) -> Result<()> {
+    fs::File::options()
+        .append(true)
+        .open(path)
+        .and_then(|f| f.set_modified(SystemTime::now()))
+        .map_err(|e| CacheError::IOError { path: path.to_path_buf(), source: e })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_env::with_var;
+    use testdir::testdir;
+
+    fn create_archive(dir: &Path, contents: &str) -> (PathBuf, String) {
+        let path = dir.join("archive.zip");
+        fs::write(&path, contents).unwrap();
+        let checksum = hash_file(&path).unwrap().0;
+        (path, checksum)
+    }
+
+    #[test]
+    fn test_add_and_get_cached_archive() {
+        let dir = testdir!();
+        let cache = dir.join("cache");
+        let (archive, checksum) = create_archive(&dir, "foo");
+        with_var("SOLDEER_CACHE_DIR", Some(&cache), || {
+            assert!(get_cached_archive(&checksum).unwrap().is_none());
+            let path = add_to_cache(&archive, &checksum).unwrap();
+            assert_eq!(path, cache.join(format!("{checksum}.zip")));
+            assert_eq!(get_cached_archive(&checksum).unwrap(), Some(path));
+            let entries = list_cache().unwrap();
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].checksum, checksum);
+            assert_eq!(entries[0].size, 3);
+        });
+    }
+
+    #[test]
+    fn test_add_to_cache_tarball() {
+        let dir = testdir!();
+        let cache = dir.join("cache");
+        let archive = dir.join("archive.tar.gz");
+        fs::write(&archive, [0x1f, 0x8b, 0x08, 0x00]).unwrap();
+        let checksum = hash_file(&archive).unwrap().0;
+        with_var("SOLDEER_CACHE_DIR", Some(&cache), || {
+            let path = add_to_cache(&archive, &checksum).unwrap();
+            assert_eq!(path, cache.join(format!("{checksum}.tar.gz")));
+            assert_eq!(get_cached_archive(&checksum).unwrap(), Some(path.clone()));
+            let entries = list_cache().unwrap();
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].checksum, checksum);
+            assert_eq!(entries[0].path, path);
+        });
+    }
+
+    #[test]
+    fn test_get_cached_archive_corrupted() {
+        let dir = testdir!();
+        let cache = dir.join("cache");
+        let (archive, checksum) = create_archive(&dir, "foo");
+        with_var("SOLDEER_CACHE_DIR", Some(&cache), || {
+            let path = add_to_cache(&archive, &checksum).unwrap();
+            fs::write(&path, "bar").unwrap();
+            assert!(get_cached_archive(&checksum).unwrap().is_none());
+            assert!(!path.exists());
+        });
+    }
+
+    #[test]
+    fn test_cached_archive_by_url() {
+        let dir = testdir!();
+        let cache = dir.join("cache");
+        let (archive, checksum) = create_archive(&dir, "foo");
+        let url = "https://example.com/foo.zip";
+        with_var("SOLDEER_CACHE_DIR", Some(&cache), || {
+            assert!(get_cached_archive_by_url(url).unwrap().is_none());
+            let path = add_to_cache(&archive, &checksum).unwrap();
+            record_url(url, &checksum).unwrap();
+            assert_eq!(get_cached_archive_by_url(url).unwrap(), Some((path, checksum.clone())));
+
+            // the index entry is removed once the archive is pruned
+            prune_cache(Duration::ZERO).unwrap();
+            assert!(get_cached_archive_by_url(url).unwrap().is_none());
+            assert!(!url_index_path(url).unwrap().exists());
+        });
+    }
+
+    #[test]
+    fn test_cached_revisions() {
+        let dir = testdir!();
+        let cache = dir.join("cache");
+        with_var("SOLDEER_CACHE_DIR", Some(&cache), || {
+            let registry = "api.soldeer.xyz";
+            assert!(get_cached_revisions(registry, "forge-std").unwrap().is_none());
+            cache_revisions(registry, "forge-std", "{}").unwrap();
+            assert_eq!(
+                get_cached_revisions(registry, "forge-std").unwrap(),
+                Some("{}".to_string())
+            );
+            assert!(get_cached_revisions("localhost", "forge-std").unwrap().is_none());
+            clean_cache().unwrap();
+            assert!(get_cached_revisions(registry, "forge-std").unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn test_prune_and_clean_cache() {
+        let dir = testdir!();
+        let cache = dir.join("cache");
+        let (archive, checksum) = create_archive(&dir, "foo");
+        with_var("SOLDEER_CACHE_DIR", Some(&cache), || {
+            add_to_cache(&archive, &checksum).unwrap();
+            fs::write(cache.join("1234.zip"), "corrupted").unwrap();
+            fs::write(cache.join("1234.abcd.tmp"), "partial").unwrap();
+            let removed = prune_cache(Duration::from_secs(3600)).unwrap();
+            assert_eq!(removed.len(), 1);
+            assert_eq!(removed[0].checksum, "1234");
+            assert!(!cache.join("1234.abcd.tmp").exists());
+            assert_eq!(list_cache().unwrap().len(), 1);
+
+            let removed = prune_cache(Duration::ZERO).unwrap();
+            assert_eq!(removed.len(), 1);
+            assert!(list_cache().unwrap().is_empty());
+
+            add_to_cache(&archive, &checksum).unwrap();
+            let removed = clean_cache().unwrap();
+            assert_eq!(removed.len(), 1);
+            assert!(list_cache().unwrap().is_empty());
+        });
+    }
+}
@@ -3,12 +3,19 @@ use crate::{
     download::{find_install_path, find_install_path_sync},
     errors::ConfigError,
     lock::SOLDEER_LOCK,
+    policy::Policy,
+    registry::{DEFAULT_REGISTRY, Registries, Registry, RegistryAuth},
     remappings::RemappingsLocation,
+    signing::{TrustPolicy, parse_public_key},
+    workspace::find_workspace_root,
 };
 use derive_more::derive::{Display, From, FromStr};
 use log::{debug, warn};
+use path_slash::PathBufExt as _;
+use reqwest::Url;
 use serde::Deserialize;
 use std::{
+    collections::BTreeMap,
     env, fmt, fs,
     path::{Path, PathBuf},
 };
@@ -21,6 +28,7 @@ pub type Result<T> = std::result::Result<T, ConfigError>;
 pub enum UrlType {
     Git(String),
     Http(String),
+    Path(String),
 }
 
 impl UrlType {
@@ -31,6 +39,10 @@ impl UrlType {
     pub fn http(url: impl Into<String>) -> Self {
         Self::Http(url.into())
     }
+
+    pub fn path(path: impl Into<String>) -> Self {
+        Self::Path(path.into())
+    }
 }
 
 /// The paths used by Soldeer.
@@ -73,12 +85,14 @@ pub struct Paths {
 
     /// The path to the dependencies folder (does not need to exist).
     ///
-    /// This is `/dependencies` inside the root directory.
+    /// This is `/dependencies` inside the root directory, or inside the workspace root directory
+    /// if the project is part of a workspace.
     pub dependencies: PathBuf,
 
     /// The path to the lockfile (does not need to exist).
     ///
-    /// This is `/soldeer.lock` inside the root directory.
+    /// This is `/soldeer.lock` inside the root directory, or inside the workspace root directory
+    /// if the project is part of a workspace.
     pub lock: PathBuf,
 
     /// The path to the remappings file (does not need to exist).
@@ -86,6 +100,12 @@ pub struct Paths {
     /// This path gets ignored if the remappings should be generated in the `foundry.toml` file.
     /// This is `/remappings.txt` inside the root directory.
     pub remappings: PathBuf,
+
+    /// The root directory of the workspace which the project belongs to, if any.
+    ///
+    /// This is the root directory itself if the project is the workspace root. See
+    /// [`crate::workspace`].
+    pub workspace: Option<PathBuf>,
 }
 
 impl Paths {
@@ -128,11 +148,7 @@ impl Paths {
     ) -> Result<Self> {
         let root = root.as_ref();
         let config = Self::get_config_path(root, config_location)?;
-        let dependencies = root.join("dependencies");
-        let lock = root.join(SOLDEER_LOCK);
-        let remappings = root.join("remappings.txt");
-
-        Ok(Self { root: root.to_path_buf(), config, dependencies, lock, remappings })
+        Ok(Self::with_config_path(root.to_path_buf(), config))
     }
 
     /// Generate the paths object from a known root directory.
@@ -143,11 +159,20 @@ impl Paths {
     pub fn from_root(root: impl AsRef<Path>) -> Result<Self> {
         let root = dunce::canonicalize(root.as_ref())?;
         let config = Self::get_config_path(&root, None)?;
-        let dependencies = root.join("dependencies");
-        let lock = root.join(SOLDEER_LOCK);
-        let remappings = root.join("remappings.txt");
+        Ok(Self::with_config_path(root, config))
+    }
 
-        Ok(Self { root, config, dependencies, lock, remappings })
+    /// Instantiate the paths from the root directory and the path to the config file.
+    ///
+    /// If the project is part of a workspace, the dependencies folder and the lockfile are shared
+    /// with the other projects of the workspace and located in the workspace root.
+    fn with_config_path(root: PathBuf, config: PathBuf) -> Self {
+        let workspace = find_workspace_root(&root);
+        let shared = workspace.as_ref().unwrap_or(&root);
+        let dependencies = shared.join("dependencies");
+        let lock = shared.join(SOLDEER_LOCK);
+        let remappings = root.join("remappings.txt");
+        Self { root, config, dependencies, lock, remappings, workspace }
     }
 
     /// Get the root directory path.
@@ -198,6 +223,35 @@ impl Paths {
         create_or_modify_config(location, &foundry_path, &soldeer_path)
     }
 
+    /// Read the registries which are available to the project.
+    ///
+    /// The projects of a workspace use the registries declared in the config file of the workspace
+    /// root (see [`read_registries`]).
+    pub fn registries(&self) -> Result<Registries> {
+        read_registries(self.workspace_config())
+    }
+
+    /// Read the trust policy for package signatures.
+    ///
+    /// Like for the registries, the projects of a workspace use the policy declared in the config
+    /// file of the workspace root (see [`read_trust_policy`]).
+    pub fn trust_policy(&self) -> Result<TrustPolicy> {
+        read_trust_policy(self.workspace_config())
+    }
+
+    /// The config file of the workspace root, or the project's config file if the project is not
+    /// part of a workspace.
+    fn workspace_config(&self) -> PathBuf {
+        match &self.workspace {
+            Some(root) if root != &self.root => match detect_config_location(root) {
+                Some(ConfigLocation::Foundry) => root.join("foundry.toml"),
+                Some(ConfigLocation::Soldeer) => root.join("soldeer.toml"),
+                None => self.config.clone(),
+            },
+            _ => self.config.clone(),
+        }
+    }
+
     /// Default Foundry config file path
     pub fn foundry_default() -> PathBuf {
         let root: PathBuf =
@@ -264,6 +318,19 @@ pub struct SoldeerConfig {
     /// Defaults to `false`.
     #[serde(default)]
     pub recursive_deps: bool,
+
+    /// How the dependencies folder is populated from the extracted package store.
+    ///
+    /// Defaults to [`LinkMode::Copy`].
+    #[serde(default)]
+    pub link_mode: LinkMode,
+
+    /// The supply-chain policy for the sources and licenses of dependencies, from the
+    /// `[soldeer.policy]` section.
+    ///
+    /// Defaults to a policy which allows everything.
+    #[serde(default)]
+    pub policy: Policy,
 }
 
 impl Default for SoldeerConfig {
@@ -275,10 +342,37 @@ impl Default for SoldeerConfig {
             remappings_prefix: String::new(),
             remappings_location: RemappingsLocation::default(),
             recursive_deps: false,
+            link_mode: LinkMode::default(),
+            policy: Policy::default(),
         }
     }
 }
 
+/// How the files of a dependency are placed into the dependencies folder.
+///
+/// With [`LinkMode::Hardlink`] and [`LinkMode::Symlink`], each package is extracted (or cloned)
+/// once into a store inside of the global cache folder, and the dependencies folders of all
+/// projects link to it.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(rename_all = "lowercase")]
+pub enum LinkMode {
+    /// Extract or clone each dependency directly into the dependencies folder.
+    #[default]
+    Copy,
+
+    /// Hardlink each file from the store into the dependencies folder.
+    ///
+    /// If hardlinks are not supported (e.g. the store is on another filesystem), the files are
+    /// copied.
+    Hardlink,
+
+    /// Replace the dependency folder by a symbolic link to the folder in the store.
+    ///
+    /// Any modification of the dependency's files affects all projects using it.
+    Symlink,
+}
+
 /// A git identifier used to specify a revision, branch or tag.
 ///
 /// # Examples
@@ -353,8 +447,15 @@ pub struct GitDependency {
     /// An optional relative path to the project's root within the repository.
     ///
     /// The project root is where the soldeer.toml or foundry.toml resides. If no path is provided,
-    /// then the repo's root must contain a Soldeer config.
+    /// then the repo's root must contain a Soldeer config, unless a `subdir` is specified.
     pub project_root: Option<PathBuf>,
+
+    /// An optional relative path to a subdirectory of the repository.
+    ///
+    /// If provided, only this subdirectory (and the files at the root of the repository) is
+    /// checked out with a sparse checkout, and the remappings point into it. It is also used
+    /// as the project root if `project_root` is not provided.
+    pub subdir: Option<String>,
 }
 
 impl fmt::Display for GitDependency {
@@ -389,11 +490,17 @@ pub struct HttpDependency {
     /// name).
     pub url: Option<String>,
 
-    /// An optional relative path to the project's root within the zip file.
+    /// An optional relative path to the project's root within the archive.
     ///
     /// The project root is where the soldeer.toml or foundry.toml resides. If no path is provided,
     /// then the zip's root must contain a Soldeer config.
     pub project_root: Option<PathBuf>,
+
+    /// The name of the registry to download the dependency from.
+    ///
+    /// The registry must be declared in the `[registries]` table of the config file. If omitted,
+    /// the default registry is used. This field is ignored if a `url` is provided.
+    pub registry: Option<String>,
 }
 
 impl fmt::Display for HttpDependency {
@@ -402,10 +509,40 @@ impl fmt::Display for HttpDependency {
     }
 }
 
-/// A git or HTTP dependency config item.
+/// A local path dependency config item.
+///
+/// This struct is used to represent a dependency on a local folder from the config file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, bon::Builder)]
+#[allow(clippy::duplicated_attributes)]
+#[builder(on(String, into), on(PathBuf, into))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, Deserialize))]
+pub struct PathDependency {
+    /// The name of the dependency (user-defined).
+    pub name: String,
+
+    /// The version requirement string.
+    ///
+    /// Since the folder can only provide a single version, this is only used for the install
+    /// folder name and the remappings.
+    #[cfg_attr(feature = "serde", serde(rename = "version"))]
+    pub version_req: String,
+
+    /// The path to the dependency folder.
+    ///
+    /// Relative paths are relative to the project root.
+    pub path: PathBuf,
+}
+
+impl fmt::Display for PathDependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}~{}", self.name, self.version_req)
+    }
+}
+
+/// A git, HTTP or local path dependency config item.
 ///
-/// A builder can be used to create the underlying [`HttpDependency`] or [`GitDependency`] and then
-/// converted into this type with `.into()`.
+/// A builder can be used to create the underlying [`HttpDependency`], [`GitDependency`] or
+/// [`PathDependency`] and then converted into this type with `.into()`.
 ///
 /// # Examples
 ///
@@ -426,6 +563,9 @@ pub enum Dependency {
 
     #[from(GitDependency)]
     Git(GitDependency),
+
+    #[from(PathDependency)]
+    Path(PathDependency),
 }
 
 impl Dependency {
@@ -437,7 +577,7 @@ impl Dependency {
     ///
     /// Example: `dependency~^1.0.0`
     ///
-    /// If a custom URL is provided, then the version requirement string
+    /// If a custom URL or path is provided, then the version requirement string
     /// cannot contain the `=` character, as it would break the remappings.
     ///
     /// # Examples
@@ -483,8 +623,8 @@ impl Dependency {
         }
         Ok(match custom_url {
             Some(url) => {
-                // in this case (custom url or git dependency), the version requirement string is
-                // going to be used as part of the folder name inside the
+                // in this case (custom url, git or path dependency), the version requirement string
+                // is going to be used as part of the folder name inside the
                 // dependencies folder. As such, it's not allowed to contain the "="
                 // character, because that would break the remappings.
                 if dependency_version_req.contains('=') {
@@ -498,6 +638,7 @@ impl Dependency {
                         git: url,
                         identifier,
                         project_root: None,
+                        subdir: None,
                     }
                     .into(),
                     UrlType::Http(url) => HttpDependency {
@@ -505,6 +646,13 @@ impl Dependency {
                         version_req: dependency_version_req.to_string(),
                         url: Some(url),
                         project_root: None,
+                        registry: None,
+                    }
+                    .into(),
+                    UrlType::Path(path) => PathDependency {
+                        name: dependency_name.to_string(),
+                        version_req: dependency_version_req.to_string(),
+                        path: path.into(),
                     }
                     .into(),
                 }
@@ -514,6 +662,7 @@ impl Dependency {
                 version_req: dependency_version_req.to_string(),
                 url: None,
                 project_root: None,
+                registry: None,
             }
             .into(),
         })
@@ -524,6 +673,7 @@ impl Dependency {
         match self {
             Self::Http(dep) => &dep.name,
             Self::Git(dep) => &dep.name,
+            Self::Path(dep) => &dep.name,
         }
     }
 
@@ -532,14 +682,31 @@ impl Dependency {
         match self {
             Self::Http(dep) => &dep.version_req,
             Self::Git(dep) => &dep.version_req,
+            Self::Path(dep) => &dep.version_req,
         }
     }
 
     /// Get the URL of the dependency.
+    ///
+    /// Path dependencies have no URL.
     pub fn url(&self) -> Option<&String> {
         match self {
             Self::Http(dep) => dep.url.as_ref(),
             Self::Git(dep) => Some(&dep.git),
+            Self::Path(_) => None,
+        }
+    }
+
+    /// Get the name of the registry the dependency is downloaded from.
+    ///
+    /// Only HTTP dependencies without a custom URL can have a registry. If `None`, the default
+    /// registry is used.
+    pub fn registry(&self) -> Option<&str> {
+        match self {
+            Self::Http(dep) if dep.url.is_none() => {
+                dep.registry.as_deref().filter(|r| *r != DEFAULT_REGISTRY)
+            }
+            _ => None,
         }
     }
 
@@ -560,6 +727,15 @@ impl Dependency {
         match self {
             Self::Http(dep) => dep.project_root.clone(),
             Self::Git(dep) => dep.project_root.clone(),
+            Self::Path(_) => None,
+        }
+    }
+
+    /// Get the subdirectory of the repository which is checked out, for git dependencies.
+    pub fn subdir(&self) -> Option<&str> {
+        match self {
+            Self::Git(dep) => dep.subdir.as_deref(),
+            _ => None,
         }
     }
 
@@ -568,30 +744,39 @@ impl Dependency {
         match self {
             Self::Http(dep) => (
                 dep.name.clone(),
-                match &dep.url {
-                    Some(url) => {
-                        let mut table = InlineTable::new();
+                if dep.url.is_some() || dep.registry.is_some() {
+                    let mut table = InlineTable::new();
+                    table.insert(
+                        "version",
+                        value(&dep.version_req)
+                            .into_value()
+                            .expect("version should be a valid toml value"),
+                    );
+                    if let Some(url) = dep.url.as_ref() {
+                        table.insert(
+                            "url",
+                            value(url).into_value().expect("url should be a valid toml value"),
+                        );
+                    }
+                    if let Some(registry) = dep.registry.as_ref() {
                         table.insert(
-                            "version",
-                            value(&dep.version_req)
+                            "registry",
+                            value(registry)
                                 .into_value()
-                                .expect("version should be a valid toml value"),
+                                .expect("registry should be a valid toml value"),
                         );
+                    }
+                    if let Some(path) = dep.project_root.as_ref() {
                         table.insert(
-                            "url",
-                            value(url).into_value().expect("url should be a valid toml value"),
+                            "project_root",
+                            value(path.to_string_lossy().into_owned())
+                                .into_value()
+                                .expect("project_root should be a valid toml value"),
                         );
-                        if let Some(path) = dep.project_root.as_ref() {
-                            table.insert(
-                                "project_root",
-                                value(path.to_string_lossy().into_owned())
-                                    .into_value()
-                                    .expect("project_root should be a valid toml value"),
-                            );
-                        }
-                        value(table)
                     }
-                    None => value(&dep.version_req),
+                    value(table)
+                } else {
+                    value(&dep.version_req)
                 },
             ),
             Self::Git(dep) => {
@@ -637,6 +822,28 @@ impl Dependency {
                             .expect("project_root should be a valid toml value"),
                     );
                 }
+                if let Some(subdir) = dep.subdir.as_ref() {
+                    table.insert(
+                        "subdir",
+                        value(subdir).into_value().expect("subdir should be a valid toml value"),
+                    );
+                }
+                (dep.name.clone(), value(table))
+            }
+            Self::Path(dep) => {
+                let mut table = InlineTable::new();
+                table.insert(
+                    "version",
+                    value(&dep.version_req)
+                        .into_value()
+                        .expect("version should be a valid toml value"),
+                );
+                table.insert(
+                    "path",
+                    value(dep.path.to_slash_lossy().into_owned())
+                        .into_value()
+                        .expect("path should be a valid toml value"),
+                );
                 (dep.name.clone(), value(table))
             }
         }
@@ -671,6 +878,21 @@ impl Dependency {
     pub fn as_git_mut(&mut self) -> Option<&mut GitDependency> {
         if let Self::Git(v) = self { Some(v) } else { None }
     }
+
+    /// Check if the dependency is a local path dependency.
+    pub fn is_path(&self) -> bool {
+        matches!(self, Self::Path(_))
+    }
+
+    /// Cast to a path dependency if it is one.
+    pub fn as_path(&self) -> Option<&PathDependency> {
+        if let Self::Path(v) = self { Some(v) } else { None }
+    }
+
+    /// Cast to a mutable path dependency if it is one.
+    pub fn as_path_mut(&mut self) -> Option<&mut PathDependency> {
+        if let Self::Path(v) = self { Some(v) } else { None }
+    }
 }
 
 impl From<&HttpDependency> for Dependency {
@@ -685,6 +907,12 @@ impl From<&GitDependency> for Dependency {
     }
 }
 
+impl From<&PathDependency> for Dependency {
+    fn from(dep: &PathDependency) -> Self {
+        Self::Path(dep.clone())
+    }
+}
+
 /// The location where the Soldeer config should be stored.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromStr)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, Deserialize))]
@@ -746,6 +974,12 @@ impl From<GitDependency> for ParsingResult {
     }
 }
 
+impl From<PathDependency> for ParsingResult {
+    fn from(value: PathDependency) -> Self {
+        Self { dependency: value.into(), warnings: Vec::default() }
+    }
+}
+
 impl From<Dependency> for ParsingResult {
     fn from(value: Dependency) -> Self {
         Self { dependency: value, warnings: Vec::default() }
@@ -787,12 +1021,15 @@ pub fn detect_config_location(root: impl AsRef<Path>) -> Option<ConfigLocation>
 /// - a string representing the version requirement
 /// - a table with the following fields:
 ///   - `version` (required): the version requirement string
-///   - `url` (optional): the URL to the dependency's zip file
+///   - `url` (optional): the URL to the dependency's archive (zip or tarball)
 ///   - `git` (optional): the git URL for git dependencies
 ///   - `rev` (optional): the revision hash for git dependencies
 ///   - `branch` (optional): the branch name for git dependencies
 ///   - `tag` (optional): the tag name for git dependencies
+///   - `path` (optional): the path to the folder of a local dependency
 ///   - `project_root` (optional): relative path to the folder containing the config file
+///   - `registry` (optional): the name of the registry to download the dependency from
+///   - `subdir` (optional): the subdirectory of the repository to check out for git dependencies
 pub fn read_config_deps(path: impl AsRef<Path>) -> Result<(Vec<Dependency>, Vec<ParsingWarning>)> {
     let contents = fs::read_to_string(&path)?;
     let doc: DocumentMut = contents.parse::<DocumentMut>()?;
@@ -828,6 +1065,104 @@ pub fn read_soldeer_config(path: impl AsRef<Path>) -> Result<SoldeerConfig> {
     Ok(config.soldeer)
 }
 
+/// Read the registries declared in the `[registries]` table of the config file.
+///
+/// Each entry maps a registry name to its base URL and to an optional source for its auth token:
+/// either an environment variable (`token_env`) or a file (`token_file`, relative to the folder of
+/// the config file). The default `soldeer` registry is always available and can be overridden by
+/// an entry with the same name.
+pub fn read_registries(path: impl AsRef<Path>) -> Result<Registries> {
+    #[derive(Deserialize)]
+    struct RegistryParsed {
+        url: String,
+        token_env: Option<String>,
+        token_file: Option<PathBuf>,
+    }
+
+    #[derive(Deserialize)]
+    struct RegistriesParsed {
+        #[serde(default)]
+        registries: BTreeMap<String, RegistryParsed>,
+    }
+
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path)?;
+    let parsed: RegistriesParsed = toml_edit::de::from_str(&contents)?;
+
+    let config_dir = path.parent().unwrap_or(Path::new("."));
+    let registries = parsed
+        .registries
+        .into_iter()
+        .map(|(name, registry)| {
+            let url: Url = registry.url.parse().map_err(|_| ConfigError::InvalidRegistry {
+                name: name.clone(),
+                reason: format!("`{}` is not a valid URL", registry.url),
+            })?;
+            let auth = match (registry.token_env, registry.token_file) {
+                (Some(_), Some(_)) => {
+                    return Err(ConfigError::InvalidRegistry {
+                        name,
+                        reason: "`token_env` and `token_file` are mutually exclusive".to_string(),
+                    });
+                }
+                (Some(var), None) => RegistryAuth::Env(var),
+                (None, Some(file)) => RegistryAuth::File(config_dir.join(file)),
+                // the credentials of the default registry are only sent to its official URL
+                (None, None) if name == DEFAULT_REGISTRY && url == Registry::soldeer_url() => {
+                    RegistryAuth::soldeer()
+                }
+                (None, None) if name == DEFAULT_REGISTRY => {
+                    warn!(url:%; "the default registry was overridden, its credentials will not be sent");
+                    RegistryAuth::None
+                }
+                (None, None) => RegistryAuth::None,
+            };
+            Ok(Registry::new(name, url, auth))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    debug!(path:?; "found {} registries in config file", registries.len());
+    Ok(Registries::new(registries))
+}
+
+/// Read the trust policy for package signatures from the `[trust]` table of the config file.
+///
+/// The table contains the `require_signatures` flag and the list of trusted public `keys` (see
+/// [`crate::signing`]). If the table is missing, signatures are not required.
+pub fn read_trust_policy(path: impl AsRef<Path>) -> Result<TrustPolicy> {
+    #[derive(Deserialize, Default)]
+    struct TrustParsed {
+        #[serde(default)]
+        require_signatures: bool,
+        #[serde(default)]
+        keys: Vec<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct TrustPolicyParsed {
+        #[serde(default)]
+        trust: TrustParsed,
+    }
+
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path)?;
+    let parsed: TrustPolicyParsed = toml_edit::de::from_str(&contents)?;
+    let keys = parsed
+        .trust
+        .keys
+        .into_iter()
+        .map(|key| {
+            parse_public_key(&key)?;
+            Ok(key.to_lowercase())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    debug!(path:?; "found {} trusted keys in config file", keys.len());
+    Ok(TrustPolicy::builder()
+        .require_signatures(parsed.trust.require_signatures)
+        .keys(keys)
+        .build())
+}
+
 /// Add a dependency to the config file.
 pub fn add_to_config(dependency: &Dependency, config_path: impl AsRef<Path>) -> Result<()> {
     let contents = fs::read_to_string(&config_path)?;
@@ -955,15 +1290,18 @@ fn find_project_root(cwd: Option<impl AsRef<Path>>) -> Result<PathBuf> {
 /// The value can be a string (version requirement) or a table.
 /// The table can have the following fields:
 /// - `version` (required): the version requirement string
-/// - `url` (optional): the URL to the dependency's zip file
+/// - `url` (optional): the URL to the dependency's archive (zip or tarball)
 /// - `git` (optional): the git URL for git dependencies
 /// - `rev` (optional): the revision hash for git dependencies
 /// - `branch` (optional): the branch name for git dependencies
 /// - `tag` (optional): the tag name for git dependencies
+/// - `path` (optional): the path to the folder of a local dependency
 /// - `project_root` (optional): relative path to the folder containing the config file
+/// - `registry` (optional): the name of the registry to download the dependency from
+/// - `subdir` (optional): the subdirectory of the repository to check out for git dependencies
 ///
-/// Note that the version requirement string cannot contain the `=` symbol for git dependencies
-/// and HTTP dependencies with a custom URL.
+/// Note that the version requirement string cannot contain the `=` symbol for git dependencies,
+/// path dependencies and HTTP dependencies with a custom URL.
 fn parse_dependency(name: impl Into<String>, value: &Item) -> Result<ParsingResult> {
     let name: String = name.into();
     if let Some(version_req) = value.as_str() {
@@ -976,6 +1314,7 @@ fn parse_dependency(name: impl Into<String>, value: &Item) -> Result<ParsingResu
             version_req: version_req.to_string(),
             url: None,
             project_root: None,
+            registry: None,
         }
         .into());
     }
@@ -999,7 +1338,20 @@ fn parse_dependency(name: impl Into<String>, value: &Item) -> Result<ParsingResu
 
     // check for unsupported fields
     warnings.extend(table.iter().filter_map(|(k, _)| {
-        if !["version", "url", "git", "rev", "branch", "tag", "project_root"].contains(&k) {
+        if ![
+            "version",
+            "url",
+            "git",
+            "rev",
+            "branch",
+            "tag",
+            "path",
+            "project_root",
+            "registry",
+            "subdir",
+        ]
+        .contains(&k)
+        {
             warn!(dependency = name; "toml parsing: `{k}` is not a valid dependency option");
             Some(ParsingWarning {
                 dependency_name: name.clone(),
@@ -1035,6 +1387,45 @@ fn parse_dependency(name: impl Into<String>, value: &Item) -> Result<ParsingResu
         None => None,
     };
 
+    // check if it's a path dependency
+    match table.get("path").map(|v| v.as_str()) {
+        Some(None) => {
+            debug!(dep = name; "dependency's `path` field is not a string");
+            return Err(ConfigError::InvalidField { field: "path".to_string(), dep: name });
+        }
+        Some(Some(path)) => {
+            // a path dependency has no other source
+            if let Some(field) = ["url", "git"].into_iter().find(|f| table.contains_key(f)) {
+                return Err(ConfigError::FieldConflict {
+                    field: field.to_string(),
+                    conflicts_with: "path".to_string(),
+                    dep: name,
+                });
+            }
+            // the version requirement string is used as part of the folder name inside the
+            // dependencies folder
+            if version_req.contains('=') {
+                return Err(ConfigError::InvalidVersionReq(name));
+            }
+            warnings.extend(table.iter().filter_map(|(k, _)| {
+                if ["rev", "branch", "tag", "project_root", "registry", "subdir"].contains(&k) {
+                    warn!(dependency = name; "toml parsing: `{k}` is ignored for path dependencies");
+                    Some(ParsingWarning {
+                        dependency_name: name.clone(),
+                        message: format!("`{k}` is ignored for path dependencies"),
+                    })
+                } else {
+                    None
+                }
+            }));
+            return Ok(ParsingResult {
+                dependency: PathDependency { name, version_req, path: path.into() }.into(),
+                warnings,
+            });
+        }
+        None => {}
+    }
+
     // check if it's a git dependency
     match table.get("git").map(|v| v.as_str()) {
         Some(None) => {
@@ -1057,6 +1448,13 @@ fn parse_dependency(name: impl Into<String>, value: &Item) -> Result<ParsingResu
             if version_req.contains('=') {
                 return Err(ConfigError::InvalidVersionReq(name));
             }
+            if table.contains_key("registry") {
+                warn!(dependency = name; "toml parsing: `registry` is ignored for git dependencies");
+                warnings.push(ParsingWarning {
+                    dependency_name: name.clone(),
+                    message: "`registry` is ignored for git dependencies".to_string(),
+                });
+            }
             // rev/branch/tag fields are optional but need to be a string if present
             let rev = match table.get("rev").map(|v| v.as_str()) {
                 Some(Some(rev)) => Some(rev.to_string()),
@@ -1094,6 +1492,21 @@ fn parse_dependency(name: impl Into<String>, value: &Item) -> Result<ParsingResu
                     return Err(ConfigError::GitIdentifierConflict(name));
                 }
             };
+            // the subdir field is optional but needs to be a relative path inside the repository
+            let subdir = match table.get("subdir").map(|v| v.as_str()) {
+                Some(Some(subdir)) => Some(normalize_subdir(subdir).ok_or_else(|| {
+                    debug!(dep = name; "dependency's `subdir` field is not a relative path");
+                    ConfigError::InvalidField { field: "subdir".to_string(), dep: name.clone() }
+                })?),
+                Some(None) => {
+                    debug!(dep = name; "dependency's `subdir` field is not a string");
+                    return Err(ConfigError::InvalidField {
+                        field: "subdir".to_string(),
+                        dep: name,
+                    });
+                }
+                None => None,
+            };
             return Ok(ParsingResult {
                 dependency: GitDependency {
                     name,
@@ -1101,6 +1514,7 @@ fn parse_dependency(name: impl Into<String>, value: &Item) -> Result<ParsingResu
                     version_req,
                     identifier,
                     project_root,
+                    subdir,
                 }
                 .into(),
                 warnings,
@@ -1113,7 +1527,7 @@ fn parse_dependency(name: impl Into<String>, value: &Item) -> Result<ParsingResu
 
     // check for extra fields in the HTTP context
     warnings.extend(table.iter().filter_map(|(k, _)| {
-        if ["rev", "branch", "tag"].contains(&k) {
+        if ["rev", "branch", "tag", "subdir"].contains(&k) {
             warn!(dependency = name; "toml parsing: `{k}` is ignored if no `git` URL is provided");
             Some(ParsingWarning {
                 dependency_name: name.clone(),
@@ -1124,13 +1538,24 @@ fn parse_dependency(name: impl Into<String>, value: &Item) -> Result<ParsingResu
         }
     }));
 
+    // the registry field is optional but needs to be a string if present
+    let registry = match table.get("registry").map(|v| v.as_str()) {
+        Some(Some(registry)) => Some(registry.to_string()),
+        Some(None) => {
+            debug!(dep = name; "dependency's `registry` field is not a string");
+            return Err(ConfigError::InvalidField { field: "registry".to_string(), dep: name });
+        }
+        None => None,
+    };
+
     match table.get("url").map(|v| v.as_str()) {
         Some(None) => {
             debug!(dep = name; "dependency's `url` field is not a string");
             Err(ConfigError::InvalidField { field: "url".to_string(), dep: name })
         }
         None => Ok(ParsingResult {
-            dependency: HttpDependency { name, version_req, url: None, project_root }.into(),
+            dependency: HttpDependency { name, version_req, url: None, project_root, registry }
+                .into(),
             warnings,
         }),
         Some(Some(url)) => {
@@ -1141,12 +1566,20 @@ fn parse_dependency(name: impl Into<String>, value: &Item) -> Result<ParsingResu
             if version_req.contains('=') {
                 return Err(ConfigError::InvalidVersionReq(name));
             }
+            if registry.is_some() {
+                warn!(dependency = name; "toml parsing: `registry` is ignored if a `url` is provided");
+                warnings.push(ParsingWarning {
+                    dependency_name: name.clone(),
+                    message: "`registry` is ignored if a `url` is provided".to_string(),
+                });
+            }
             Ok(ParsingResult {
                 dependency: HttpDependency {
                     name,
                     version_req,
                     url: Some(url.to_string()),
                     project_root,
+                    registry: None,
                 }
                 .into(),
                 warnings,
@@ -1155,6 +1588,18 @@ fn parse_dependency(name: impl Into<String>, value: &Item) -> Result<ParsingResu
     }
 }
 
+/// Normalize the path to a subdirectory of a git repository.
+///
+/// Leading and trailing slashes are removed. Returns `None` if the path is empty or if it could
+/// point outside of the repository.
+pub fn normalize_subdir(subdir: &str) -> Option<String> {
+    let subdir = subdir.replace('\\', "/").trim_matches('/').to_string();
+    if subdir.is_empty() || subdir.split('/').any(|c| c.is_empty() || c == "." || c == "..") {
+        return None;
+    }
+    Some(subdir)
+}
+
 /// Create a basic config file with default contents if it doesn't exist, otherwise add
 /// `[dependencies]` if necessary.
 fn create_or_modify_config(
@@ -1462,6 +1907,13 @@ remappings_version = false
 remappings_prefix = "@"
 remappings_location = "config"
 recursive_deps = true
+link_mode = "hardlink"
+
+[soldeer.policy]
+allowed_git_hosts = ["github.com"]
+require_git_rev = true
+banned = ["evil-lib"]
+denied_licenses = ["BUSL-1.1"]
 "#;
         let expected = SoldeerConfig {
             remappings_generate: false,
@@ -1470,6 +1922,13 @@ recursive_deps = true
             remappings_prefix: "@".to_string(),
             remappings_location: RemappingsLocation::Config,
             recursive_deps: true,
+            link_mode: LinkMode::Hardlink,
+            policy: Policy::builder()
+                .allowed_git_hosts(vec!["github.com".to_string()])
+                .require_git_rev(true)
+                .banned(vec!["evil-lib".to_string()])
+                .denied_licenses(vec!["BUSL-1.1".to_string()])
+                .build(),
         };
 
         let config_path = write_to_config(config_contents, "soldeer.toml");
@@ -1783,6 +2242,18 @@ libs = ["dependencies"]
                 .project_root("foo/bar")
                 .build()
                 .into(),
+            PathDependency::builder()
+                .name("lib8")
+                .version_req("1.0.0")
+                .path("../lib8")
+                .build()
+                .into(),
+            HttpDependency::builder()
+                .name("lib9")
+                .version_req("1.0.0")
+                .registry("internal")
+                .build()
+                .into(),
         ];
         for dep in deps {
             let res = add_to_config(dep, &config_path);
@@ -2019,6 +2490,198 @@ libs = ["dependencies"]
         }
     }
 
+    #[test]
+    fn test_parse_dependency_path() {
+        let config_contents = r#"[dependencies]
+"lib1" = { version = "1.0.0", path = "../lib1" }
+"lib2" = { version = "1.0.0", path = "../lib2", url = "https://example.com" }
+"lib3" = { version = "1.0.0", path = "../lib3", git = "https://example.com/repo.git" }
+"lib4" = { version = "=1.0.0", path = "../lib4" }
+"lib5" = { version = "1.0.0", path = "../lib5", tag = "v1.0.0" }
+"#;
+        let doc: DocumentMut = config_contents.parse::<DocumentMut>().unwrap();
+        let data = doc.get("dependencies").map(|v| v.as_table()).unwrap().unwrap();
+        let res = parse_dependency("lib1", &data["lib1"]).unwrap();
+        assert_eq!(
+            res.dependency,
+            PathDependency::builder()
+                .name("lib1")
+                .version_req("1.0.0")
+                .path("../lib1")
+                .build()
+                .into()
+        );
+        assert!(!res.has_warnings());
+        let res = parse_dependency("lib2", &data["lib2"]);
+        assert!(
+            matches!(res, Err(ConfigError::FieldConflict { ref field, .. }) if field == "url"),
+            "{res:?}"
+        );
+        let res = parse_dependency("lib3", &data["lib3"]);
+        assert!(
+            matches!(res, Err(ConfigError::FieldConflict { ref field, .. }) if field == "git"),
+            "{res:?}"
+        );
+        let res = parse_dependency("lib4", &data["lib4"]);
+        assert!(matches!(res, Err(ConfigError::InvalidVersionReq(_))), "{res:?}");
+        let res = parse_dependency("lib5", &data["lib5"]).unwrap();
+        assert_eq!(res.warnings[0].message, "`tag` is ignored for path dependencies");
+    }
+
+    #[test]
+    fn test_parse_dependency_registry() {
+        let config_contents = r#"[dependencies]
+"lib1" = { version = "1.0.0", registry = "internal" }
+"lib2" = { version = "1.0.0", registry = 1 }
+"lib3" = { version = "1.0.0", url = "https://example.com", registry = "internal" }
+"lib4" = { version = "1.0.0", git = "https://example.com/repo.git", registry = "internal" }
+"lib5" = { version = "1.0.0", registry = "soldeer" }
+"#;
+        let doc: DocumentMut = config_contents.parse::<DocumentMut>().unwrap();
+        let data = doc.get("dependencies").map(|v| v.as_table()).unwrap().unwrap();
+        let res = parse_dependency("lib1", &data["lib1"]).unwrap();
+        assert_eq!(
+            res.dependency,
+            HttpDependency::builder()
+                .name("lib1")
+                .version_req("1.0.0")
+                .registry("internal")
+                .build()
+                .into()
+        );
+        assert_eq!(res.dependency.registry(), Some("internal"));
+        assert!(!res.has_warnings());
+        let res = parse_dependency("lib2", &data["lib2"]);
+        assert!(
+            matches!(res, Err(ConfigError::InvalidField { ref field, .. }) if field == "registry"),
+            "{res:?}"
+        );
+        let res = parse_dependency("lib3", &data["lib3"]).unwrap();
+        assert_eq!(res.dependency.registry(), None);
+        assert_eq!(res.warnings[0].message, "`registry` is ignored if a `url` is provided");
+        let res = parse_dependency("lib4", &data["lib4"]).unwrap();
+        assert_eq!(res.dependency.registry(), None);
+        assert_eq!(res.warnings[0].message, "`registry` is ignored for git dependencies");
+        let res = parse_dependency("lib5", &data["lib5"]).unwrap();
+        assert_eq!(res.dependency.registry(), None);
+    }
+
+    #[test]
+    fn test_parse_dependency_subdir() {
+        let config_contents = r#"[dependencies]
+"lib1" = { version = "1.0.0", git = "https://example.com/repo.git", subdir = "/packages/core/" }
+"lib2" = { version = "1.0.0", git = "https://example.com/repo.git", subdir = "../core" }
+"lib3" = { version = "1.0.0", git = "https://example.com/repo.git", subdir = 1 }
+"lib4" = { version = "1.0.0", url = "https://example.com", subdir = "core" }
+"#;
+        let doc: DocumentMut = config_contents.parse::<DocumentMut>().unwrap();
+        let data = doc.get("dependencies").map(|v| v.as_table()).unwrap().unwrap();
+        let res = parse_dependency("lib1", &data["lib1"]).unwrap();
+        assert_eq!(
+            res.dependency,
+            GitDependency::builder()
+                .name("lib1")
+                .version_req("1.0.0")
+                .git("https://example.com/repo.git")
+                .subdir("packages/core")
+                .build()
+                .into()
+        );
+        assert_eq!(res.dependency.subdir(), Some("packages/core"));
+        assert!(!res.has_warnings());
+        let (_, value) = res.dependency.to_toml_value();
+        assert_eq!(value["subdir"].as_str(), Some("packages/core"));
+        for name in ["lib2", "lib3"] {
+            let res = parse_dependency(name, &data[name]);
+            assert!(
+                matches!(res, Err(ConfigError::InvalidField { ref field, .. }) if field == "subdir"),
+                "{res:?}"
+            );
+        }
+        let res = parse_dependency("lib4", &data["lib4"]).unwrap();
+        assert_eq!(res.dependency.subdir(), None);
+        assert_eq!(res.warnings[0].message, "`subdir` is ignored if no `git` URL is provided");
+    }
+
+    #[test]
+    fn test_read_registries() {
+        let config_contents = r#"[registries]
+internal = { url = "https://soldeer.example.com", token_env = "INTERNAL_TOKEN" }
+mirror = { url = "https://mirror.example.com", token_file = "mirror-token" }
+public = { url = "https://public.example.com" }
+
+[dependencies]
+"#;
+        let config_path = write_to_config(config_contents, "soldeer.toml");
+        let res = read_registries(&config_path);
+        assert!(res.is_ok(), "{res:?}");
+        let registries = res.unwrap();
+        let internal = registries.get(Some("internal")).unwrap();
+        assert_eq!(internal.url.as_str(), "https://soldeer.example.com/");
+        assert_eq!(internal.auth, RegistryAuth::Env("INTERNAL_TOKEN".to_string()));
+        let mirror = registries.get(Some("mirror")).unwrap();
+        assert_eq!(
+            mirror.auth,
+            RegistryAuth::File(config_path.parent().unwrap().join("mirror-token"))
+        );
+        assert_eq!(registries.get(Some("public")).unwrap().auth, RegistryAuth::None);
+        assert!(registries.get(None).unwrap().is_default());
+
+        let config_path = write_to_config(
+            "[registries]\nfoo = { url = \"https://foo.com\", token_env = \"FOO\", token_file = \"foo\" }\n",
+            "soldeer.toml",
+        );
+        let res = read_registries(&config_path);
+        assert!(matches!(res, Err(ConfigError::InvalidRegistry { .. })), "{res:?}");
+
+        let config_path =
+            write_to_config("[registries]\nfoo = { url = \"not a url\" }\n", "soldeer.toml");
+        let res = read_registries(&config_path);
+        assert!(matches!(res, Err(ConfigError::InvalidRegistry { .. })), "{res:?}");
+
+        // an override of the default registry doesn't receive its credentials
+        let config_path = write_to_config(
+            "[registries]\nsoldeer = { url = \"https://mirror.example.com\" }\n",
+            "soldeer.toml",
+        );
+        let registries = read_registries(&config_path).unwrap();
+        assert_eq!(registries.default_registry().auth, RegistryAuth::None);
+        let config_path = write_to_config(
+            "[registries]\nsoldeer = { url = \"https://mirror.example.com\", token_env = \"MIRROR\" }\n",
+            "soldeer.toml",
+        );
+        let registries = read_registries(&config_path).unwrap();
+        assert_eq!(registries.default_registry().auth, RegistryAuth::Env("MIRROR".to_string()));
+    }
+
+    #[test]
+    fn test_read_trust_policy() {
+        let config_path = write_to_config("[dependencies]\n", "soldeer.toml");
+        let res = read_trust_policy(&config_path);
+        assert!(res.is_ok(), "{res:?}");
+        assert_eq!(res.unwrap(), TrustPolicy::default());
+
+        let config_path = write_to_config(
+            r#"[trust]
+require_signatures = true
+keys = ["D75A980182B10AB7D54BFED3C964073A0EE172F3DAA62325AF021A68F707511A"]
+"#,
+            "soldeer.toml",
+        );
+        let res = read_trust_policy(&config_path);
+        assert!(res.is_ok(), "{res:?}");
+        let policy = res.unwrap();
+        assert!(policy.require_signatures);
+        assert_eq!(
+            policy.keys,
+            vec!["d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a".to_string()]
+        );
+
+        let config_path = write_to_config("[trust]\nkeys = [\"foo\"]\n", "soldeer.toml");
+        let res = read_trust_policy(&config_path);
+        assert!(matches!(res, Err(ConfigError::InvalidTrustedKey(_))), "{res:?}");
+    }
+
     #[test]
     fn test_find_git_root() {
         let test_dir = testdir!();
@@ -1,14 +1,15 @@
 //! Download and/or extract dependencies
 use crate::{
+    archive::{self, ArchiveFormat},
     config::{Dependency, GitIdentifier},
     errors::DownloadError,
+    manifest::IntegrityManifest,
     utils::{path_matches, run_git_command, sanitize_filename},
 };
 use log::{debug, trace, warn};
-use reqwest::{IntoUrl, Url};
+use reqwest::{IntoUrl, Url, header::CONTENT_TYPE};
 use std::{
     fs,
-    io::Cursor,
     path::{Path, PathBuf},
     str,
 };
@@ -16,10 +17,12 @@ use tokio::io::AsyncWriteExt as _;
 
 pub type Result<T> = std::result::Result<T, DownloadError>;
 
-/// Download a zip file into the provided folder.
+/// Download an archive into the provided folder.
 ///
 /// Depending on the platform, the folder path must exist prior to calling this function.
-/// The filename for the zip file will be the provided base name with the ".zip" extension
+/// The filename for the archive will be the provided base name with the extension of the archive
+/// format (e.g. ".zip" or ".tar.gz"). The format is detected from the first bytes of the file, or
+/// from the `Content-Type` header and the URL path if that fails, defaulting to zip.
 pub async fn download_file(
     url: impl IntoUrl,
     folder_path: impl AsRef<Path>,
@@ -27,42 +30,63 @@ pub async fn download_file(
 ) -> Result<PathBuf> {
     let url: Url = url.into_url()?;
     debug!(name = base_name, url:% = url; "downloading file");
+    let url_format = ArchiveFormat::from_file_name(url.path());
     let resp = reqwest::get(url).await?;
     let mut resp = resp.error_for_status()?;
 
-    let zip_path = folder_path.as_ref().join(sanitize_filename(&format!("{base_name}.zip")));
-    let mut file = tokio::fs::File::create(&zip_path)
+    let header_format = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(ArchiveFormat::from_content_type);
+    let mut chunk = resp.chunk().await?;
+    let format = chunk
+        .as_deref()
+        .and_then(ArchiveFormat::from_magic)
+        .or(header_format)
+        .or(url_format)
+        .unwrap_or_default();
+    debug!(format:?; "detected archive format");
+
+    let archive_path = folder_path
+        .as_ref()
+        .join(sanitize_filename(&format!("{base_name}.{}", format.extension())));
+    let mut file = tokio::fs::File::create(&archive_path)
         .await
-        .map_err(|e| DownloadError::IOError { path: zip_path.clone(), source: e })?;
-    while let Some(mut chunk) = resp.chunk().await? {
-        file.write_all_buf(&mut chunk)
+        .map_err(|e| DownloadError::IOError { path: archive_path.clone(), source: e })?;
+    while let Some(mut bytes) = chunk {
+        file.write_all_buf(&mut bytes)
             .await
-            .map_err(|e| DownloadError::IOError { path: zip_path.clone(), source: e })?;
+            .map_err(|e| DownloadError::IOError { path: archive_path.clone(), source: e })?;
+        chunk = resp.chunk().await?;
     }
-    file.flush().await.map_err(|e| DownloadError::IOError { path: zip_path.clone(), source: e })?;
-    debug!(path:? = zip_path; "saved downloaded file");
-    Ok(zip_path)
+    file.flush()
+        .await
+        .map_err(|e| DownloadError::IOError { path: archive_path.clone(), source: e })?;
+    debug!(path:? = archive_path; "saved downloaded file");
+    Ok(archive_path)
 }
 
-/// Unzip a file into a directory and then delete it.
-pub async fn unzip_file(path: impl AsRef<Path>, into: impl AsRef<Path>) -> Result<()> {
+/// Extract an archive into a directory and then delete it.
+///
+/// See [`archive`][crate::archive] for the supported formats.
+pub async fn extract_archive(path: impl AsRef<Path>, into: impl AsRef<Path>) -> Result<()> {
     let path = path.as_ref().to_path_buf();
-    let zip_contents = tokio::fs::read(&path)
+    let contents = tokio::fs::read(&path)
         .await
         .map_err(|e| DownloadError::IOError { path: path.clone(), source: e })?;
 
     tokio::task::spawn_blocking({
         let out_dir = into.as_ref().to_path_buf();
-        #[allow(deprecated)] // until we can get rid of zip_extract
-        move || zip_extract::extract(Cursor::new(zip_contents), &out_dir, true)
+        move || archive::extract(&contents, &out_dir)
     })
     .await??;
-    debug!(file:? = path, dest:? = into.as_ref(); "unzipped file");
+    debug!(file:? = path, dest:? = into.as_ref(); "extracted archive");
 
     tokio::fs::remove_file(&path)
         .await
         .map_err(|e| DownloadError::IOError { path: path.clone(), source: e })?;
-    debug!(path:?; "removed zip file");
+    debug!(path:?; "removed archive file");
     Ok(())
 }
 
@@ -72,24 +96,48 @@ pub async fn unzip_file(path: impl AsRef<Path>, into: impl AsRef<Path>) -> Resul
 /// needed. Contrary to a shallow clone, it's possible to checkout any ref and the missing trees
 /// will be retrieved as they are needed.
 ///
+/// If a subdirectory is provided, the repository is cloned without blobs and a sparse checkout
+/// restricts the working tree to that subdirectory (and the files at the root of the repository),
+/// so that only the corresponding files are downloaded.
+///
 /// This function returns the commit hash corresponding to  the checked out reference (branch, tag,
 /// commit).
 pub async fn clone_repo(
     url: &str,
     identifier: Option<&GitIdentifier>,
+    subdir: Option<&str>,
     path: impl AsRef<Path>,
 ) -> Result<String> {
     let path = path.as_ref().to_path_buf();
-    run_git_command(
-        &["clone", "--tags", "--filter=tree:0", url, path.to_string_lossy().as_ref()],
-        None,
-    )
-    .await?;
-    debug!(repo:? = path; "git repo cloned");
+    let path_str = path.to_string_lossy();
+    match subdir {
+        Some(subdir) => {
+            run_git_command(
+                &["clone", "--tags", "--filter=blob:none", "--sparse", url, path_str.as_ref()],
+                None,
+            )
+            .await?;
+            run_git_command(&["sparse-checkout", "set", subdir], Some(&path)).await?;
+            debug!(repo:? = path, subdir; "git repo cloned with sparse checkout");
+        }
+        None => {
+            run_git_command(&["clone", "--tags", "--filter=tree:0", url, path_str.as_ref()], None)
+                .await?;
+            debug!(repo:? = path; "git repo cloned");
+        }
+    }
     if let Some(identifier) = identifier {
         run_git_command(&["checkout", &identifier.to_string()], Some(&path)).await?;
         debug!(ref:? = identifier, repo:? = path; "checked out ref");
     }
+    if let Some(subdir) = subdir &&
+        !path.join(subdir).is_dir()
+    {
+        return Err(DownloadError::SubdirNotFound {
+            url: url.to_string(),
+            subdir: subdir.to_string(),
+        });
+    }
     let commit =
         run_git_command(&["rev-parse", "--verify", "HEAD"], Some(&path)).await?.trim().to_string();
     debug!(repo:? = path; "checked out commit is {commit}");
@@ -104,7 +152,9 @@ pub fn delete_dependency_files_sync(dependency: &Dependency, deps: impl AsRef<Pa
     let Some(path) = find_install_path_sync(dependency, deps) else {
         return Err(DownloadError::DependencyNotFound(dependency.to_string()));
     };
-    fs::remove_dir_all(&path).map_err(|e| DownloadError::IOError { path, source: e })?;
+    fs::remove_dir_all(&path)
+        .map_err(|e| DownloadError::IOError { path: path.clone(), source: e })?;
+    IntegrityManifest::remove(&path);
     debug!(dep:% = dependency; "removed all files for dependency (sync)");
     Ok(())
 }
@@ -168,7 +218,8 @@ pub async fn delete_dependency_files(
     };
     tokio::fs::remove_dir_all(&path)
         .await
-        .map_err(|e| DownloadError::IOError { path, source: e })?;
+        .map_err(|e| DownloadError::IOError { path: path.clone(), source: e })?;
+    IntegrityManifest::remove(&path);
     debug!(dep:% = dependency; "removed all files for dependency (async)");
     Ok(())
 }
@@ -211,7 +262,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_unzip_file() {
+    async fn test_extract_archive() {
         let dir = testdir!();
         // create dummy zip
         let file_path = dir.join("file.txt");
@@ -220,7 +271,7 @@ mod tests {
         zip_file(&dir, &[file_path], &zip_path).unwrap();
 
         let out_dir = dir.join("out");
-        let res = unzip_file(&zip_path, &out_dir).await;
+        let res = extract_archive(&zip_path, &out_dir).await;
         assert!(res.is_ok(), "{res:?}");
         let file_path = out_dir.join("file.txt");
         assert!(file_path.exists());
@@ -230,7 +281,7 @@ mod tests {
     #[tokio::test]
     async fn test_clone_repo() {
         let dir = testdir!();
-        let res = clone_repo("https://github.com/beeb/test-repo.git", None, &dir).await;
+        let res = clone_repo("https://github.com/beeb/test-repo.git", None, None, &dir).await;
         assert!(res.is_ok(), "{res:?}");
         assert_eq!(&res.unwrap(), "d5d72fa135d28b2e8307650b3ea79115183f2406");
     }
@@ -241,6 +292,7 @@ mod tests {
         let res = clone_repo(
             "https://github.com/beeb/test-repo.git",
             Some(&GitIdentifier::from_rev("d230f5c588c0ed00821a4eb3ef38e300e4a519dc")),
+            None,
             &dir,
         )
         .await;
@@ -254,6 +306,7 @@ mod tests {
         let res = clone_repo(
             "https://github.com/beeb/test-repo.git",
             Some(&GitIdentifier::from_branch("dev")),
+            None,
             &dir,
         )
         .await;
@@ -267,6 +320,7 @@ mod tests {
         let res = clone_repo(
             "https://github.com/beeb/test-repo.git",
             Some(&GitIdentifier::from_tag("v0.1.0")),
+            None,
             &dir,
         )
         .await;
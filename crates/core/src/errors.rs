@@ -1,3 +1,4 @@
+use crate::{audit::Severity, lock::LockDrift};
 use std::{
     io,
     path::{PathBuf, StripPrefixError},
@@ -10,6 +11,9 @@ pub enum SoldeerError {
     #[error("error during login: {0}")]
     AuthError(#[from] AuthError),
 
+    #[error("error during cache operation: {0}")]
+    CacheError(#[from] CacheError),
+
     #[error("error during config operation: {0}")]
     ConfigError(#[from] ConfigError),
 
@@ -25,6 +29,9 @@ pub enum SoldeerError {
     #[error("error during publishing: {0}")]
     PublishError(#[from] PublishError),
 
+    #[error("dependency policy violated: {0}")]
+    PolicyError(#[from] PolicyError),
+
     #[error("error during remappings operation: {0}")]
     RemappingsError(#[from] RemappingsError),
 
@@ -34,10 +41,58 @@ pub enum SoldeerError {
     #[error("error during update operation: {0}")]
     UpdateError(#[from] UpdateError),
 
+    #[error("error during workspace operation: {0}")]
+    WorkspaceError(#[from] WorkspaceError),
+
+    #[error("error while building the dependency graph: {0}")]
+    GraphError(#[from] GraphError),
+
+    #[error("error during verification: {0}")]
+    VerifyError(#[from] VerifyError),
+
+    #[error("error during vendoring: {0}")]
+    VendorError(#[from] VendorError),
+
+    #[error("error during audit: {0}")]
+    AuditError(#[from] AuditError),
+
     #[error("error during IO operation: {0}")]
     IOError(#[from] io::Error),
 }
 
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum AuditError {
+    #[error("advisory database not found at {0:?}, sync it with `soldeer audit --sync <FILE|URL>`")]
+    MissingDatabase(PathBuf),
+
+    #[error("error while parsing the advisory database: {0}")]
+    ParseError(String),
+
+    #[error("advisory {0} must have a `package` or a `git` field")]
+    MissingTarget(String),
+
+    #[error("advisory {id} has an invalid version range {range:?}")]
+    InvalidRange { id: String, range: String },
+
+    #[error("unknown severity {0:?}, expected low, medium, high or critical")]
+    UnknownSeverity(String),
+
+    #[error("error downloading the advisory database: {0}")]
+    DownloadError(#[from] reqwest::Error),
+
+    #[error("error during IO operation for {path:?}: {source}")]
+    IOError { path: PathBuf, source: io::Error },
+
+    #[error("{count} advisories with severity {severity} or higher affect the dependencies")]
+    Vulnerable { count: usize, severity: Severity },
+
+    #[error(
+        "{count} advisories with severity {severity} or higher could not be evaluated, check them and use `--ignore`"
+    )]
+    Unevaluated { count: usize, severity: Severity },
+}
+
 #[derive(Error, Debug)]
 #[non_exhaustive]
 pub enum AuthError {
@@ -50,6 +105,9 @@ pub enum AuthError {
     #[error("missing token, run `soldeer login`")]
     MissingToken,
 
+    #[error("cannot save the token for registry {0}, it needs a `token_file` in the config")]
+    TokenNotWritable(String),
+
     #[error("error during IO operation for the security file: {0}")]
     IOError(#[from] io::Error),
 
@@ -60,6 +118,13 @@ pub enum AuthError {
     TuiDisabled,
 }
 
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum CacheError {
+    #[error("error during IO operation for {path:?}: {source}")]
+    IOError { path: PathBuf, source: io::Error },
+}
+
 #[derive(Error, Debug)]
 #[non_exhaustive]
 pub enum ConfigError {
@@ -109,6 +174,59 @@ pub enum ConfigError {
 
     #[error("invalid project root path in {dep_path}: {project_root}")]
     InvalidProjectRoot { project_root: PathBuf, dep_path: PathBuf },
+
+    #[error("registry {name} is not valid: {reason}")]
+    InvalidRegistry { name: String, reason: String },
+
+    #[error("invalid trusted key in the `[trust]` table: {0}")]
+    InvalidTrustedKey(#[from] SigningError),
+}
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum SigningError {
+    #[error("invalid public key {0:?}, expected 32 hex-encoded bytes")]
+    InvalidPublicKey(String),
+
+    #[error("invalid signing key in {0:?}, expected 32 hex-encoded bytes")]
+    InvalidSigningKey(PathBuf),
+
+    #[error("invalid archive checksum {0:?}")]
+    InvalidChecksum(String),
+
+    #[error("the signature does not match the archive")]
+    SignatureMismatch,
+
+    #[error("the signature of {0} does not match its archive")]
+    InvalidSignature(String),
+
+    #[error("{0} is not signed, but signatures are required")]
+    Unsigned(String),
+
+    #[error("{dependency} is signed with key {key}, which is not a trusted key")]
+    UntrustedKey { dependency: String, key: String },
+
+    #[error("error during IO operation for {path:?}: {source}")]
+    IOError { path: PathBuf, source: io::Error },
+}
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ArchiveError {
+    #[error("unsupported archive format, expected zip, tar.gz, tar.xz or tar.zst")]
+    UnsupportedFormat,
+
+    #[error("error reading zip archive: {0}")]
+    ZipError(#[from] zip::result::ZipError),
+
+    #[error("error extracting zip archive: {0}")]
+    ZipExtractError(#[from] zip_extract::ZipExtractError),
+
+    #[error("error decompressing archive: {0}")]
+    DecompressError(String),
+
+    #[error("IO error while reading archive: {0}")]
+    IOError(#[from] io::Error),
 }
 
 #[derive(Error, Debug)]
@@ -118,7 +236,7 @@ pub enum DownloadError {
     HttpError(#[from] reqwest::Error),
 
     #[error("error extracting dependency: {0}")]
-    UnzipError(#[from] zip_extract::ZipExtractError),
+    ArchiveError(#[from] ArchiveError),
 
     #[error("error during git command {args:?}: {message}")]
     GitError { message: String, args: Vec<String> },
@@ -140,13 +258,27 @@ pub enum DownloadError {
 
     #[error("dependency not found: {0}")]
     DependencyNotFound(String),
+
+    #[error("cannot download {0} in offline mode")]
+    Offline(String),
+
+    #[error("subdirectory {subdir} does not exist in git repository {url}")]
+    SubdirNotFound { url: String, subdir: String },
 }
 
 #[derive(Error, Debug)]
 #[non_exhaustive]
 pub enum InstallError {
-    #[error("zip checksum for {path} does not match lock file: expected {expected}, got {actual}")]
-    ZipIntegrityError { path: PathBuf, expected: String, actual: String },
+    #[error(
+        "archive checksum for {path} does not match lock file: expected {expected}, got {actual}"
+    )]
+    ArchiveIntegrityError { path: PathBuf, expected: String, actual: String },
+
+    #[error("signature check failed: {0}")]
+    SigningError(#[from] SigningError),
+
+    #[error("dependency policy violated: {0}")]
+    PolicyError(#[from] PolicyError),
 
     #[error("error during IO operation for {path:?}: {source}")]
     IOError { path: PathBuf, source: io::Error },
@@ -171,6 +303,30 @@ pub enum InstallError {
 
     #[error("error with lockfile: {0}")]
     LockError(#[from] LockError),
+
+    #[error("no version of {dependency} satisfies all requirements: {requirements}")]
+    VersionConflict { dependency: String, requirements: String },
+
+    #[error("soldeer.lock is missing, run `soldeer install` without `--locked` to generate it")]
+    LockfileMissing,
+
+    #[error("soldeer.lock is out of sync with the dependencies: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    LockfileOutOfSync(Vec<LockDrift>),
+
+    #[error("dependency {0} cannot be installed without contacting the registry")]
+    RegistryRequired(String),
+
+    #[error("the folder of path dependency {dependency} does not exist: {path:?}")]
+    PathNotFound { dependency: String, path: PathBuf },
+
+    #[error("{0} is not installed and not in the cache, it cannot be installed in offline mode")]
+    Offline(String),
+
+    #[error("error with the package store: {0}")]
+    CacheError(#[from] CacheError),
+
+    #[error("the archive of {0} was removed from the cache during the installation, try again")]
+    CacheEntryMissing(String),
 }
 
 #[derive(Error, Debug)]
@@ -247,10 +403,61 @@ pub enum PublishError {
     #[error("user cancelled operation")]
     UserAborted,
 
+    #[error(
+        "the package has no license, add a LICENSE file or `// SPDX-License-Identifier:` headers to the Solidity files"
+    )]
+    NoLicense,
+
+    #[error("error while signing the package: {0}")]
+    SigningError(#[from] SigningError),
+
     #[error("unknown http error")]
     UnknownError,
 }
 
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum PolicyError {
+    #[error("{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    Violations(Vec<PolicyViolation>),
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum PolicyViolation {
+    #[error("{dependency} comes from registry {registry:?}, which is not allowed")]
+    RegistryNotAllowed { dependency: String, registry: String },
+
+    #[error("{dependency} is downloaded from {url}, which is not an allowed host")]
+    UrlHostNotAllowed { dependency: String, url: String },
+
+    #[error("{dependency} is cloned from {url}, which is not an allowed git host")]
+    GitHostNotAllowed { dependency: String, url: String },
+
+    #[error("{dependency} must be pinned to a commit hash with `rev`")]
+    GitNotPinned { dependency: String },
+
+    #[error("{dependency} is banned by rule {rule:?}")]
+    Banned { dependency: String, rule: String },
+
+    #[error("{dependency} was published on {published}, less than {min_days} days ago")]
+    TooRecent { dependency: String, published: String, min_days: u32 },
+
+    #[error("{dependency} has no publication date, which is required to check its age")]
+    UnknownAge { dependency: String },
+
+    #[error("{dependency} is licensed under {license}, which is not an allowed license")]
+    LicenseNotAllowed { dependency: String, license: String },
+
+    #[error("{dependency} is licensed under {license}, which is a denied license")]
+    LicenseDenied { dependency: String, license: String },
+
+    #[error("{dependency} has no license, which is required to check the allowed licenses")]
+    UnknownLicense { dependency: String },
+}
+
 #[derive(Error, Debug)]
 #[non_exhaustive]
 pub enum RegistryError {
@@ -273,6 +480,15 @@ pub enum RegistryError {
 
     #[error("no matching version found for {dependency} with version requirement {version_req}")]
     NoMatchingVersion { dependency: String, version_req: String },
+
+    #[error("cannot contact the registry in offline mode and no cached data was found for {0}")]
+    Offline(String),
+
+    #[error("error parsing registry response: {0}")]
+    DeserializeError(#[from] serde_json::Error),
+
+    #[error("registry {0} is not declared in the `[registries]` table of the config file")]
+    UnknownRegistry(String),
 }
 
 #[derive(Error, Debug)]
@@ -302,4 +518,108 @@ pub enum UpdateError {
 
     #[error("error during async operation: {0}")]
     AsyncError(#[from] tokio::task::JoinError),
+
+    #[error("some dependencies are outdated: {}", .0.join(", "))]
+    OutdatedDependencies(Vec<String>),
+}
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum WorkspaceError {
+    #[error("error during IO operation for {path:?}: {source}")]
+    IOError { path: PathBuf, source: io::Error },
+
+    #[error("error while interacting with the config file: {0}")]
+    ConfigError(#[from] ConfigError),
+
+    #[error("error while generating remappings: {0}")]
+    RemappingsError(#[from] RemappingsError),
+
+    #[error("the `workspace.members` field must be an array of strings")]
+    InvalidMembers,
+
+    #[error("invalid workspace member pattern {pattern}: {source}")]
+    InvalidPattern { pattern: String, source: globset::Error },
+
+    #[error("workspace member {0:?} does not have a config file")]
+    MemberWithoutConfig(PathBuf),
+
+    #[error("workspace members require different versions of {dependency}: {requirements}")]
+    ConflictingRequirements { dependency: String, requirements: String },
+}
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum GraphError {
+    #[error("error while interacting with the config file: {0}")]
+    ConfigError(#[from] ConfigError),
+
+    #[error("error while reading the lockfile: {0}")]
+    LockError(#[from] LockError),
+
+    #[error("error while reading the installed packages: {0}")]
+    InstallError(#[from] InstallError),
+
+    #[error("package {0} is not part of the dependency graph")]
+    PackageNotFound(String),
+}
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum VerifyError {
+    #[error("error while checking the integrity of a dependency: {0}")]
+    InstallError(#[from] InstallError),
+
+    #[error("error while reading the cache: {0}")]
+    CacheError(#[from] CacheError),
+
+    #[error("error while retrieving the download URL: {0}")]
+    RegistryError(#[from] RegistryError),
+
+    #[error("error downloading archive: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("error reading archive: {0}")]
+    ArchiveError(#[from] ArchiveError),
+
+    #[error("error during IO operation for {path:?}: {source}")]
+    IOError { path: PathBuf, source: io::Error },
+
+    #[error("error during async operation: {0}")]
+    AsyncError(#[from] tokio::task::JoinError),
+
+    #[error(
+        "archive checksum for {dependency} does not match lock file: expected {expected}, got {actual}"
+    )]
+    ArchiveChecksum { dependency: String, expected: String, actual: String },
+
+    #[error("the files of some dependencies were modified: {}", .0.join(", "))]
+    Tampered(Vec<String>),
+}
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum VendorError {
+    #[error("error downloading artifact: {0}")]
+    DownloadError(#[from] DownloadError),
+
+    #[error("error while retrieving the download URL: {0}")]
+    RegistryError(#[from] RegistryError),
+
+    #[error("error while reading the cache: {0}")]
+    CacheError(#[from] CacheError),
+
+    #[error("error during IO operation for {path:?}: {source}")]
+    IOError { path: PathBuf, source: io::Error },
+
+    #[error("error while parsing the vendor index {path:?}: {source}")]
+    IndexError { path: PathBuf, source: serde_json::Error },
+
+    #[error("error during async operation: {0}")]
+    AsyncError(#[from] tokio::task::JoinError),
+
+    #[error(
+        "archive checksum for {dependency} does not match lock file: expected {expected}, got {actual}"
+    )]
+    ArchiveChecksum { dependency: String, expected: String, actual: String },
 }
@@ -0,0 +1,566 @@
+//! Dependency graph of a project, as displayed by `soldeer tree`.
+//!
+//! The graph is built from the dependencies declared in the config file, the lockfile entries and
+//! the packages installed on disk. Each installed package can declare its own soldeer dependencies
+//! in its config file, which are resolved into the project's dependencies folder. Packages can also
+//! vendor git submodules, which are cloned inside of the package folder. If a submodule is itself a
+//! soldeer project, its dependencies are installed inside of the submodule and are part of the
+//! graph too.
+use crate::{
+    config::{Dependency, Paths, detect_config_location, read_config_deps},
+    errors::GraphError,
+    install::get_submodules,
+    lock::{LockEntry, read_lockfile},
+    resolver::{dependency_from_lock, read_subdependencies},
+    utils::run_git_command,
+};
+use log::{debug, warn};
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+};
+
+pub type Result<T> = std::result::Result<T, GraphError>;
+
+type GraphFuture = Pin<Box<dyn Future<Output = Result<DependencyGraph>> + Send>>;
+
+/// The source of a package of the dependency graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum PackageSource {
+    /// A public package of the Soldeer registry.
+    Registry,
+
+    /// A zip file downloaded from a custom URL.
+    Http,
+
+    /// A git repository.
+    Git,
+
+    /// A private package of the Soldeer registry.
+    Private,
+
+    /// A local folder.
+    Path,
+
+    /// A git submodule vendored inside of another package.
+    Submodule,
+}
+
+impl fmt::Display for PackageSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Registry => write!(f, "registry"),
+            Self::Http => write!(f, "http"),
+            Self::Git => write!(f, "git"),
+            Self::Private => write!(f, "private"),
+            Self::Path => write!(f, "path"),
+            Self::Submodule => write!(f, "submodule"),
+        }
+    }
+}
+
+/// A package of the dependency graph.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, bon::Builder)]
+#[builder(on(String, into))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Package {
+    /// The unique identifier of the package in the graph.
+    ///
+    /// This is the name of the package for the packages installed in the project's dependencies
+    /// folder. For git submodules and the dependencies of nested soldeer projects, the name is
+    /// prefixed with the identifier of the parent package and a slash.
+    pub id: String,
+
+    /// The name of the package.
+    pub name: String,
+
+    /// The resolved version of the package.
+    ///
+    /// For git submodules, this is the checked out commit (or the tracked branch if the submodule
+    /// is not initialized).
+    pub version: String,
+
+    /// Where the package comes from.
+    pub source: PackageSource,
+
+    /// The download URL, git URL or folder of the package, if known.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub url: Option<String>,
+
+    /// The git commit of the package, for git dependencies and submodules.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub rev: Option<String>,
+
+    /// The folder where the package is installed.
+    pub install_path: PathBuf,
+}
+
+/// An edge of the dependency graph, from a package to one of its dependencies.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, bon::Builder)]
+#[builder(on(String, into))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Edge {
+    /// The identifier of the dependent package, or `None` for the project itself.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub from: Option<String>,
+
+    /// The identifier of the dependency.
+    pub to: String,
+
+    /// The version requirement declared by the dependent package, if any.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub version_req: Option<String>,
+}
+
+/// The dependency graph of a project.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct DependencyGraph {
+    /// The packages of the graph, in breadth-first order from the project.
+    pub packages: Vec<Package>,
+
+    /// The edges of the graph.
+    pub edges: Vec<Edge>,
+}
+
+impl DependencyGraph {
+    /// Get a package by its identifier.
+    pub fn package(&self, id: &str) -> Option<&Package> {
+        self.packages.iter().find(|p| p.id == id)
+    }
+
+    /// Get the edges from the project to its direct dependencies.
+    pub fn roots(&self) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(|e| e.from.is_none())
+    }
+
+    /// Get the edges from a package to its dependencies.
+    pub fn children<'a>(&'a self, id: &'a str) -> impl Iterator<Item = &'a Edge> {
+        self.edges.iter().filter(move |e| e.from.as_deref() == Some(id))
+    }
+
+    /// Get the edges from the dependents of a package to the package.
+    pub fn parents<'a>(&'a self, id: &'a str) -> impl Iterator<Item = &'a Edge> {
+        self.edges.iter().filter(move |e| e.to == id)
+    }
+
+    /// Get the sub-graph of the packages which depend (directly or not) on the packages with the
+    /// given name.
+    ///
+    /// The sub-graph contains the packages with that name, all the packages which pull them in and
+    /// the edges between those. If no package has that name, a [`GraphError::PackageNotFound`]
+    /// error is returned.
+    pub fn dependents_of(&self, name: &str) -> Result<Self> {
+        let mut ids: HashSet<&str> =
+            self.packages.iter().filter(|p| p.name == name).map(|p| p.id.as_str()).collect();
+        if ids.is_empty() {
+            return Err(GraphError::PackageNotFound(name.to_string()));
+        }
+        let mut queue: VecDeque<_> = ids.iter().copied().collect();
+        while let Some(id) = queue.pop_front() {
+            for parent in self.parents(id).filter_map(|e| e.from.as_deref()) {
+                if ids.insert(parent) {
+                    queue.push_back(parent);
+                }
+            }
+        }
+        Ok(Self {
+            packages: self
+                .packages
+                .iter()
+                .filter(|p| ids.contains(p.id.as_str()))
+                .cloned()
+                .collect(),
+            edges: self
+                .edges
+                .iter()
+                .filter(|e| {
+                    ids.contains(e.to.as_str()) &&
+                        e.from.as_deref().is_none_or(|from| ids.contains(from))
+                })
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Get every path from the project to the packages with the given name.
+    ///
+    /// Each path is the list of edges from a dependency declared in the project's config file down
+    /// to a package with that name. If no package has that name, a
+    /// [`GraphError::PackageNotFound`] error is returned.
+    pub fn paths_to(&self, name: &str) -> Result<Vec<Vec<&Edge>>> {
+        if self.packages.iter().all(|p| p.name != name) {
+            return Err(GraphError::PackageNotFound(name.to_string()));
+        }
+        let mut paths = Vec::new();
+        for edge in self.roots() {
+            self.collect_paths(name, vec![edge], &mut paths);
+        }
+        Ok(paths)
+    }
+
+    /// Depth-first search of the paths to the packages with the given name.
+    ///
+    /// Edges which would lead back to a package of the current path are skipped, so that cycles
+    /// don't cause infinite recursion.
+    fn collect_paths<'a>(
+        &'a self,
+        name: &str,
+        path: Vec<&'a Edge>,
+        paths: &mut Vec<Vec<&'a Edge>>,
+    ) {
+        let last = path.last().expect("path should not be empty");
+        if self.package(&last.to).is_some_and(|p| p.name == name) {
+            paths.push(path);
+            return;
+        }
+        for edge in self.children(&last.to) {
+            if path.iter().any(|e| e.to == edge.to) {
+                continue;
+            }
+            let mut next = path.clone();
+            next.push(edge);
+            self.collect_paths(name, next, paths);
+        }
+    }
+
+    /// Add the packages and edges of another graph, skipping the packages which are already known.
+    fn extend(&mut self, other: Self) {
+        for package in other.packages {
+            if self.package(&package.id).is_none() {
+                self.packages.push(package);
+            }
+        }
+        for edge in other.edges {
+            if !self.edges.contains(&edge) {
+                self.edges.push(edge);
+            }
+        }
+    }
+}
+
+/// Build the dependency graph of a project.
+///
+/// The `dependencies` are the ones declared in the project's config file, and `locks` are the
+/// entries of its lockfile. Dependencies which are not in the lockfile are not installed, and are
+/// left out of the graph.
+pub async fn build_graph(
+    dependencies: &[Dependency],
+    locks: &[LockEntry],
+    deps: impl AsRef<Path>,
+) -> Result<DependencyGraph> {
+    project_graph(None, dependencies.to_vec(), locks.to_vec(), deps.as_ref().to_path_buf()).await
+}
+
+/// Build the graph of a soldeer project.
+///
+/// For the root project, `parent` is `None`. For a soldeer project nested inside of a git
+/// submodule, it's the identifier of the submodule package, which is used as a prefix for the
+/// identifiers of the packages of the nested project.
+fn project_graph(
+    parent: Option<String>,
+    dependencies: Vec<Dependency>,
+    locks: Vec<LockEntry>,
+    deps: PathBuf,
+) -> GraphFuture {
+    Box::pin(async move {
+        let prefix = parent.as_ref().map(|p| format!("{p}/")).unwrap_or_default();
+        let mut graph = DependencyGraph::default();
+        let mut declared = dependencies.clone();
+        let mut queue = VecDeque::new();
+        for dependency in &dependencies {
+            if locks.iter().all(|l| l.name() != dependency.name()) {
+                warn!(dep:% = dependency; "dependency is not in the lockfile, skipping");
+                continue;
+            }
+            graph.edges.push(Edge {
+                from: parent.clone(),
+                to: format!("{prefix}{}", dependency.name()),
+                version_req: Some(dependency.version_req().to_string()),
+            });
+            queue.push_back(dependency.name().to_string());
+        }
+        let mut visited = HashSet::new();
+        while let Some(name) = queue.pop_front() {
+            if !visited.insert(name.clone()) {
+                continue;
+            }
+            let lock = locks.iter().find(|l| l.name() == name).expect("lock entry should exist");
+            let dependency = declared
+                .iter()
+                .find(|d| d.name() == name)
+                .cloned()
+                .unwrap_or_else(|| dependency_from_lock(lock));
+            let id = format!("{prefix}{name}");
+            let install_path = lock.install_path(&deps);
+            graph.packages.push(package_from_lock(&id, &dependency, lock, &install_path));
+
+            let mut subdependencies: Vec<_> = read_subdependencies(&dependency, lock, &deps)
+                .await?
+                .into_iter()
+                .map(|d| {
+                    let req = d.version_req().to_string();
+                    (d, Some(req))
+                })
+                .collect();
+            // the lockfile records the edges even if the package's config can't be read, but
+            // without the version requirement
+            for sub in lock.dependencies() {
+                if subdependencies.iter().all(|(d, _)| d.name() != sub) &&
+                    let Some(sub_lock) = locks.iter().find(|l| l.name() == sub)
+                {
+                    subdependencies.push((dependency_from_lock(sub_lock), None));
+                }
+            }
+            subdependencies.sort_unstable_by(|(a, _), (b, _)| a.name().cmp(b.name()));
+            for (sub, version_req) in subdependencies {
+                if locks.iter().all(|l| l.name() != sub.name()) {
+                    debug!(dep:% = sub, parent = name; "transitive dependency is not installed");
+                    continue;
+                }
+                graph.edges.push(Edge {
+                    from: Some(id.clone()),
+                    to: format!("{prefix}{}", sub.name()),
+                    version_req,
+                });
+                queue.push_back(sub.name().to_string());
+                if declared.iter().all(|d| d.name() != sub.name()) {
+                    declared.push(sub);
+                }
+            }
+
+            if install_path.join(".gitmodules").exists() {
+                graph.extend(submodules_graph(id, install_path).await?);
+            }
+        }
+        Ok(graph)
+    })
+}
+
+/// Build the graph of the git submodules vendored inside of a package.
+///
+/// Submodules can themselves have submodules, or be soldeer projects with their own dependencies.
+fn submodules_graph(parent: String, path: PathBuf) -> GraphFuture {
+    Box::pin(async move {
+        let mut graph = DependencyGraph::default();
+        let mut submodules: Vec<_> = get_submodules(&path).await?.into_values().collect();
+        submodules.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+        for submodule in submodules {
+            let sub_path = path.join(&submodule.path);
+            let id = format!("{parent}/{}", submodule.path);
+            let name = submodule.path.rsplit('/').next().unwrap_or(&submodule.path).to_string();
+            let rev = if sub_path.join(".git").exists() {
+                run_git_command(&["rev-parse", "HEAD"], Some(&sub_path))
+                    .await
+                    .ok()
+                    .map(|rev| rev.trim().to_string())
+            } else {
+                None
+            };
+            let version = rev
+                .clone()
+                .or_else(|| submodule.branch.clone())
+                .unwrap_or_else(|| "HEAD".to_string());
+            graph.packages.push(Package {
+                id: id.clone(),
+                name,
+                version,
+                source: PackageSource::Submodule,
+                url: Some(submodule.url.clone()),
+                rev,
+                install_path: sub_path.clone(),
+            });
+            graph.edges.push(Edge {
+                from: Some(parent.clone()),
+                to: id.clone(),
+                version_req: submodule.branch.clone(),
+            });
+            if sub_path.join(".gitmodules").exists() {
+                graph.extend(submodules_graph(id.clone(), sub_path.clone()).await?);
+            }
+            if detect_config_location(&sub_path).is_some() {
+                debug!(sub_path:?; "found soldeer config in submodule, reading its dependencies");
+                let paths = Paths::from_root(&sub_path).map_err(GraphError::ConfigError)?;
+                let (dependencies, _) = read_config_deps(&paths.config)?;
+                let lockfile = read_lockfile(&paths.lock)?;
+                graph.extend(
+                    project_graph(Some(id), dependencies, lockfile.entries, paths.dependencies)
+                        .await?,
+                );
+            }
+        }
+        Ok(graph)
+    })
+}
+
+/// Create the package of the graph which corresponds to a lock entry.
+///
+/// The declared dependency is used to tell registry packages apart from custom URLs.
+pub(crate) fn package_from_lock(
+    id: &str,
+    dependency: &Dependency,
+    lock: &LockEntry,
+    install_path: &Path,
+) -> Package {
+    let (source, url, rev) = match lock {
+        LockEntry::Http(lock) if dependency.url().is_none() => {
+            (PackageSource::Registry, Some(lock.url.clone()), None)
+        }
+        LockEntry::Http(lock) => (PackageSource::Http, Some(lock.url.clone()), None),
+        LockEntry::Git(lock) => {
+            (PackageSource::Git, Some(lock.git.clone()), Some(lock.rev.clone()))
+        }
+        LockEntry::Private(_) => (PackageSource::Private, None, None),
+        LockEntry::Path(lock) => (PackageSource::Path, Some(lock.path.clone()), None),
+    };
+    Package {
+        id: id.to_string(),
+        name: lock.name().to_string(),
+        version: lock.version().to_string(),
+        source,
+        url,
+        rev,
+        install_path: install_path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{GitDependency, HttpDependency},
+        lock::{GitLockEntry, HttpLockEntry},
+    };
+    use std::fs;
+    use testdir::testdir;
+
+    fn create_graph_fixture(deps: &Path) -> (Vec<Dependency>, Vec<LockEntry>) {
+        fs::create_dir_all(deps.join("lib-1.0.0")).unwrap();
+        fs::write(deps.join("lib-1.0.0/soldeer.toml"), "[dependencies]\nforge-std = \"^1.9.0\"\n")
+            .unwrap();
+        fs::create_dir_all(deps.join("forge-std-1.9.2")).unwrap();
+        fs::create_dir_all(deps.join("mylib-1.0.0")).unwrap();
+        let dependencies: Vec<Dependency> = vec![
+            HttpDependency::builder().name("lib").version_req("1.0.0").build().into(),
+            GitDependency::builder()
+                .name("mylib")
+                .version_req("1.0.0")
+                .git("https://example.com/mylib.git")
+                .build()
+                .into(),
+        ];
+        let locks: Vec<LockEntry> = vec![
+            HttpLockEntry::builder()
+                .name("lib")
+                .version("1.0.0")
+                .url("https://example.com/lib.zip")
+                .checksum("")
+                .integrity("")
+                .dependencies(vec!["forge-std".to_string()])
+                .build()
+                .into(),
+            HttpLockEntry::builder()
+                .name("forge-std")
+                .version("1.9.2")
+                .url("https://example.com/forge-std.zip")
+                .checksum("")
+                .integrity("")
+                .build()
+                .into(),
+            GitLockEntry::builder()
+                .name("mylib")
+                .version("1.0.0")
+                .git("https://example.com/mylib.git")
+                .rev("1234567890123456789012345678901234567890")
+                .build()
+                .into(),
+        ];
+        (dependencies, locks)
+    }
+
+    #[tokio::test]
+    async fn test_build_graph() {
+        let dir = testdir!();
+        let deps = dir.join("dependencies");
+        let (dependencies, locks) = create_graph_fixture(&deps);
+        let graph = build_graph(&dependencies, &locks, &deps).await.unwrap();
+        assert_eq!(
+            graph.packages.iter().map(|p| (p.id.as_str(), p.source)).collect::<Vec<_>>(),
+            vec![
+                ("lib", PackageSource::Registry),
+                ("mylib", PackageSource::Git),
+                ("forge-std", PackageSource::Registry)
+            ]
+        );
+        let forge_std = graph.package("forge-std").unwrap();
+        assert_eq!(forge_std.version, "1.9.2");
+        assert_eq!(forge_std.install_path, deps.join("forge-std-1.9.2"));
+        let mylib = graph.package("mylib").unwrap();
+        assert_eq!(mylib.rev.as_deref(), Some("1234567890123456789012345678901234567890"));
+        assert_eq!(graph.roots().count(), 2);
+        let edges: Vec<_> = graph.children("lib").collect();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].to, "forge-std");
+        assert_eq!(edges[0].version_req.as_deref(), Some("^1.9.0"));
+    }
+
+    #[tokio::test]
+    async fn test_build_graph_missing_lock() {
+        let dir = testdir!();
+        let deps = dir.join("dependencies");
+        let (dependencies, locks) = create_graph_fixture(&deps);
+        // the transitive dependency is not installed
+        let graph = build_graph(&dependencies, &locks[..1], &deps).await.unwrap();
+        assert_eq!(graph.packages.len(), 1);
+        assert_eq!(graph.edges.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dependents_of() {
+        let dir = testdir!();
+        let deps = dir.join("dependencies");
+        let (dependencies, locks) = create_graph_fixture(&deps);
+        let graph = build_graph(&dependencies, &locks, &deps).await.unwrap();
+        let inverted = graph.dependents_of("forge-std").unwrap();
+        assert_eq!(
+            inverted.packages.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(),
+            vec!["lib", "forge-std"]
+        );
+        assert_eq!(inverted.edges.len(), 2);
+        let res = graph.dependents_of("foo");
+        assert!(matches!(res, Err(GraphError::PackageNotFound(_))), "{res:?}");
+    }
+
+    #[tokio::test]
+    async fn test_paths_to() {
+        let dir = testdir!();
+        let deps = dir.join("dependencies");
+        let (mut dependencies, locks) = create_graph_fixture(&deps);
+        dependencies
+            .push(HttpDependency::builder().name("forge-std").version_req("1.9.2").build().into());
+        let graph = build_graph(&dependencies, &locks, &deps).await.unwrap();
+        let paths = graph.paths_to("forge-std").unwrap();
+        let paths: Vec<Vec<_>> = paths
+            .iter()
+            .map(|p| p.iter().map(|e| (e.to.as_str(), e.version_req.as_deref())).collect())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                vec![("lib", Some("1.0.0")), ("forge-std", Some("^1.9.0"))],
+                vec![("forge-std", Some("1.9.2"))]
+            ]
+        );
+        assert_eq!(graph.paths_to("mylib").unwrap().len(), 1);
+        let res = graph.paths_to("foo");
+        assert!(matches!(res, Err(GraphError::PackageNotFound(_))), "{res:?}");
+    }
+}
@@ -3,18 +3,28 @@
 //! This module contains functions to install dependencies from the config object or from the
 //! lockfile. Dependencies can be installed in parallel.
 use crate::{
+    archive::ArchiveFormat,
+    cache::{add_to_cache, get_cached_archive, get_cached_archive_by_url, record_url},
     config::{
-        Dependency, GitIdentifier, HttpDependency, Paths, detect_config_location, read_config_deps,
-        read_soldeer_config,
+        Dependency, GitIdentifier, HttpDependency, LinkMode, PathDependency, Paths,
+        detect_config_location, read_config_deps, read_soldeer_config,
     },
-    download::{clone_repo, delete_dependency_files, download_file, unzip_file},
-    errors::{ConfigError, InstallError, LockError},
+    download::{clone_repo, delete_dependency_files, download_file, extract_archive},
+    errors::{ConfigError, DownloadError, InstallError, LockError},
+    graph::{PackageSource, package_from_lock},
+    license::scan_package,
     lock::{
-        GitLockEntry, HttpLockEntry, Integrity, LockEntry, PrivateLockEntry, forge,
+        GitLockEntry, HttpLockEntry, Integrity, LockEntry, PathLockEntry, PrivateLockEntry, forge,
         format_install_path, read_lockfile,
     },
-    registry::{DownloadUrl, get_dependency_url_remote, get_latest_supported_version},
-    utils::{IntegrityChecksum, canonicalize, hash_file, hash_folder, run_git_command},
+    manifest::{FileChange, IntegrityManifest, check_folder_integrity},
+    policy::Policy,
+    registry::{DownloadUrl, Registries, get_dependency_url_remote, get_latest_supported_version},
+    resolver::dependency_from_lock,
+    signing::{PackageSignature, TrustPolicy},
+    store::{clone_to_store, extract_to_store, is_linked, link_from_store, remove_path},
+    utils::{IntegrityChecksum, canonicalize, hash_file, run_git_command, sanitize_filename},
+    vendor::{vendored_archive, vendored_git},
 };
 use derive_more::derive::Display;
 use log::{debug, info, warn};
@@ -149,6 +159,35 @@ impl InstallProgress {
     }
 }
 
+/// Options which apply to all the dependencies of an install.
+#[derive(Debug, Clone, Default, PartialEq, Eq, bon::Builder)]
+#[non_exhaustive]
+pub struct InstallOptions {
+    /// How dependencies are installed from the [`crate::store`].
+    #[builder(default)]
+    pub link_mode: LinkMode,
+
+    /// Never use the network: dependencies are installed from the global cache or from the
+    /// dependencies folder, and registry data is read from the cache.
+    #[builder(default)]
+    pub offline: bool,
+
+    /// The vendor folder to install dependencies from before using the cache or the network (see
+    /// [`crate::vendor`]).
+    pub vendor_dir: Option<PathBuf>,
+
+    /// The policy for the signatures of the dependencies.
+    #[builder(default)]
+    pub trust_policy: TrustPolicy,
+
+    /// The policy for the sources and licenses of the dependencies.
+    ///
+    /// The policy of the root project applies to all the dependencies, including the transitive
+    /// ones.
+    #[builder(default)]
+    pub policy: Policy,
+}
+
 /// Status of a dependency, which can either be missing, installed and untouched, or installed but
 /// failing the integrity check.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -164,6 +203,41 @@ pub enum DependencyStatus {
     Installed,
 }
 
+/// Information about a dependency of the project, as reported by `soldeer list`.
+///
+/// This merges the dependency declared in the config file, its lock entry and the status of its
+/// install folder. Transitive dependencies have no version requirement, and dependencies which are
+/// not in the lockfile have no locked version, source information or install path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, bon::Builder)]
+#[builder(on(String, into))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct DependencyInfo {
+    /// The name of the dependency.
+    pub name: String,
+
+    /// The version requirement from the config file.
+    pub version_req: Option<String>,
+
+    /// The version recorded in the lockfile.
+    pub locked_version: Option<String>,
+
+    /// Where the dependency comes from.
+    pub source: Option<PackageSource>,
+
+    /// The download URL, git URL or folder of the dependency.
+    pub url: Option<String>,
+
+    /// The locked git commit, for git dependencies.
+    pub rev: Option<String>,
+
+    /// The folder where the dependency is installed.
+    pub install_path: Option<PathBuf>,
+
+    /// The status of the install folder.
+    pub status: DependencyStatus,
+}
+
 /// HTTP dependency installation information.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, bon::Builder)]
 #[builder(on(String, into))]
@@ -175,17 +249,20 @@ struct HttpInstallInfo {
     /// version.
     version: String,
 
-    /// The URL from which the zip file will be downloaded.
+    /// The URL from which the archive will be downloaded.
     url: String,
 
-    /// The checksum of the downloaded zip file, if available (e.g. from the lockfile)
+    /// The checksum of the downloaded archive, if available (e.g. from the lockfile)
     checksum: Option<String>,
 
-    /// An optional relative path to the project's root within the zip file.
-    ///
-    /// The project root is where the soldeer.toml or foundry.toml resides. If no path is provided,
-    /// then the zip's root must contain a Soldeer config.
-    project_root: Option<PathBuf>,
+    /// The integrity checksum of the extracted archive, if available (e.g. from the lockfile)
+    integrity: Option<String>,
+
+    /// The name of the registry the dependency is downloaded from, if not the default registry.
+    registry: Option<String>,
+
+    /// The signature of the archive, if the package is signed.
+    signature: Option<PackageSignature>,
 }
 
 impl fmt::Display for HttpInstallInfo {
@@ -212,11 +289,8 @@ struct GitInstallInfo {
     /// `None` is provided, the default branch is used.
     identifier: Option<GitIdentifier>,
 
-    /// An optional relative path to the project's root within the repository.
-    ///
-    /// The project root is where the soldeer.toml or foundry.toml resides. If no path is provided,
-    /// then the repo's root must contain a Soldeer config.
-    project_root: Option<PathBuf>,
+    /// The subdirectory of the repository to check out, if any.
+    subdir: Option<String>,
 }
 
 impl fmt::Display for GitInstallInfo {
@@ -254,14 +328,20 @@ impl From<GitInstallInfo> for InstallInfo {
 }
 
 impl InstallInfo {
-    async fn from_lock(lock: LockEntry, project_root: Option<PathBuf>) -> Result<Self> {
+    async fn from_lock(
+        lock: LockEntry,
+        registries: &Registries,
+        options: &InstallOptions,
+    ) -> Result<Self> {
         match lock {
             LockEntry::Http(lock) => Ok(HttpInstallInfo {
                 name: lock.name,
                 version: lock.version,
                 url: lock.url,
                 checksum: Some(lock.checksum),
-                project_root,
+                integrity: Some(lock.integrity),
+                registry: lock.registry,
+                signature: lock.signature,
             }
             .into()),
             LockEntry::Git(lock) => Ok(GitInstallInfo {
@@ -269,18 +349,41 @@ impl InstallInfo {
                 version: lock.version,
                 git: lock.git,
                 identifier: Some(GitIdentifier::from_rev(lock.rev)),
-                project_root,
+                subdir: lock.subdir,
             }
             .into()),
             LockEntry::Private(lock) => {
+                if is_cached(&lock.checksum).await ||
+                    options
+                        .vendor_dir
+                        .as_deref()
+                        .is_some_and(|dir| vendored_archive(dir, &lock.checksum).is_some())
+                {
+                    // the archive will be copied from the vendor folder or the cache, no need for
+                    // a download URL
+                    return Ok(Self::Private(HttpInstallInfo {
+                        name: lock.name,
+                        version: lock.version,
+                        url: String::new(),
+                        checksum: Some(lock.checksum),
+                        integrity: Some(lock.integrity),
+                        registry: lock.registry,
+                        signature: lock.signature,
+                    }));
+                }
+                if options.offline {
+                    return Err(InstallError::Offline(format!("{}-{}", lock.name, lock.version)));
+                }
                 // need to retrieve a signed download URL from the registry
                 let download = get_dependency_url_remote(
+                    registries.get(lock.registry.as_deref())?,
                     &HttpDependency::builder()
                         .name(&lock.name)
                         .version_req(&lock.version)
                         .build()
                         .into(),
                     &lock.version,
+                    options.offline,
                 )
                 .await?;
                 Ok(Self::Private(HttpInstallInfo {
@@ -288,32 +391,38 @@ impl InstallInfo {
                     version: lock.version,
                     url: download.url,
                     checksum: Some(lock.checksum),
-                    project_root,
+                    integrity: Some(lock.integrity),
+                    registry: lock.registry,
+                    signature: lock.signature,
                 }))
             }
+            // path dependencies are installed with `install_path_dependency`
+            LockEntry::Path(_) => Err(LockError::InvalidLockEntry.into()),
         }
     }
 }
 
 /// Git submodule information
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
-struct Submodule {
-    url: String,
-    path: String,
-    branch: Option<String>,
+pub(crate) struct Submodule {
+    pub(crate) url: String,
+    pub(crate) path: String,
+    pub(crate) branch: Option<String>,
 }
 
 /// Install a list of dependencies in parallel.
 ///
 /// This function spawns a task for each dependency and waits for all of them to finish. Each task
-/// checks the integrity of the dependency if found on disk, downloads the dependency (zip file or
-/// cloning repo) if not already present, unzips the zip file if necessary, installs
+/// checks the integrity of the dependency if found on disk, downloads the dependency (archive or
+/// cloning repo) if not already present, extracts the archive if necessary, installs
 /// sub-dependencies and generates the lockfile entry.
 pub async fn install_dependencies(
     dependencies: &[Dependency],
     locks: &[LockEntry],
     deps: impl AsRef<Path>,
+    registries: &Registries,
     recursive_deps: bool,
+    options: &InstallOptions,
     progress: InstallProgress,
 ) -> Result<Vec<LockEntry>> {
     let mut set = JoinSet::new();
@@ -324,13 +433,17 @@ pub async fn install_dependencies(
             let p = progress.clone();
             let lock = locks.iter().find(|l| l.name() == dep.name()).cloned();
             let deps = deps.as_ref().to_path_buf();
+            let registries = registries.clone();
+            let options = options.clone();
             async move {
                 install_dependency(
                     &d,
                     lock.as_ref(),
                     deps,
                     None,
+                    &registries,
                     recursive_deps,
+                    &options,
                     p,
                 )
                 .await
@@ -352,13 +465,15 @@ pub async fn install_dependencies(
 ///
 /// This function can be used inside another tokio task to avoid spawning more tasks, useful for
 /// recursive install. For each dep, checks the integrity of the dependency if found on disk,
-/// downloads the dependency (zip file or cloning repo) if not already present, unzips the zip file
+/// downloads the dependency (archive or cloning repo) if not already present, extracts the archive
 /// if necessary, installs sub-dependencies and generates the lockfile entry.
 pub async fn install_dependencies_sequential(
     dependencies: &[Dependency],
     locks: &[LockEntry],
     deps: impl AsRef<Path> + Clone,
+    registries: &Registries,
     recursive_deps: bool,
+    options: &InstallOptions,
     progress: InstallProgress,
 ) -> Result<Vec<LockEntry>> {
     let mut results = Vec::new();
@@ -366,8 +481,17 @@ pub async fn install_dependencies_sequential(
         debug!(dep:% = dep; "installing dependency sequentially");
         let lock = locks.iter().find(|l| l.name() == dep.name());
         results.push(
-            install_dependency(dep, lock, deps.clone(), None, recursive_deps, progress.clone())
-                .await?,
+            install_dependency(
+                dep,
+                lock,
+                deps.clone(),
+                None,
+                registries,
+                recursive_deps,
+                options,
+                progress.clone(),
+            )
+            .await?,
         );
         debug!(dep:% = dep; "sequential install finished");
     }
@@ -378,33 +502,60 @@ pub async fn install_dependencies_sequential(
 /// Install a single dependency.
 ///
 /// This function checks the integrity of the dependency if found on disk, downloads the dependency
-/// (zip file or cloning repo) if not already present, unzips the zip file if necessary, installs
+/// (archive or cloning repo) if not already present, extracts the archive if necessary, installs
 /// sub-dependencies and generates the lockfile entry.
 ///
 /// If no lockfile entry is provided, the dependency is installed from the config object and
 /// integrity checks are skipped.
+///
+/// Path dependencies are always linked again, regardless of the lockfile entry.
+#[allow(clippy::too_many_arguments)]
 pub async fn install_dependency(
     dependency: &Dependency,
     lock: Option<&LockEntry>,
     deps: impl AsRef<Path>,
     force_version: Option<String>,
+    registries: &Registries,
     recursive_deps: bool,
+    options: &InstallOptions,
     progress: InstallProgress,
 ) -> Result<LockEntry> {
+    if options.trust_policy.require_signatures &&
+        matches!(dependency, Dependency::Git(_) | Dependency::Path(_))
+    {
+        warn!(dep:% = dependency; "signatures are required but git and path dependencies can't be signed, skipping signature check");
+        progress.log(format!("Dependency {dependency} can't be signed, skipping signature check"));
+    }
+    if let Dependency::Path(dep) = dependency {
+        return install_path_dependency(dep, deps, &options.policy, progress).await;
+    }
     if let Some(lock) = lock {
         debug!(dep:% = dependency; "installing based on lock entry");
+        options.policy.check_resolved(dependency, lock.version())?;
+        if let Some((checksum, signature)) = lock.archive_signature() {
+            options.trust_policy.check(&dependency.to_string(), checksum, signature)?;
+        }
         match check_dependency_integrity(lock, &deps).await? {
             DependencyStatus::Installed => {
                 info!(dep:% = dependency; "skipped install, dependency already up-to-date with lockfile");
+                check_licenses(lock, &lock.install_path(&deps), &options.policy).await?;
                 progress.update_all(dependency.into());
 
                 return Ok(lock.clone());
             }
             DependencyStatus::FailedIntegrity => match dependency {
                 Dependency::Http(_) => {
-                    info!(dep:% = dependency; "dependency failed integrity check, reinstalling");
+                    let changes = integrity_changes(lock, &deps)
+                        .await?
+                        .filter(|c| !c.is_empty())
+                        .map(|c| {
+                            let c: Vec<_> = c.iter().map(ToString::to_string).collect();
+                            format!(" ({})", c.join(", "))
+                        })
+                        .unwrap_or_default();
+                    info!(dep:% = dependency, changes; "dependency failed integrity check, reinstalling");
                     progress.log(format!(
-                        "Dependency {dependency} failed integrity check, reinstalling"
+                        "Dependency {dependency} failed integrity check{changes}, reinstalling"
                     ));
                     // we know the folder exists because otherwise we would have gotten
                     // `Missing`
@@ -413,6 +564,19 @@ pub async fn install_dependency(
                     // we won't need to retrieve the version number so we mark it as done
                     progress.versions.send(dependency.into()).ok();
                 }
+                Dependency::Git(_)
+                    if options.link_mode != LinkMode::Copy ||
+                        is_linked(&lock.install_path(&deps)).await =>
+                {
+                    // the folder is shared with the store, so it's installed again from the store
+                    // instead of being reset
+                    info!(dep:% = dependency; "linked dependency failed integrity check, reinstalling");
+                    progress.log(format!(
+                        "Dependency {dependency} failed integrity check, reinstalling"
+                    ));
+                    remove_path(&lock.install_path(&deps)).await?;
+                    progress.versions.send(dependency.into()).ok();
+                }
                 Dependency::Git(_) => {
                     let commit = &lock.as_git().expect("lock entry should be of type git").rev;
                     info!(dep:% = dependency, commit; "dependency failed integrity check, resetting to commit");
@@ -426,11 +590,13 @@ pub async fn install_dependency(
                     )
                     .await?;
                     debug!(dep:% = dependency; "reset git dependency");
+                    check_licenses(lock, &lock.install_path(&deps), &options.policy).await?;
                     // dependency should now be at the correct commit, we can exit
                     progress.update_all(dependency.into());
 
                     return Ok(lock.clone());
                 }
+                Dependency::Path(_) => unreachable!("path dependencies are installed separately"),
             },
             DependencyStatus::Missing => {
                 // make sure there is no existing directory for the dependency
@@ -445,9 +611,10 @@ pub async fn install_dependency(
             }
         }
         install_dependency_inner(
-            &InstallInfo::from_lock(lock.clone(), dependency.project_root()).await?,
+            &InstallInfo::from_lock(lock.clone(), registries, options).await?,
             lock.install_path(&deps),
             recursive_deps,
+            options,
             progress,
         )
         .await
@@ -466,19 +633,30 @@ pub async fn install_dependency(
             // version requirement string as version, because in that case a version requirement has
             // little sense (we can't automatically bump the version)
             Some(url) => (
-                DownloadUrl { url: url.clone(), private: false },
+                DownloadUrl { url: url.clone(), private: false, signature: None, created_at: None },
                 dependency.version_req().to_string(),
             ),
             None => {
+                let registry = registries.for_dependency(dependency)?;
                 let version = match force_version {
                     Some(v) => v,
-                    None => get_latest_supported_version(dependency).await?,
+                    None => {
+                        get_latest_supported_version(registry, dependency, options.offline).await?
+                    }
                 };
-                (get_dependency_url_remote(dependency, &version).await?, version)
+                (
+                    get_dependency_url_remote(registry, dependency, &version, options.offline)
+                        .await?,
+                    version,
+                )
             }
         };
         debug!(dep:% = dependency, version; "resolved version");
         debug!(dep:% = dependency, url:? = download; "resolved download URL");
+        match dependency.url() {
+            Some(_) => options.policy.check_resolved(dependency, &version)?,
+            None => options.policy.check_published(dependency, &version, download.created_at)?,
+        }
         // indicate that we have retrieved the version number
         progress.versions.send(dependency.into()).ok();
 
@@ -490,6 +668,8 @@ pub async fn install_dependency(
                             .name(&dep.name)
                             .version(&version)
                             .url(download.url)
+                            .maybe_registry(dependency.registry())
+                            .maybe_signature(download.signature)
                             .build(),
                     )
                 } else {
@@ -497,6 +677,8 @@ pub async fn install_dependency(
                         .name(&dep.name)
                         .version(&version)
                         .url(download.url)
+                        .maybe_registry(dependency.registry())
+                        .maybe_signature(download.signature)
                         .build()
                         .into()
                 }
@@ -506,12 +688,14 @@ pub async fn install_dependency(
                 .version(&version)
                 .git(download.url)
                 .maybe_identifier(dep.identifier.clone())
+                .maybe_subdir(dep.subdir.clone())
                 .build()
                 .into(),
+            Dependency::Path(_) => unreachable!("path dependencies are installed separately"),
         };
         let install_path = format_install_path(dependency.name(), &version, &deps);
         debug!(dep:% = dependency; "installing to path {install_path:?}");
-        install_dependency_inner(&info, install_path, recursive_deps, progress).await
+        install_dependency_inner(&info, install_path, recursive_deps, options, progress).await
     }
 }
 
@@ -527,9 +711,76 @@ pub async fn check_dependency_integrity(
         LockEntry::Http(lock) => check_http_dependency(lock, deps).await,
         LockEntry::Private(lock) => check_http_dependency(lock, deps).await,
         LockEntry::Git(lock) => check_git_dependency(lock, deps).await,
+        LockEntry::Path(lock) => {
+            // the folder can be modified at any time, so only its presence is checked
+            if fs::metadata(lock.install_path(deps)).await.is_ok() {
+                Ok(DependencyStatus::Installed)
+            } else {
+                Ok(DependencyStatus::Missing)
+            }
+        }
     }
 }
 
+/// List the dependencies of the project along with their lock entry and install status.
+///
+/// The dependencies declared in the config file come first, in the same order, followed by the
+/// transitive dependencies which are recorded in the lockfile.
+pub async fn list_dependencies(
+    dependencies: &[Dependency],
+    locks: &[LockEntry],
+    deps: impl AsRef<Path>,
+) -> Result<Vec<DependencyInfo>> {
+    let deps = deps.as_ref();
+    let mut results = Vec::with_capacity(locks.len().max(dependencies.len()));
+    for dependency in dependencies {
+        let lock = locks.iter().find(|l| l.name() == dependency.name());
+        results.push(dependency_info(Some(dependency), lock, deps).await?);
+    }
+    for lock in locks {
+        if dependencies.iter().all(|d| d.name() != lock.name()) {
+            results.push(dependency_info(None, Some(lock), deps).await?);
+        }
+    }
+    Ok(results)
+}
+
+/// Gather the information about a single dependency.
+///
+/// At least one of the dependency or lock entry must be provided.
+async fn dependency_info(
+    dependency: Option<&Dependency>,
+    lock: Option<&LockEntry>,
+    deps: &Path,
+) -> Result<DependencyInfo> {
+    let Some(lock) = lock else {
+        let dependency = dependency.expect("dependency or lock should be provided");
+        return Ok(DependencyInfo {
+            name: dependency.name().to_string(),
+            version_req: Some(dependency.version_req().to_string()),
+            locked_version: None,
+            source: None,
+            url: None,
+            rev: None,
+            install_path: None,
+            status: DependencyStatus::Missing,
+        });
+    };
+    let install_path = lock.install_path(deps);
+    let declared = dependency.cloned().unwrap_or_else(|| dependency_from_lock(lock));
+    let package = package_from_lock(lock.name(), &declared, lock, &install_path);
+    Ok(DependencyInfo {
+        name: package.name,
+        version_req: dependency.map(|d| d.version_req().to_string()),
+        locked_version: Some(package.version),
+        source: Some(package.source),
+        url: package.url,
+        rev: package.rev,
+        install_path: Some(install_path),
+        status: check_dependency_integrity(lock, deps).await?,
+    })
+}
+
 /// Ensure that the dependencies directory exists.
 ///
 /// If the directory does not exist, it will be created.
@@ -544,74 +795,199 @@ pub fn ensure_dependencies_dir(path: impl AsRef<Path>) -> Result<()> {
 }
 
 /// Install a single dependency.
+///
+/// If the link mode is not [`LinkMode::Copy`], the dependency is extracted or cloned into the
+/// [`crate::store`] and the install folder is linked to it.
 async fn install_dependency_inner(
     dep: &InstallInfo,
     path: impl AsRef<Path>,
     subdependencies: bool,
+    options: &InstallOptions,
     progress: InstallProgress,
 ) -> Result<LockEntry> {
-    match dep {
+    let path = path.as_ref();
+    let lock: LockEntry = match dep {
         InstallInfo::Http(dep) => {
-            let (zip_integrity, integrity) =
-                install_http_dependency(dep, path, subdependencies, progress).await?;
-            Ok(HttpLockEntry::builder()
+            let (archive_checksum, integrity) =
+                install_http_dependency(dep, path, subdependencies, options, progress).await?;
+            HttpLockEntry::builder()
                 .name(&dep.name)
                 .version(&dep.version)
                 .url(&dep.url)
-                .checksum(zip_integrity.to_string())
+                .maybe_registry(dep.registry.clone())
+                .checksum(archive_checksum.to_string())
                 .integrity(integrity.to_string())
+                .maybe_signature(dep.signature.clone())
                 .build()
-                .into())
+                .into()
         }
         InstallInfo::Private(dep) => {
-            let (zip_integrity, integrity) =
-                install_http_dependency(dep, path, subdependencies, progress).await?;
-            Ok(PrivateLockEntry::builder()
+            let (archive_checksum, integrity) =
+                install_http_dependency(dep, path, subdependencies, options, progress).await?;
+            PrivateLockEntry::builder()
                 .name(&dep.name)
                 .version(&dep.version)
-                .checksum(zip_integrity.to_string())
+                .maybe_registry(dep.registry.clone())
+                .checksum(archive_checksum.to_string())
                 .integrity(integrity.to_string())
+                .maybe_signature(dep.signature.clone())
                 .build()
-                .into())
+                .into()
         }
         InstallInfo::Git(dep) => {
             // if the dependency was specified without a commit hash and we didn't have a lockfile,
             // clone the default branch
-            let commit = clone_repo(&dep.git, dep.identifier.as_ref(), &path).await?;
+            // a vendored git bundle can be cloned instead of the remote repository
+            let vendored = match &dep.identifier {
+                Some(GitIdentifier::Rev(rev)) => {
+                    options.vendor_dir.as_deref().and_then(|dir| vendored_git(dir, &dep.git, rev))
+                }
+                _ => None,
+            };
+            if let Some(bundle) = &vendored {
+                debug!(dep:% = dep, bundle:?; "cloning from vendored git bundle");
+            }
+            let source = vendored
+                .as_ref()
+                .map_or_else(|| dep.git.clone(), |p| p.to_string_lossy().into_owned());
+            // cloning a local bundle doesn't need the network
+            let offline = options.offline && vendored.is_none();
+            let commit = if options.link_mode == LinkMode::Copy {
+                if offline {
+                    return Err(InstallError::Offline(dep.to_string()));
+                }
+                clone_repo(&source, dep.identifier.as_ref(), dep.subdir.as_deref(), &path).await?
+            } else {
+                let (store_path, commit) = match clone_to_store(
+                    &source,
+                    dep.identifier.as_ref(),
+                    dep.subdir.as_deref(),
+                    offline,
+                )
+                .await
+                {
+                    Err(InstallError::DownloadError(DownloadError::Offline(_))) => {
+                        return Err(InstallError::Offline(dep.to_string()));
+                    }
+                    res => res?,
+                };
+                let mode =
+                    effective_link_mode(&store_path, subdependencies, options.link_mode).await;
+                link_from_store(&store_path, &path, mode).await?;
+                commit
+            };
             progress.downloads.send(dep.into()).ok();
 
             if subdependencies {
                 debug!(dep:% = dep; "installing subdependencies");
-                install_subdependencies(&path, dep.project_root.as_ref()).await?;
+                install_subdependencies(path, false, options).await?;
                 debug!(dep:% = dep; "finished installing subdependencies");
             }
             progress.unzip.send(dep.into()).ok();
             progress.subdependencies.send(dep.into()).ok();
             progress.integrity.send(dep.into()).ok();
-            Ok(GitLockEntry::builder()
+            GitLockEntry::builder()
                 .name(&dep.name)
                 .version(&dep.version)
                 .git(&dep.git)
                 .rev(commit)
+                .maybe_subdir(dep.subdir.clone())
                 .build()
-                .into())
+                .into()
         }
+    };
+    if let Err(err) = check_licenses(&lock, path, &options.policy).await {
+        // the folder was created by this install, it must not be considered as installed afterwards
+        remove_path(path).await?;
+        debug!(path:?; "removed dependency folder");
+        return Err(err);
+    }
+    Ok(lock)
+}
+
+/// Check the licenses of an installed dependency against the policy, if it has license rules.
+///
+/// This runs for fresh installs as well as for dependencies which were already installed and for
+/// path dependencies. The install folder is left untouched, callers remove it if it was created by
+/// the current install.
+async fn check_licenses(lock: &LockEntry, path: &Path, policy: &Policy) -> Result<()> {
+    if !policy.has_license_rules() {
+        return Ok(());
+    }
+    let scan = tokio::task::spawn_blocking({
+        let path = path.to_path_buf();
+        move || scan_package(path)
+    })
+    .await?;
+    let dependency = format!("{}~{}", lock.name(), lock.version());
+    debug!(dep = dependency, licenses:? = scan.licenses(); "checking licenses");
+    policy.check_licenses(&dependency, &scan)?;
+    Ok(())
+}
+
+/// Install a local path dependency.
+///
+/// The install folder is a symbolic link to the dependency folder, or a copy of it if the link
+/// can't be created. Relative paths are resolved from the parent of the dependencies folder, which
+/// is the project (or workspace) root.
+async fn install_path_dependency(
+    dep: &PathDependency,
+    deps: impl AsRef<Path>,
+    policy: &Policy,
+    progress: InstallProgress,
+) -> Result<LockEntry> {
+    let deps = deps.as_ref();
+    let source = deps.parent().unwrap_or(deps).join(&dep.path);
+    let source = canonicalize(&source)
+        .await
+        .map_err(|_| InstallError::PathNotFound { dependency: dep.to_string(), path: source })?;
+    let path = format_install_path(&dep.name, &dep.version_req, deps);
+    debug!(dep:% = dep, source:?; "installing path dependency to {path:?}");
+    if let Err(err) = link_from_store(&source, &path, LinkMode::Symlink).await {
+        warn!(dep:% = dep, err:%; "could not create symbolic link for path dependency, copying it");
+        link_from_store(&source, &path, LinkMode::Copy).await?;
+    }
+    let lock: LockEntry = PathLockEntry::builder()
+        .name(&dep.name)
+        .version(&dep.version_req)
+        .path(dep.path.to_slash_lossy())
+        .build()
+        .into();
+    if let Err(err) = check_licenses(&lock, &path, policy).await {
+        // only the link is removed, the source folder is left untouched
+        remove_path(&path).await?;
+        debug!(path:?; "removed dependency link");
+        return Err(err);
     }
+    progress.update_all(dep.into());
+    Ok(lock)
 }
 
 /// Install subdependencies of a dependency.
 ///
 /// This function checks for a `.gitmodules` file in the dependency directory and clones the
-/// submodules if it exists. If a valid Soldeer config is found at the project root (optionally a
-/// sub-dir of the dependency folder), the soldeer dependencies are installed.
+/// submodules if it exists. Git submodules are vendored inside of their parent dependency, so if a
+/// submodule contains a valid Soldeer config, its soldeer dependencies are installed inside of the
+/// submodule (`nested` is `true` in that case).
+///
+/// The soldeer dependencies of the dependency itself are not installed here, they are resolved and
+/// installed into the project's dependencies folder by the [`crate::resolver`].
 fn install_subdependencies(
     path: impl AsRef<Path>,
-    project_root: Option<&PathBuf>,
-) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+    nested: bool,
+    options: &InstallOptions,
+) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
     let path = path.as_ref().to_path_buf();
+    let options = options.clone();
     Box::pin(async move {
         let gitmodules_path = path.join(".gitmodules");
         if fs::metadata(&gitmodules_path).await.is_ok() {
+            if options.offline {
+                return Err(InstallError::Offline(format!(
+                    "{} (git submodules)",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                )));
+            }
             debug!(path:?; "found .gitmodules, installing subdependencies with git");
             if fs::metadata(path.join(".git")).await.is_ok() {
                 debug!(path:?; "subdependency contains .git directory, cloning submodules");
@@ -623,7 +999,7 @@ fn install_subdependencies(
                 for (_, submodule) in submodules {
                     let sub_path = path.join(submodule.path);
                     debug!(sub_path:?; "recursing into the git submodule");
-                    set.spawn(async move { install_subdependencies(sub_path, None).await });
+                    set.spawn(install_subdependencies(sub_path, true, &options));
                 }
                 while let Some(res) = set.join_next().await {
                     res??;
@@ -636,19 +1012,17 @@ fn install_subdependencies(
                 let mut set = JoinSet::new();
                 for sub_path in submodule_paths {
                     debug!(sub_path:?; "recursing into the git submodule");
-                    set.spawn(async move { install_subdependencies(sub_path, None).await });
+                    set.spawn(install_subdependencies(sub_path, true, &options));
                 }
                 while let Some(res) = set.join_next().await {
                     res??;
                 }
             }
         }
-        // if there's a suitable soldeer config, install the soldeer deps
-        let path = get_subdependency_root(path, project_root).await?;
-        if detect_config_location(&path).is_some() {
-            // install subdependencies
-            debug!(path:?; "found soldeer config, installing subdependencies");
-            install_subdependencies_inner(Paths::from_root(path)?).await?;
+        // if a git submodule has a suitable soldeer config, install its soldeer deps
+        if nested && detect_config_location(&path).is_some() {
+            debug!(path:?; "found soldeer config in submodule, installing subdependencies");
+            install_subdependencies_inner(Paths::from_root(path)?, &options).await?;
         }
         Ok(())
     })
@@ -657,81 +1031,199 @@ fn install_subdependencies(
 /// Inner logic for installing subdependencies at a given path.
 ///
 /// This is a similar implementation to the one found in `soldeer_commands` but
-/// simplified.
-async fn install_subdependencies_inner(paths: Paths) -> Result<()> {
+/// simplified. The link mode is read from the config of the submodule.
+async fn install_subdependencies_inner(paths: Paths, options: &InstallOptions) -> Result<()> {
     let config = read_soldeer_config(&paths.config)?;
     ensure_dependencies_dir(&paths.dependencies)?;
     let (dependencies, _) = read_config_deps(&paths.config)?;
+    let registries = paths.registries()?;
     let lockfile = read_lockfile(&paths.lock)?;
     let (progress, _) = InstallProgress::new(); // not used at the moment
     let _ = install_dependencies(
         &dependencies,
         &lockfile.entries,
         &paths.dependencies,
+        &registries,
         config.recursive_deps,
+        &InstallOptions { link_mode: config.link_mode, ..options.clone() },
         progress,
     )
     .await?;
     Ok(())
 }
 
-/// Download and unzip an HTTP dependency
+/// Download and extract an HTTP dependency
 async fn install_http_dependency(
     dep: &HttpInstallInfo,
     path: impl AsRef<Path>,
     subdependencies: bool,
+    options: &InstallOptions,
     progress: InstallProgress,
 ) -> Result<(IntegrityChecksum, IntegrityChecksum)> {
     let path = path.as_ref();
-    let zip_path = download_file(
-        &dep.url,
-        path.parent().expect("dependency install path should have a parent"),
-        &format!("{}-{}", dep.name, dep.version),
-    )
-    .await?;
+    let folder = path.parent().expect("dependency install path should have a parent");
+    let base_name = format!("{}-{}", dep.name, dep.version);
+    let cached = copy_from_cache(dep, folder, &base_name, options).await?;
+    let from_cache = cached.is_some();
+    let archive_path = match cached {
+        Some(archive_path) => archive_path,
+        None if options.offline => return Err(InstallError::Offline(dep.to_string())),
+        // private packages found in the cache have no download URL (see `InstallInfo::from_lock`)
+        None if dep.url.is_empty() => return Err(InstallError::CacheEntryMissing(dep.to_string())),
+        None => download_file(&dep.url, folder, &base_name).await?,
+    };
     progress.downloads.send(dep.into()).ok();
 
-    let zip_integrity = tokio::task::spawn_blocking({
-        let zip_path = zip_path.clone();
-        move || hash_file(zip_path)
+    let archive_checksum = tokio::task::spawn_blocking({
+        let archive_path = archive_path.clone();
+        move || hash_file(archive_path)
     })
     .await?
-    .map_err(|e| InstallError::IOError { path: zip_path.clone(), source: e })?;
+    .map_err(|e| InstallError::IOError { path: archive_path.clone(), source: e })?;
     if let Some(checksum) = &dep.checksum {
-        if checksum != &zip_integrity.to_string() {
-            return Err(InstallError::ZipIntegrityError {
-                path: zip_path.clone(),
+        if checksum != &archive_checksum.to_string() {
+            return Err(InstallError::ArchiveIntegrityError {
+                path: archive_path.clone(),
                 expected: checksum.to_string(),
-                actual: zip_integrity.to_string(),
+                actual: archive_checksum.to_string(),
             });
         }
-        debug!(zip_path:?; "archive integrity check successful");
+        debug!(archive_path:?; "archive integrity check successful");
+    } else {
+        debug!(archive_path:?; "no checksum available for archive integrity check");
+    }
+    if let Err(err) =
+        options.trust_policy.check(&dep.to_string(), &archive_checksum.0, dep.signature.as_ref())
+    {
+        let _ = fs::remove_file(&archive_path).await;
+        return Err(err.into());
+    }
+    if !from_cache {
+        let res = tokio::task::spawn_blocking({
+            let archive_path = archive_path.clone();
+            let checksum = archive_checksum.to_string();
+            let url = dep.url.clone();
+            move || add_to_cache(archive_path, &checksum).and_then(|_| record_url(&url, &checksum))
+        })
+        .await?;
+        if let Err(err) = res {
+            warn!(dep:% = dep, err:%; "could not add archive to the cache");
+        }
+    }
+    if options.link_mode == LinkMode::Copy {
+        extract_archive(&archive_path, path).await?;
     } else {
-        debug!(zip_path:?; "no checksum available for archive integrity check");
+        // submodules and sub-dependencies are installed into the folder after linking, so the
+        // integrity of the lockfile can only be compared to the store without them
+        let integrity = dep.integrity.as_deref().filter(|_| !subdependencies);
+        let store_path =
+            extract_to_store(&archive_path, &archive_checksum.to_string(), integrity).await?;
+        let mode = effective_link_mode(&store_path, subdependencies, options.link_mode).await;
+        link_from_store(&store_path, path, mode).await?;
     }
-    unzip_file(&zip_path, path).await?;
     progress.unzip.send(dep.into()).ok();
 
     if subdependencies {
         debug!(dep:% = dep; "installing subdependencies");
-        install_subdependencies(path, dep.project_root.as_ref()).await?;
+        install_subdependencies(path, false, options).await?;
         debug!(dep:% = dep; "finished installing subdependencies");
     }
     progress.subdependencies.send(dep.into()).ok();
 
     let integrity = tokio::task::spawn_blocking({
         let path = path.to_path_buf();
-        move || hash_folder(&path)
+        move || {
+            let manifest = IntegrityManifest::generate(&path)?;
+            if let Err(err) = manifest.write(&path) {
+                warn!(path:?, err:err; "could not write integrity manifest");
+            }
+            Ok(manifest.integrity())
+        }
     })
     .await?
     .map_err(|e| InstallError::IOError { path: path.to_path_buf(), source: e })?;
     debug!(dep:% = dep, checksum = integrity.0; "integrity checksum computed");
     progress.integrity.send(dep.into()).ok();
-    Ok((zip_integrity, integrity))
+    Ok((archive_checksum, integrity))
+}
+
+/// Get the link mode to use for a dependency from the store.
+///
+/// Installing git submodules writes into the dependency folder, which would modify the store if
+/// the files were linked. In that case, the files are copied instead.
+async fn effective_link_mode(
+    store_path: &Path,
+    subdependencies: bool,
+    link_mode: LinkMode,
+) -> LinkMode {
+    if subdependencies && fs::metadata(store_path.join(".gitmodules")).await.is_ok() {
+        debug!(store_path:?; "dependency has git submodules, copying files from store");
+        return LinkMode::Copy;
+    }
+    link_mode
+}
+
+/// Check whether an archive with the given checksum is present in the global cache.
+pub async fn is_cached(checksum: &str) -> bool {
+    let checksum = checksum.to_string();
+    matches!(
+        tokio::task::spawn_blocking(move || get_cached_archive(&checksum)).await,
+        Ok(Ok(Some(_)))
+    )
+}
+
+/// Copy an archive from the vendor folder or the global cache into the given folder, if it's
+/// present in either of them.
+///
+/// The archive is searched by checksum if it's known (e.g. from the lockfile), first in the vendor
+/// folder (see [`crate::vendor`]) and then in the cache. In offline mode, it can also be found in
+/// the cache by the URL it was downloaded from.
+///
+/// Errors with the cache are logged and result in a cache miss. An error is only returned if the
+/// archive can't be copied into the folder.
+async fn copy_from_cache(
+    dep: &HttpInstallInfo,
+    folder: &Path,
+    base_name: &str,
+    options: &InstallOptions,
+) -> Result<Option<PathBuf>> {
+    let offline = options.offline;
+    let cached = tokio::task::spawn_blocking({
+        let checksum = dep.checksum.clone();
+        let url = dep.url.clone();
+        let vendor_dir = options.vendor_dir.clone();
+        move || match checksum {
+            Some(checksum) => match vendor_dir.and_then(|dir| vendored_archive(&dir, &checksum)) {
+                Some(path) => Ok(Some(path)),
+                None => get_cached_archive(&checksum),
+            },
+            None if offline && !url.is_empty() => {
+                get_cached_archive_by_url(&url).map(|c| c.map(|(path, _)| path))
+            }
+            None => Ok(None),
+        }
+    })
+    .await?;
+    let cached = match cached {
+        Ok(Some(cached)) => cached,
+        Ok(None) => return Ok(None),
+        Err(err) => {
+            warn!(err:%; "could not read from the cache");
+            return Ok(None);
+        }
+    };
+    let format = ArchiveFormat::from_file_name(&cached.to_string_lossy()).unwrap_or_default();
+    let archive_path =
+        folder.join(sanitize_filename(&format!("{base_name}.{}", format.extension())));
+    fs::copy(&cached, &archive_path)
+        .await
+        .map_err(|e| InstallError::IOError { path: archive_path.clone(), source: e })?;
+    debug!(cached:?, archive_path:?; "copied archive from cache");
+    Ok(Some(archive_path))
 }
 
 /// Retrieve a map of git submodules for a path by looking at the `.gitmodules` file.
-async fn get_submodules(path: &PathBuf) -> Result<HashMap<String, Submodule>> {
+pub(crate) async fn get_submodules(path: &PathBuf) -> Result<HashMap<String, Submodule>> {
     let submodules_config =
         run_git_command(&["config", "-f", ".gitmodules", "-l"], Some(path)).await?;
     let mut submodules = HashMap::<String, Submodule>::new();
@@ -807,30 +1299,58 @@ async fn check_http_dependency(
     if fs::metadata(&path).await.is_err() {
         return Ok(DependencyStatus::Missing);
     }
-    let current_hash = tokio::task::spawn_blocking({
-        let path = path.clone();
-        move || hash_folder(&path)
-    })
-    .await?
-    .map_err(|e| InstallError::IOError { path: path.to_path_buf(), source: e })?;
     let Some(integrity) = lock.integrity() else {
         return Err(LockError::MissingField {
             field: "integrity".to_string(),
             dep: path.to_string_lossy().to_string(),
         }
-        .into())
+        .into());
     };
-    if &current_hash.to_string() != integrity {
-        debug!(path:?, expected = integrity, computed = current_hash.0; "integrity checksum mismatch");
+    let current = tokio::task::spawn_blocking({
+        let path = path.clone();
+        let integrity = integrity.clone();
+        move || check_folder_integrity(&path, &integrity)
+    })
+    .await?
+    .map_err(|e| InstallError::IOError { path: path.to_path_buf(), source: e })?;
+    if &current.integrity.to_string() != integrity {
+        debug!(path:?, expected = integrity, computed = current.integrity.0, changes:? = current.changes; "integrity checksum mismatch");
         return Ok(DependencyStatus::FailedIntegrity);
     }
     Ok(DependencyStatus::Installed)
 }
 
+/// List the files of a dependency which changed since it was installed.
+///
+/// This is only possible for dependencies installed from an archive which have an integrity
+/// manifest matching their lock entry (see [`crate::manifest`]). Otherwise, `None` is returned.
+pub async fn integrity_changes(
+    lock: &LockEntry,
+    deps: impl AsRef<Path>,
+) -> Result<Option<Vec<FileChange>>> {
+    let integrity = match lock {
+        LockEntry::Http(lock) => lock.integrity.clone(),
+        LockEntry::Private(lock) => lock.integrity.clone(),
+        _ => return Ok(None),
+    };
+    let path = lock.install_path(deps);
+    if fs::metadata(&path).await.is_err() {
+        return Ok(None);
+    }
+    let current = tokio::task::spawn_blocking({
+        let path = path.clone();
+        move || check_folder_integrity(&path, &integrity)
+    })
+    .await?
+    .map_err(|e| InstallError::IOError { path, source: e })?;
+    Ok(current.changes)
+}
+
 /// Check the integrity of a git dependency.
 ///
 /// This function checks that the dependency is a git repository and that the current commit is the
-/// one specified in the lockfile entry.
+/// one specified in the lockfile entry. The sparse checkout of the repository must also match the
+/// subdirectory of the lockfile entry, otherwise the dependency needs to be cloned again.
 async fn check_git_dependency(
     lock: &GitLockEntry,
     deps: impl AsRef<Path>,
@@ -868,6 +1388,15 @@ async fn check_git_dependency(
         debug!(path:?; "dependency's toplevel dir is outside of dependency folder: not a git repo");
         return Ok(DependencyStatus::Missing);
     }
+    // the command fails if the repository is not a sparse checkout
+    let sparse = run_git_command(&["sparse-checkout", "list"], Some(&path))
+        .await
+        .ok()
+        .map(|list| list.trim().to_string());
+    if sparse != lock.subdir {
+        debug!(path:?, sparse:?, subdir:? = lock.subdir; "git repo checkout does not match lockfile subdir");
+        return Ok(DependencyStatus::Missing);
+    }
     // for git dependencies, the `rev` field holds the commit hash
     match run_git_command(&["diff", "--exit-code", &lock.rev], Some(&path)).await {
         Ok(_) => Ok(DependencyStatus::Installed),
@@ -893,7 +1422,7 @@ async fn reset_git_dependency(lock: &GitLockEntry, deps: impl AsRef<Path>) -> Re
 ///
 /// The combination of the subdependency path with the relative path to the root must be at or below
 /// the level of the subdependency, to avoid directory traversal.
-async fn get_subdependency_root(
+pub(crate) async fn get_subdependency_root(
     subdependency_path: PathBuf,
     relative_root: Option<&PathBuf>,
 ) -> Result<PathBuf> {
@@ -924,11 +1453,23 @@ async fn get_subdependency_root(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{GitDependency, HttpDependency};
+    use crate::{
+        config::{GitDependency, HttpDependency},
+        errors::RegistryError,
+        registry::{DEFAULT_REGISTRY, Registry, RegistryAuth},
+        utils::hash_folder,
+    };
     use mockito::{Matcher, Server, ServerGuard};
-    use temp_env::async_with_vars;
     use testdir::testdir;
 
+    fn registries(server: &ServerGuard) -> Registries {
+        Registries::new([Registry::new(
+            DEFAULT_REGISTRY,
+            server.url().parse().unwrap(),
+            RegistryAuth::None,
+        )])
+    }
+
     async fn mock_api_server() -> ServerGuard {
         let mut server = Server::new_async().await;
         let data = r#"{"data":[{"created_at":"2024-08-06T17:31:25.751079Z","deleted":false,"downloads":3389,"id":"660132e6-4902-4804-8c4b-7cae0a648054","internal_name":"forge-std/1_9_2_06-08-2024_17:31:25_forge-std-1.9.2.zip","project_id":"37adefe5-9bc6-4777-aaf2-e56277d1f30b","url":"https://soldeer-revisions.s3.amazonaws.com/forge-std/1_9_2_06-08-2024_17:31:25_forge-std-1.9.2.zip","version":"1.9.2"},{"created_at":"2024-07-03T14:44:59.729623Z","deleted":false,"downloads":5290,"id":"fa5160fc-ba7b-40fd-8e99-8becd6dadbe4","internal_name":"forge-std/v1_9_1_03-07-2024_14:44:59_forge-std-v1.9.1.zip","project_id":"37adefe5-9bc6-4777-aaf2-e56277d1f30b","url":"https://soldeer-revisions.s3.amazonaws.com/forge-std/v1_9_1_03-07-2024_14:44:59_forge-std-v1.9.1.zip","version":"1.9.1"},{"created_at":"2024-07-03T14:44:58.148723Z","deleted":false,"downloads":21,"id":"b463683a-c4b4-40bf-b707-1c4eb343c4d2","internal_name":"forge-std/v1_9_0_03-07-2024_14:44:57_forge-std-v1.9.0.zip","project_id":"37adefe5-9bc6-4777-aaf2-e56277d1f30b","url":"https://soldeer-revisions.s3.amazonaws.com/forge-std/v1_9_0_03-07-2024_14:44:57_forge-std-v1.9.0.zip","version":"1.9.0"}],"status":"success"}"#;
@@ -1017,7 +1558,8 @@ mod tests {
         // happy path
         let dir = testdir!();
         let path = &dir.join("test-repo-1.0.0");
-        let rev = clone_repo("https://github.com/beeb/test-repo.git", None, &path).await.unwrap();
+        let rev =
+            clone_repo("https://github.com/beeb/test-repo.git", None, None, &path).await.unwrap();
         let lock =
             GitLockEntry::builder().name("test-repo").version("1.0.0").git("").rev(rev).build();
         let res = check_git_dependency(&lock, &dir).await;
@@ -1060,7 +1602,7 @@ mod tests {
     async fn test_reset_git_dependency() {
         let dir = testdir!();
         let path = &dir.join("test-repo-1.0.0");
-        clone_repo("https://github.com/beeb/test-repo.git", None, &path).await.unwrap();
+        clone_repo("https://github.com/beeb/test-repo.git", None, None, &path).await.unwrap();
         let lock = GitLockEntry::builder()
             .name("test-repo")
             .version("1.0.0")
@@ -1088,7 +1630,9 @@ mod tests {
         let dir = testdir!();
         let install: InstallInfo = HttpInstallInfo::builder().name("test").version("1.0.0").url("https://github.com/mario-eth/soldeer/archive/8585a7ec85a29889cec8d08f4770e15ec4795943.zip").checksum("94a73dbe106f48179ea39b00d42e5d4dd96fdc6252caa3a89ce7efdaec0b9468").build().into();
         let (progress, _) = InstallProgress::new();
-        let res = install_dependency_inner(&install, &dir, false, progress).await;
+        let res =
+            install_dependency_inner(&install, &dir, false, &InstallOptions::default(), progress)
+                .await;
         assert!(res.is_ok(), "{res:?}");
         let lock = res.unwrap();
         assert_eq!(lock.name(), "test");
@@ -1116,7 +1660,9 @@ mod tests {
             .build()
             .into();
         let (progress, _) = InstallProgress::new();
-        let res = install_dependency_inner(&install, &dir, false, progress).await;
+        let res =
+            install_dependency_inner(&install, &dir, false, &InstallOptions::default(), progress)
+                .await;
         assert!(res.is_ok(), "{res:?}");
         let lock = res.unwrap();
         assert_eq!(lock.name(), "test");
@@ -1138,7 +1684,9 @@ mod tests {
             .build()
             .into();
         let (progress, _) = InstallProgress::new();
-        let res = install_dependency_inner(&install, &dir, false, progress).await;
+        let res =
+            install_dependency_inner(&install, &dir, false, &InstallOptions::default(), progress)
+                .await;
         assert!(res.is_ok(), "{res:?}");
         let lock = res.unwrap();
         assert_eq!(lock.name(), "test");
@@ -1160,7 +1708,9 @@ mod tests {
             .build()
             .into();
         let (progress, _) = InstallProgress::new();
-        let res = install_dependency_inner(&install, &dir, false, progress).await;
+        let res =
+            install_dependency_inner(&install, &dir, false, &InstallOptions::default(), progress)
+                .await;
         assert!(res.is_ok(), "{res:?}");
         let lock = res.unwrap();
         assert_eq!(lock.name(), "test");
@@ -1182,7 +1732,9 @@ mod tests {
             .build()
             .into();
         let (progress, _) = InstallProgress::new();
-        let res = install_dependency_inner(&install, &dir, false, progress).await;
+        let res =
+            install_dependency_inner(&install, &dir, false, &InstallOptions::default(), progress)
+                .await;
         assert!(res.is_ok(), "{res:?}");
         let lock = res.unwrap();
         assert_eq!(lock.name(), "test");
@@ -1199,9 +1751,15 @@ mod tests {
         let dir = testdir!();
         let dep = HttpDependency::builder().name("forge-std").version_req("1.9.2").build().into();
         let (progress, _) = InstallProgress::new();
-        let res = async_with_vars(
-            [("SOLDEER_API_URL", Some(server.url()))],
-            install_dependency(&dep, None, &dir, None, false, progress),
+        let res = install_dependency(
+            &dep,
+            None,
+            &dir,
+            None,
+            &registries(&server),
+            false,
+            &InstallOptions::default(),
+            progress,
         )
         .await;
         assert!(res.is_ok(), "{res:?}");
@@ -1227,9 +1785,15 @@ mod tests {
         let dir = testdir!();
         let dep = HttpDependency::builder().name("forge-std").version_req("^1.9.0").build().into();
         let (progress, _) = InstallProgress::new();
-        let res = async_with_vars(
-            [("SOLDEER_API_URL", Some(server.url()))],
-            install_dependency(&dep, None, &dir, None, false, progress),
+        let res = install_dependency(
+            &dep,
+            None,
+            &dir,
+            None,
+            &registries(&server),
+            false,
+            &InstallOptions::default(),
+            progress,
         )
         .await;
         assert!(res.is_ok(), "{res:?}");
@@ -1245,12 +1809,75 @@ mod tests {
         assert_eq!(lock.integrity, hash.to_string());
     }
 
+    #[tokio::test]
+    async fn test_install_dependency_named_registry() {
+        let server = mock_api_private().await;
+        let dir = testdir!();
+        let dep = HttpDependency::builder()
+            .name("test-private")
+            .version_req("0.1.0")
+            .registry("internal")
+            .build()
+            .into();
+        let registries = Registries::new([Registry::new(
+            "internal",
+            server.url().parse().unwrap(),
+            RegistryAuth::None,
+        )]);
+        let (progress, _) = InstallProgress::new();
+        let res = install_dependency(
+            &Dependency::from(
+                HttpDependency::builder()
+                    .name("test-private")
+                    .version_req("0.1.0")
+                    .registry("unknown")
+                    .build(),
+            ),
+            None,
+            &dir,
+            None,
+            &registries,
+            false,
+            &InstallOptions::default(),
+            progress.clone(),
+        )
+        .await;
+        assert!(
+            matches!(res, Err(InstallError::RegistryError(RegistryError::UnknownRegistry(_)))),
+            "{res:?}"
+        );
+        let res = install_dependency(
+            &dep,
+            None,
+            &dir,
+            None,
+            &registries,
+            false,
+            &InstallOptions::default(),
+            progress,
+        )
+        .await;
+        assert!(res.is_ok(), "{res:?}");
+        let lock = res.unwrap();
+        assert_eq!(lock.registry(), Some("internal"));
+    }
+
     #[tokio::test]
     async fn test_install_dependency_http() {
         let dir = testdir!();
         let dep = HttpDependency::builder().name("test").version_req("1.0.0").url("https://github.com/mario-eth/soldeer/archive/8585a7ec85a29889cec8d08f4770e15ec4795943.zip").build().into();
         let (progress, _) = InstallProgress::new();
-        let res = install_dependency(&dep, None, &dir, None, false, progress).await;
+        let res = install_dependency(
+            &dep,
+            None,
+            &dir,
+            None,
+            &Registries::default(),
+            false,
+            &InstallOptions::default(),
+            progress,
+        )
+        .await;
         assert!(res.is_ok(), "{res:?}");
         let lock = res.unwrap();
         assert_eq!(lock.name(), dep.name());
@@ -1275,7 +1902,17 @@ mod tests {
             .build()
             .into();
         let (progress, _) = InstallProgress::new();
-        let res = install_dependency(&dep, None, &dir, None, false, progress).await;
+        let res = install_dependency(
+            &dep,
+            None,
+            &dir,
+            None,
+            &Registries::default(),
+            false,
+            &InstallOptions::default(),
+            progress,
+        )
+        .await;
         assert!(res.is_ok(), "{res:?}");
         let lock = res.unwrap();
         assert_eq!(lock.name(), dep.name());
@@ -1292,9 +1929,15 @@ mod tests {
         let dep =
             HttpDependency::builder().name("test-private").version_req("0.1.0").build().into();
         let (progress, _) = InstallProgress::new();
-        let res = async_with_vars(
-            [("SOLDEER_API_URL", Some(server.url()))],
-            install_dependency(&dep, None, &dir, None, false, progress),
+        let res = install_dependency(
+            &dep,
+            None,
+            &dir,
+            None,
+            &registries(&server),
+            false,
+            &InstallOptions::default(),
+            progress,
         )
         .await;
         assert!(res.is_ok(), "{res:?}");
@@ -1309,4 +1952,44 @@ mod tests {
         let hash = hash_folder(lock.install_path(&dir)).unwrap();
         assert_eq!(lock.integrity, hash.to_string());
     }
+
+    #[tokio::test]
+    async fn test_list_dependencies() {
+        let dir = testdir!();
+        fs::create_dir_all(dir.join("lib-1.0.0")).await.unwrap();
+        let dependencies: Vec<Dependency> = vec![
+            PathDependency::builder()
+                .name("lib")
+                .version_req("1.0.0")
+                .path("../lib")
+                .build()
+                .into(),
+            HttpDependency::builder().name("missing").version_req("^1.0.0").build().into(),
+        ];
+        let locks: Vec<LockEntry> = vec![
+            PathLockEntry::builder().name("lib").version("1.0.0").path("../lib").build().into(),
+            GitLockEntry::builder()
+                .name("transitive")
+                .version("2.0.0")
+                .git("https://example.com/transitive.git")
+                .rev("1234567890123456789012345678901234567890")
+                .build()
+                .into(),
+        ];
+        let res = list_dependencies(&dependencies, &locks, &dir).await.unwrap();
+        assert_eq!(res.len(), 3);
+        assert_eq!(res[0].name, "lib");
+        assert_eq!(res[0].source, Some(PackageSource::Path));
+        assert_eq!(res[0].status, DependencyStatus::Installed);
+        assert_eq!(res[0].install_path, Some(dir.join("lib-1.0.0")));
+        assert_eq!(res[1].name, "missing");
+        assert_eq!(res[1].version_req.as_deref(), Some("^1.0.0"));
+        assert_eq!(res[1].locked_version, None);
+        assert_eq!(res[1].status, DependencyStatus::Missing);
+        assert_eq!(res[2].name, "transitive");
+        assert_eq!(res[2].version_req, None);
+        assert_eq!(res[2].source, Some(PackageSource::Git));
+        assert_eq!(res[2].rev.as_deref(), Some("1234567890123456789012345678901234567890"));
+        assert_eq!(res[2].status, DependencyStatus::Missing);
+    }
 }
@@ -4,14 +4,28 @@ pub use errors::SoldeerError;
 
 pub type Result<T> = std::result::Result<T, SoldeerError>;
 
+pub mod archive;
+pub mod audit;
 pub mod auth;
+pub mod cache;
 pub mod config;
 pub mod download;
 pub mod errors;
+pub mod graph;
 pub mod install;
+pub mod license;
 pub mod lock;
+pub mod manifest;
+pub mod policy;
 pub mod push;
 pub mod registry;
 pub mod remappings;
+pub mod resolver;
+pub mod sbom;
+pub mod signing;
+pub mod store;
 pub mod update;
 pub mod utils;
+pub mod vendor;
+pub mod verify;
+pub mod workspace;
@@ -0,0 +1,478 @@
+//! Detection of the license of a package.
+//!
+//! The license is read from the license files at the root of the package folder (`LICENSE`,
+//! `LICENCE` or `COPYING`, with an optional extension or suffix like `LICENSE-MIT`). If a file
+//! contains an `SPDX-License-Identifier` line, it's used as-is. Otherwise, the text is compared
+//! with the most common open-source licenses to find the corresponding SPDX identifier.
+//!
+//! The `// SPDX-License-Identifier:` headers of the Solidity files of the package are also
+//! collected (see [`scan_package`]), since many packages only declare their license there.
+use crate::{errors::PolicyViolation, lock::LockEntry, policy::Policy};
+use ignore::WalkBuilder;
+use log::debug;
+use path_slash::PathExt as _;
+use rayon::prelude::*;
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+/// The prefixes of the names of license files (case-insensitive).
+pub const LICENSE_FILE_PREFIXES: &[&str] = &["license", "licence", "copying"];
+
+static SPDX_IDENTIFIER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"SPDX-License-Identifier:\s*([A-Za-z0-9.+\-() ]+?)\s*(?:\*/)?\s*$")
+        .expect("SPDX identifier regex should be valid")
+});
+
+/// Folders which are not scanned for Solidity files, since they contain the vendored dependencies
+/// of a package, which have their own license.
+const SKIPPED_FOLDERS: &[&str] = &[".git", "dependencies", "lib", "node_modules"];
+
+/// The licenses declared in a package.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct LicenseScan {
+    /// The license files, by path relative to the package root, with their identified license.
+    ///
+    /// The license is `None` if the text of the file could not be identified.
+    pub license_files: BTreeMap<String, Option<String>>,
+
+    /// The license expressions of the `SPDX-License-Identifier` headers of the Solidity files,
+    /// with the number of files which declare them.
+    pub spdx_headers: BTreeMap<String, usize>,
+}
+
+impl LicenseScan {
+    /// Whether the package declares a license, with a license file or an SPDX header.
+    ///
+    /// License files whose text could not be identified count as a declaration.
+    pub fn is_declared(&self) -> bool {
+        !self.license_files.is_empty() || !self.spdx_headers.is_empty()
+    }
+
+    /// All the distinct license expressions found in the package, from the license files and the
+    /// SPDX headers.
+    pub fn licenses(&self) -> BTreeSet<String> {
+        self.license_files.values().flatten().chain(self.spdx_headers.keys()).cloned().collect()
+    }
+
+    /// The license of the package as a whole, as an SPDX expression.
+    ///
+    /// The identified license files take precedence, and are combined with `OR`, since that's the
+    /// common meaning of several license files (e.g. `LICENSE-MIT` and `LICENSE-APACHE`).
+    /// Otherwise, the licenses of the SPDX headers are combined with `AND`, since each of them
+    /// applies to some of the files.
+    pub fn license(&self) -> Option<String> {
+        let files: BTreeSet<_> = self.license_files.values().flatten().collect();
+        let (licenses, operator): (Vec<_>, _) = if files.is_empty() {
+            (self.spdx_headers.keys().collect(), " AND ")
+        } else {
+            (files.into_iter().collect(), " OR ")
+        };
+        match licenses.as_slice() {
+            [] => None,
+            [license] => Some((*license).clone()),
+            _ => Some(licenses.iter().map(|l| parenthesize(l)).collect::<Vec<_>>().join(operator)),
+        }
+    }
+}
+
+/// The licenses of a dependency, as reported by `soldeer licenses`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct PackageLicenses {
+    /// The name of the dependency.
+    pub name: String,
+
+    /// The locked version of the dependency.
+    pub version: String,
+
+    /// Whether the dependency is installed.
+    ///
+    /// Dependencies which are not installed can't be scanned.
+    pub installed: bool,
+
+    /// The license of the package as a whole (see [`LicenseScan::license`]).
+    pub license: Option<String>,
+
+    /// The licenses found in the files of the package.
+    pub scan: LicenseScan,
+
+    /// The rules of the policy which are violated by the licenses of the package.
+    pub violations: Vec<PolicyViolation>,
+}
+
+/// Scan the licenses of the installed dependencies and check them against the policy.
+///
+/// The results are in the same order as the lockfile entries.
+pub fn scan_dependencies(
+    entries: &[LockEntry],
+    deps: impl AsRef<Path>,
+    policy: &Policy,
+) -> Vec<PackageLicenses> {
+    let deps = deps.as_ref();
+    entries
+        .par_iter()
+        .map(|entry| {
+            let path = entry.install_path(deps);
+            let installed = path.is_dir();
+            let scan = if installed { scan_package(&path) } else { LicenseScan::default() };
+            let violations = if installed {
+                policy.license_violations(&format!("{}~{}", entry.name(), entry.version()), &scan)
+            } else {
+                Vec::new()
+            };
+            PackageLicenses {
+                name: entry.name().to_string(),
+                version: entry.version().to_string(),
+                installed,
+                license: scan.license(),
+                scan,
+                violations,
+            }
+        })
+        .collect()
+}
+
+/// Scan the license files and the SPDX headers of the Solidity files of a package.
+///
+/// The folders which usually contain vendored dependencies (`lib`, `dependencies` and
+/// `node_modules`) are skipped.
+pub fn scan_package(dir: impl AsRef<Path>) -> LicenseScan {
+    let walker = WalkBuilder::new(dir.as_ref())
+        .standard_filters(false)
+        .filter_entry(|entry| {
+            !(entry.file_type().is_some_and(|t| t.is_dir()) &&
+                entry.depth() > 0 &&
+                SKIPPED_FOLDERS.iter().any(|f| entry.file_name() == *f))
+        })
+        .build();
+    let files: Vec<_> = walker
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+        .map(|e| e.into_path())
+        .collect();
+    scan_files(dir, &files)
+}
+
+/// Scan a list of files for licenses.
+///
+/// The license files must be at the root of the package, while the SPDX headers are read from the
+/// `.sol` files in any folder.
+pub fn scan_files(root: impl AsRef<Path>, files: &[PathBuf]) -> LicenseScan {
+    let root = root.as_ref();
+    let mut scan = LicenseScan::default();
+    for path in files {
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        if relative.parent().is_some_and(|p| p.as_os_str().is_empty()) && is_license_file(path) {
+            let license = match fs::read_to_string(path) {
+                Ok(text) => identify_license(&text),
+                Err(err) => {
+                    debug!(path:?, err:err; "could not read license file");
+                    None
+                }
+            };
+            if license.is_none() {
+                debug!(path:?; "could not identify license");
+            }
+            scan.license_files.insert(relative.to_slash_lossy().to_string(), license);
+        } else if path.extension().is_some_and(|e| e == "sol") {
+            let Ok(text) = fs::read_to_string(path) else {
+                debug!(path:?; "could not read Solidity file");
+                continue;
+            };
+            if let Some(id) = spdx_identifier(&text) {
+                *scan.spdx_headers.entry(id).or_default() += 1;
+            }
+        }
+    }
+    scan
+}
+
+/// Whether a file is a license file, according to its name.
+fn is_license_file(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| {
+        let name = name.to_string_lossy().to_lowercase();
+        LICENSE_FILE_PREFIXES.iter().any(|p| name.starts_with(p))
+    })
+}
+
+/// Find the license files at the root of a folder, sorted by name.
+pub fn license_files(dir: impl AsRef<Path>) -> Vec<PathBuf> {
+    let Ok(read_dir) = fs::read_dir(dir.as_ref()) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = read_dir
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+        .map(|e| e.path())
+        .filter(|p| is_license_file(p))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Detect the license of the package installed in a folder, as an SPDX expression.
+///
+/// See [`LicenseScan::license`]. Returns `None` if the package has no license file or SPDX header,
+/// or if none could be identified.
+pub fn detect_license(dir: impl AsRef<Path>) -> Option<String> {
+    scan_package(dir).license()
+}
+
+/// Parse an SPDX license expression into the alternative sets of licenses which it allows.
+///
+/// The expression is expanded so that the result is a list of alternatives (`OR`), each of which
+/// is a list of licenses which all apply (`AND`). The exceptions of `WITH` clauses are dropped.
+/// Returns `None` if the expression is invalid.
+///
+/// # Examples
+///
+/// ```
+/// # use soldeer_core::license::license_alternatives;
+/// assert_eq!(
+///     license_alternatives("(MIT OR Apache-2.0) AND BSD-3-Clause"),
+///     Some(vec![
+///         vec!["MIT".to_string(), "BSD-3-Clause".to_string()],
+///         vec!["Apache-2.0".to_string(), "BSD-3-Clause".to_string()]
+///     ])
+/// );
+/// ```
+pub fn license_alternatives(expression: &str) -> Option<Vec<Vec<String>>> {
+    let expression = expression.replace('(', " ( ").replace(')', " ) ");
+    let tokens: Vec<_> = expression.split_whitespace().collect();
+    let mut pos = 0;
+    let alternatives = parse_or(&tokens, &mut pos)?;
+    (pos == tokens.len()).then_some(alternatives)
+}
+
+/// Parse a list of `OR` operands.
+fn parse_or(tokens: &[&str], pos: &mut usize) -> Option<Vec<Vec<String>>> {
+    let mut alternatives = parse_and(tokens, pos)?;
+    while tokens.get(*pos).is_some_and(|t| t.eq_ignore_ascii_case("OR")) {
+        *pos += 1;
+        alternatives.extend(parse_and(tokens, pos)?);
+    }
+    Some(alternatives)
+}
+
+/// Parse a list of `AND` operands.
+fn parse_and(tokens: &[&str], pos: &mut usize) -> Option<Vec<Vec<String>>> {
+    let mut alternatives = parse_atom(tokens, pos)?;
+    while tokens.get(*pos).is_some_and(|t| t.eq_ignore_ascii_case("AND")) {
+        *pos += 1;
+        let right = parse_atom(tokens, pos)?;
+        alternatives = alternatives
+            .iter()
+            .flat_map(|l| right.iter().map(move |r| l.iter().chain(r).cloned().collect()))
+            .collect();
+    }
+    Some(alternatives)
+}
+
+/// Parse a license identifier or a parenthesized expression.
+fn parse_atom(tokens: &[&str], pos: &mut usize) -> Option<Vec<Vec<String>>> {
+    let token = *tokens.get(*pos)?;
+    *pos += 1;
+    if token == "(" {
+        let alternatives = parse_or(tokens, pos)?;
+        if tokens.get(*pos) != Some(&")") {
+            return None;
+        }
+        *pos += 1;
+        return Some(alternatives);
+    }
+    if token == ")" || ["AND", "OR", "WITH"].iter().any(|o| token.eq_ignore_ascii_case(o)) {
+        return None;
+    }
+    if tokens.get(*pos).is_some_and(|t| t.eq_ignore_ascii_case("WITH")) {
+        // skip the exception
+        tokens.get(*pos + 1)?;
+        *pos += 2;
+    }
+    Some(vec![vec![token.to_string()]])
+}
+
+/// Wrap a compound expression in parentheses.
+fn parenthesize(expression: &str) -> String {
+    if expression.contains(' ') { format!("({expression})") } else { expression.to_string() }
+}
+
+/// Identify the license of a license file from its text, as an SPDX expression.
+pub fn identify_license(text: &str) -> Option<String> {
+    if let Some(id) = spdx_identifier(text) {
+        return Some(id);
+    }
+    // normalize whitespace and case, since the text is often re-wrapped
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let id = if text.contains("gnu affero general public license") {
+        gnu_version(&text, "AGPL")
+    } else if text.contains("gnu lesser general public license") {
+        gnu_version(&text, "LGPL")
+    } else if text.contains("gnu general public license") {
+        gnu_version(&text, "GPL")
+    } else if text.contains("apache license") && text.contains("version 2.0") {
+        Some("Apache-2.0")
+    } else if text.contains("mozilla public license") && text.contains("2.0") {
+        Some("MPL-2.0")
+    } else if text.contains("business source license") {
+        Some("BUSL-1.1")
+    } else if text
+        .contains("this is free and unencumbered software released into the public domain")
+    {
+        Some("Unlicense")
+    } else if text.contains("cc0 1.0 universal") {
+        Some("CC0-1.0")
+    } else if text.contains("permission is hereby granted, free of charge") {
+        Some("MIT")
+    } else if text.contains("redistribution and use in source and binary forms") {
+        if text.contains("neither the name") || text.contains("3. ") {
+            Some("BSD-3-Clause")
+        } else {
+            Some("BSD-2-Clause")
+        }
+    } else if text.contains("permission to use, copy, modify, and/or distribute this software") {
+        Some("ISC")
+    } else {
+        None
+    };
+    id.map(ToString::to_string)
+}
+
+/// Find an `SPDX-License-Identifier` line in a text.
+pub fn spdx_identifier(text: &str) -> Option<String> {
+    text.lines()
+        .find_map(|l| SPDX_IDENTIFIER.captures(l))
+        .map(|c| c[1].trim().to_string())
+        .filter(|id| !id.is_empty())
+}
+
+/// Get the SPDX identifier of a GNU license from the version mentioned in its text.
+///
+/// The "or later" variants can't be told apart from the license text alone, so the `-only`
+/// identifiers are returned.
+fn gnu_version(text: &str, prefix: &str) -> Option<&'static str> {
+    let version = if text.contains("version 3") {
+        "3.0"
+    } else if text.contains("version 2.1") {
+        "2.1"
+    } else if text.contains("version 2") {
+        "2.0"
+    } else {
+        return None;
+    };
+    Some(match (prefix, version) {
+        ("AGPL", "3.0") => "AGPL-3.0-only",
+        ("LGPL", "3.0") => "LGPL-3.0-only",
+        ("LGPL", "2.1") => "LGPL-2.1-only",
+        ("LGPL", _) => "LGPL-2.0-only",
+        ("GPL", "3.0") => "GPL-3.0-only",
+        ("GPL", _) => "GPL-2.0-only",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testdir::testdir;
+
+    #[test]
+    fn test_identify_license() {
+        assert_eq!(
+            identify_license(
+                "MIT License\n\nCopyright (c) 2024\n\nPermission is hereby granted, free of\ncharge, to any person"
+            ),
+            Some("MIT".to_string())
+        );
+        assert_eq!(
+            identify_license(
+                "                                 Apache License\n                           Version 2.0, January 2004"
+            ),
+            Some("Apache-2.0".to_string())
+        );
+        assert_eq!(
+            identify_license("GNU AFFERO GENERAL PUBLIC LICENSE\n Version 3, 19 November 2007"),
+            Some("AGPL-3.0-only".to_string())
+        );
+        assert_eq!(
+            identify_license("GNU GENERAL PUBLIC LICENSE\nVersion 2, June 1991"),
+            Some("GPL-2.0-only".to_string())
+        );
+        assert_eq!(
+            identify_license("// SPDX-License-Identifier: MIT OR Apache-2.0\n"),
+            Some("MIT OR Apache-2.0".to_string())
+        );
+        assert_eq!(identify_license("All rights reserved."), None);
+    }
+
+    #[test]
+    fn test_detect_license() {
+        let dir = testdir!();
+        assert_eq!(detect_license(&dir), None);
+        fs::write(dir.join("LICENSE-MIT"), "Permission is hereby granted, free of charge").unwrap();
+        assert_eq!(detect_license(&dir), Some("MIT".to_string()));
+        fs::write(dir.join("LICENSE-APACHE"), "Apache License\nVersion 2.0").unwrap();
+        assert_eq!(detect_license(&dir), Some("Apache-2.0 OR MIT".to_string()));
+        fs::create_dir(dir.join("licenses")).unwrap();
+        assert_eq!(license_files(&dir).len(), 2);
+    }
+
+    #[test]
+    fn test_scan_package() {
+        let dir = testdir!();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("lib/other")).unwrap();
+        fs::write(
+            dir.join("src/A.sol"),
+            "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;",
+        )
+        .unwrap();
+        fs::write(dir.join("src/B.sol"), "// SPDX-License-Identifier: MIT\n").unwrap();
+        fs::write(dir.join("src/C.sol"), "/* SPDX-License-Identifier: GPL-3.0-or-later */\n")
+            .unwrap();
+        fs::write(dir.join("src/D.sol"), "pragma solidity ^0.8.0;").unwrap();
+        fs::write(dir.join("lib/other/E.sol"), "// SPDX-License-Identifier: BUSL-1.1\n").unwrap();
+        let scan = scan_package(&dir);
+        assert!(scan.license_files.is_empty());
+        assert_eq!(
+            scan.spdx_headers,
+            BTreeMap::from([("GPL-3.0-or-later".to_string(), 1), ("MIT".to_string(), 2)])
+        );
+        assert_eq!(scan.license(), Some("GPL-3.0-or-later AND MIT".to_string()));
+
+        fs::write(dir.join("COPYING"), "Some custom terms").unwrap();
+        let scan = scan_package(&dir);
+        assert!(scan.is_declared());
+        assert_eq!(scan.license_files, BTreeMap::from([("COPYING".to_string(), None)]));
+        assert_eq!(scan.license(), Some("GPL-3.0-or-later AND MIT".to_string()));
+        fs::write(dir.join("LICENSE"), "Apache License\nVersion 2.0").unwrap();
+        let scan = scan_package(&dir);
+        assert_eq!(scan.license(), Some("Apache-2.0".to_string()));
+        assert_eq!(scan.licenses().len(), 3);
+    }
+
+    #[test]
+    fn test_license_alternatives() {
+        assert_eq!(license_alternatives("MIT"), Some(vec![vec!["MIT".to_string()]]));
+        assert_eq!(
+            license_alternatives("MIT OR Apache-2.0"),
+            Some(vec![vec!["MIT".to_string()], vec!["Apache-2.0".to_string()]])
+        );
+        assert_eq!(
+            license_alternatives("GPL-2.0-or-later WITH Classpath-exception-2.0 AND MIT"),
+            Some(vec![vec!["GPL-2.0-or-later".to_string(), "MIT".to_string()]])
+        );
+        assert_eq!(license_alternatives("MIT OR"), None);
+        assert_eq!(license_alternatives("(MIT"), None);
+        assert_eq!(license_alternatives(""), None);
+    }
+}
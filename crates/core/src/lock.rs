@@ -6,11 +6,13 @@
 //! The lockfile is used to ensure that the same versions of dependencies are installed across
 //! different machines. It is also used to skip the installation of dependencies that are already
 //! installed.
-use crate::{config::Dependency, errors::LockError, utils::sanitize_filename};
+use crate::{
+    config::Dependency, errors::LockError, signing::PackageSignature, utils::sanitize_filename,
+};
 use log::{debug, warn};
 use serde::{Deserialize, Serialize};
 use std::{
-    fs,
+    fmt, fs,
     path::{Path, PathBuf},
 };
 
@@ -46,6 +48,13 @@ pub struct GitLockEntry {
 
     /// The resolved git commit hash.
     pub rev: String,
+
+    /// The subdirectory of the repository which is checked out, if any.
+    pub subdir: Option<String>,
+
+    /// The names of the packages this dependency depends on (transitive dependencies).
+    #[builder(default)]
+    pub dependencies: Vec<String>,
 }
 
 impl Integrity for GitLockEntry {
@@ -82,11 +91,21 @@ pub struct HttpLockEntry {
     /// The URL from where the dependency was downloaded.
     pub url: String,
 
-    /// The checksum of the downloaded zip file.
+    /// The name of the registry the dependency was downloaded from, if not the default registry.
+    pub registry: Option<String>,
+
+    /// The checksum of the downloaded archive (zip or tarball).
     pub checksum: String,
 
-    /// The integrity hash of the downloaded zip file after extraction.
+    /// The integrity hash of the downloaded archive after extraction.
     pub integrity: String,
+
+    /// The signature of the archive, if the package was signed by its publisher.
+    pub signature: Option<PackageSignature>,
+
+    /// The names of the packages this dependency depends on (transitive dependencies).
+    #[builder(default)]
+    pub dependencies: Vec<String>,
 }
 
 impl Integrity for HttpLockEntry {
@@ -123,11 +142,21 @@ pub struct PrivateLockEntry {
     /// resolved.
     pub version: String,
 
-    /// The checksum of the downloaded zip file.
+    /// The name of the registry the dependency was downloaded from, if not the default registry.
+    pub registry: Option<String>,
+
+    /// The checksum of the downloaded archive (zip or tarball).
     pub checksum: String,
 
-    /// The integrity hash of the downloaded zip file after extraction.
+    /// The integrity hash of the downloaded archive after extraction.
     pub integrity: String,
+
+    /// The signature of the archive, if the package was signed by its publisher.
+    pub signature: Option<PackageSignature>,
+
+    /// The names of the packages this dependency depends on (transitive dependencies).
+    #[builder(default)]
+    pub dependencies: Vec<String>,
 }
 
 impl Integrity for PrivateLockEntry {
@@ -145,6 +174,41 @@ impl Integrity for PrivateLockEntry {
     }
 }
 
+/// A lock entry for a local path dependency.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, bon::Builder)]
+#[builder(on(String, into))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct PathLockEntry {
+    /// The name of the dependency.
+    pub name: String,
+
+    /// The version (this corresponds to the version requirement of the dependency).
+    pub version: String,
+
+    /// The path to the dependency folder, relative to the project root.
+    pub path: String,
+
+    /// The names of the packages this dependency depends on (transitive dependencies).
+    #[builder(default)]
+    pub dependencies: Vec<String>,
+}
+
+impl Integrity for PathLockEntry {
+    /// Returns the install path of the dependency.
+    ///
+    /// The directory does not need to exist. Since the lock entry contains the version,
+    /// the install path can be calculated without needing to check the actual directory.
+    fn install_path(&self, deps: impl AsRef<Path>) -> PathBuf {
+        format_install_path(&self.name, &self.version, deps)
+    }
+
+    /// There is no integrity checksum for path lock entries, since the folder can be modified
+    fn integrity(&self) -> Option<&String> {
+        None
+    }
+}
+
 /// A lock entry for a dependency.
 ///
 /// A builder should be used to create the underlying [`HttpLockEntry`] or [`GitLockEntry`] and then
@@ -176,13 +240,16 @@ pub enum LockEntry {
 
     /// A lock entry for a git dependency.
     Private(PrivateLockEntry),
+
+    /// A lock entry for a local path dependency.
+    Path(PathLockEntry),
 }
 
-/// A TOML representation of a lock entry, which merges all fields from the two variants of
+/// A TOML representation of a lock entry, which merges all fields from the variants of
 /// [`LockEntry`].
 ///
 /// This is used to serialize and deserialize lock entries to and from TOML. All fields which are
-/// not present in both variants are optional.
+/// not present in all variants are optional.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 pub struct TomlLockEntry {
@@ -190,9 +257,16 @@ pub struct TomlLockEntry {
     pub version: String,
     pub git: Option<String>,
     pub url: Option<String>,
+    pub registry: Option<String>,
     pub rev: Option<String>,
+    pub subdir: Option<String>,
+    pub path: Option<String>,
     pub checksum: Option<String>,
     pub integrity: Option<String>,
+    pub public_key: Option<String>,
+    pub signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
 }
 
 impl From<LockEntry> for TomlLockEntry {
@@ -204,27 +278,60 @@ impl From<LockEntry> for TomlLockEntry {
                 version: lock.version,
                 git: None,
                 url: Some(lock.url),
+                registry: lock.registry,
                 rev: None,
+                subdir: None,
+                path: None,
                 checksum: Some(lock.checksum),
                 integrity: Some(lock.integrity),
+                public_key: lock.signature.as_ref().map(|s| s.public_key.clone()),
+                signature: lock.signature.map(|s| s.signature),
+                dependencies: lock.dependencies,
             },
             LockEntry::Git(lock) => Self {
                 name: lock.name,
                 version: lock.version,
                 git: Some(lock.git),
                 url: None,
+                registry: None,
                 rev: Some(lock.rev),
+                subdir: lock.subdir,
+                path: None,
                 checksum: None,
                 integrity: None,
+                public_key: None,
+                signature: None,
+                dependencies: lock.dependencies,
             },
             LockEntry::Private(lock) => Self {
                 name: lock.name,
                 version: lock.version,
                 git: None,
                 url: None,
+                registry: lock.registry,
                 rev: None,
+                subdir: None,
+                path: None,
                 checksum: Some(lock.checksum),
                 integrity: Some(lock.integrity),
+                public_key: lock.signature.as_ref().map(|s| s.public_key.clone()),
+                signature: lock.signature.map(|s| s.signature),
+                dependencies: lock.dependencies,
+            },
+            LockEntry::Path(lock) => Self {
+                name: lock.name,
+                version: lock.version,
+                git: None,
+                url: None,
+                registry: None,
+                rev: None,
+                subdir: None,
+                path: Some(lock.path),
+                checksum: None,
+                integrity: None,
+                public_key: None,
+                signature: None,
+                dependencies: lock.dependencies,
             },
         }
     }
@@ -235,10 +342,41 @@ impl TryFrom<TomlLockEntry> for LockEntry {
 
     /// Convert a [`TomlLockEntry`] into a [`LockEntry`] if possible.
     fn try_from(value: TomlLockEntry) -> std::result::Result<Self, Self::Error> {
+        if let Some(path) = value.path {
+            if value.url.is_some() || value.git.is_some() {
+                return Err(LockError::InvalidLockEntry);
+            }
+            return Ok(PathLockEntry::builder()
+                .name(value.name)
+                .version(value.version)
+                .path(path)
+                .dependencies(value.dependencies)
+                .build()
+                .into());
+        }
+        let signature = match (value.public_key, value.signature) {
+            (Some(public_key), Some(signature)) => Some(
+                PackageSignature::builder().public_key(public_key).signature(signature).build(),
+            ),
+            (None, None) => None,
+            (None, Some(_)) => {
+                return Err(LockError::MissingField {
+                    field: "public_key".to_string(),
+                    dep: value.name,
+                });
+            }
+            (Some(_), None) => {
+                return Err(LockError::MissingField {
+                    field: "signature".to_string(),
+                    dep: value.name,
+                });
+            }
+        };
         match (value.url, value.git) {
             (None, None) => Ok(PrivateLockEntry::builder()
                 .name(&value.name)
                 .version(value.version)
+                .maybe_registry(value.registry)
                 .checksum(value.checksum.ok_or(LockError::MissingField {
                     field: "checksum".to_string(),
                     dep: value.name.clone(),
@@ -247,6 +385,8 @@ impl TryFrom<TomlLockEntry> for LockEntry {
                     field: "integrity".to_string(),
                     dep: value.name,
                 })?)
+                .maybe_signature(signature)
+                .dependencies(value.dependencies)
                 .build()
                 .into()),
             (None, Some(git)) => {
@@ -258,6 +398,8 @@ impl TryFrom<TomlLockEntry> for LockEntry {
                         field: "rev".to_string(),
                         dep: value.name,
                     })?)
+                    .maybe_subdir(value.subdir)
+                    .dependencies(value.dependencies)
                     .build()
                     .into())
             }
@@ -265,6 +407,7 @@ impl TryFrom<TomlLockEntry> for LockEntry {
                 .name(&value.name)
                 .version(value.version)
                 .url(url)
+                .maybe_registry(value.registry)
                 .checksum(value.checksum.ok_or(LockError::MissingField {
                     field: "checksum".to_string(),
                     dep: value.name.clone(),
@@ -273,6 +416,8 @@ impl TryFrom<TomlLockEntry> for LockEntry {
                     field: "integrity".to_string(),
                     dep: value.name,
                 })?)
+                .maybe_signature(signature)
+                .dependencies(value.dependencies)
                 .build()
                 .into()),
             (Some(_), Some(_)) => Err(LockError::InvalidLockEntry),
@@ -287,6 +432,7 @@ impl LockEntry {
             Self::Git(lock) => &lock.name,
             Self::Http(lock) => &lock.name,
             Self::Private(lock) => &lock.name,
+            Self::Path(lock) => &lock.name,
         }
     }
 
@@ -296,6 +442,29 @@ impl LockEntry {
             Self::Git(lock) => &lock.version,
             Self::Http(lock) => &lock.version,
             Self::Private(lock) => &lock.version,
+            Self::Path(lock) => &lock.version,
+        }
+    }
+
+    /// The name of the registry the dependency was downloaded from.
+    ///
+    /// `None` for the default registry and for dependencies that don't come from a registry.
+    pub fn registry(&self) -> Option<&str> {
+        match self {
+            Self::Http(lock) => lock.registry.as_deref(),
+            Self::Private(lock) => lock.registry.as_deref(),
+            Self::Git(_) | Self::Path(_) => None,
+        }
+    }
+
+    /// The checksum of the archive and its signature, for HTTP and private dependencies.
+    ///
+    /// The signature is `None` if the package is not signed.
+    pub fn archive_signature(&self) -> Option<(&str, Option<&PackageSignature>)> {
+        match self {
+            Self::Http(lock) => Some((&lock.checksum, lock.signature.as_ref())),
+            Self::Private(lock) => Some((&lock.checksum, lock.signature.as_ref())),
+            Self::Git(_) | Self::Path(_) => None,
         }
     }
 
@@ -305,6 +474,31 @@ impl LockEntry {
             Self::Git(lock) => lock.install_path(deps),
             Self::Http(lock) => lock.install_path(deps),
             Self::Private(lock) => lock.install_path(deps),
+            Self::Path(lock) => lock.install_path(deps),
+        }
+    }
+
+    /// The names of the packages this dependency depends on.
+    pub fn dependencies(&self) -> &[String] {
+        match self {
+            Self::Git(lock) => &lock.dependencies,
+            Self::Http(lock) => &lock.dependencies,
+            Self::Private(lock) => &lock.dependencies,
+            Self::Path(lock) => &lock.dependencies,
+        }
+    }
+
+    /// Set the names of the packages this dependency depends on.
+    ///
+    /// The names are sorted and de-duplicated so that the lockfile output is deterministic.
+    pub fn set_dependencies(&mut self, mut dependencies: Vec<String>) {
+        dependencies.sort_unstable();
+        dependencies.dedup();
+        match self {
+            Self::Git(lock) => lock.dependencies = dependencies,
+            Self::Http(lock) => lock.dependencies = dependencies,
+            Self::Private(lock) => lock.dependencies = dependencies,
+            Self::Path(lock) => lock.dependencies = dependencies,
         }
     }
 
@@ -322,6 +516,11 @@ impl LockEntry {
     pub fn as_private(&self) -> Option<&PrivateLockEntry> {
         if let Self::Private(l) = self { Some(l) } else { None }
     }
+
+    /// Get the underlying [`PathLockEntry`] if this is a path lock entry.
+    pub fn as_path(&self) -> Option<&PathLockEntry> {
+        if let Self::Path(l) = self { Some(l) } else { None }
+    }
 }
 
 impl From<HttpLockEntry> for LockEntry {
@@ -345,6 +544,51 @@ impl From<PrivateLockEntry> for LockEntry {
     }
 }
 
+impl From<PathLockEntry> for LockEntry {
+    /// Wrap a [`PathLockEntry`] in a [`LockEntry`].
+    fn from(value: PathLockEntry) -> Self {
+        Self::Path(value)
+    }
+}
+
+/// A difference between the lockfile and the dependencies it should describe.
+///
+/// This is used to report which entries are out of sync when the lockfile must not be modified
+/// (e.g. `soldeer install --locked`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LockDrift {
+    /// A dependency has no entry in the lockfile.
+    Missing(String),
+
+    /// The lock entry of a dependency does not match its requirement.
+    Mismatch {
+        /// The name of the dependency.
+        name: String,
+
+        /// The locked version and source.
+        locked: String,
+
+        /// The required version and source.
+        required: String,
+    },
+
+    /// A lock entry is not required by any dependency.
+    Extraneous(String),
+}
+
+impl fmt::Display for LockDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(name) => write!(f, "{name} is missing from the lockfile"),
+            Self::Mismatch { name, locked, required } => {
+                write!(f, "{name} is locked at {locked} but {required} is required")
+            }
+            Self::Extraneous(name) => write!(f, "{name} is not required by any dependency"),
+        }
+    }
+}
+
 /// A parsed TOML lock file.
 ///
 /// The lockfile is a table with one entry `dependencies` containing an array of [`TomlLockEntry`]s.
@@ -461,11 +705,17 @@ mod tests {
             version: "1.0.0".to_string(),
             git: None,
             url: Some("https://example.com/zip.zip".to_string()),
+            registry: None,
             rev: None,
+            subdir: None,
+            path: None,
             checksum: Some("123456".to_string()),
             integrity: Some("beef".to_string()),
+            public_key: Some("abcd".to_string()),
+            signature: Some("ef01".to_string()),
+            dependencies: vec![],
         };
-        let entry: Result<LockEntry> = toml_entry.try_into();
+        let entry: Result<LockEntry> = toml_entry.clone().try_into();
         assert!(entry.is_ok(), "{entry:?}");
         let entry = entry.unwrap();
         assert_eq!(entry.name(), "test");
@@ -474,6 +724,18 @@ mod tests {
         assert_eq!(http.url, "https://example.com/zip.zip");
         assert_eq!(http.checksum, "123456");
         assert_eq!(http.integrity, "beef");
+        assert_eq!(
+            http.signature,
+            Some(PackageSignature::builder().public_key("abcd").signature("ef01").build())
+        );
+        assert_eq!(TomlLockEntry::from(entry), toml_entry);
+
+        let toml_entry = TomlLockEntry { public_key: None, ..toml_entry };
+        let entry: Result<LockEntry> = toml_entry.try_into();
+        assert!(
+            matches!(entry, Err(LockError::MissingField { ref field, dep: _ }) if field == "public_key"),
+            "{entry:?}"
+        );
     }
 
     #[test]
@@ -483,9 +745,15 @@ mod tests {
             version: "1.0.0".to_string(),
             git: Some("git@github.com:test/test.git".to_string()),
             url: None,
+            registry: None,
             rev: Some("123456".to_string()),
+            subdir: Some("contracts".to_string()),
+            path: None,
             checksum: None,
             integrity: None,
+            public_key: None,
+            signature: None,
+            dependencies: vec![],
         };
         let entry: Result<LockEntry> = toml_entry.try_into();
         assert!(entry.is_ok(), "{entry:?}");
@@ -495,6 +763,36 @@ mod tests {
         let git = entry.as_git().unwrap();
         assert_eq!(git.git, "git@github.com:test/test.git");
         assert_eq!(git.rev, "123456");
+        assert_eq!(git.subdir, Some("contracts".to_string()));
+    }
+
+    #[test]
+    fn test_toml_to_lock_entry_conversion_path() {
+        let toml_entry = TomlLockEntry {
+            name: "test".to_string(),
+            version: "1.0.0".to_string(),
+            git: None,
+            url: None,
+            registry: None,
+            rev: None,
+            subdir: None,
+            path: Some("../test".to_string()),
+            checksum: None,
+            integrity: None,
+            public_key: None,
+            signature: None,
+            dependencies: vec![],
+        };
+        let entry: Result<LockEntry> = toml_entry.clone().try_into();
+        assert!(entry.is_ok(), "{entry:?}");
+        let entry = entry.unwrap();
+        assert_eq!(entry.as_path().unwrap().path, "../test");
+        assert_eq!(TomlLockEntry::from(entry), toml_entry);
+
+        let toml_entry =
+            TomlLockEntry { git: Some("git@github.com:test/test.git".to_string()), ..toml_entry };
+        let entry: Result<LockEntry> = toml_entry.try_into();
+        assert!(matches!(entry, Err(LockError::InvalidLockEntry)), "{entry:?}");
     }
 
     #[test]
@@ -504,9 +802,15 @@ mod tests {
             version: "1.0.0".to_string(),
             git: None,
             url: Some("https://example.com/zip.zip".to_string()),
+            registry: None,
             rev: None,
+            subdir: None,
+            path: None,
             checksum: None,
             integrity: None,
+            public_key: None,
+            signature: None,
+            dependencies: vec![],
         };
         let entry: Result<LockEntry> = toml_entry.try_into();
         assert!(
@@ -519,9 +823,15 @@ mod tests {
             version: "1.0.0".to_string(),
             git: None,
             url: Some("https://example.com/zip.zip".to_string()),
+            registry: None,
             rev: None,
+            subdir: None,
+            path: None,
             checksum: Some("123456".to_string()),
             integrity: None,
+            public_key: None,
+            signature: None,
+            dependencies: vec![],
         };
         let entry: Result<LockEntry> = toml_entry.try_into();
         assert!(
@@ -537,9 +847,15 @@ mod tests {
             version: "1.0.0".to_string(),
             git: None,
             url: None,
+            registry: None,
             rev: None,
+            subdir: None,
+            path: None,
             checksum: None,
             integrity: None,
+            public_key: None,
+            signature: None,
+            dependencies: vec![],
         };
         let entry: Result<LockEntry> = toml_entry.try_into();
         assert!(
@@ -555,9 +871,15 @@ mod tests {
             version: "1.0.0".to_string(),
             git: Some("git@github.com:test/test.git".to_string()),
             url: Some("https://example.com/zip.zip".to_string()),
+            registry: None,
             rev: None,
+            subdir: None,
+            path: None,
             checksum: None,
             integrity: None,
+            public_key: None,
+            signature: None,
+            dependencies: vec![],
         };
         let entry: Result<LockEntry> = toml_entry.try_into();
         assert!(matches!(entry, Err(LockError::InvalidLockEntry)), "{entry:?}");
@@ -567,9 +889,15 @@ mod tests {
             version: "1.0.0".to_string(),
             git: Some("git@github.com:test/test.git".to_string()),
             url: None,
+            registry: None,
             rev: None,
+            subdir: None,
+            path: None,
             checksum: None,
             integrity: None,
+            public_key: None,
+            signature: None,
+            dependencies: vec![],
         };
         let entry: Result<LockEntry> = toml_entry.try_into();
         assert!(
@@ -635,9 +963,17 @@ version = "1.0.0"
 url = "https://example.com/zip.zip"
 checksum = "123456"
 integrity = "beef"
+
+[[dependencies]]
+name = "test3"
+version = "1.0.0"
+registry = "internal"
+checksum = "123456"
+integrity = "beef"
 "#;
         fs::write(&file_path, content).unwrap();
         let lockfile = read_lockfile(&file_path).unwrap();
+        assert_eq!(lockfile.entries[2].as_private().unwrap().registry.as_deref(), Some("internal"));
         let new_content = generate_lockfile_contents(lockfile.entries);
         assert_eq!(new_content, content);
     }
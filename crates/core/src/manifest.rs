@@ -0,0 +1,336 @@
+//! Per-file integrity manifest of the installed dependencies.
+//!
+//! The integrity checksum recorded in the lockfile is a single hash of the whole install folder
+//! (see [`crate::utils::hash_folder`]), which doesn't tell what changed when the check fails. When
+//! a dependency is installed from an archive, a manifest with the SHA256 hash and size of each file
+//! is written to the [`MANIFEST_DIR`] folder, next to the install folder in the dependencies
+//! folder. The manifest is never written inside of the install folder, which might be linked to
+//! the global store.
+//!
+//! The integrity check always hashes the whole install folder. The manifest is only used to name
+//! the files which were modified, added or deleted when the check fails. Since the integrity
+//! checksum of the folder can be computed from the manifest, a manifest which doesn't match the
+//! lockfile is ignored.
+use crate::utils::{
+    IntegrityChecksum, combine_integrity_hashes, hash_content, integrity_entry_hash,
+    integrity_walker,
+};
+use log::{debug, warn};
+use path_slash::PathExt as _;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The name of the folder holding the manifests, inside of the dependencies folder.
+pub const MANIFEST_DIR: &str = ".soldeer-manifests";
+
+/// Get the path of the manifest of an install folder.
+///
+/// The manifest of `dependencies/foo-1.0.0` is `dependencies/.soldeer-manifests/foo-1.0.0.json`.
+pub fn manifest_path(folder: impl AsRef<Path>) -> PathBuf {
+    let folder = folder.as_ref();
+    let name = folder.file_name().unwrap_or_default().to_string_lossy();
+    folder.parent().unwrap_or(folder).join(MANIFEST_DIR).join(format!("{name}.json"))
+}
+
+/// The kind of change of a file compared to the installed package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ChangeKind {
+    /// The file exists in the package but its contents differ.
+    Modified,
+
+    /// The file does not exist in the package.
+    Added,
+
+    /// The file of the package is missing.
+    Deleted,
+}
+
+/// A file which differs from the installed package.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileChange {
+    /// The path of the file, relative to the install folder of the dependency.
+    pub path: String,
+
+    /// How the file differs.
+    pub kind: ChangeKind,
+}
+
+impl fmt::Display for FileChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ChangeKind::Modified => write!(f, "modified {}", self.path),
+            ChangeKind::Added => write!(f, "added {}", self.path),
+            ChangeKind::Deleted => write!(f, "deleted {}", self.path),
+        }
+    }
+}
+
+/// A file of the manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// The SHA256 hash of the file contents.
+    pub sha256: String,
+
+    /// The size of the file in bytes.
+    pub size: u64,
+}
+
+/// The integrity manifest of an install folder.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityManifest {
+    /// The paths of the entries which have no contents (folders and symbolic links), relative to
+    /// the install folder. The install folder itself is the empty path.
+    pub dirs: BTreeSet<String>,
+
+    /// The files, by path relative to the install folder.
+    pub files: BTreeMap<String, ManifestFile>,
+}
+
+impl IntegrityManifest {
+    /// Walk a folder and hash all of its files.
+    pub fn generate(folder: impl AsRef<Path>) -> io::Result<Self> {
+        let root = dunce::canonicalize(folder.as_ref())?;
+        let mut manifest = Self::default();
+        for entry in integrity_walker(&root).build() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let relative = entry
+                .path()
+                .strip_prefix(&root)
+                .expect("path should be a child of root")
+                .to_slash_lossy()
+                .to_string();
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                manifest.dirs.insert(relative);
+                continue;
+            }
+            let size = entry.metadata().map_err(io::Error::other)?.len();
+            let mut reader = io::BufReader::new(fs::File::open(entry.path())?);
+            let sha256 = const_hex::encode(hash_content(&mut reader));
+            manifest.files.insert(relative, ManifestFile { sha256, size });
+        }
+        Ok(manifest)
+    }
+
+    /// Compute the integrity checksum of the folder described by the manifest.
+    ///
+    /// This is the same as [`crate::utils::hash_folder`] on the folder.
+    pub fn integrity(&self) -> IntegrityChecksum {
+        let mut hashes: Vec<_> =
+            self.dirs.iter().map(|path| integrity_entry_hash(path, None)).collect();
+        for (path, file) in &self.files {
+            let mut content_hash = [0u8; 32];
+            if const_hex::decode_to_slice(&file.sha256, &mut content_hash).is_err() {
+                // an invalid hash can't match the lockfile anyway
+                warn!(path; "invalid hash in integrity manifest");
+            }
+            hashes.push(integrity_entry_hash(path, Some(&content_hash)));
+        }
+        combine_integrity_hashes(hashes)
+    }
+
+    /// List the files which differ between this manifest and the current state of the folder.
+    pub fn changes(&self, current: &Self) -> Vec<FileChange> {
+        let mut changes = Vec::new();
+        for (path, file) in &self.files {
+            match current.files.get(path) {
+                Some(current) if current.sha256 != file.sha256 => {
+                    changes.push(FileChange { path: path.clone(), kind: ChangeKind::Modified });
+                }
+                Some(_) => {}
+                None => changes.push(FileChange { path: path.clone(), kind: ChangeKind::Deleted }),
+            }
+        }
+        for path in current.files.keys().filter(|p| !self.files.contains_key(*p)) {
+            changes.push(FileChange { path: path.clone(), kind: ChangeKind::Added });
+        }
+        changes.sort_unstable();
+        changes
+    }
+
+    /// Read the manifest of an install folder, if any.
+    pub fn read(folder: impl AsRef<Path>) -> Option<Self> {
+        let path = manifest_path(folder);
+        let contents = fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(&contents) {
+            Ok(manifest) => Some(manifest),
+            Err(err) => {
+                warn!(path:?, err:err; "could not parse integrity manifest");
+                None
+            }
+        }
+    }
+
+    /// Write the manifest of an install folder (see [`manifest_path`]).
+    pub fn write(&self, folder: impl AsRef<Path>) -> io::Result<()> {
+        let path = manifest_path(folder);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, contents)
+    }
+
+    /// Remove the manifest of an install folder, if any.
+    pub fn remove(folder: impl AsRef<Path>) {
+        let path = manifest_path(folder);
+        if let Err(err) = fs::remove_file(&path) &&
+            err.kind() != io::ErrorKind::NotFound
+        {
+            warn!(path:?, err:err; "could not remove integrity manifest");
+        }
+    }
+}
+
+/// The result of the integrity check of an install folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FolderIntegrity {
+    /// The current integrity checksum of the folder.
+    pub integrity: IntegrityChecksum,
+
+    /// The files which changed since the installation, if the folder has a valid manifest.
+    pub changes: Option<Vec<FileChange>>,
+}
+
+/// Compute the integrity checksum of an install folder.
+///
+/// The whole folder is always hashed. If the folder has a manifest which matches the `expected`
+/// integrity checksum, the files which changed since the installation are listed.
+pub fn check_folder_integrity(
+    folder: impl AsRef<Path>,
+    expected: &str,
+) -> io::Result<FolderIntegrity> {
+    let folder = folder.as_ref();
+    let current = IntegrityManifest::generate(folder)?;
+    let changes = match IntegrityManifest::read(folder) {
+        Some(manifest) if manifest.integrity().0 == expected => Some(manifest.changes(&current)),
+        Some(_) => {
+            debug!(folder:?; "integrity manifest does not match the lockfile, ignoring it");
+            None
+        }
+        None => None,
+    };
+    Ok(FolderIntegrity { integrity: current.integrity(), changes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash_folder;
+    use std::fs;
+    use testdir::testdir;
+
+    fn create_folder(dir: &Path) -> PathBuf {
+        let folder = dir.join("dependencies/pkg-1.0.0");
+        fs::create_dir_all(folder.join("src/utils")).unwrap();
+        fs::write(folder.join("src/A.sol"), "contract A {}").unwrap();
+        fs::write(folder.join("src/B.sol"), "contract B {}").unwrap();
+        fs::write(folder.join("src/utils/C.sol"), "contract C {}").unwrap();
+        fs::write(folder.join("README.md"), "# pkg").unwrap();
+        folder
+    }
+
+    #[test]
+    fn test_manifest_integrity() {
+        let dir = create_folder(&testdir!());
+        let manifest = IntegrityManifest::generate(&dir).unwrap();
+        assert_eq!(manifest.files.len(), 4);
+        assert!(manifest.files.contains_key("src/utils/C.sol"));
+        assert_eq!(manifest.integrity(), hash_folder(&dir).unwrap());
+    }
+
+    #[test]
+    fn test_manifest_location() {
+        let root = testdir!();
+        let dir = create_folder(&root);
+        let hash = hash_folder(&dir).unwrap();
+        let manifest = IntegrityManifest::generate(&dir).unwrap();
+        manifest.write(&dir).unwrap();
+        assert!(root.join("dependencies/.soldeer-manifests/pkg-1.0.0.json").is_file());
+        assert_eq!(hash_folder(&dir).unwrap(), hash);
+        assert_eq!(IntegrityManifest::read(&dir), Some(manifest));
+        IntegrityManifest::remove(&dir);
+        assert_eq!(IntegrityManifest::read(&dir), None);
+    }
+
+    #[test]
+    fn test_manifest_changes() {
+        let dir = create_folder(&testdir!());
+        let manifest = IntegrityManifest::generate(&dir).unwrap();
+        fs::write(dir.join("src/A.sol"), "contract Evil {}").unwrap();
+        fs::remove_file(dir.join("src/B.sol")).unwrap();
+        fs::write(dir.join("src/D.sol"), "contract D {}").unwrap();
+        let current = IntegrityManifest::generate(&dir).unwrap();
+        assert_eq!(current.integrity(), hash_folder(&dir).unwrap());
+        assert_eq!(
+            manifest.changes(&current),
+            vec![
+                FileChange { path: "src/A.sol".to_string(), kind: ChangeKind::Modified },
+                FileChange { path: "src/B.sol".to_string(), kind: ChangeKind::Deleted },
+                FileChange { path: "src/D.sol".to_string(), kind: ChangeKind::Added },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_folder_integrity() {
+        let dir = create_folder(&testdir!());
+        let manifest = IntegrityManifest::generate(&dir).unwrap();
+        manifest.write(&dir).unwrap();
+        let expected = manifest.integrity().to_string();
+        let res = check_folder_integrity(&dir, &expected).unwrap();
+        assert_eq!(res.integrity.to_string(), expected);
+        assert_eq!(res.changes, Some(vec![]));
+
+        fs::write(dir.join("src/utils/C.sol"), "contract Evil {}").unwrap();
+        let res = check_folder_integrity(&dir, &expected).unwrap();
+        assert_ne!(res.integrity.to_string(), expected);
+        assert_eq!(res.integrity, hash_folder(&dir).unwrap());
+        assert_eq!(
+            res.changes,
+            Some(vec![FileChange {
+                path: "src/utils/C.sol".to_string(),
+                kind: ChangeKind::Modified
+            }])
+        );
+    }
+
+    #[test]
+    fn test_check_folder_integrity_same_mtime() {
+        let dir = create_folder(&testdir!());
+        let manifest = IntegrityManifest::generate(&dir).unwrap();
+        manifest.write(&dir).unwrap();
+        let expected = manifest.integrity().to_string();
+        // a modification with the same size and modification time is detected
+        let path = dir.join("src/A.sol");
+        let mtime = fs::metadata(&path).unwrap().modified().unwrap();
+        fs::write(&path, "contract X {}").unwrap();
+        fs::File::options().write(true).open(&path).unwrap().set_modified(mtime).unwrap();
+        let res = check_folder_integrity(&dir, &expected).unwrap();
+        assert_ne!(res.integrity.to_string(), expected);
+        assert_eq!(
+            res.changes,
+            Some(vec![FileChange { path: "src/A.sol".to_string(), kind: ChangeKind::Modified }])
+        );
+    }
+
+    #[test]
+    fn test_check_folder_integrity_tampered_manifest() {
+        let dir = create_folder(&testdir!());
+        let expected = hash_folder(&dir).unwrap().to_string();
+        fs::write(dir.join("src/A.sol"), "contract Evil {}").unwrap();
+        // a manifest matching the modified folder doesn't match the lockfile and is ignored
+        IntegrityManifest::generate(&dir).unwrap().write(&dir).unwrap();
+        let res = check_folder_integrity(&dir, &expected).unwrap();
+        assert_ne!(res.integrity.to_string(), expected);
+        assert_eq!(res.changes, None);
+    }
+}
@@ -0,0 +1,466 @@
+//! Supply-chain policy for the sources and licenses of dependencies.
+//!
+//! The `[soldeer.policy]` section of the config file restricts where dependencies can come from,
+//! and which licenses they can be distributed under:
+//!
+//! ```toml
+//! [soldeer.policy]
+//! allowed_registries = ["soldeer", "internal"]
+//! allowed_url_hosts = [] # no dependency with a custom URL
+//! allowed_git_hosts = ["github.com"]
+//! require_git_rev = true
+//! banned = ["evil-lib", "forge-std~<1.9.0"]
+//! min_package_age_days = 7
+//! allowed_licenses = ["MIT", "Apache-2.0", "BSD-3-Clause"]
+//! denied_licenses = ["BUSL-1.1"]
+//! ```
+//!
+//! The policy of the project is checked against all of its dependencies before installing them,
+//! and against each dependency (including transitive ones) once its version is resolved. The
+//! licenses are checked once a dependency is installed, from its license files and the SPDX headers
+//! of its Solidity files (see [`scan_package`][crate::license::scan_package]). All the rules which
+//! are violated are reported together in a [`PolicyError`].
+use crate::{
+    config::{Dependency, GitIdentifier},
+    errors::{PolicyError, PolicyViolation},
+    license::{LicenseScan, license_alternatives},
+    registry::DEFAULT_REGISTRY,
+    resolver::version_satisfies,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use log::debug;
+use reqwest::Url;
+use serde::Deserialize;
+
+pub type Result<T> = std::result::Result<T, PolicyError>;
+
+/// The policy for the sources of dependencies, from the `[soldeer.policy]` section of the config.
+///
+/// The default policy allows everything.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash, bon::Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct Policy {
+    /// The names of the registries which dependencies can be downloaded from.
+    ///
+    /// The default registry is named `soldeer`. If `None`, all registries are allowed.
+    pub allowed_registries: Option<Vec<String>>,
+
+    /// The hosts which dependencies with a custom URL can be downloaded from.
+    ///
+    /// An empty list forbids dependencies with a custom URL. If `None`, all hosts are allowed.
+    pub allowed_url_hosts: Option<Vec<String>>,
+
+    /// The hosts which git dependencies can be cloned from.
+    ///
+    /// If `None`, all hosts are allowed.
+    pub allowed_git_hosts: Option<Vec<String>>,
+
+    /// Whether git dependencies must be pinned to a commit with `rev`.
+    ///
+    /// The `rev` must be a commit hash of 7 to 40 hexadecimal characters, since a branch or tag
+    /// name can also be used as `rev`. Defaults to `false`.
+    #[serde(default)]
+    #[builder(default)]
+    pub require_git_rev: bool,
+
+    /// Packages which cannot be installed, either by name (`name`) or by name and version
+    /// requirement (`name~<1.2.0`).
+    #[serde(default)]
+    #[builder(default)]
+    pub banned: Vec<String>,
+
+    /// The minimum number of days since a version was published on the registry before it can be
+    /// installed.
+    ///
+    /// Only versions which are resolved from the registry are checked, versions which are already
+    /// in the lockfile are not.
+    pub min_package_age_days: Option<u32>,
+
+    /// The licenses which dependencies can be distributed under, as SPDX identifiers.
+    ///
+    /// A license expression is allowed if one of its alternatives (`OR`) only contains allowed
+    /// licenses. Dependencies without a license are rejected. If `None`, all licenses are allowed.
+    pub allowed_licenses: Option<Vec<String>>,
+
+    /// The licenses which dependencies cannot be distributed under, as SPDX identifiers.
+    ///
+    /// A license expression is denied if all of its alternatives (`OR`) contain a denied license.
+    #[serde(default)]
+    #[builder(default)]
+    pub denied_licenses: Vec<String>,
+}
+
+impl Policy {
+    /// Check a list of dependencies against the policy, before their versions are resolved.
+    ///
+    /// Every rule which is violated by any of the dependencies is listed in the error.
+    pub fn check_dependencies<'a>(
+        &self,
+        dependencies: impl IntoIterator<Item = &'a Dependency>,
+    ) -> Result<()> {
+        let violations =
+            dependencies.into_iter().flat_map(|d| self.violations(d, None)).collect::<Vec<_>>();
+        into_result(violations)
+    }
+
+    /// Check a dependency against the policy once its version is resolved.
+    pub fn check_resolved(&self, dependency: &Dependency, version: &str) -> Result<()> {
+        into_result(self.violations(dependency, Some(version)))
+    }
+
+    /// Check a dependency against the policy once its version is resolved from the registry,
+    /// including the age of the version given its `published` date.
+    pub fn check_published(
+        &self,
+        dependency: &Dependency,
+        version: &str,
+        published: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        let mut violations = self.violations(dependency, Some(version));
+        violations.extend(self.age_violation(dependency, version, published));
+        into_result(violations)
+    }
+
+    /// Get the rules violated by a dependency, with its resolved version if known.
+    pub fn violations(
+        &self,
+        dependency: &Dependency,
+        version: Option<&str>,
+    ) -> Vec<PolicyViolation> {
+        let name = match version {
+            Some(version) => format!("{}~{version}", dependency.name()),
+            None => dependency.to_string(),
+        };
+        let mut violations = Vec::new();
+        match dependency {
+            Dependency::Http(dep) => match &dep.url {
+                Some(url) => {
+                    if !host_allowed(self.allowed_url_hosts.as_deref(), url) {
+                        violations.push(PolicyViolation::UrlHostNotAllowed {
+                            dependency: name.clone(),
+                            url: url.clone(),
+                        });
+                    }
+                }
+                None => {
+                    let registry = dependency.registry().unwrap_or(DEFAULT_REGISTRY);
+                    if let Some(allowed) = &self.allowed_registries &&
+                        !allowed.iter().any(|r| r == registry)
+                    {
+                        violations.push(PolicyViolation::RegistryNotAllowed {
+                            dependency: name.clone(),
+                            registry: registry.to_string(),
+                        });
+                    }
+                }
+            },
+            Dependency::Git(dep) => {
+                if !host_allowed(self.allowed_git_hosts.as_deref(), &dep.git) {
+                    violations.push(PolicyViolation::GitHostNotAllowed {
+                        dependency: name.clone(),
+                        url: dep.git.clone(),
+                    });
+                }
+                if self.require_git_rev &&
+                    !matches!(&dep.identifier, Some(GitIdentifier::Rev(rev)) if is_commit_hash(rev))
+                {
+                    violations.push(PolicyViolation::GitNotPinned { dependency: name.clone() });
+                }
+            }
+            Dependency::Path(_) => {}
+        }
+        for rule in &self.banned {
+            let banned = match rule.split_once('~') {
+                Some((banned_name, version_req)) => {
+                    banned_name == dependency.name() &&
+                        version.is_some_and(|v| version_satisfies(v, version_req))
+                }
+                None => rule == dependency.name(),
+            };
+            if banned {
+                violations
+                    .push(PolicyViolation::Banned { dependency: name.clone(), rule: rule.clone() });
+            }
+        }
+        if !violations.is_empty() {
+            debug!(dep = name, violations:?; "dependency violates the policy");
+        }
+        violations
+    }
+
+    /// Check the age of a version published on the registry.
+    ///
+    /// Dependencies with a custom URL and git dependencies are not concerned.
+    pub fn age_violation(
+        &self,
+        dependency: &Dependency,
+        version: &str,
+        published: Option<DateTime<Utc>>,
+    ) -> Option<PolicyViolation> {
+        let min_days = self.min_package_age_days?;
+        if !matches!(dependency, Dependency::Http(dep) if dep.url.is_none()) {
+            return None;
+        }
+        let dependency = format!("{}~{version}", dependency.name());
+        let Some(published) = published else {
+            return Some(PolicyViolation::UnknownAge { dependency });
+        };
+        if Utc::now() - published < TimeDelta::days(min_days.into()) {
+            return Some(PolicyViolation::TooRecent {
+                dependency,
+                published: published.format("%Y-%m-%d").to_string(),
+                min_days,
+            });
+        }
+        None
+    }
+
+    /// Whether the policy has rules about licenses.
+    ///
+    /// If not, there is no need to scan the licenses of the dependencies.
+    pub fn has_license_rules(&self) -> bool {
+        self.allowed_licenses.is_some() || !self.denied_licenses.is_empty()
+    }
+
+    /// Check the licenses of an installed dependency against the policy.
+    ///
+    /// The `dependency` is the name and version of the dependency, for display.
+    pub fn check_licenses(&self, dependency: &str, scan: &LicenseScan) -> Result<()> {
+        into_result(self.license_violations(dependency, scan))
+    }
+
+    /// Get the rules violated by the licenses of an installed dependency.
+    ///
+    /// Each distinct license expression found in the dependency must be allowed.
+    pub fn license_violations(&self, dependency: &str, scan: &LicenseScan) -> Vec<PolicyViolation> {
+        let licenses = scan.licenses();
+        if licenses.is_empty() {
+            if self.allowed_licenses.is_some() {
+                return vec![PolicyViolation::UnknownLicense { dependency: dependency.to_string() }];
+            }
+            return Vec::new();
+        }
+        let mut violations = Vec::new();
+        for license in licenses {
+            let alternatives =
+                license_alternatives(&license).unwrap_or_else(|| vec![vec![license.clone()]]);
+            let is_denied = |id: &String| {
+                self.denied_licenses
+                    .iter()
+                    .any(|d| d.eq_ignore_ascii_case(id.trim_end_matches('+')))
+            };
+            let is_allowed = |id: &String| {
+                self.allowed_licenses.as_ref().is_none_or(|allowed| {
+                    allowed.iter().any(|a| a.eq_ignore_ascii_case(id.trim_end_matches('+')))
+                })
+            };
+            if alternatives.iter().any(|ids| ids.iter().all(|id| !is_denied(id) && is_allowed(id)))
+            {
+                continue;
+            }
+            let dependency = dependency.to_string();
+            if alternatives.iter().all(|ids| ids.iter().any(is_denied)) {
+                violations.push(PolicyViolation::LicenseDenied { dependency, license });
+            } else {
+                violations.push(PolicyViolation::LicenseNotAllowed { dependency, license });
+            }
+        }
+        if !violations.is_empty() {
+            debug!(dep = dependency, violations:?; "dependency license violates the policy");
+        }
+        violations
+    }
+}
+
+/// Turn a list of violations into a result.
+fn into_result(violations: Vec<PolicyViolation>) -> Result<()> {
+    if violations.is_empty() { Ok(()) } else { Err(PolicyError::Violations(violations)) }
+}
+
+/// Check whether the host of a URL is in the list of allowed hosts.
+///
+/// Git URLs with the SCP-like syntax (`git@github.com:org/repo.git`) are supported.
+fn host_allowed(allowed: Option<&[String]>, url: &str) -> bool {
+    let Some(allowed) = allowed else {
+        return true;
+    };
+    let host = match Url::parse(url) {
+        Ok(url) => url.host_str().map(ToString::to_string),
+        Err(_) => url
+            .split_once(':')
+            .and_then(|(user_host, _)| user_host.rsplit('@').next())
+            .map(ToString::to_string),
+    };
+    host.is_some_and(|host| allowed.iter().any(|h| h.eq_ignore_ascii_case(&host)))
+}
+
+/// Check whether a git `rev` is a (possibly abbreviated) commit hash.
+fn is_commit_hash(rev: &str) -> bool {
+    (7..=40).contains(&rev.len()) && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{GitDependency, HttpDependency, PathDependency};
+
+    #[test]
+    fn test_check_sources() {
+        let policy = Policy::builder()
+            .allowed_registries(vec!["internal".to_string()])
+            .allowed_url_hosts(vec![])
+            .allowed_git_hosts(vec!["github.com".to_string()])
+            .require_git_rev(true)
+            .build();
+        let registry: Dependency =
+            HttpDependency::builder().name("lib").version_req("1.0.0").build().into();
+        let internal: Dependency = HttpDependency::builder()
+            .name("lib")
+            .version_req("1.0.0")
+            .registry("internal")
+            .build()
+            .into();
+        let url: Dependency = HttpDependency::builder()
+            .name("lib")
+            .version_req("1.0.0")
+            .url("https://example.com/lib.zip")
+            .build()
+            .into();
+        let git_branch: Dependency = GitDependency::builder()
+            .name("lib")
+            .version_req("1.0.0")
+            .git("https://gitlab.com/org/lib.git")
+            .identifier(GitIdentifier::from_branch("main"))
+            .build()
+            .into();
+        let git_rev: Dependency = GitDependency::builder()
+            .name("lib")
+            .version_req("1.0.0")
+            .git("git@github.com:org/lib.git")
+            .identifier(GitIdentifier::from_rev("abcdef1"))
+            .build()
+            .into();
+        let git_rev_branch: Dependency = GitDependency::builder()
+            .name("lib")
+            .version_req("1.0.0")
+            .git("git@github.com:org/lib.git")
+            .identifier(GitIdentifier::from_rev("main"))
+            .build()
+            .into();
+        let path: Dependency =
+            PathDependency::builder().name("lib").version_req("1.0.0").path("lib").build().into();
+
+        assert!(policy.check_dependencies([&internal, &git_rev, &path]).is_ok());
+        assert!(matches!(
+            &policy.violations(&registry, None)[..],
+            [PolicyViolation::RegistryNotAllowed { registry, .. }] if registry == DEFAULT_REGISTRY
+        ));
+        assert!(matches!(
+            &policy.violations(&url, None)[..],
+            [PolicyViolation::UrlHostNotAllowed { .. }]
+        ));
+        assert!(matches!(
+            &policy.violations(&git_branch, None)[..],
+            [PolicyViolation::GitHostNotAllowed { .. }, PolicyViolation::GitNotPinned { .. }]
+        ));
+        // a branch name passed as `rev` is not a commit
+        assert!(matches!(
+            &policy.violations(&git_rev_branch, None)[..],
+            [PolicyViolation::GitNotPinned { .. }]
+        ));
+        let res = policy.check_dependencies([&registry, &url, &git_branch]);
+        assert!(matches!(res, Err(PolicyError::Violations(v)) if v.len() == 4));
+
+        // the default policy allows everything
+        assert!(Policy::default().check_dependencies([&registry, &url, &git_branch]).is_ok());
+    }
+
+    #[test]
+    fn test_check_banned() {
+        let policy =
+            Policy::builder().banned(vec!["evil".to_string(), "lib~<1.2.0".to_string()]).build();
+        let evil: Dependency =
+            HttpDependency::builder().name("evil").version_req("^1.0.0").build().into();
+        let lib: Dependency =
+            HttpDependency::builder().name("lib").version_req("^1.0.0").build().into();
+        assert!(matches!(
+            policy.check_dependencies([&evil, &lib]),
+            Err(PolicyError::Violations(v)) if v.len() == 1
+        ));
+        assert!(policy.check_resolved(&lib, "1.2.1").is_ok());
+        assert!(matches!(
+            &policy.violations(&lib, Some("1.1.0"))[..],
+            [PolicyViolation::Banned { rule, .. }] if rule == "lib~<1.2.0"
+        ));
+    }
+
+    #[test]
+    fn test_check_age() {
+        let policy = Policy::builder().min_package_age_days(7).build();
+        let lib: Dependency =
+            HttpDependency::builder().name("lib").version_req("^1.0.0").build().into();
+        let old = Utc::now() - TimeDelta::days(8);
+        let recent = Utc::now() - TimeDelta::days(2);
+        assert!(policy.check_published(&lib, "1.0.0", Some(old)).is_ok());
+        // versions from the lockfile are not checked
+        assert!(policy.check_resolved(&lib, "1.0.0").is_ok());
+        assert!(matches!(
+            policy.age_violation(&lib, "1.0.0", Some(recent)),
+            Some(PolicyViolation::TooRecent { min_days: 7, .. })
+        ));
+        assert!(matches!(
+            policy.age_violation(&lib, "1.0.0", None),
+            Some(PolicyViolation::UnknownAge { .. })
+        ));
+
+        let url: Dependency = HttpDependency::builder()
+            .name("lib")
+            .version_req("1.0.0")
+            .url("https://example.com/lib.zip")
+            .build()
+            .into();
+        assert!(policy.age_violation(&url, "1.0.0", None).is_none());
+    }
+
+    #[test]
+    fn test_check_licenses() {
+        let policy = Policy::builder()
+            .allowed_licenses(vec!["MIT".to_string(), "Apache-2.0".to_string()])
+            .denied_licenses(vec!["BUSL-1.1".to_string()])
+            .build();
+        assert!(policy.has_license_rules());
+        assert!(!Policy::default().has_license_rules());
+        let scan = |licenses: &[&str]| {
+            let mut scan = LicenseScan::default();
+            for license in licenses {
+                scan.spdx_headers.insert(license.to_string(), 1);
+            }
+            scan
+        };
+        assert!(policy.check_licenses("lib~1.0.0", &scan(&["MIT"])).is_ok());
+        assert!(policy.check_licenses("lib~1.0.0", &scan(&["MIT", "GPL-3.0 OR mit"])).is_ok());
+        assert!(policy.check_licenses("lib~1.0.0", &scan(&["(MIT AND Apache-2.0)"])).is_ok());
+        assert!(matches!(
+            &policy.license_violations("lib~1.0.0", &scan(&[]))[..],
+            [PolicyViolation::UnknownLicense { .. }]
+        ));
+        assert!(matches!(
+            &policy.license_violations("lib~1.0.0", &scan(&["MIT", "GPL-3.0-only"]))[..],
+            [PolicyViolation::LicenseNotAllowed { license, .. }] if license == "GPL-3.0-only"
+        ));
+        assert!(matches!(
+            &policy
+                .license_violations("lib~1.0.0", &scan(&["BUSL-1.1 OR GPL-3.0-only AND BUSL-1.1"]))
+                [..],
+            [PolicyViolation::LicenseDenied { .. }]
+        ));
+        // the allowed alternative can be chosen
+        assert!(policy.check_licenses("lib~1.0.0", &scan(&["BUSL-1.1 OR MIT"])).is_ok());
+
+        let deny_only = Policy::builder().denied_licenses(vec!["GPL-3.0-only".to_string()]).build();
+        assert!(deny_only.check_licenses("lib~1.0.0", &scan(&[])).is_ok());
+        assert!(deny_only.check_licenses("lib~1.0.0", &scan(&["UNLICENSED"])).is_ok());
+        assert!(deny_only.check_licenses("lib~1.0.0", &scan(&["GPL-3.0-only"])).is_err());
+    }
+}
@@ -2,7 +2,10 @@
 use crate::{
     auth::get_token,
     errors::{AuthError, PublishError},
-    registry::{api_url, get_project_id},
+    license::{LicenseScan, scan_files},
+    registry::{Registry, get_project_id},
+    signing::{PackageSignature, SigningKey},
+    utils::hash_file,
 };
 use ignore::{WalkBuilder, WalkState};
 use log::debug;
@@ -29,15 +32,20 @@ pub type Result<T> = std::result::Result<T, PublishError>;
 /// `dry_run` argument is set to `true`. In that case, the function will only create the zip file
 /// and return its path.
 ///
+/// If a `signing_key` is provided, the SHA256 hash of the zip file is signed and the signature is
+/// uploaded along with the archive (see [`signing`][crate::signing]).
+///
 /// An authentication token is required to push a zip file to the registry. The token is retrieved
-/// from the login file (see [`login_file_path`][crate::utils::login_file_path] and
+/// from the auth source of the registry (see [`get_token`] and
 /// [`execute_login`][crate::auth::execute_login]).
 pub async fn push_version(
+    registry: &Registry,
     dependency_name: &str,
     dependency_version: &str,
     root_directory_path: impl AsRef<Path>,
     files_to_copy: &[PathBuf],
     dry_run: bool,
+    signing_key: Option<&SigningKey>,
 ) -> Result<Option<PathBuf>> {
     let file_name =
         root_directory_path.as_ref().file_name().expect("path should have a last component");
@@ -55,7 +63,24 @@ pub async fn push_version(
         return Ok(Some(PathBuf::from_slash_lossy(&zip_archive)));
     }
 
-    if let Err(error) = push_to_repo(&zip_archive, dependency_name, dependency_version).await {
+    let signature = match signing_key.map(|key| sign_zip(key, &zip_archive)).transpose() {
+        Ok(signature) => signature,
+        Err(error) => {
+            let _ = fs::remove_file(&zip_archive);
+            debug!(zip_archive:?; "zip file deleted");
+            return Err(error);
+        }
+    };
+
+    if let Err(error) = push_to_repo(
+        registry,
+        &zip_archive,
+        dependency_name,
+        dependency_version,
+        signature.as_ref(),
+    )
+    .await
+    {
         let _ = fs::remove_file(&zip_archive);
         debug!(zip_archive:?; "zip file deleted");
         return Err(error);
@@ -91,6 +116,22 @@ pub fn validate_version(version: &str) -> Result<()> {
     Ok(())
 }
 
+/// Check that the files to publish declare a license.
+///
+/// A license file at the root of the package, or an `SPDX-License-Identifier` header in one of the
+/// Solidity files is required. The licenses found in the files are returned.
+pub fn check_license(
+    root_directory_path: impl AsRef<Path>,
+    files_to_copy: &[PathBuf],
+) -> Result<LicenseScan> {
+    let scan = scan_files(root_directory_path, files_to_copy);
+    if !scan.is_declared() {
+        debug!("no license file or SPDX header found in the files to publish");
+        return Err(PublishError::NoLicense);
+    }
+    Ok(scan)
+}
+
 /// Create a zip file from a list of files.
 ///
 /// The zip file will be created in the root directory, with the provided name and the `.zip`
@@ -198,21 +239,32 @@ pub fn filter_ignored_files(root_directory_path: impl AsRef<Path>) -> Vec<PathBu
     files
 }
 
+/// Sign a zip file with the provided key.
+fn sign_zip(key: &SigningKey, zip_file: &Path) -> Result<PackageSignature> {
+    let checksum = hash_file(zip_file)
+        .map_err(|e| PublishError::IOError { path: zip_file.to_path_buf(), source: e })?;
+    let signature = key.sign(&checksum.0)?;
+    debug!(zip_file:?, public_key = signature.public_key; "zip file signed");
+    Ok(signature)
+}
+
 /// Push a zip file to the registry.
 ///
 /// An authentication token is required to push a zip file to the registry. The token is retrieved
-/// from the login file (see [`login_file_path`][crate::utils::login_file_path] and
+/// from the auth source of the registry (see [`get_token`] and
 /// [`execute_login`][crate::auth::execute_login]).
 async fn push_to_repo(
+    registry: &Registry,
     zip_file: &Path,
     dependency_name: &str,
     dependency_version: &str,
+    signature: Option<&PackageSignature>,
 ) -> Result<()> {
     debug!(zip_file:?; "uploading zip archive to registry");
-    let token = get_token()?;
+    let token = get_token(registry)?;
     let client = Client::new();
 
-    let url = api_url("v1", "revision/upload", &[]);
+    let url = registry.api_url("v1", "revision/upload", &[]);
 
     let mut headers: HeaderMap = HeaderMap::new();
 
@@ -234,13 +286,18 @@ async fn push_to_repo(
     // set the mime as app zip
     part = part.mime_str("application/zip").expect("Could not set mime type");
 
-    let project_id = get_project_id(dependency_name).await?;
+    let project_id = get_project_id(registry, dependency_name).await?;
     debug!(project_id; "project ID fetched from registry");
 
-    let form = Form::new()
+    let mut form = Form::new()
         .text("project_id", project_id)
         .text("revision", dependency_version.to_string())
         .part("zip_name", part);
+    if let Some(signature) = signature {
+        form = form
+            .text("public_key", signature.public_key.clone())
+            .text("signature", signature.signature.clone());
+    }
 
     headers.insert(
         CONTENT_TYPE,
@@ -264,7 +321,7 @@ async fn push_to_repo(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::download::unzip_file;
+    use crate::download::extract_archive;
     use std::fs;
     use testdir::testdir;
 
@@ -382,7 +439,7 @@ mod tests {
         fs::copy(dir.join("test.zip"), testdir!().join("test.zip")).unwrap();
         fs::remove_dir_all(&dir).unwrap();
         fs::create_dir(&dir).unwrap();
-        unzip_file(testdir!().join("test.zip"), &dir).await.unwrap();
+        extract_archive(testdir!().join("test.zip"), &dir).await.unwrap();
         for f in files {
             assert!(f.exists());
         }
@@ -1,18 +1,33 @@
 //! Soldeer registry client.
 //!
 //! The registry client is responsible for fetching information about packages from the Soldeer
-//! registry at <https://soldeer.xyz>.
+//! registry at <https://soldeer.xyz>, or from other registries which implement the same API (e.g.
+//! an internal mirror).
+//!
+//! Additional registries are declared in the `[registries]` table of the config file, and an HTTP
+//! dependency can select one of them by name with its `registry` field:
+//!
+//! ```toml
+//! [registries]
+//! internal = { url = "https://soldeer.example.com", token_env = "INTERNAL_SOLDEER_TOKEN" }
+//!
+//! [dependencies]
+//! my-lib = { version = "1.0.0", registry = "internal" }
+//! ```
 use crate::{
     auth::get_auth_headers,
+    cache::{cache_revisions, get_cached_revisions},
     config::{Dependency, HttpDependency},
     errors::RegistryError,
+    signing::PackageSignature,
+    utils::login_file_path,
 };
 use chrono::{DateTime, Utc};
 use log::{debug, warn};
 use reqwest::{Client, Url};
 use semver::{Version, VersionReq};
 use serde::Deserialize;
-use std::env;
+use std::{collections::BTreeMap, env, path::PathBuf};
 
 pub type Result<T> = std::result::Result<T, RegistryError>;
 
@@ -43,6 +58,27 @@ pub struct Revision {
 
     /// Whether the revision is private.
     pub private: Option<bool>,
+
+    /// The hex-encoded public key of the publisher, if the revision is signed.
+    #[serde(default)]
+    pub public_key: Option<String>,
+
+    /// The hex-encoded signature of the archive, if the revision is signed (see
+    /// [`crate::signing`]).
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl Revision {
+    /// The signature of the revision's archive, if it's signed.
+    pub fn package_signature(&self) -> Option<PackageSignature> {
+        match (&self.public_key, &self.signature) {
+            (Some(public_key), Some(signature)) => Some(
+                PackageSignature::builder().public_key(public_key).signature(signature).build(),
+            ),
+            _ => None,
+        }
+    }
 }
 
 /// A project (package) in the registry.
@@ -81,6 +117,9 @@ pub struct Project {
     pub deprecated: Option<bool>,
     pub organization_name: Option<String>,
     pub organization_verified: Option<bool>,
+
+    /// The SPDX license expression of the project, if known.
+    pub license: Option<String>,
 }
 
 /// The response from the revision endpoint.
@@ -94,6 +133,13 @@ pub struct RevisionResponse {
     status: String,
 }
 
+impl RevisionResponse {
+    /// Create a successful response containing the given revisions.
+    pub fn new(data: Vec<Revision>) -> Self {
+        Self { data, status: "success".to_string() }
+    }
+}
+
 /// The response from the project endpoint.
 #[derive(Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
@@ -105,6 +151,13 @@ pub struct ProjectResponse {
     status: String,
 }
 
+impl ProjectResponse {
+    /// Create a successful response containing the given projects.
+    pub fn new(data: Vec<Project>) -> Self {
+        Self { data, status: "success".to_string() }
+    }
+}
+
 /// A download URL for a revision.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -114,86 +167,284 @@ pub struct DownloadUrl {
 
     /// Whether this revision is private.
     pub private: bool,
+
+    /// The signature of the revision's archive, if it's signed.
+    pub signature: Option<PackageSignature>,
+
+    /// The creation date of the revision.
+    pub created_at: Option<DateTime<Utc>>,
 }
 
-/// Construct a URL for the Soldeer API.
-///
-/// The URL is constructed from the `SOLDEER_API_URL` environment variable, or defaults to
-/// <https://api.soldeer.xyz>. The API version prefix and path are appended to the base URL,
-/// and any query parameters are URL-encoded and appended to the URL.
+/// The name of the default registry at <https://soldeer.xyz>.
+pub const DEFAULT_REGISTRY: &str = "soldeer";
+
+/// Where the authentication token for a registry comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RegistryAuth {
+    /// The environment variable with the given name.
+    Env(String),
+
+    /// The environment variable with the given name if it is set, or else the file at the given
+    /// path.
+    EnvOrFile { var: String, file: PathBuf },
+
+    /// The file at the given path.
+    File(PathBuf),
+
+    /// The registry does not require authentication.
+    None,
+}
+
+impl RegistryAuth {
+    /// The token source of the default registry.
+    ///
+    /// The token is read from the `SOLDEER_API_TOKEN` environment variable, or from the login file
+    /// (see [`login_file_path`]).
+    pub fn soldeer() -> Self {
+        let var = "SOLDEER_API_TOKEN".to_string();
+        match login_file_path() {
+            Ok(file) => Self::EnvOrFile { var, file },
+            Err(err) => {
+                debug!(err:err; "could not get the path of the login file");
+                Self::Env(var)
+            }
+        }
+    }
+}
+
+/// A handle to a registry.
 ///
-/// # Examples
+/// The default registry is obtained with [`Registry::soldeer`], and other registries are declared
+/// in the config file (see [`read_registries`][crate::config::read_registries]).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Registry {
+    /// The name of the registry.
+    pub name: String,
+
+    /// The base URL of the registry API.
+    pub url: Url,
+
+    /// Where the authentication token comes from.
+    pub auth: RegistryAuth,
+}
+
+impl Registry {
+    /// Create a new registry handle.
+    pub fn new(name: impl Into<String>, url: Url, auth: RegistryAuth) -> Self {
+        Self { name: name.into(), url, auth }
+    }
+
+    /// Get a handle to the default registry.
+    ///
+    /// The URL is read from the `SOLDEER_API_URL` environment variable, or defaults to
+    /// <https://api.soldeer.xyz>. The token source is [`RegistryAuth::soldeer`].
+    pub fn soldeer() -> Self {
+        Self::new(DEFAULT_REGISTRY, Self::soldeer_url(), RegistryAuth::soldeer())
+    }
+
+    /// Get the URL of the default registry (see [`Registry::soldeer`]).
+    pub fn soldeer_url() -> Url {
+        let url = env::var("SOLDEER_API_URL").unwrap_or("https://api.soldeer.xyz".to_string());
+        Url::parse(&url).expect("SOLDEER_API_URL is invalid")
+    }
+
+    /// Whether this is the default registry.
+    pub fn is_default(&self) -> bool {
+        self.name == DEFAULT_REGISTRY
+    }
+
+    /// Construct a URL for the registry API.
+    ///
+    /// The API version prefix and path are appended to the path of the base URL of the registry,
+    /// and any query parameters are URL-encoded and appended to the URL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use soldeer_core::registry::{Registry, RegistryAuth};
+    /// let registry =
+    ///     Registry::new("soldeer", "https://api.soldeer.xyz".parse().unwrap(), RegistryAuth::None);
+    /// let url = registry.api_url(
+    ///     "v1",
+    ///     "revision",
+    ///     &[("project_name", "forge-std"), ("offset", "0"), ("limit", "1")],
+    /// );
+    /// assert_eq!(
+    ///     url.as_str(),
+    ///     "https://api.soldeer.xyz/api/v1/revision?project_name=forge-std&offset=0&limit=1"
+    /// );
+    ///
+    /// let registry = Registry::new(
+    ///     "internal",
+    ///     "https://example.com/soldeer/".parse().unwrap(),
+    ///     RegistryAuth::None,
+    /// );
+    /// let url = registry.api_url("v1", "auth/login", &[]);
+    /// assert_eq!(url.as_str(), "https://example.com/soldeer/api/v1/auth/login");
+    /// ```
+    pub fn api_url(&self, version: &str, path: &str, params: &[(&str, &str)]) -> Url {
+        let mut url = self.url.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty().extend(["api", version]).extend(path.split('/'));
+        }
+        if params.is_empty() {
+            return url;
+        }
+        url.query_pairs_mut().extend_pairs(params.iter());
+        url
+    }
+
+    /// Identify the registry in the cache by the host and port of the API URL.
+    fn cache_key(&self) -> String {
+        match self.url.port() {
+            Some(port) => format!("{}_{port}", self.url.host_str().unwrap_or_default()),
+            None => self.url.host_str().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// The registries which can be used to retrieve packages, by name.
 ///
-/// ```
-/// # use soldeer_core::registry::api_url;
-/// let url = api_url(
-///     "v1",
-///     "revision",
-///     &[("project_name", "forge-std"), ("offset", "0"), ("limit", "1")],
-/// );
-/// assert_eq!(
-///     url.as_str(),
-///     "https://api.soldeer.xyz/api/v1/revision?project_name=forge-std&offset=0&limit=1"
-/// );
-/// ```
-pub fn api_url(version: &str, path: &str, params: &[(&str, &str)]) -> Url {
-    let url = env::var("SOLDEER_API_URL").unwrap_or("https://api.soldeer.xyz".to_string());
-    let mut url = Url::parse(&url).expect("SOLDEER_API_URL is invalid");
-    url.set_path(&format!("api/{version}/{path}"));
-    if params.is_empty() {
-        return url;
-    }
-    url.query_pairs_mut().extend_pairs(params.iter());
-    url
+/// The default registry is always available, under the name [`DEFAULT_REGISTRY`]. It can be
+/// overridden by declaring a registry with that name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registries {
+    /// The default registry.
+    default: Registry,
+
+    /// The other registries, by name.
+    named: BTreeMap<String, Registry>,
+}
+
+impl Registries {
+    /// Create the list of registries from the registries declared in the config file.
+    ///
+    /// If none of them is named [`DEFAULT_REGISTRY`], the default registry is
+    /// [`Registry::soldeer`].
+    pub fn new(registries: impl IntoIterator<Item = Registry>) -> Self {
+        let mut named: BTreeMap<_, _> =
+            registries.into_iter().map(|r| (r.name.clone(), r)).collect();
+        let default = named.remove(DEFAULT_REGISTRY).unwrap_or_else(Registry::soldeer);
+        Self { default, named }
+    }
+
+    /// Get a registry by name, or the default registry if no name is provided.
+    pub fn get(&self, name: Option<&str>) -> Result<&Registry> {
+        match name {
+            None | Some(DEFAULT_REGISTRY) => Ok(&self.default),
+            Some(name) => {
+                self.named.get(name).ok_or_else(|| RegistryError::UnknownRegistry(name.to_string()))
+            }
+        }
+    }
+
+    /// Get the registry of a dependency.
+    ///
+    /// Dependencies which are not retrieved from a registry use the default registry.
+    pub fn for_dependency(&self, dependency: &Dependency) -> Result<&Registry> {
+        self.get(dependency.registry())
+    }
+
+    /// Get the default registry.
+    pub fn default_registry(&self) -> &Registry {
+        &self.default
+    }
+}
+
+impl Default for Registries {
+    /// Only the default registry.
+    fn default() -> Self {
+        Self::new([])
+    }
 }
 
 /// Get the download URL for a dependency at a specific version.
+///
+/// In offline mode, the URL is read from the revisions saved in the global cache.
 pub async fn get_dependency_url_remote(
+    registry: &Registry,
     dependency: &Dependency,
     version: &str,
+    offline: bool,
 ) -> Result<DownloadUrl> {
-    debug!(dep:% = dependency; "retrieving URL for dependency");
-    let url = api_url(
+    debug!(dep:% = dependency, registry = registry.name; "retrieving URL for dependency");
+    if offline {
+        let revisions = get_revisions(registry, dependency.name(), true).await?;
+        let Some(r) = revisions.data.iter().find(|r| r.version == version) else {
+            return Err(RegistryError::URLNotFound(dependency.to_string()));
+        };
+        debug!(dep:% = dependency, url = r.url; "URL for dependency was found in cached revisions");
+        return Ok(DownloadUrl {
+            url: r.url.clone(),
+            private: r.private.unwrap_or_default(),
+            signature: r.package_signature(),
+            created_at: r.created_at,
+        });
+    }
+    let url = registry.api_url(
         "v1",
         "revision-cli",
         &[("project_name", dependency.name()), ("revision", version)],
     );
 
-    let res = Client::new().get(url).headers(get_auth_headers()?).send().await?;
+    let res = Client::new().get(url).headers(get_auth_headers(registry)?).send().await?;
     let res = res.error_for_status()?;
     let revision: RevisionResponse = res.json().await?;
     let Some(r) = revision.data.first() else {
         return Err(RegistryError::URLNotFound(dependency.to_string()));
     };
     debug!(dep:% = dependency, url = r.url; "URL for dependency was found");
-    Ok(DownloadUrl { url: r.url.clone(), private: r.private.unwrap_or_default() })
+    Ok(DownloadUrl {
+        url: r.url.clone(),
+        private: r.private.unwrap_or_default(),
+        signature: r.package_signature(),
+        created_at: r.created_at,
+    })
 }
 
-/// Get the unique ID for a project by name.
-pub async fn get_project_id(dependency_name: &str) -> Result<String> {
-    debug!(name = dependency_name; "retrieving project ID");
-    let url = api_url("v2", "project", &[("project_name", dependency_name)]);
-    let res = Client::new().get(url).headers(get_auth_headers()?).send().await?;
+/// Get a project by name.
+pub async fn get_project(registry: &Registry, dependency_name: &str) -> Result<Project> {
+    debug!(name = dependency_name, registry = registry.name; "retrieving project");
+    let url = registry.api_url("v2", "project", &[("project_name", dependency_name)]);
+    let res = Client::new().get(url).headers(get_auth_headers(registry)?).send().await?;
     let res = res.error_for_status()?;
     let project: ProjectResponse = res.json().await?;
-    let Some(p) = project.data.first() else {
+    let Some(p) = project.data.into_iter().next() else {
         return Err(RegistryError::ProjectNotFound(dependency_name.to_string()));
     };
-    debug!(name = dependency_name, id:% = p.id; "project ID was found");
-    Ok(p.id.to_string())
+    debug!(name = dependency_name, id:% = p.id; "project was found");
+    Ok(p)
+}
+
+/// Get the unique ID for a project by name.
+pub async fn get_project_id(registry: &Registry, dependency_name: &str) -> Result<String> {
+    Ok(get_project(registry, dependency_name).await?.id.to_string())
 }
 
 /// Get the latest version of a dependency.
-pub async fn get_latest_version(dependency_name: &str) -> Result<Dependency> {
-    debug!(dep = dependency_name; "retrieving latest version for dependency");
-    let url = api_url(
-        "v1",
-        "revision",
-        &[("project_name", dependency_name), ("offset", "0"), ("limit", "1")],
-    );
-    let res = Client::new().get(url).headers(get_auth_headers()?).send().await?;
-    let res = res.error_for_status()?;
-    let revision: RevisionResponse = res.json().await?;
+///
+/// The returned dependency refers to the registry by name, unless it's the default registry. In
+/// offline mode, the version is read from the revisions saved in the global cache.
+pub async fn get_latest_version(
+    registry: &Registry,
+    dependency_name: &str,
+    offline: bool,
+) -> Result<Dependency> {
+    debug!(dep = dependency_name, registry = registry.name; "retrieving latest version for dependency");
+    let revision = if offline {
+        get_revisions(registry, dependency_name, true).await?
+    } else {
+        let url = registry.api_url(
+            "v1",
+            "revision",
+            &[("project_name", dependency_name), ("offset", "0"), ("limit", "1")],
+        );
+        let res = Client::new().get(url).headers(get_auth_headers(registry)?).send().await?;
+        let res = res.error_for_status()?;
+        res.json().await?
+    };
     let Some(data) = revision.data.first() else {
         return Err(RegistryError::URLNotFound(dependency_name.to_string()));
     };
@@ -202,6 +453,7 @@ pub async fn get_latest_version(dependency_name: &str) -> Result<Dependency> {
         name: dependency_name.to_string(),
         version_req: data.clone().version,
         url: None,
+        registry: (!registry.is_default()).then(|| registry.name.clone()),
         project_root: None,
     }
     .into())
@@ -226,18 +478,17 @@ pub enum Versions {
 /// If all versions can be parsed as semver, then the versions are sorted in descending order
 /// according to semver. If not all versions can be parsed as semver, then the versions are returned
 /// in the order they were received from the API (descending creation date).
-pub async fn get_all_versions_descending(dependency_name: &str) -> Result<Versions> {
+///
+/// In offline mode, the versions are read from the revisions saved in the global cache.
+pub async fn get_all_versions_descending(
+    registry: &Registry,
+    dependency_name: &str,
+    offline: bool,
+) -> Result<Versions> {
     // TODO: provide a more efficient endpoint which already sorts by descending semver if possible
     // and only returns the version strings
-    debug!(dep = dependency_name; "retrieving all dependency versions");
-    let url = api_url(
-        "v1",
-        "revision",
-        &[("project_name", dependency_name), ("offset", "0"), ("limit", "10000")],
-    );
-    let res = Client::new().get(url).headers(get_auth_headers()?).send().await?;
-    let res = res.error_for_status()?;
-    let revision: RevisionResponse = res.json().await?;
+    debug!(dep = dependency_name, registry = registry.name; "retrieving all dependency versions");
+    let revision = get_revisions(registry, dependency_name, offline).await?;
     if revision.data.is_empty() {
         return Err(RegistryError::NoVersion(dependency_name.to_string()));
     }
@@ -260,13 +511,53 @@ pub async fn get_all_versions_descending(dependency_name: &str) -> Result<Versio
     }
 }
 
+/// Get all revisions of a dependency.
+///
+/// The listing retrieved from the registry is saved in the global cache, so that it can be used in
+/// offline mode instead of contacting the registry.
+async fn get_revisions(
+    registry: &Registry,
+    dependency_name: &str,
+    offline: bool,
+) -> Result<RevisionResponse> {
+    if offline {
+        debug!(dep = dependency_name; "offline mode, reading revisions from cache");
+        let listing = match get_cached_revisions(&registry.cache_key(), dependency_name) {
+            Ok(Some(listing)) => listing,
+            Ok(None) => return Err(RegistryError::Offline(dependency_name.to_string())),
+            Err(err) => {
+                warn!(dep = dependency_name, err:%; "could not read revisions from cache");
+                return Err(RegistryError::Offline(dependency_name.to_string()));
+            }
+        };
+        return Ok(serde_json::from_str(&listing)?);
+    }
+    let url = registry.api_url(
+        "v1",
+        "revision",
+        &[("project_name", dependency_name), ("offset", "0"), ("limit", "10000")],
+    );
+    let res = Client::new().get(url).headers(get_auth_headers(registry)?).send().await?;
+    let res = res.error_for_status()?;
+    let listing = res.text().await?;
+    let revision = serde_json::from_str(&listing)?;
+    if let Err(err) = cache_revisions(&registry.cache_key(), dependency_name, &listing) {
+        warn!(dep = dependency_name, err:%; "could not save revisions in cache");
+    }
+    Ok(revision)
+}
+
 /// Get the latest version of a dependency that satisfies the version requirement.
 ///
 /// If the API response contains non-semver-compliant versions, then we attempt to find an exact
 /// match for the requirement, or error out.
-pub async fn get_latest_supported_version(dependency: &Dependency) -> Result<String> {
+pub async fn get_latest_supported_version(
+    registry: &Registry,
+    dependency: &Dependency,
+    offline: bool,
+) -> Result<String> {
     debug!(dep:% = dependency, version_req = dependency.version_req(); "retrieving latest version according to version requirement");
-    match get_all_versions_descending(dependency.name()).await? {
+    match get_all_versions_descending(registry, dependency.name(), offline).await? {
         Versions::Semver(all_versions) => {
             match parse_version_req(dependency.version_req()) {
                 Some(req) => {
@@ -324,7 +615,7 @@ pub fn parse_version_req(version_req: &str) -> Option<VersionReq> {
     // parsed result has the same number of comparators as the original string
 
     if orig_items.len() == req.comparators.len() {
-        for (comparator, orig) in req.comparators.iter_mut().zip(orig_items.into_iter()) {
+        for (comparator, orig) in req.comparators.iter_mut().zip(orig_items) {
             if comparator.op == semver::Op::Caret && !orig.trim_start_matches(' ').starts_with('^')
             {
                 debug!(comparator:% = comparator; "adding exact operator for comparator");
@@ -338,9 +629,13 @@ pub fn parse_version_req(version_req: &str) -> Option<VersionReq> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use mockito::{Matcher, Server};
+    use mockito::{Matcher, Server, ServerGuard};
     use temp_env::async_with_vars;
 
+    fn registry(server: &ServerGuard) -> Registry {
+        Registry::new(DEFAULT_REGISTRY, server.url().parse().unwrap(), RegistryAuth::None)
+    }
+
     #[tokio::test]
     async fn test_get_dependency_url() {
         let mut server = Server::new_async().await;
@@ -355,11 +650,7 @@ mod tests {
 
         let dependency =
             HttpDependency::builder().name("forge-std").version_req("^1.9.0").build().into();
-        let res = async_with_vars(
-            [("SOLDEER_API_URL", Some(server.url()))],
-            get_dependency_url_remote(&dependency, "1.9.2"),
-        )
-        .await;
+        let res = get_dependency_url_remote(&registry(&server), &dependency, "1.9.2", false).await;
         assert!(res.is_ok(), "{res:?}");
         assert_eq!(
             res.unwrap().url,
@@ -381,11 +672,7 @@ mod tests {
 
         let dependency =
             HttpDependency::builder().name("forge-std").version_req("^1.9.0").build().into();
-        let res = async_with_vars(
-            [("SOLDEER_API_URL", Some(server.url()))],
-            get_dependency_url_remote(&dependency, "1.9.2"),
-        )
-        .await;
+        let res = get_dependency_url_remote(&registry(&server), &dependency, "1.9.2", false).await;
         assert!(matches!(res, Err(RegistryError::URLNotFound(_))));
     }
 
@@ -400,9 +687,7 @@ mod tests {
             .with_body(data)
             .create_async()
             .await;
-        let res =
-            async_with_vars([("SOLDEER_API_URL", Some(server.url()))], get_project_id("forge-std"))
-                .await;
+        let res = get_project_id(&registry(&server), "forge-std").await;
         assert!(res.is_ok(), "{res:?}");
         assert_eq!(res.unwrap(), "37adefe5-9bc6-4777-aaf2-e56277d1f30b");
     }
@@ -419,9 +704,7 @@ mod tests {
             .create_async()
             .await;
 
-        let res =
-            async_with_vars([("SOLDEER_API_URL", Some(server.url()))], get_project_id("forge-std"))
-                .await;
+        let res = get_project_id(&registry(&server), "forge-std").await;
         assert!(matches!(res, Err(RegistryError::ProjectNotFound(_))));
     }
 
@@ -439,11 +722,7 @@ mod tests {
 
         let dependency =
             HttpDependency::builder().name("forge-std").version_req("1.9.2").build().into();
-        let res = async_with_vars(
-            [("SOLDEER_API_URL", Some(server.url()))],
-            get_latest_version("forge-std"),
-        )
-        .await;
+        let res = get_latest_version(&registry(&server), "forge-std", false).await;
         assert!(res.is_ok(), "{res:?}");
         assert_eq!(res.unwrap(), dependency);
     }
@@ -461,11 +740,7 @@ mod tests {
             .create_async()
             .await;
 
-        let res = async_with_vars(
-            [("SOLDEER_API_URL", Some(server.url()))],
-            get_all_versions_descending("forge-std"),
-        )
-        .await;
+        let res = get_all_versions_descending(&registry(&server), "forge-std", false).await;
         assert!(res.is_ok(), "{res:?}");
         assert_eq!(
             res.unwrap(),
@@ -491,13 +766,53 @@ mod tests {
 
         let dependency: Dependency =
             HttpDependency::builder().name("forge-std").version_req("^1.9.0").build().into();
+        let res = get_latest_supported_version(&registry(&server), &dependency, false).await;
+        assert!(res.is_ok(), "{res:?}");
+        assert_eq!(res.unwrap(), "1.9.2");
+    }
+
+    #[tokio::test]
+    async fn test_get_latest_supported_version_offline() {
+        let mut server = Server::new_async().await;
+        let data = r#"{"data":[{"created_at":"2024-08-06T17:31:25.751079Z","deleted":false,"downloads":3389,"id":"660132e6-4902-4804-8c4b-7cae0a648054","internal_name":"forge-std/1_9_2.zip","project_id":"37adefe5-9bc6-4777-aaf2-e56277d1f30b","url":"https://example.com/1_9_2.zip","version":"1.9.2"},{"created_at":"2024-07-03T14:44:58.148723Z","deleted":false,"downloads":21,"id":"b463683a-c4b4-40bf-b707-1c4eb343c4d2","internal_name":"forge-std/1_9_0.zip","project_id":"37adefe5-9bc6-4777-aaf2-e56277d1f30b","url":"https://example.com/1_9_0.zip","version":"1.9.0"}],"status":"success"}"#;
+        let mock = server
+            .mock("GET", "/api/v1/revision")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(data)
+            .create_async()
+            .await;
+        let cache = testdir::testdir!().join("cache");
+        let dependency: Dependency =
+            HttpDependency::builder().name("forge-std").version_req("^1.9.0").build().into();
+        let registry = registry(&server);
+        let vars = [("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().to_string()))];
+        // nothing in the cache yet
         let res = async_with_vars(
-            [("SOLDEER_API_URL", Some(server.url()))],
-            get_latest_supported_version(&dependency),
+            vars.clone(),
+            get_latest_supported_version(&registry, &dependency, true),
+        )
+        .await;
+        assert!(matches!(res, Err(RegistryError::Offline(_))), "{res:?}");
+
+        let res = async_with_vars(
+            vars.clone(),
+            get_latest_supported_version(&registry, &dependency, false),
+        )
+        .await;
+        assert_eq!(res.unwrap(), "1.9.2");
+        mock.remove_async().await;
+
+        let res = async_with_vars(
+            vars.clone(),
+            get_latest_supported_version(&registry, &dependency, true),
         )
         .await;
-        assert!(res.is_ok(), "{res:?}");
         assert_eq!(res.unwrap(), "1.9.2");
+        let res =
+            async_with_vars(vars, get_dependency_url_remote(&registry, &dependency, "1.9.0", true))
+                .await;
+        assert_eq!(res.unwrap().url, "https://example.com/1_9_0.zip");
     }
 
     #[tokio::test]
@@ -514,24 +829,57 @@ mod tests {
 
         let dependency: Dependency =
             HttpDependency::builder().name("forge-std").version_req("2024-06").build().into();
-        let res = async_with_vars(
-            [("SOLDEER_API_URL", Some(server.url()))],
-            get_latest_supported_version(&dependency),
-        )
-        .await;
+        let res = get_latest_supported_version(&registry(&server), &dependency, false).await;
         assert!(res.is_ok(), "{res:?}");
         assert_eq!(res.unwrap(), "2024-06"); // should resolve to the exact match
 
         let dependency: Dependency =
             HttpDependency::builder().name("forge-std").version_req("non-existant").build().into();
-        let res = async_with_vars(
-            [("SOLDEER_API_URL", Some(server.url()))],
-            get_latest_supported_version(&dependency),
-        )
-        .await;
+        let res = get_latest_supported_version(&registry(&server), &dependency, false).await;
         assert!(matches!(res, Err(RegistryError::NoMatchingVersion { .. })));
     }
 
+    #[test]
+    fn test_registries_get() {
+        let internal = Registry::new(
+            "internal",
+            "https://soldeer.example.com".parse().unwrap(),
+            RegistryAuth::Env("INTERNAL_TOKEN".to_string()),
+        );
+        let registries = Registries::new([internal.clone()]);
+        assert_eq!(registries.get(Some("internal")).unwrap(), &internal);
+        assert!(registries.get(None).unwrap().is_default());
+        assert!(registries.get(Some(DEFAULT_REGISTRY)).unwrap().is_default());
+        assert!(matches!(
+            registries.get(Some("foo")),
+            Err(RegistryError::UnknownRegistry(name)) if name == "foo"
+        ));
+
+        // the default registry can be overridden
+        let mirror = Registry::new(
+            DEFAULT_REGISTRY,
+            "https://mirror.example.com".parse().unwrap(),
+            RegistryAuth::None,
+        );
+        let registries = Registries::new([mirror.clone()]);
+        assert_eq!(registries.default_registry(), &mirror);
+        assert_eq!(registries.get(None).unwrap(), &mirror);
+    }
+
+    #[test]
+    fn test_api_url_base_path() {
+        let registry = Registry::new(
+            "internal",
+            "https://example.com/registry".parse().unwrap(),
+            RegistryAuth::None,
+        );
+        let url = registry.api_url("v2", "project", &[("project_name", "forge-std")]);
+        assert_eq!(
+            url.as_str(),
+            "https://example.com/registry/api/v2/project?project_name=forge-std"
+        );
+    }
+
     #[test]
     fn test_parse_version_req() {
         assert_eq!(parse_version_req("1.9.0"), Some(VersionReq::parse("=1.9.0").unwrap()));
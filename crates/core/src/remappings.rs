@@ -2,7 +2,7 @@
 use crate::{
     config::{Dependency, Paths, SoldeerConfig, read_config_deps},
     errors::RemappingsError,
-    utils::path_matches,
+    utils::{path_matches, relative_path},
 };
 use derive_more::derive::From;
 use log::debug;
@@ -30,6 +30,10 @@ pub enum RemappingsAction {
 
     /// Update the remappings according to the config file.
     Update,
+
+    /// Update the remappings of a single dependency so that they point to its current install
+    /// folder (e.g. after its version changed).
+    Refresh(Dependency),
 }
 
 /// Location where to store the remappings, either in `remappings.txt` or the config file
@@ -257,70 +261,30 @@ fn generate_remappings(
                 }
                 if !found {
                     debug!(dep:% = add_dep; "remapping not found, adding it");
-                    new_remappings.push(format!("{add_dep_remapped}={add_dep_og}/"));
+                    new_remappings.push(format!(
+                        "{add_dep_remapped}={}/",
+                        remapping_target(add_dep, &add_dep_og)
+                    ));
                 }
             }
             RemappingsAction::Update => {
                 // This is where we end up in the `update` command if we don't want to re-generate
                 // all remappings. We need to merge existing remappings with the full list of deps.
-                // We generate all remappings from the dependencies, then replace existing items.
                 debug!(
                     "updating remappings, merging existing ones with the ones generated from config"
                 );
                 let (dependencies, _) = read_config_deps(&paths.config)?;
-                let new_remappings_info =
-                    remappings_from_deps(&dependencies, paths, soldeer_config)?;
-                if existing_remappings.is_empty() {
-                    debug!("no existing remappings, using the ones from config");
-                    new_remappings =
-                        new_remappings_info.into_iter().map(|i| i.remapping_string).collect();
-                } else {
-                    let mut existing_remappings = Vec::from(existing_remappings);
-                    for RemappingInfo { remapping_string: item, dependency: dep } in
-                        new_remappings_info
-                    {
-                        debug!(dep:% = dep; "trying to find a matching existing remapping for config item");
-                        let (_, item_og) =
-                            item.split_once('=').expect("remappings should have two parts");
-                        // try to find all existing items pointing to a matching dependency folder
-                        let mut found = false;
-                        existing_remappings.retain(|(existing_remapped, existing_og)| {
-                            // only keep the first two components of the path (`dependencies`
-                            // folder and the dependency folder)
-                            let path: PathBuf =
-                                PathBuf::from(existing_og).components().take(2).collect();
-                            // if path matches, we should update the item's path with the new
-                            // one and add it to the final list
-                            if path_matches(&dep, &path) {
-                                debug!(path = existing_og; "existing remapping matches the config item");
-                                let path: PathBuf =
-                                    PathBuf::from(existing_og).components().take(2).collect();
-                                let existing_og_updated = existing_og.replace(
-                                    path.to_slash_lossy().as_ref(),
-                                    item_og.trim_end_matches('/'),
-                                );
-                                debug!(new_path = existing_og_updated; "updated remapping path");
-                                new_remappings
-                                    .push(format!("{existing_remapped}={existing_og_updated}"));
-                                found = true;
-                                // we remove this item from the existing remappings list as it's
-                                // been processed
-                                return false;
-                            }
-                            // keep this item to add it to the remappings again later
-                            true
-                        });
-                        if !found {
-                            debug!(dep:% = dep;"no existing remapping found for config item, adding it");
-                            new_remappings.push(item);
-                        }
-                    }
-                    // add extra existing remappings back
-                    for (existing_remapped, existing_og) in existing_remappings {
-                        debug!(path = existing_og; "adding extra remapping which was existing but didn't match a config item");
-                        new_remappings.push(format!("{existing_remapped}={existing_og}"));
-                    }
-                }
+                new_remappings =
+                    merge_remappings(&dependencies, paths, soldeer_config, existing_remappings)?;
+            }
+            RemappingsAction::Refresh(dep) => {
+                debug!(dep:% = dep; "refreshing remappings for dependency");
+                new_remappings = merge_remappings(
+                    std::slice::from_ref(dep),
+                    paths,
+                    soldeer_config,
+                    existing_remappings,
+                )?;
             }
         }
     }
@@ -330,17 +294,78 @@ fn generate_remappings(
     Ok(new_remappings)
 }
 
+/// Merge existing remappings with the ones generated for a list of dependencies.
+///
+/// We generate the remappings for the dependencies, then replace the existing items which point to
+/// a folder matching one of the dependencies. Existing remappings which don't match any of the
+/// dependencies are kept as is.
+fn merge_remappings(
+    dependencies: &[Dependency],
+    paths: &Paths,
+    soldeer_config: &SoldeerConfig,
+    existing_remappings: &[(&str, &str)],
+) -> Result<Vec<String>> {
+    let new_remappings_info = remappings_from_deps(dependencies, paths, soldeer_config)?;
+    if existing_remappings.is_empty() {
+        debug!("no existing remappings, using the ones from config");
+        return Ok(new_remappings_info.into_iter().map(|i| i.remapping_string).collect());
+    }
+    let mut new_remappings = Vec::new();
+    let mut existing_remappings = Vec::from(existing_remappings);
+    // the number of components of a path to a dependency folder, e.g. 2 for
+    // `dependencies/lib1-1.0.0`, more if the dependencies folder is in a workspace root
+    let depth = relative_path(&paths.root, &paths.dependencies).components().count() + 1;
+    for RemappingInfo { remapping_string: item, dependency: dep, install_dir } in
+        new_remappings_info
+    {
+        debug!(dep:% = dep; "trying to find a matching existing remapping for config item");
+        // try to find all existing items pointing to a matching dependency folder
+        let mut found = false;
+        existing_remappings.retain(|(existing_remapped, existing_og)| {
+            // only keep the components of the path up to the dependency folder
+            let path: PathBuf = PathBuf::from(existing_og).components().take(depth).collect();
+            // if path matches, we should update the item's path with the new
+            // one and add it to the final list
+            if path_matches(&dep, &path) {
+                debug!(path = existing_og; "existing remapping matches the config item");
+                let existing_og_updated =
+                    existing_og.replace(path.to_slash_lossy().as_ref(), &install_dir);
+                debug!(new_path = existing_og_updated; "updated remapping path");
+                new_remappings.push(format!("{existing_remapped}={existing_og_updated}"));
+                found = true;
+                // we remove this item from the existing remappings list as it's
+                // been processed
+                return false;
+            }
+            // keep this item to add it to the remappings again later
+            true
+        });
+        if !found {
+            debug!(dep:% = dep;"no existing remapping found for config item, adding it");
+            new_remappings.push(item);
+        }
+    }
+    // add extra existing remappings back
+    for (existing_remapped, existing_og) in existing_remappings {
+        debug!(path = existing_og; "adding extra remapping which was existing but didn't match a config item");
+        new_remappings.push(format!("{existing_remapped}={existing_og}"));
+    }
+    Ok(new_remappings)
+}
+
 #[derive(Debug, Clone, From)]
 struct RemappingInfo {
     remapping_string: String,
     dependency: Dependency,
+    /// The install folder of the dependency, relative to the project root.
+    install_dir: String,
 }
 
 /// Generate remappings from the dependencies list.
 ///
 /// The remappings are generated in the form `alias/=path/`, where `alias` is the dependency name
 /// with an optional prefix and version requirement suffix, and `path` is the relative path to the
-/// dependency folder.
+/// dependency folder (or to the checked out subdirectory for git dependencies with a `subdir`).
 fn remappings_from_deps(
     dependencies: &[Dependency],
     paths: &Paths,
@@ -350,27 +375,41 @@ fn remappings_from_deps(
         .par_iter()
         .map(|dependency| {
             let dependency_name_formatted = format_remap_name(soldeer_config, dependency); // contains trailing slash
-            let relative_path = get_install_dir_relative(dependency, paths)?;
-            Ok((format!("{dependency_name_formatted}={relative_path}/"), dependency.clone()).into())
+            let install_dir = get_install_dir_relative(dependency, paths)?;
+            let target = remapping_target(dependency, &install_dir);
+            Ok((format!("{dependency_name_formatted}={target}/"), dependency.clone(), install_dir)
+                .into())
         })
         .collect::<Result<Vec<RemappingInfo>>>()
 }
 
 /// Find the install path (relative to project root) for a dependency that was already installed
 ///
+/// If the project is part of a workspace, the path can start with `..` components.
+///
 /// # Errors
 /// If the there is no folder in the dependencies folder corresponding to the dependency
 fn get_install_dir_relative(dependency: &Dependency, paths: &Paths) -> Result<String> {
-    let path = dunce::canonicalize(
-        dependency
-            .install_path_sync(&paths.dependencies)
-            .ok_or(RemappingsError::DependencyNotFound(dependency.to_string()))?,
-    )?;
-    Ok(path
-        .strip_prefix(&paths.root) // already canonicalized
-        .map_err(|_| RemappingsError::DependencyNotFound(dependency.to_string()))?
-        .to_slash_lossy()
-        .to_string())
+    let path = dependency
+        .install_path_sync(&paths.dependencies)
+        .ok_or(RemappingsError::DependencyNotFound(dependency.to_string()))?;
+    // only the dependencies folder is canonicalized, because the install folder can be a symlink to
+    // the package store (see `LinkMode::Symlink`)
+    let path = dunce::canonicalize(&paths.dependencies)?
+        .join(path.file_name().expect("install path should have a folder name"));
+    // the root is already canonicalized
+    Ok(relative_path(&paths.root, path).to_slash_lossy().to_string())
+}
+
+/// Get the path a dependency's remapping points to (without trailing slash), from its install
+/// folder.
+///
+/// For git dependencies with a `subdir`, the remapping points into the subdirectory.
+fn remapping_target(dependency: &Dependency, install_dir: &str) -> String {
+    match dependency.subdir() {
+        Some(subdir) => format!("{install_dir}/{subdir}"),
+        None => install_dir.to_string(),
+    }
 }
 
 /// Format a TOML array as a multi-line array with indentation in case there is more than one
@@ -507,6 +546,7 @@ mod tests {
 dep1 = "^1.0.0"
 dep2 = "2.0.0"
 dep3 = { version = "foobar", git = "git@github.com:test/test.git", branch = "foobar" }
+dep4 = { version = "1.0.0", git = "git@github.com:test/mono.git", subdir = "packages/core" }
 "#;
         fs::write(dir.join("soldeer.toml"), config).unwrap();
         let dependencies_dir = dir.join("dependencies");
@@ -516,15 +556,17 @@ dep3 = { version = "foobar", git = "git@github.com:test/test.git", branch = "foo
         fs::create_dir_all(dependencies_dir.join("dep1-1.1.1")).unwrap();
         fs::create_dir_all(dependencies_dir.join("dep2-2.0.0")).unwrap();
         fs::create_dir_all(dependencies_dir.join("dep3-foobar")).unwrap();
+        fs::create_dir_all(dependencies_dir.join("dep4-1.0.0")).unwrap();
 
         let (dependencies, _) = read_config_deps(&paths.config).unwrap();
         let res = remappings_from_deps(&dependencies, &paths, &SoldeerConfig::default());
         assert!(res.is_ok(), "{res:?}");
         let res = res.unwrap();
-        assert_eq!(res.len(), 3);
+        assert_eq!(res.len(), 4);
         assert_eq!(res[0].remapping_string, "dep1-^1.0.0/=dependencies/dep1-1.1.1/");
         assert_eq!(res[1].remapping_string, "dep2-2.0.0/=dependencies/dep2-2.0.0/");
         assert_eq!(res[2].remapping_string, "dep3-foobar/=dependencies/dep3-foobar/");
+        assert_eq!(res[3].remapping_string, "dep4-1.0.0/=dependencies/dep4-1.0.0/packages/core/");
     }
 
     #[test]
@@ -560,6 +602,29 @@ dep3 = { version = "foobar", git = "git@github.com:test/test.git", branch = "foo
         assert_eq!(res.unwrap(), vec!["@lib1-1.0.0/foo=dependencies/lib1-1.0.0/src"]);
     }
 
+    #[test]
+    fn test_generate_remappings_subdir() {
+        let dir = testdir!();
+        let contents = r#"[dependencies]
+lib1 = { version = "1.0.0", git = "https://example.com/lib1.git", subdir = "contracts" }
+"#;
+        fs::write(dir.join("soldeer.toml"), contents).unwrap();
+        let paths = Paths::from_root(&dir).unwrap();
+        fs::create_dir_all(paths.dependencies.join("lib1-1.0.0")).unwrap();
+        let config = SoldeerConfig::default();
+        let (dependencies, _) = read_config_deps(&paths.config).unwrap();
+        let dep = dependencies[0].clone();
+        let res = generate_remappings(&RemappingsAction::Add(dep), &paths, &config, &[]);
+        assert!(res.is_ok(), "{res:?}");
+        assert_eq!(res.unwrap(), vec!["lib1-1.0.0/=dependencies/lib1-1.0.0/contracts/"]);
+
+        // the subdirectory is not duplicated when updating an existing remapping
+        let existing_deps = vec![("lib1/", "dependencies/lib1-1.0.0/contracts/")];
+        let res = generate_remappings(&RemappingsAction::Update, &paths, &config, &existing_deps);
+        assert!(res.is_ok(), "{res:?}");
+        assert_eq!(res.unwrap(), vec!["lib1/=dependencies/lib1-1.0.0/contracts/"]);
+    }
+
     #[test]
     fn test_generate_remappings_remove() {
         let dir = testdir!();
@@ -0,0 +1,817 @@
+//! Resolve and install transitive dependencies.
+//!
+//! Dependencies which use Soldeer declare their own dependencies in their config file. The resolver
+//! walks this graph starting from the project's dependencies, installs each package once into the
+//! project's dependencies folder and records the edges of the graph in the lock entries, so that
+//! the lockfile describes the full dependency tree.
+use crate::{
+    config::{
+        ConfigLocation, Dependency, GitDependency, GitIdentifier, HttpDependency, PathDependency,
+        detect_config_location, read_config_deps,
+    },
+    errors::InstallError,
+    install::{
+        DependencyStatus, InstallOptions, InstallProgress, Result, check_dependency_integrity,
+        get_subdependency_root, install_dependencies, install_dependency, is_cached,
+    },
+    lock::{LockDrift, LockEntry},
+    registry::{Registries, Versions, get_all_versions_descending, parse_version_req},
+    utils::{canonicalize, relative_path},
+};
+use log::{debug, warn};
+use path_slash::PathBufExt as _;
+use semver::Version;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    path::{Path, PathBuf},
+};
+use tokio::{fs, task::JoinSet};
+
+/// Install a list of dependencies and, if `recursive_deps` is `true`, all of their transitive
+/// dependencies.
+///
+/// The returned lock entries contain one entry per package of the dependency graph. Lock entries
+/// which are provided are used to pin the version of any package of the graph, including
+/// transitive dependencies.
+pub async fn install_resolved(
+    dependencies: &[Dependency],
+    locks: &[LockEntry],
+    deps: impl AsRef<Path>,
+    registries: &Registries,
+    recursive_deps: bool,
+    options: &InstallOptions,
+    progress: InstallProgress,
+) -> Result<Vec<LockEntry>> {
+    let installed = install_dependencies(
+        dependencies,
+        locks,
+        &deps,
+        registries,
+        recursive_deps,
+        options,
+        progress.clone(),
+    )
+    .await?;
+    if !recursive_deps {
+        return Ok(installed);
+    }
+    resolve_transitive(dependencies, installed, locks, &[], deps, registries, options, progress)
+        .await
+}
+
+/// Install exactly the packages of the lockfile, without resolving any version.
+///
+/// Packages are downloaded from the URL recorded in their lock entry, or copied from the global
+/// cache. Private packages need a download URL from the registry: if `allow_registry` is `false`,
+/// they must already be installed or cached, otherwise an [`InstallError::RegistryRequired`] error
+/// is returned.
+///
+/// The lockfile is expected to be in sync with the dependencies (see [`check_lockfile_sync`]).
+#[allow(clippy::too_many_arguments)]
+pub async fn install_locked(
+    dependencies: &[Dependency],
+    locks: &[LockEntry],
+    deps: impl AsRef<Path>,
+    registries: &Registries,
+    allow_registry: bool,
+    recursive_deps: bool,
+    options: &InstallOptions,
+    progress: InstallProgress,
+) -> Result<Vec<LockEntry>> {
+    let deps = deps.as_ref();
+    let mut set = JoinSet::new();
+    for lock in locks {
+        if !allow_registry &&
+            let LockEntry::Private(private) = lock &&
+            check_dependency_integrity(lock, deps).await? != DependencyStatus::Installed &&
+            !is_cached(&private.checksum).await
+        {
+            return Err(InstallError::RegistryRequired(lock.name().to_string()));
+        }
+        let dependency = dependencies
+            .iter()
+            .find(|d| d.name() == lock.name())
+            .cloned()
+            .unwrap_or_else(|| dependency_from_lock(lock));
+        debug!(dep:% = dependency; "spawning task to install dependency from lock entry");
+        set.spawn({
+            let lock = lock.clone();
+            let deps = deps.to_path_buf();
+            let registries = registries.clone();
+            let options = options.clone();
+            let progress = progress.clone();
+            async move {
+                install_dependency(
+                    &dependency,
+                    Some(&lock),
+                    deps,
+                    None,
+                    &registries,
+                    recursive_deps,
+                    &options,
+                    progress,
+                )
+                .await
+            }
+        });
+    }
+    let mut results = Vec::with_capacity(locks.len());
+    while let Some(res) = set.join_next().await {
+        let mut lock = res??;
+        // keep the edges of the dependency graph recorded in the lockfile
+        if let Some(old) = locks.iter().find(|l| l.name() == lock.name()) {
+            lock.set_dependencies(old.dependencies().to_vec());
+        }
+        results.push(lock);
+    }
+    debug!("all packages of the lockfile are installed");
+    Ok(results)
+}
+
+/// Check that the lockfile entries describe the list of dependencies.
+///
+/// Each dependency must have a lock entry which matches its version requirement and source, each
+/// transitive dependency recorded in the lockfile must have an entry, and each entry must be
+/// reachable from the dependencies. The requirements on transitive dependencies are declared in
+/// the config file of their parent, so only their presence is checked.
+///
+/// An empty list means that the lockfile is in sync.
+pub fn check_lockfile_sync(dependencies: &[Dependency], entries: &[LockEntry]) -> Vec<LockDrift> {
+    let mut drift = Vec::new();
+    for dependency in dependencies {
+        match entries.iter().find(|e| e.name() == dependency.name()) {
+            None => drift.push(LockDrift::Missing(dependency.name().to_string())),
+            Some(lock) if !lock_satisfies(lock, dependency) => drift.push(LockDrift::Mismatch {
+                name: dependency.name().to_string(),
+                locked: describe_lock(lock),
+                required: describe_dependency(dependency),
+            }),
+            Some(_) => {}
+        }
+    }
+    let reachable = reachable_entries(dependencies, entries.to_vec());
+    for name in reachable.iter().flat_map(|e| e.dependencies()) {
+        let missing = LockDrift::Missing(name.clone());
+        if !entries.iter().any(|e| e.name() == name) && !drift.contains(&missing) {
+            drift.push(missing);
+        }
+    }
+    for entry in entries {
+        if !reachable.iter().any(|e| e.name() == entry.name()) {
+            drift.push(LockDrift::Extraneous(entry.name().to_string()));
+        }
+    }
+    drift
+}
+
+/// Compare the entries of the lockfile with the entries resulting from an installation.
+///
+/// An empty list means that the lockfile doesn't need to be modified.
+pub fn diff_lock_entries(old: &[LockEntry], new: &[LockEntry]) -> Vec<LockDrift> {
+    let mut drift = Vec::new();
+    for entry in new {
+        match old.iter().find(|e| e.name() == entry.name()) {
+            None => drift.push(LockDrift::Missing(entry.name().to_string())),
+            Some(old) if old != entry => drift.push(LockDrift::Mismatch {
+                name: entry.name().to_string(),
+                locked: describe_lock(old),
+                required: describe_lock(entry),
+            }),
+            Some(_) => {}
+        }
+    }
+    for entry in old {
+        if !new.iter().any(|e| e.name() == entry.name()) {
+            drift.push(LockDrift::Extraneous(entry.name().to_string()));
+        }
+    }
+    drift
+}
+
+/// A requirement placed on a package by the project or by another package of the graph.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Requirement {
+    /// The name of the package which declares the requirement, or `None` for the project itself.
+    parent: Option<String>,
+
+    /// The dependency as declared in the parent's config file.
+    dependency: Dependency,
+}
+
+/// A package of the dependency graph.
+#[derive(Debug, Clone, Default)]
+struct Node {
+    /// All the requirements on this package.
+    requirements: Vec<Requirement>,
+
+    /// The lock entry of the currently installed version, if any.
+    lock: Option<LockEntry>,
+}
+
+/// Resolve and install the transitive dependencies of a list of already installed dependencies.
+///
+/// The `installed` lock entries must correspond to the `dependencies`. The config file of each
+/// installed package is read to find its own dependencies, which are then installed into the same
+/// `deps` folder. This is repeated until all packages of the graph are installed.
+///
+/// Each package is installed only once. When several packages depend on the same package, a
+/// single version which satisfies all of the requirements is selected: the installed or locked
+/// version is kept if it is suitable, otherwise the most recent version from the registry which
+/// matches all the version requirements is installed. If no such version exists, an
+/// [`InstallError::VersionConflict`] error is returned with the conflicting chains of
+/// requirements.
+///
+/// Packages which have an entry in `pinned` always keep the version and `dependencies` of that
+/// entry. If it doesn't satisfy all the requirements, an [`InstallError::VersionConflict`] error is
+/// returned instead of selecting another version.
+///
+/// The returned list contains the entries for all packages of the graph, with their `dependencies`
+/// field populated.
+#[allow(clippy::too_many_arguments)]
+pub async fn resolve_transitive(
+    dependencies: &[Dependency],
+    installed: Vec<LockEntry>,
+    locks: &[LockEntry],
+    pinned: &[LockEntry],
+    deps: impl AsRef<Path>,
+    registries: &Registries,
+    options: &InstallOptions,
+    progress: InstallProgress,
+) -> Result<Vec<LockEntry>> {
+    let deps = deps.as_ref();
+    let mut nodes: BTreeMap<String, Node> = BTreeMap::new();
+    for lock in installed {
+        let Some(dep) = dependencies.iter().find(|d| d.name() == lock.name()) else {
+            continue;
+        };
+        nodes.insert(
+            lock.name().to_string(),
+            Node {
+                requirements: vec![Requirement { parent: None, dependency: dep.clone() }],
+                lock: Some(lock),
+            },
+        );
+    }
+    let mut queue: Vec<String> = nodes.keys().cloned().collect();
+    while !queue.is_empty() {
+        let mut dirty: BTreeSet<String> = BTreeSet::new();
+        for name in queue {
+            let node = nodes.get(&name).expect("package should be in the graph");
+            let Some(lock) = &node.lock else {
+                continue;
+            };
+            let dependency = &node.requirements.first().expect("node has a requirement").dependency;
+            let subdependencies = read_subdependencies(dependency, lock, deps).await?;
+            let names = subdependencies.iter().map(|d| d.name().to_string()).collect();
+            if let Some(lock) =
+                &mut nodes.get_mut(&name).expect("package should be in the graph").lock
+            {
+                lock.set_dependencies(names);
+            }
+            // the requirements of a previously installed version of this package are replaced
+            for (other, node) in &mut nodes {
+                let len = node.requirements.len();
+                node.requirements.retain(|r| r.parent.as_ref() != Some(&name));
+                if node.requirements.len() != len {
+                    dirty.insert(other.clone());
+                }
+            }
+            for sub in subdependencies {
+                debug!(dep:% = sub, parent = name; "found transitive dependency requirement");
+                let sub_name = sub.name().to_string();
+                nodes
+                    .entry(sub_name.clone())
+                    .or_default()
+                    .requirements
+                    .push(Requirement { parent: Some(name.clone()), dependency: sub });
+                dirty.insert(sub_name);
+            }
+        }
+        queue = Vec::new();
+        let mut set = JoinSet::new();
+        for name in dirty {
+            let node = nodes.get(&name).expect("package should be in the graph");
+            if node.requirements.is_empty() {
+                continue;
+            }
+            let Some((dependency, lock, version)) =
+                select_version(&nodes, &name, locks, pinned, registries, options.offline).await?
+            else {
+                continue;
+            };
+            if let Some(old) = &node.lock {
+                remove_install_dir(old, deps).await?;
+            }
+            debug!(dep:% = dependency, version:?; "installing package of the dependency graph");
+            set.spawn({
+                let deps = deps.to_path_buf();
+                let registries = registries.clone();
+                let options = options.clone();
+                let progress = progress.clone();
+                async move {
+                    install_dependency(
+                        &dependency,
+                        lock.as_ref(),
+                        deps,
+                        version,
+                        &registries,
+                        true,
+                        &options,
+                        progress,
+                    )
+                    .await
+                }
+            });
+        }
+        if !set.is_empty() {
+            progress.log(format!("Installing {} transitive dependencies", set.len()));
+        }
+        while let Some(res) = set.join_next().await {
+            let lock = res??;
+            let name = lock.name().to_string();
+            nodes.get_mut(&name).expect("package should be in the graph").lock = Some(lock);
+            queue.push(name);
+        }
+    }
+    // packages which are not required anymore (e.g. because their parent's version changed) are
+    // removed
+    let mut entries = Vec::with_capacity(nodes.len());
+    for (name, node) in nodes {
+        let Some(mut lock) = node.lock else {
+            continue;
+        };
+        if node.requirements.is_empty() {
+            debug!(dep = name; "package is not required anymore, removing");
+            remove_install_dir(&lock, deps).await?;
+            continue;
+        }
+        if let Some(pin) = pinned.iter().find(|p| p.name() == name) {
+            lock.set_dependencies(pin.dependencies().to_vec());
+        }
+        entries.push(lock);
+    }
+    debug!("finished resolving transitive dependencies");
+    Ok(entries)
+}
+
+/// Retain only the lock entries which are reachable from the list of dependencies.
+///
+/// The graph is traversed by following the `dependencies` field of each lock entry. This is useful
+/// to remove transitive dependencies which are not needed anymore after a dependency was removed.
+pub fn reachable_entries(dependencies: &[Dependency], entries: Vec<LockEntry>) -> Vec<LockEntry> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut stack: Vec<String> = dependencies.iter().map(|d| d.name().to_string()).collect();
+    while let Some(name) = stack.pop() {
+        if !seen.insert(name.clone()) {
+            continue;
+        }
+        if let Some(entry) = entries.iter().find(|e| e.name() == name) {
+            stack.extend(entry.dependencies().iter().cloned());
+        }
+    }
+    entries.into_iter().filter(|e| seen.contains(e.name())).collect()
+}
+
+/// Read the dependencies declared in the config file of an installed package.
+///
+/// The config file is searched at the package's project root, which is the install folder unless a
+/// `project_root` (or for git dependencies, a `subdir`) was specified for the dependency. If no
+/// config file is found, the package has no soldeer dependencies.
+pub(crate) async fn read_subdependencies(
+    dependency: &Dependency,
+    lock: &LockEntry,
+    deps: &Path,
+) -> Result<Vec<Dependency>> {
+    let path = lock.install_path(deps);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let relative_root =
+        dependency.project_root().or_else(|| dependency.subdir().map(PathBuf::from));
+    let root = get_subdependency_root(path, relative_root.as_ref()).await?;
+    let Some(location) = detect_config_location(&root) else {
+        debug!(dep:% = dependency; "no soldeer config found, no transitive dependencies");
+        return Ok(Vec::new());
+    };
+    let config_path = config_path(&root, location);
+    let (mut subdependencies, warnings) = read_config_deps(&config_path)?;
+    for w in warnings {
+        warn!(dep:% = dependency, warning:% = w; "config warning in dependency");
+    }
+    // path dependencies of the package are relative to its root, but they are installed from the
+    // project root
+    let project_root = deps.parent().unwrap_or(deps);
+    for dep in subdependencies.iter_mut().filter_map(Dependency::as_path_mut) {
+        let path = root.join(&dep.path);
+        dep.path = match canonicalize(&path).await {
+            Ok(path) => relative_path(project_root, path),
+            Err(_) => path,
+        };
+    }
+    Ok(subdependencies)
+}
+
+/// Get the path to the config file for a project root and config location.
+pub(crate) fn config_path(root: impl AsRef<Path>, location: ConfigLocation) -> PathBuf {
+    match location {
+        ConfigLocation::Foundry => root.as_ref().join("foundry.toml"),
+        ConfigLocation::Soldeer => root.as_ref().join("soldeer.toml"),
+    }
+}
+
+/// Select the version to install for a package of the graph.
+///
+/// Returns `None` if the currently installed version satisfies all the requirements. Otherwise,
+/// returns the dependency to install along with the lock entry or the forced version to use.
+///
+/// A pinned package can only use the version of its pinned entry.
+async fn select_version(
+    nodes: &BTreeMap<String, Node>,
+    name: &str,
+    locks: &[LockEntry],
+    pinned: &[LockEntry],
+    registries: &Registries,
+    offline: bool,
+) -> Result<Option<(Dependency, Option<LockEntry>, Option<String>)>> {
+    let node = nodes.get(name).expect("package should be in the graph");
+    let dependency = node.requirements.first().expect("node has a requirement").dependency.clone();
+    if let Some(pin) = pinned.iter().find(|l| l.name() == name) {
+        if !node.requirements.iter().all(|r| lock_satisfies(pin, &r.dependency)) {
+            return Err(conflict_error(nodes, name));
+        }
+        if node.lock.as_ref().is_some_and(|l| l.version() == pin.version()) {
+            debug!(dep = name, version = pin.version(); "pinned version is installed");
+            return Ok(None);
+        }
+        debug!(dep = name, version = pin.version(); "installing pinned version");
+        return Ok(Some((dependency, Some(pin.clone()), None)));
+    }
+    if let Some(lock) = &node.lock &&
+        node.requirements.iter().all(|r| lock_satisfies(lock, &r.dependency))
+    {
+        debug!(dep = name, version = lock.version(); "installed version satisfies all requirements");
+        return Ok(None);
+    }
+    if let Some(lock) = locks.iter().find(|l| l.name() == name) &&
+        node.requirements.iter().all(|r| lock_satisfies(lock, &r.dependency))
+    {
+        debug!(dep = name, version = lock.version(); "locked version satisfies all requirements");
+        return Ok(Some((dependency, Some(lock.clone()), None)));
+    }
+    if node.requirements.len() == 1 {
+        return Ok(Some((dependency, None, None)));
+    }
+
+    // custom sources (git, URL or path) can only provide a single version, which all requirements
+    // must accept
+    if let Some(custom) =
+        node.requirements.iter().find(|r| r.dependency.url().is_some() || r.dependency.is_path())
+    {
+        let version = custom.dependency.version_req();
+        if node.requirements.iter().all(|r| version_satisfies(version, r.dependency.version_req()))
+        {
+            return Ok(Some((custom.dependency.clone(), None, None)));
+        }
+        return Err(conflict_error(nodes, name));
+    }
+
+    let registry = registries.for_dependency(&dependency)?;
+    let version = match get_all_versions_descending(registry, name, offline).await? {
+        Versions::Semver(all_versions) => {
+            all_versions.into_iter().map(|v| v.to_string()).find(|v| {
+                node.requirements.iter().all(|r| version_satisfies(v, r.dependency.version_req()))
+            })
+        }
+        Versions::NonSemver(all_versions) => all_versions
+            .into_iter()
+            .find(|v| node.requirements.iter().all(|r| r.dependency.version_req() == v)),
+    };
+    match version {
+        Some(version) => {
+            debug!(dep = name, version; "found version which satisfies all requirements");
+            let lock = locks.iter().find(|l| l.name() == name && l.version() == version).cloned();
+            Ok(Some((dependency, lock, Some(version))))
+        }
+        None => Err(conflict_error(nodes, name)),
+    }
+}
+
+/// Check whether a lock entry can be used for a dependency requirement.
+fn lock_satisfies(lock: &LockEntry, dependency: &Dependency) -> bool {
+    let source_matches = match (dependency, lock) {
+        (Dependency::Git(dep), LockEntry::Git(lock)) => {
+            dep.git == lock.git && dep.subdir == lock.subdir
+        }
+        (Dependency::Http(dep), LockEntry::Http(lock)) => match &dep.url {
+            Some(url) => url == &lock.url,
+            None => lock.registry.as_deref() == dependency.registry(),
+        },
+        (Dependency::Http(dep), LockEntry::Private(lock)) => {
+            dep.url.is_none() && lock.registry.as_deref() == dependency.registry()
+        }
+        (Dependency::Path(dep), LockEntry::Path(lock)) => dep.path.to_slash_lossy() == lock.path,
+        _ => false,
+    };
+    source_matches && version_satisfies(lock.version(), dependency.version_req())
+}
+
+/// Check whether a version matches a version requirement.
+///
+/// If either of them is not semver-compliant, the strings must be equal.
+pub(crate) fn version_satisfies(version: &str, version_req: &str) -> bool {
+    match (parse_version_req(version_req), Version::parse(version)) {
+        (Some(req), Ok(version)) => req.matches(&version),
+        _ => version == version_req,
+    }
+}
+
+/// Format the chain of packages leading to a requirement, e.g. `a -> b~^4.0`.
+fn requirement_chain(nodes: &BTreeMap<String, Node>, requirement: &Requirement) -> String {
+    let mut parts = vec![requirement.dependency.to_string()];
+    let mut parent = requirement.parent.clone();
+    while let Some(name) = parent {
+        if parts.contains(&name) {
+            break; // cycle
+        }
+        parent =
+            nodes.get(&name).and_then(|n| n.requirements.first()).and_then(|r| r.parent.clone());
+        parts.push(name);
+    }
+    parts.reverse();
+    parts.join(" -> ")
+}
+
+/// Create an error describing the conflicting requirements of a package.
+fn conflict_error(nodes: &BTreeMap<String, Node>, name: &str) -> InstallError {
+    let node = nodes.get(name).expect("package should be in the graph");
+    InstallError::VersionConflict {
+        dependency: name.to_string(),
+        requirements: node
+            .requirements
+            .iter()
+            .map(|r| requirement_chain(nodes, r))
+            .collect::<Vec<_>>()
+            .join(" vs "),
+    }
+}
+
+/// Create a dependency which corresponds exactly to a lock entry.
+///
+/// This is used for transitive dependencies, which are not declared in the project's config file.
+pub(crate) fn dependency_from_lock(lock: &LockEntry) -> Dependency {
+    match lock {
+        LockEntry::Http(lock) => HttpDependency::builder()
+            .name(&lock.name)
+            .version_req(&lock.version)
+            .url(&lock.url)
+            .build()
+            .into(),
+        LockEntry::Git(lock) => GitDependency::builder()
+            .name(&lock.name)
+            .version_req(&lock.version)
+            .git(&lock.git)
+            .identifier(GitIdentifier::from_rev(&lock.rev))
+            .maybe_subdir(lock.subdir.clone())
+            .build()
+            .into(),
+        LockEntry::Private(lock) => HttpDependency::builder()
+            .name(&lock.name)
+            .version_req(&lock.version)
+            .maybe_registry(lock.registry.clone())
+            .build()
+            .into(),
+        LockEntry::Path(lock) => PathDependency::builder()
+            .name(&lock.name)
+            .version_req(&lock.version)
+            .path(&lock.path)
+            .build()
+            .into(),
+    }
+}
+
+/// Format the version and source of a lock entry for error messages.
+fn describe_lock(lock: &LockEntry) -> String {
+    match lock {
+        LockEntry::Http(lock) => {
+            format!("{} from {} (checksum {})", lock.version, lock.url, lock.checksum)
+        }
+        LockEntry::Git(lock) => match &lock.subdir {
+            Some(subdir) => {
+                format!("{} from {} (rev {}, subdir {subdir})", lock.version, lock.git, lock.rev)
+            }
+            None => format!("{} from {} (rev {})", lock.version, lock.git, lock.rev),
+        },
+        LockEntry::Private(lock) => match &lock.registry {
+            Some(registry) => {
+                format!("{} from registry {registry} (checksum {})", lock.version, lock.checksum)
+            }
+            None => format!("{} from the registry (checksum {})", lock.version, lock.checksum),
+        },
+        LockEntry::Path(lock) => format!("{} from path {}", lock.version, lock.path),
+    }
+}
+
+/// Format the version requirement and source of a dependency for error messages.
+fn describe_dependency(dependency: &Dependency) -> String {
+    if let Dependency::Path(dep) = dependency {
+        return format!("{} from path {}", dep.version_req, dep.path.to_slash_lossy());
+    }
+    if let Some(subdir) = dependency.subdir() {
+        return format!(
+            "{} from {} (subdir {subdir})",
+            dependency.version_req(),
+            dependency.url().map(String::as_str).unwrap_or_default()
+        );
+    }
+    match (dependency.url(), dependency.registry()) {
+        (Some(url), _) => format!("{} from {url}", dependency.version_req()),
+        (None, Some(registry)) => format!("{} from registry {registry}", dependency.version_req()),
+        (None, None) => format!("{} from the registry", dependency.version_req()),
+    }
+}
+
+/// Remove the install folder of a package, if it exists.
+async fn remove_install_dir(lock: &LockEntry, deps: &Path) -> Result<()> {
+    let path = lock.install_path(deps);
+    if fs::metadata(&path).await.is_ok() {
+        fs::remove_dir_all(&path).await.map_err(|e| InstallError::IOError { path, source: e })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{GitDependency, HttpDependency},
+        lock::{GitLockEntry, HttpLockEntry},
+    };
+
+    fn entry(name: &str, dependencies: &[&str]) -> LockEntry {
+        HttpLockEntry::builder()
+            .name(name)
+            .version("1.0.0")
+            .url("https://example.com/zip.zip")
+            .checksum("123")
+            .integrity("456")
+            .dependencies(dependencies.iter().map(ToString::to_string).collect())
+            .build()
+            .into()
+    }
+
+    #[test]
+    fn test_reachable_entries() {
+        let deps: Vec<Dependency> =
+            vec![HttpDependency::builder().name("a").version_req("1.0.0").build().into()];
+        let entries =
+            vec![entry("a", &["b"]), entry("b", &["c"]), entry("c", &[]), entry("d", &["c"])];
+        let res = reachable_entries(&deps, entries);
+        let names: Vec<_> = res.iter().map(|e| e.name()).collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_version_satisfies() {
+        assert!(version_satisfies("1.2.0", "^1.0"));
+        assert!(version_satisfies("1.2.0", "1.2.0"));
+        assert!(!version_satisfies("1.2.0", "1.0.0"));
+        assert!(!version_satisfies("2.0.0", "^1.0"));
+        assert!(version_satisfies("v1", "v1"));
+        assert!(!version_satisfies("v1", "^1.0"));
+    }
+
+    #[test]
+    fn test_lock_satisfies() {
+        let lock = entry("a", &[]);
+        let dep: Dependency =
+            HttpDependency::builder().name("a").version_req("^1.0").build().into();
+        assert!(lock_satisfies(&lock, &dep));
+        let dep: Dependency = HttpDependency::builder()
+            .name("a")
+            .version_req("1.0.0")
+            .url("https://example.com/other.zip")
+            .build()
+            .into();
+        assert!(!lock_satisfies(&lock, &dep));
+        let dep: Dependency = GitDependency::builder()
+            .name("a")
+            .version_req("1.0.0")
+            .git("https://example.com/a.git")
+            .build()
+            .into();
+        assert!(!lock_satisfies(&lock, &dep));
+        let lock: LockEntry = GitLockEntry::builder()
+            .name("a")
+            .version("1.0.0")
+            .git("https://example.com/a.git")
+            .rev("abc")
+            .subdir("contracts")
+            .build()
+            .into();
+        assert!(!lock_satisfies(&lock, &dep));
+        let dep: Dependency = GitDependency::builder()
+            .name("a")
+            .version_req("1.0.0")
+            .git("https://example.com/a.git")
+            .subdir("contracts")
+            .build()
+            .into();
+        assert!(lock_satisfies(&lock, &dep));
+    }
+
+    #[test]
+    fn test_conflict_error() {
+        let req = |parent: Option<&str>, version_req: &str| Requirement {
+            parent: parent.map(ToString::to_string),
+            dependency: HttpDependency::builder().name("b").version_req(version_req).build().into(),
+        };
+        let mut nodes = BTreeMap::new();
+        nodes.insert(
+            "a".to_string(),
+            Node {
+                requirements: vec![Requirement {
+                    parent: None,
+                    dependency: HttpDependency::builder()
+                        .name("a")
+                        .version_req("1.0.0")
+                        .build()
+                        .into(),
+                }],
+                lock: None,
+            },
+        );
+        nodes.insert(
+            "c".to_string(),
+            Node {
+                requirements: vec![Requirement {
+                    parent: Some("a".to_string()),
+                    dependency: HttpDependency::builder()
+                        .name("c")
+                        .version_req("1.0.0")
+                        .build()
+                        .into(),
+                }],
+                lock: None,
+            },
+        );
+        nodes.insert(
+            "b".to_string(),
+            Node { requirements: vec![req(Some("a"), "^4.0"), req(Some("c"), "^5.0")], lock: None },
+        );
+        let err = conflict_error(&nodes, "b");
+        assert_eq!(
+            err.to_string(),
+            "no version of b satisfies all requirements: a -> b~^4.0 vs a -> c -> b~^5.0"
+        );
+    }
+
+    #[test]
+    fn test_check_lockfile_sync() {
+        let deps: Vec<Dependency> = vec![
+            HttpDependency::builder().name("a").version_req("^1.0").build().into(),
+            HttpDependency::builder().name("b").version_req("^2.0").build().into(),
+            HttpDependency::builder().name("e").version_req("1.0.0").build().into(),
+        ];
+        let entries = vec![entry("a", &["c"]), entry("b", &[]), entry("e", &[]), entry("d", &[])];
+        let drift = check_lockfile_sync(&deps, &entries);
+        assert_eq!(
+            drift,
+            vec![
+                LockDrift::Mismatch {
+                    name: "b".to_string(),
+                    locked: "1.0.0 from https://example.com/zip.zip (checksum 123)".to_string(),
+                    required: "^2.0 from the registry".to_string()
+                },
+                LockDrift::Missing("c".to_string()),
+                LockDrift::Extraneous("d".to_string()),
+            ]
+        );
+        let entries = vec![entry("a", &[]), entry("e", &[])];
+        assert_eq!(
+            check_lockfile_sync(&deps[..1], &entries),
+            vec![LockDrift::Extraneous("e".to_string())]
+        );
+        assert!(check_lockfile_sync(&deps[..1], &entries[..1]).is_empty());
+    }
+
+    #[test]
+    fn test_diff_lock_entries() {
+        let old = vec![entry("a", &[]), entry("b", &[])];
+        let new = vec![entry("a", &["c"]), entry("c", &[])];
+        let drift = diff_lock_entries(&old, &new);
+        assert_eq!(drift.len(), 3);
+        assert!(matches!(&drift[0], LockDrift::Mismatch { name, .. } if name == "a"));
+        assert_eq!(drift[1], LockDrift::Missing("c".to_string()));
+        assert_eq!(drift[2], LockDrift::Extraneous("b".to_string()));
+        assert!(diff_lock_entries(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_reachable_entries_cycle() {
+        let deps: Vec<Dependency> =
+            vec![HttpDependency::builder().name("a").version_req("1.0.0").build().into()];
+        let entries = vec![entry("a", &["b"]), entry("b", &["a"])];
+        let res = reachable_entries(&deps, entries);
+        assert_eq!(res.len(), 2);
+    }
+}
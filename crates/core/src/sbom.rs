@@ -0,0 +1,635 @@
+//! Software bill of materials (SBOM) of the locked dependencies, as exported by `soldeer sbom`.
+//!
+//! Each lockfile entry is turned into a [`Component`] with its package URL
+//! ([purl](https://github.com/package-url/purl-spec)), download location, hashes and license. The
+//! components can then be exported as a [CycloneDX](https://cyclonedx.org) 1.5 or an
+//! [SPDX](https://spdx.dev) 2.3 JSON document.
+use crate::{
+    audit::normalize_git_url,
+    config::Dependency,
+    graph::{PackageSource, build_graph, package_from_lock},
+    license::detect_license,
+    lock::LockEntry,
+    registry::{Registries, Registry, get_project},
+    resolver::dependency_from_lock,
+};
+use chrono::{SecondsFormat, Utc};
+use log::{debug, warn};
+use serde_json::{Value, json};
+use std::path::Path;
+
+/// The name and version of the tool which generates the documents.
+const TOOL_NAME: &str = "soldeer";
+const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A component of the SBOM, which corresponds to a lockfile entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, bon::Builder)]
+#[builder(on(String, into))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Component {
+    /// The name of the package.
+    pub name: String,
+
+    /// The locked version of the package.
+    pub version: String,
+
+    /// Where the package comes from.
+    pub source: PackageSource,
+
+    /// The package URL, which uniquely identifies the component.
+    pub purl: String,
+
+    /// The URL of the archive or git repository, if the package was downloaded.
+    pub download_url: Option<String>,
+
+    /// The locked commit hash, for git dependencies.
+    pub rev: Option<String>,
+
+    /// The SHA-256 checksum of the archive.
+    pub checksum: Option<String>,
+
+    /// The integrity hash of the extracted archive.
+    pub integrity: Option<String>,
+
+    /// The SPDX license expression of the package, if known.
+    pub license: Option<String>,
+
+    /// The names of the packages this package depends on.
+    #[builder(default)]
+    pub dependencies: Vec<String>,
+}
+
+/// Create the components of the SBOM from the lockfile entries.
+///
+/// The source of each package is the same as in the dependency graph (see
+/// [`crate::graph::build_graph`]), which uses the dependencies declared by the project and by the
+/// installed packages to tell registry packages apart from custom URLs. The license is detected
+/// from the license file of the installed package if possible. Otherwise, the project is retrieved
+/// from the registry for registry packages (unless in offline mode).
+pub async fn components(
+    dependencies: &[Dependency],
+    entries: &[LockEntry],
+    deps: impl AsRef<Path>,
+    registries: &Registries,
+    offline: bool,
+) -> Vec<Component> {
+    let deps = deps.as_ref();
+    let graph = build_graph(dependencies, entries, deps).await.unwrap_or_else(|err| {
+        warn!(err:err; "could not build the dependency graph, only using the declared dependencies");
+        Default::default()
+    });
+    let mut components = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let source = match graph.packages.iter().find(|p| p.id == entry.name()) {
+            Some(package) => package.source,
+            None => lock_source(dependencies, entry, deps),
+        };
+        let mut component = component_from_lock(source, entry, registries);
+        component.license = detect_license(entry.install_path(deps));
+        if component.license.is_none() &&
+            matches!(component.source, PackageSource::Registry | PackageSource::Private) &&
+            !offline
+        {
+            component.license = registry_license(registries, entry).await;
+        }
+        components.push(component);
+    }
+    components
+}
+
+/// Get the source of a lock entry which is not part of the dependency graph.
+///
+/// The declared dependency is used if any, otherwise the dependency is recreated from the lock
+/// entry, like for the graph.
+fn lock_source(dependencies: &[Dependency], lock: &LockEntry, deps: &Path) -> PackageSource {
+    let dependency = dependencies
+        .iter()
+        .find(|d| d.name() == lock.name())
+        .cloned()
+        .unwrap_or_else(|| dependency_from_lock(lock));
+    package_from_lock(lock.name(), &dependency, lock, &lock.install_path(deps)).source
+}
+
+/// Create a component from a lock entry and the source of the package, without license.
+///
+/// The registries are used for the `repository_url` qualifier of the purl of registry packages.
+pub fn component_from_lock(
+    source: PackageSource,
+    entry: &LockEntry,
+    registries: &Registries,
+) -> Component {
+    let name = entry.name().to_string();
+    let version = entry.version().to_string();
+    let dependencies = entry.dependencies().to_vec();
+    match entry {
+        LockEntry::Http(lock) if source == PackageSource::Registry => Component {
+            purl: soldeer_purl(&name, &version, repository_url(registries, entry).as_deref()),
+            name,
+            version,
+            source: PackageSource::Registry,
+            download_url: Some(lock.url.clone()),
+            rev: None,
+            checksum: Some(lock.checksum.clone()),
+            integrity: Some(lock.integrity.clone()),
+            license: None,
+            dependencies,
+        },
+        LockEntry::Http(lock) => Component {
+            purl: format!(
+                "pkg:generic/{}@{}?checksum=sha256:{}&download_url={}",
+                encode(&name),
+                encode(&version),
+                lock.checksum,
+                encode(&lock.url)
+            ),
+            name,
+            version,
+            source: PackageSource::Http,
+            download_url: Some(lock.url.clone()),
+            rev: None,
+            checksum: Some(lock.checksum.clone()),
+            integrity: Some(lock.integrity.clone()),
+            license: None,
+            dependencies,
+        },
+        LockEntry::Git(lock) => Component {
+            purl: git_purl(&name, &lock.git, &lock.rev, lock.subdir.as_deref()),
+            name,
+            version,
+            source: PackageSource::Git,
+            download_url: Some(lock.git.clone()),
+            rev: Some(lock.rev.clone()),
+            checksum: None,
+            integrity: None,
+            license: None,
+            dependencies,
+        },
+        LockEntry::Private(lock) => Component {
+            purl: soldeer_purl(&name, &version, repository_url(registries, entry).as_deref()),
+            name,
+            version,
+            source: PackageSource::Private,
+            download_url: None,
+            rev: None,
+            checksum: Some(lock.checksum.clone()),
+            integrity: Some(lock.integrity.clone()),
+            license: None,
+            dependencies,
+        },
+        LockEntry::Path(_) => Component {
+            purl: format!("pkg:generic/{}@{}", encode(&name), encode(&version)),
+            name,
+            version,
+            source: PackageSource::Path,
+            download_url: None,
+            rev: None,
+            checksum: None,
+            integrity: None,
+            license: None,
+            dependencies,
+        },
+    }
+}
+
+/// Create a CycloneDX 1.5 JSON document from the components.
+///
+/// The project is the root component, which depends on the declared dependencies.
+pub fn cyclonedx_document(
+    project: &str,
+    dependencies: &[Dependency],
+    components: &[Component],
+) -> Value {
+    let project_ref = format!("project:{project}");
+    let mut graph = vec![json!({
+        "ref": project_ref,
+        "dependsOn": direct_dependencies(dependencies, components)
+            .map(|c| c.purl.clone())
+            .collect::<Vec<_>>(),
+    })];
+    for component in components {
+        graph.push(json!({
+            "ref": component.purl,
+            "dependsOn": children(component, components).map(|c| c.purl.clone()).collect::<Vec<_>>(),
+        }));
+    }
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", uuid::Uuid::new_v4()),
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp(),
+            "tools": {
+                "components": [{ "type": "application", "name": TOOL_NAME, "version": TOOL_VERSION }],
+            },
+            "component": { "type": "application", "bom-ref": project_ref, "name": project },
+        },
+        "components": components.iter().map(cyclonedx_component).collect::<Vec<_>>(),
+        "dependencies": graph,
+    })
+}
+
+/// Create a CycloneDX component.
+fn cyclonedx_component(component: &Component) -> Value {
+    let mut value = json!({
+        "type": "library",
+        "bom-ref": component.purl,
+        "name": component.name,
+        "version": component.rev.as_ref().unwrap_or(&component.version),
+        "purl": component.purl,
+    });
+    if let Some(checksum) = &component.checksum {
+        value["hashes"] = json!([{ "alg": "SHA-256", "content": checksum }]);
+    }
+    if let Some(license) = &component.license {
+        value["licenses"] = json!([{ "expression": license }]);
+    }
+    if let Some(url) = &component.download_url {
+        let kind = if component.source == PackageSource::Git { "vcs" } else { "distribution" };
+        value["externalReferences"] = json!([{ "type": kind, "url": url }]);
+    }
+    let mut properties =
+        vec![json!({ "name": "soldeer:source", "value": component.source.to_string() })];
+    if component.rev.is_some() {
+        properties.push(json!({ "name": "soldeer:version", "value": component.version }));
+    }
+    if let Some(integrity) = &component.integrity {
+        properties.push(json!({ "name": "soldeer:integrity", "value": integrity }));
+    }
+    value["properties"] = Value::Array(properties);
+    value
+}
+
+/// Create an SPDX 2.3 JSON document from the components.
+///
+/// The project is the package described by the document, which depends on the declared
+/// dependencies.
+pub fn spdx_document(
+    project: &str,
+    dependencies: &[Dependency],
+    components: &[Component],
+) -> Value {
+    let project_id = "SPDXRef-Project";
+    let mut packages = vec![json!({
+        "SPDXID": project_id,
+        "name": project,
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+        "licenseConcluded": "NOASSERTION",
+        "licenseDeclared": "NOASSERTION",
+        "copyrightText": "NOASSERTION",
+    })];
+    packages.extend(components.iter().map(spdx_package));
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": project_id,
+    })];
+    for component in direct_dependencies(dependencies, components) {
+        relationships.push(json!({
+            "spdxElementId": project_id,
+            "relationshipType": "DEPENDS_ON",
+            "relatedSpdxElement": spdx_id(component),
+        }));
+    }
+    for component in components {
+        for child in children(component, components) {
+            relationships.push(json!({
+                "spdxElementId": spdx_id(component),
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": spdx_id(child),
+            }));
+        }
+    }
+    let id = uuid::Uuid::new_v4();
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": format!("{project}-sbom"),
+        "documentNamespace": format!("https://soldeer.xyz/spdx/{}-{id}", encode(project)),
+        "creationInfo": {
+            "created": timestamp(),
+            "creators": [format!("Tool: {TOOL_NAME}-{TOOL_VERSION}")],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+/// Create an SPDX package.
+fn spdx_package(component: &Component) -> Value {
+    let download_location = match (&component.download_url, &component.rev) {
+        (Some(url), Some(rev)) => format!("git+{url}@{rev}"),
+        (Some(url), None) => url.clone(),
+        (None, _) => "NOASSERTION".to_string(),
+    };
+    let license = component.license.as_deref().unwrap_or("NOASSERTION");
+    let mut value = json!({
+        "SPDXID": spdx_id(component),
+        "name": component.name,
+        "versionInfo": component.rev.as_ref().unwrap_or(&component.version),
+        "downloadLocation": download_location,
+        "filesAnalyzed": false,
+        "licenseConcluded": license,
+        "licenseDeclared": license,
+        "copyrightText": "NOASSERTION",
+        "externalRefs": [{
+            "referenceCategory": "PACKAGE-MANAGER",
+            "referenceType": "purl",
+            "referenceLocator": component.purl,
+        }],
+    });
+    if let Some(checksum) = &component.checksum {
+        value["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": checksum }]);
+    }
+    if let Some(integrity) = &component.integrity {
+        value["comment"] = json!(format!("soldeer integrity hash: {integrity}"));
+    }
+    value
+}
+
+/// Get the SPDX identifier of a component.
+///
+/// Only letters, numbers, `.` and `-` are allowed in identifiers. Names are unique in the lockfile.
+fn spdx_id(component: &Component) -> String {
+    let name: String = component
+        .name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '-' })
+        .collect();
+    format!("SPDXRef-Package-{name}")
+}
+
+/// The components which are declared in the config file.
+fn direct_dependencies<'a>(
+    dependencies: &'a [Dependency],
+    components: &'a [Component],
+) -> impl Iterator<Item = &'a Component> {
+    components.iter().filter(|c| dependencies.iter().any(|d| d.name() == c.name))
+}
+
+/// The components a component depends on.
+fn children<'a>(
+    component: &'a Component,
+    components: &'a [Component],
+) -> impl Iterator<Item = &'a Component> {
+    components.iter().filter(|c| component.dependencies.contains(&c.name))
+}
+
+/// Get the license of a registry package from its project.
+async fn registry_license(registries: &Registries, lock: &LockEntry) -> Option<String> {
+    let registry = match registries.get(lock.registry()) {
+        Ok(registry) => registry,
+        Err(err) => {
+            warn!(name = lock.name(), err:err; "could not get the registry of the package");
+            return None;
+        }
+    };
+    match get_project(registry, lock.name()).await {
+        Ok(project) => project.license,
+        Err(err) => {
+            debug!(name = lock.name(), err:err; "could not retrieve the project from the registry");
+            None
+        }
+    }
+}
+
+/// The value of the `repository_url` qualifier of the purl of a registry package, which is the URL
+/// of its registry.
+///
+/// Packages of the official registry don't have the qualifier.
+fn repository_url(registries: &Registries, lock: &LockEntry) -> Option<String> {
+    match registries.get(lock.registry()) {
+        Ok(registry) if registry.url == Registry::soldeer_url() => None,
+        Ok(registry) => Some(registry.url.to_string()),
+        Err(err) => {
+            warn!(name = lock.name(), err:err; "could not get the registry of the package");
+            None
+        }
+    }
+}
+
+/// Create the purl of a package from a Soldeer registry.
+fn soldeer_purl(name: &str, version: &str, repository_url: Option<&str>) -> String {
+    let purl = format!("pkg:soldeer/{}@{}", encode(name), encode(version));
+    match repository_url {
+        Some(url) => format!("{purl}?repository_url={}", encode(url)),
+        None => purl,
+    }
+}
+
+/// Create the purl of a git dependency.
+///
+/// Repositories hosted on GitHub, GitLab or Bitbucket use the corresponding purl type, other
+/// repositories use the `generic` type with a `vcs_url` qualifier.
+fn git_purl(name: &str, url: &str, rev: &str, subdir: Option<&str>) -> String {
+    let normalized = normalize_git_url(url);
+    let hosted = normalized.split_once('/').and_then(|(host, path)| {
+        let kind = match host {
+            "github.com" => "github",
+            "gitlab.com" => "gitlab",
+            "bitbucket.org" => "bitbucket",
+            _ => return None,
+        };
+        let path = path.split('/').map(encode).collect::<Vec<_>>().join("/");
+        Some(format!("pkg:{kind}/{path}@{rev}"))
+    });
+    let purl = hosted.unwrap_or_else(|| {
+        format!(
+            "pkg:generic/{}@{rev}?vcs_url={}",
+            encode(name),
+            encode(&format!("git+{url}@{rev}"))
+        )
+    });
+    match subdir {
+        Some(subdir) => format!("{purl}#{}", subdir.trim_matches('/')),
+        None => purl,
+    }
+}
+
+/// Percent-encode a component of a purl.
+fn encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'.' | b'-' | b'_' | b'~') {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{b:02X}"));
+        }
+    }
+    encoded
+}
+
+/// The current time, in the format expected by CycloneDX and SPDX.
+fn timestamp() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{GitDependency, HttpDependency},
+        lock::{GitLockEntry, HttpLockEntry},
+        registry::RegistryAuth,
+    };
+    use std::fs;
+    use testdir::testdir;
+
+    fn entries() -> Vec<LockEntry> {
+        vec![
+            HttpLockEntry::builder()
+                .name("@scope-lib")
+                .version("1.2.3")
+                .url("https://example.com/lib.zip")
+                .checksum("abcd")
+                .integrity("ef01")
+                .dependencies(vec!["repo".to_string()])
+                .build()
+                .into(),
+            HttpLockEntry::builder()
+                .name("custom")
+                .version("1.0.0")
+                .url("https://example.com/custom.zip")
+                .checksum("1234")
+                .integrity("5678")
+                .build()
+                .into(),
+            GitLockEntry::builder()
+                .name("repo")
+                .version("main")
+                .git("git@github.com:Org/Repo.git")
+                .rev("0123abc")
+                .subdir("src")
+                .build()
+                .into(),
+        ]
+    }
+
+    fn dependencies() -> Vec<Dependency> {
+        vec![
+            HttpDependency::builder().name("@scope-lib").version_req("^1.0.0").build().into(),
+            HttpDependency::builder()
+                .name("custom")
+                .version_req("1.0.0")
+                .url("https://example.com/custom.zip")
+                .build()
+                .into(),
+            GitDependency::builder()
+                .name("repo")
+                .version_req("main")
+                .git("git@github.com:Org/Repo.git")
+                .build()
+                .into(),
+        ]
+    }
+
+    fn to_components(deps: &[Dependency], entries: &[LockEntry]) -> Vec<Component> {
+        let registries = Registries::new([]);
+        entries
+            .iter()
+            .map(|e| component_from_lock(lock_source(deps, e, Path::new("")), e, &registries))
+            .collect()
+    }
+
+    #[test]
+    fn test_purls() {
+        let deps = dependencies();
+        let purls: Vec<_> = to_components(&deps, &entries()).into_iter().map(|c| c.purl).collect();
+        assert_eq!(
+            purls,
+            vec![
+                "pkg:soldeer/%40scope-lib@1.2.3",
+                "pkg:generic/custom@1.0.0?checksum=sha256:1234&download_url=https%3A%2F%2Fexample.com%2Fcustom.zip",
+                "pkg:github/org/repo@0123abc#src",
+            ]
+        );
+        // the purl of a package from another registry has the URL of the registry
+        let registries = Registries::new([Registry::new(
+            "internal",
+            "https://registry.example.com/soldeer".parse().unwrap(),
+            RegistryAuth::None,
+        )]);
+        let entry: LockEntry = HttpLockEntry::builder()
+            .name("lib")
+            .version("1.0.0")
+            .url("https://registry.example.com/files/lib/1.0.0.zip")
+            .checksum("abcd")
+            .integrity("ef01")
+            .registry("internal")
+            .build()
+            .into();
+        assert_eq!(
+            component_from_lock(PackageSource::Registry, &entry, &registries).purl,
+            "pkg:soldeer/lib@1.0.0?repository_url=https%3A%2F%2Fregistry.example.com%2Fsoldeer"
+        );
+        assert_eq!(
+            git_purl("lib", "https://git.example.com/lib.git", "abc", None),
+            "pkg:generic/lib@abc?vcs_url=git%2Bhttps%3A%2F%2Fgit.example.com%2Flib.git%40abc"
+        );
+    }
+
+    #[test]
+    fn test_documents() {
+        let deps = dependencies();
+        let components = to_components(&deps, &entries());
+
+        let bom = cyclonedx_document("my-project", &deps, &components);
+        assert_eq!(bom["bomFormat"], "CycloneDX");
+        assert_eq!(bom["components"].as_array().unwrap().len(), 3);
+        assert_eq!(bom["components"][0]["hashes"][0]["content"], "abcd");
+        assert_eq!(bom["components"][0]["externalReferences"][0]["type"], "distribution");
+        assert_eq!(bom["components"][2]["version"], "0123abc");
+        assert_eq!(bom["components"][2]["externalReferences"][0]["type"], "vcs");
+        assert_eq!(bom["dependencies"][0]["dependsOn"].as_array().unwrap().len(), 3);
+        assert_eq!(bom["dependencies"][1]["dependsOn"][0], "pkg:github/org/repo@0123abc#src");
+
+        let spdx = spdx_document("my-project", &deps, &components);
+        assert_eq!(spdx["spdxVersion"], "SPDX-2.3");
+        assert_eq!(spdx["packages"].as_array().unwrap().len(), 4);
+        assert_eq!(spdx["packages"][1]["SPDXID"], "SPDXRef-Package--scope-lib");
+        assert_eq!(spdx["packages"][1]["checksums"][0]["checksumValue"], "abcd");
+        assert_eq!(
+            spdx["packages"][3]["downloadLocation"],
+            "git+git@github.com:Org/Repo.git@0123abc"
+        );
+        // describes + 3 direct dependencies + 1 transitive dependency
+        assert_eq!(spdx["relationships"].as_array().unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_components_transitive_url() {
+        let deps_dir = testdir!().join("dependencies");
+        // `lib` declares `custom`, which is downloaded from a custom URL
+        fs::create_dir_all(deps_dir.join("@scope-lib-1.2.3")).unwrap();
+        fs::write(
+            deps_dir.join("@scope-lib-1.2.3/soldeer.toml"),
+            "[dependencies]\ncustom = { version = \"1.0.0\", url = \"https://example.com/custom.zip\" }\n",
+        )
+        .unwrap();
+        fs::write(deps_dir.join("@scope-lib-1.2.3/LICENSE"), "MIT License").unwrap();
+        fs::create_dir_all(deps_dir.join("custom-1.0.0")).unwrap();
+        let mut entries = entries();
+        entries.truncate(2);
+        entries[0] = HttpLockEntry::builder()
+            .name("@scope-lib")
+            .version("1.2.3")
+            .url("https://example.com/lib.zip")
+            .checksum("abcd")
+            .integrity("ef01")
+            .dependencies(vec!["custom".to_string()])
+            .build()
+            .into();
+        let deps = vec![dependencies().remove(0)];
+        let components = components(&deps, &entries, &deps_dir, &Registries::new([]), false).await;
+        assert_eq!(components[0].source, PackageSource::Registry);
+        assert_eq!(components[1].source, PackageSource::Http);
+        assert!(
+            components[1].purl.starts_with("pkg:generic/custom@1.0.0"),
+            "{}",
+            components[1].purl
+        );
+    }
+}
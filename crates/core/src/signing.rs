@@ -0,0 +1,254 @@
+//! Signing of published packages and signature verification on install.
+//!
+//! A publisher can sign the archive of a package when pushing it to the registry (see
+//! [`push_version`][crate::push::push_version]). The signature is a detached ed25519 signature
+//! over the SHA256 hash of the archive, which is also the `checksum` field of the lockfile. The
+//! registry exposes the signature and the publisher's public key with the revision, and both are
+//! recorded in the lockfile.
+//!
+//! Public keys and signatures are hex-encoded. The signing key is a hex-encoded 32-byte seed stored
+//! in `~/.soldeer/signing_key` by default (see [`signing_key_path`]).
+//!
+//! During install, the signature of each HTTP dependency is checked against the [`TrustPolicy`]
+//! declared in the `[trust]` table of the config file:
+//!
+//! ```toml
+//! [trust]
+//! require_signatures = true
+//! keys = ["<hex-encoded public key>"]
+//! ```
+//!
+//! When signatures are required, dependencies which are unsigned, which have an invalid signature
+//! or which were signed with a key that is not in the list are rejected. Git and path dependencies
+//! can't be signed, so a warning is logged for each of them and they are installed without a check.
+//!
+//! The policy of the root project applies to all the dependencies, including the transitive ones
+//! (see [`InstallOptions`][crate::install::InstallOptions]).
+use crate::errors::SigningError;
+use ed25519_dalek::{Signature, Signer as _, Verifier as _, VerifyingKey};
+use log::{debug, warn};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+pub type Result<T> = std::result::Result<T, SigningError>;
+
+/// The signature of a package archive.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, bon::Builder)]
+#[builder(on(String, into))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct PackageSignature {
+    /// The hex-encoded ed25519 public key of the publisher.
+    pub public_key: String,
+
+    /// The hex-encoded ed25519 signature over the SHA256 hash of the archive.
+    pub signature: String,
+}
+
+impl PackageSignature {
+    /// Verify the signature against the checksum (hex-encoded SHA256 hash) of the archive.
+    pub fn verify(&self, checksum: &str) -> Result<()> {
+        let key = parse_public_key(&self.public_key)?;
+        let signature: [u8; 64] = const_hex::decode_to_array(&self.signature)
+            .map_err(|_| SigningError::SignatureMismatch)?;
+        let digest = checksum_bytes(checksum)?;
+        key.verify(&digest, &Signature::from_bytes(&signature))
+            .map_err(|_| SigningError::SignatureMismatch)
+    }
+}
+
+/// A signing key for packages.
+#[derive(Debug, Clone)]
+pub struct SigningKey(ed25519_dalek::SigningKey);
+
+impl SigningKey {
+    /// Read a signing key from a file, or generate a new one if the file doesn't exist.
+    ///
+    /// The second element of the returned tuple indicates whether the key was generated.
+    pub fn load_or_generate(path: impl AsRef<Path>) -> Result<(Self, bool)> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(contents) => {
+                let seed: [u8; 32] = const_hex::decode_to_array(contents.trim())
+                    .map_err(|_| SigningError::InvalidSigningKey(path.to_path_buf()))?;
+                debug!(path:?; "read signing key");
+                Ok((Self(ed25519_dalek::SigningKey::from_bytes(&seed)), false))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let mut seed = [0u8; 32];
+                getrandom::fill(&mut seed).map_err(|e| SigningError::IOError {
+                    path: path.to_path_buf(),
+                    source: io::Error::other(e.to_string()),
+                })?;
+                write_secret(path, &const_hex::encode(seed))?;
+                debug!(path:?; "generated new signing key");
+                Ok((Self(ed25519_dalek::SigningKey::from_bytes(&seed)), true))
+            }
+            Err(e) => Err(SigningError::IOError { path: path.to_path_buf(), source: e }),
+        }
+    }
+
+    /// The hex-encoded public key.
+    pub fn public_key(&self) -> String {
+        const_hex::encode(self.0.verifying_key().as_bytes())
+    }
+
+    /// Sign an archive given its checksum (hex-encoded SHA256 hash).
+    pub fn sign(&self, checksum: &str) -> Result<PackageSignature> {
+        let digest = checksum_bytes(checksum)?;
+        let signature = self.0.sign(&digest);
+        Ok(PackageSignature {
+            public_key: self.public_key(),
+            signature: const_hex::encode(signature.to_bytes()),
+        })
+    }
+}
+
+/// The policy for package signatures, from the `[trust]` table of the config file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, bon::Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct TrustPolicy {
+    /// Whether to reject dependencies which are not signed by one of the trusted keys.
+    #[builder(default)]
+    pub require_signatures: bool,
+
+    /// The hex-encoded public keys of the trusted publishers.
+    #[builder(default)]
+    pub keys: Vec<String>,
+}
+
+impl TrustPolicy {
+    /// Check the signature of a dependency's archive against the policy.
+    ///
+    /// If signatures are not required, an invalid signature only produces a warning.
+    pub fn check(
+        &self,
+        dependency: &str,
+        checksum: &str,
+        signature: Option<&PackageSignature>,
+    ) -> Result<()> {
+        let Some(signature) = signature else {
+            if self.require_signatures {
+                return Err(SigningError::Unsigned(dependency.to_string()));
+            }
+            return Ok(());
+        };
+        if let Err(err) = signature.verify(checksum) {
+            if self.require_signatures {
+                return Err(SigningError::InvalidSignature(dependency.to_string()));
+            }
+            warn!(dep = dependency, err:err; "dependency has an invalid signature");
+            return Ok(());
+        }
+        if self.require_signatures &&
+            !self.keys.iter().any(|k| k.eq_ignore_ascii_case(&signature.public_key))
+        {
+            return Err(SigningError::UntrustedKey {
+                dependency: dependency.to_string(),
+                key: signature.public_key.clone(),
+            });
+        }
+        debug!(dep = dependency, key = signature.public_key; "signature verified");
+        Ok(())
+    }
+}
+
+/// Get the path of the signing key file.
+///
+/// The file is `~/.soldeer/signing_key`, or `.soldeer/signing_key` in the current directory if the
+/// home cannot be found. The path can be overridden by setting the `SOLDEER_SIGNING_KEY_FILE`
+/// environment variable.
+pub fn signing_key_path() -> io::Result<PathBuf> {
+    if let Ok(path) = env::var("SOLDEER_SIGNING_KEY_FILE") &&
+        !path.is_empty()
+    {
+        debug!("using signing key file defined in environment variable");
+        return Ok(path.into());
+    }
+    let dir = home::home_dir().unwrap_or(env::current_dir()?);
+    Ok(dir.join(".soldeer").join("signing_key"))
+}
+
+/// Parse a hex-encoded ed25519 public key.
+pub fn parse_public_key(key: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = const_hex::decode_to_array(key)
+        .map_err(|_| SigningError::InvalidPublicKey(key.to_string()))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| SigningError::InvalidPublicKey(key.to_string()))
+}
+
+/// Decode a hex-encoded SHA256 checksum.
+fn checksum_bytes(checksum: &str) -> Result<[u8; 32]> {
+    const_hex::decode_to_array(checksum)
+        .map_err(|_| SigningError::InvalidChecksum(checksum.to_string()))
+}
+
+/// Write a file which is only readable by the current user.
+fn write_secret(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| SigningError::IOError { path: parent.to_path_buf(), source: e })?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .map_err(|e| SigningError::IOError { path: path.to_path_buf(), source: e })?;
+    io::Write::write_all(&mut file, contents.as_bytes())
+        .map_err(|e| SigningError::IOError { path: path.to_path_buf(), source: e })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testdir::testdir;
+
+    const CHECKSUM: &str = "94a73dbe106f48179ea39b00d42e5d4dd96fdc6252caa3a89ce7efdaec0b9468";
+
+    #[test]
+    fn test_sign_and_verify() {
+        let path = testdir!().join("keys/signing_key");
+        let (key, generated) = SigningKey::load_or_generate(&path).unwrap();
+        assert!(generated);
+        let (loaded, generated) = SigningKey::load_or_generate(&path).unwrap();
+        assert!(!generated);
+        assert_eq!(loaded.public_key(), key.public_key());
+
+        let signature = key.sign(CHECKSUM).unwrap();
+        assert_eq!(signature.public_key, key.public_key());
+        assert!(signature.verify(CHECKSUM).is_ok());
+        let other = CHECKSUM.replace('9', "8");
+        assert!(matches!(signature.verify(&other), Err(SigningError::SignatureMismatch)));
+    }
+
+    #[test]
+    fn test_trust_policy() {
+        let (key, _) = SigningKey::load_or_generate(testdir!().join("signing_key")).unwrap();
+        let signature = key.sign(CHECKSUM).unwrap();
+        let other = CHECKSUM.replace('9', "8");
+
+        // without required signatures, anything goes
+        let policy = TrustPolicy::default();
+        assert!(policy.check("lib", CHECKSUM, None).is_ok());
+        assert!(policy.check("lib", &other, Some(&signature)).is_ok());
+
+        let policy =
+            TrustPolicy::builder().require_signatures(true).keys(vec![key.public_key()]).build();
+        assert!(policy.check("lib", CHECKSUM, Some(&signature)).is_ok());
+        assert!(matches!(policy.check("lib", CHECKSUM, None), Err(SigningError::Unsigned(_))));
+        assert!(matches!(
+            policy.check("lib", &other, Some(&signature)),
+            Err(SigningError::InvalidSignature(_))
+        ));
+
+        let policy = TrustPolicy::builder().require_signatures(true).build();
+        assert!(matches!(
+            policy.check("lib", CHECKSUM, Some(&signature)),
+            Err(SigningError::UntrustedKey { .. })
+        ));
+    }
+}
@@ -0,0 +1,382 @@
+//! Extracted package store.
+//!
+//! When the [`LinkMode`] is not [`LinkMode::Copy`], packages are extracted (or cloned) once into a
+//! store located in the global cache folder, and the dependencies folder of each project is
+//! populated by linking to the store:
+//!
+//! - HTTP packages are extracted to `store/<checksum>`, where the checksum is the SHA256 hash of
+//!   the archive. The integrity checksum of the extracted folder is saved next to it in
+//!   `store/<checksum>.integrity`, so that a modified folder is detected and extracted again.
+//! - Git packages are cloned to `store/git/<url hash>/<commit>`.
+//!
+//! Since the relative paths and contents of the files are the same in the store and in the linked
+//! folder, the integrity checksum computed with [`hash_folder`] is the same as for a copy.
+//!
+//! A linked folder is shared by every project which uses the same package, so a modification in
+//! one project would affect the others. Store entries are therefore checked before being linked
+//! and replaced if they were modified, and linked folders are never modified in place.
+use crate::{
+    cache::{STORE_DIR, cache_dir},
+    config::{GitIdentifier, LinkMode},
+    download::{clone_repo, extract_archive},
+    errors::{DownloadError, InstallError},
+    install::Result,
+    utils::{hash_content, hash_folder, run_git_command},
+};
+use log::{debug, warn};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Get the path to the extracted package store.
+pub fn store_dir() -> Result<PathBuf> {
+    Ok(cache_dir()?.join(STORE_DIR))
+}
+
+/// Extract an archive into the store, unless it's already present and unmodified.
+///
+/// An existing folder is compared to the `integrity` checksum if provided (e.g. from the
+/// lockfile), or else to the integrity checksum which was saved when the archive was extracted.
+///
+/// The archive is deleted in any case, and the path to the extracted folder is returned.
+pub async fn extract_to_store(
+    archive_path: impl AsRef<Path>,
+    checksum: &str,
+    integrity: Option<&str>,
+) -> Result<PathBuf> {
+    let archive_path = archive_path.as_ref();
+    let store = store_dir()?;
+    let path = store.join(checksum);
+    let integrity_path = store.join(format!("{checksum}.integrity"));
+    if fs::metadata(&path).await.is_ok() {
+        let expected = match integrity {
+            Some(integrity) => integrity.to_string(),
+            None => fs::read_to_string(&integrity_path).await.unwrap_or_default(),
+        };
+        let actual = hash_folder_async(&path).await?;
+        if expected.trim() == actual {
+            debug!(path:?; "archive is already extracted in store");
+            fs::remove_file(archive_path).await.map_err(|e| InstallError::IOError {
+                path: archive_path.to_path_buf(),
+                source: e,
+            })?;
+            return Ok(path);
+        }
+        warn!(path:?; "extracted package in store was modified, extracting again");
+        remove_path(&path).await?;
+    }
+    fs::create_dir_all(&store)
+        .await
+        .map_err(|e| InstallError::IOError { path: store.clone(), source: e })?;
+    // extract into a temporary folder first, so that other processes never see a partial folder
+    let tmp_path = store.join(format!("{checksum}.{}.tmp", uuid::Uuid::new_v4()));
+    extract_archive(archive_path, &tmp_path).await?;
+    let integrity = hash_folder_async(&tmp_path).await?;
+    if let Err(e) = fs::rename(&tmp_path, &path).await {
+        // another process might have extracted the same archive in the meantime
+        remove_path(&tmp_path).await?;
+        if fs::metadata(&path).await.is_err() {
+            return Err(InstallError::IOError { path, source: e });
+        }
+    }
+    fs::write(&integrity_path, integrity)
+        .await
+        .map_err(|e| InstallError::IOError { path: integrity_path, source: e })?;
+    debug!(path:?; "extracted archive into store");
+    Ok(path)
+}
+
+/// Clone a git repository into the store, unless the required commit is already present.
+///
+/// If the identifier is a full commit hash which is already in the store, the repository is not
+/// cloned again, unless its checkout was modified. The path to the repository in the store and the
+/// commit hash are returned.
+///
+/// Sparse checkouts of a subdirectory are stored separately from full checkouts of the same
+/// repository. In offline mode, only the repositories which are already in the store can be used.
+pub async fn clone_to_store(
+    url: &str,
+    identifier: Option<&GitIdentifier>,
+    subdir: Option<&str>,
+    offline: bool,
+) -> Result<(PathBuf, String)> {
+    let key = match subdir {
+        Some(subdir) => format!("{url}#{subdir}"),
+        None => url.to_string(),
+    };
+    let repos = store_dir()?.join("git").join(const_hex::encode(hash_content(&mut key.as_bytes())));
+    if let Some(GitIdentifier::Rev(rev)) = identifier &&
+        rev.len() == 40 &&
+        check_store_repo(&repos.join(rev), rev).await?
+    {
+        debug!(url, rev; "git repository is already in store");
+        return Ok((repos.join(rev), rev.clone()));
+    }
+    if offline {
+        return Err(DownloadError::Offline(url.to_string()).into());
+    }
+    fs::create_dir_all(&repos)
+        .await
+        .map_err(|e| InstallError::IOError { path: repos.clone(), source: e })?;
+    let tmp_path = repos.join(format!("{}.tmp", uuid::Uuid::new_v4()));
+    let commit = clone_repo(url, identifier, subdir, &tmp_path).await?;
+    let path = repos.join(&commit);
+    check_store_repo(&path, &commit).await?;
+    if fs::metadata(&path).await.is_ok() || fs::rename(&tmp_path, &path).await.is_err() {
+        debug!(url, commit; "git repository was already in store");
+        remove_path(&tmp_path).await?;
+    }
+    debug!(path:?; "cloned git repository into store");
+    Ok((path, commit))
+}
+
+/// Check that a git repository of the store is checked out at the commit and has no changes.
+///
+/// A modified repository is removed from the store and `false` is returned, as well as if the
+/// repository doesn't exist.
+async fn check_store_repo(path: &Path, commit: &str) -> Result<bool> {
+    if fs::metadata(path).await.is_err() {
+        return Ok(false);
+    }
+    let head = run_git_command(&["rev-parse", "HEAD"], Some(&path.to_path_buf())).await;
+    let status =
+        run_git_command(&["status", "--porcelain", "--ignored"], Some(&path.to_path_buf())).await;
+    if let (Ok(head), Ok(status)) = (head, status) &&
+        head.trim() == commit &&
+        status.trim().is_empty()
+    {
+        return Ok(true);
+    }
+    warn!(path:?; "git repository in store was modified, cloning it again");
+    remove_path(path).await?;
+    Ok(false)
+}
+
+/// Check whether a dependency folder is a symbolic link, e.g. to a folder of the store.
+pub async fn is_linked(path: impl AsRef<Path>) -> bool {
+    fs::symlink_metadata(path).await.is_ok_and(|m| m.file_type().is_symlink())
+}
+
+/// Populate a dependency folder from a folder of the store.
+///
+/// With [`LinkMode::Hardlink`], the folder structure is re-created and each file is hardlinked,
+/// falling back to a copy if the link can't be created. With [`LinkMode::Symlink`], the dependency
+/// folder is a symbolic link to the store folder. With [`LinkMode::Copy`], all files are copied.
+pub async fn link_from_store(
+    source: impl AsRef<Path>,
+    dest: impl AsRef<Path>,
+    mode: LinkMode,
+) -> Result<()> {
+    let source = source.as_ref().to_path_buf();
+    let dest = dest.as_ref().to_path_buf();
+    if fs::symlink_metadata(&dest).await.is_ok() {
+        remove_path(&dest).await?;
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| InstallError::IOError { path: parent.to_path_buf(), source: e })?;
+    }
+    debug!(source:?, dest:?, mode:?; "populating dependency folder from store");
+    match mode {
+        LinkMode::Symlink => symlink_dir(&source, &dest).await,
+        LinkMode::Hardlink | LinkMode::Copy => {
+            tokio::task::spawn_blocking(move || link_tree(&source, &dest, mode)).await?
+        }
+    }
+}
+
+/// Recursively re-create a folder, hardlinking or copying each file.
+fn link_tree(source: &Path, dest: &Path, mode: LinkMode) -> Result<()> {
+    let io_err = |path: &Path| {
+        let path = path.to_path_buf();
+        move |e| InstallError::IOError { path, source: e }
+    };
+    std::fs::create_dir_all(dest).map_err(io_err(dest))?;
+    for entry in std::fs::read_dir(source).map_err(io_err(source))? {
+        let entry = entry.map_err(io_err(source))?;
+        let from = entry.path();
+        let to = dest.join(entry.file_name());
+        let file_type = entry.file_type().map_err(io_err(&from))?;
+        if file_type.is_dir() {
+            link_tree(&from, &to, mode)?;
+        } else if file_type.is_symlink() {
+            let target = std::fs::read_link(&from).map_err(io_err(&from))?;
+            symlink_sync(&target, &to, from.is_dir()).map_err(io_err(&to))?;
+        } else if mode == LinkMode::Copy || std::fs::hard_link(&from, &to).is_err() {
+            std::fs::copy(&from, &to).map_err(io_err(&to))?;
+        }
+    }
+    Ok(())
+}
+
+/// Create a symbolic link to a directory.
+async fn symlink_dir(source: &Path, dest: &Path) -> Result<()> {
+    let (source, dest) = (source.to_path_buf(), dest.to_path_buf());
+    tokio::task::spawn_blocking(move || {
+        symlink_sync(&source, &dest, true)
+            .map_err(|e| InstallError::IOError { path: dest.clone(), source: e })
+    })
+    .await?
+}
+
+#[cfg(unix)]
+fn symlink_sync(target: &Path, link: &Path, _is_dir: bool) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink_sync(target: &Path, link: &Path, is_dir: bool) -> std::io::Result<()> {
+    if is_dir {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+/// Compute the integrity checksum of a folder in a blocking task.
+async fn hash_folder_async(path: &Path) -> Result<String> {
+    let path = path.to_path_buf();
+    Ok(tokio::task::spawn_blocking({
+        let path = path.clone();
+        move || hash_folder(path)
+    })
+    .await?
+    .map_err(|e| InstallError::IOError { path, source: e })?
+    .to_string())
+}
+
+/// Remove a file, folder or symbolic link.
+///
+/// Symbolic links are removed without modifying their target.
+pub(crate) async fn remove_path(path: &Path) -> Result<()> {
+    let metadata = fs::symlink_metadata(path)
+        .await
+        .map_err(|e| InstallError::IOError { path: path.to_path_buf(), source: e })?;
+    let res = if metadata.is_dir() {
+        fs::remove_dir_all(path).await
+    } else {
+        fs::remove_file(path).await
+    };
+    res.map_err(|e| InstallError::IOError { path: path.to_path_buf(), source: e })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::push::zip_file;
+    use temp_env::async_with_vars;
+    use testdir::testdir;
+
+    fn create_zip(dir: &Path) -> PathBuf {
+        let pkg = dir.join("pkg");
+        std::fs::create_dir_all(pkg.join("src")).unwrap();
+        std::fs::write(pkg.join("src/Lib.sol"), "contract Lib {}").unwrap();
+        std::fs::write(pkg.join("README.md"), "readme").unwrap();
+        zip_file(&pkg, &[pkg.join("src/Lib.sol"), pkg.join("README.md")], "pkg").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_link_from_store_integrity() {
+        let dir = testdir!();
+        let cache = dir.join("cache");
+        let zip_path = create_zip(&dir);
+        let copy = dir.join("copy");
+        std::fs::create_dir(&copy).unwrap();
+        let copy_zip = copy.join("pkg.zip");
+        std::fs::copy(&zip_path, &copy_zip).unwrap();
+        extract_archive(&copy_zip, &copy).await.unwrap();
+        let expected = hash_folder(&copy).unwrap();
+
+        async_with_vars([("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().as_ref()))], async {
+            let store = extract_to_store(&zip_path, "1234", None).await.unwrap();
+            assert!(!zip_path.exists());
+            assert_eq!(store, cache.join("store/1234"));
+            for mode in [LinkMode::Copy, LinkMode::Hardlink, LinkMode::Symlink] {
+                let dest = dir.join(format!("deps/{mode:?}"));
+                link_from_store(&store, &dest, mode).await.unwrap();
+                assert!(dest.join("src/Lib.sol").is_file());
+                assert_eq!(hash_folder(&dest).unwrap(), expected, "{mode:?}");
+            }
+            assert!(std::fs::symlink_metadata(dir.join("deps/Symlink")).unwrap().is_symlink());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_extract_to_store_modified() {
+        let dir = testdir!();
+        let cache = dir.join("cache");
+        async_with_vars([("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().as_ref()))], async {
+            let zip_path = create_zip(&dir);
+            let store = extract_to_store(&zip_path, "1234", None).await.unwrap();
+            std::fs::write(store.join("src/Lib.sol"), "modified").unwrap();
+
+            // the modified folder is extracted again
+            std::fs::remove_dir_all(dir.join("pkg")).unwrap();
+            let zip_path = create_zip(&dir);
+            let store = extract_to_store(&zip_path, "1234", None).await.unwrap();
+            assert_eq!(
+                std::fs::read_to_string(store.join("src/Lib.sol")).unwrap(),
+                "contract Lib {}"
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_extract_to_store_lock_integrity() {
+        let dir = testdir!();
+        let cache = dir.join("cache");
+        async_with_vars([("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().as_ref()))], async {
+            let zip_path = create_zip(&dir);
+            let store = extract_to_store(&zip_path, "1234", None).await.unwrap();
+            let integrity = hash_folder(&store).unwrap().to_string();
+            // the saved integrity checksum is modified along with the folder
+            std::fs::write(store.join("src/Lib.sol"), "modified").unwrap();
+            std::fs::write(cache.join("store/1234.integrity"), hash_folder(&store).unwrap().0)
+                .unwrap();
+
+            std::fs::remove_dir_all(dir.join("pkg")).unwrap();
+            let zip_path = create_zip(&dir);
+            let store = extract_to_store(&zip_path, "1234", Some(&integrity)).await.unwrap();
+            assert_eq!(hash_folder(&store).unwrap().to_string(), integrity);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_clone_to_store_modified() {
+        let dir = testdir!();
+        let cache = dir.join("cache");
+        let repo = dir.join("repo");
+        std::fs::create_dir(&repo).unwrap();
+        std::fs::write(repo.join("Lib.sol"), "contract Lib {}").unwrap();
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(&repo)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{output:?}");
+            String::from_utf8(output.stdout).unwrap().trim().to_string()
+        };
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "init"]);
+        let commit = git(&["rev-parse", "HEAD"]);
+        let url = repo.to_string_lossy().to_string();
+        let identifier = GitIdentifier::from_rev(&commit);
+
+        async_with_vars([("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().as_ref()))], async {
+            let (store, rev) = clone_to_store(&url, Some(&identifier), None, false).await.unwrap();
+            assert_eq!(rev, commit);
+            std::fs::write(store.join("Lib.sol"), "modified").unwrap();
+
+            // the modified checkout is cloned again
+            let (store, _) = clone_to_store(&url, Some(&identifier), None, false).await.unwrap();
+            assert_eq!(std::fs::read_to_string(store.join("Lib.sol")).unwrap(), "contract Lib {}");
+        })
+        .await;
+    }
+}
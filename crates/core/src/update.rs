@@ -1,18 +1,48 @@
 //! Update dependencies to the latest version.
 use crate::{
-    config::{Dependency, GitIdentifier},
+    config::{Dependency, GitIdentifier, LinkMode},
     errors::UpdateError,
-    install::{InstallProgress, install_dependency},
+    install::{InstallOptions, InstallProgress, install_dependency},
     lock::{GitLockEntry, LockEntry, format_install_path},
-    registry::get_latest_supported_version,
+    registry::{Registries, Versions, get_all_versions_descending, get_latest_supported_version},
+    store::is_linked,
     utils::run_git_command,
 };
-use log::debug;
+use log::{debug, warn};
+use semver::Version;
 use std::path::Path;
 use tokio::task::JoinSet;
 
 pub type Result<T> = std::result::Result<T, UpdateError>;
 
+/// Version information for a dependency of the lockfile, as reported by `soldeer outdated`.
+///
+/// For git dependencies, the versions are commit hashes, or tag names followed by the commit hash
+/// in parentheses.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, bon::Builder)]
+#[builder(on(String, into))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct OutdatedDependency {
+    /// The name of the dependency.
+    pub name: String,
+
+    /// The version (or commit) currently in the lockfile.
+    pub current: String,
+
+    /// The newest version (or commit) allowed by the version requirement in the config file.
+    ///
+    /// This is `None` for transitive dependencies and for git dependencies pinned to a rev or tag.
+    pub wanted: Option<String>,
+
+    /// The newest version (or commit) available.
+    pub latest: Option<String>,
+
+    /// Whether a newer version than the current one is available.
+    #[builder(default)]
+    pub outdated: bool,
+}
+
 /// Update the dependencies to a new version.
 ///
 /// This function spawns a task for each dependency and waits for all of them to finish.
@@ -32,7 +62,9 @@ pub async fn update_dependencies(
     dependencies: &[Dependency],
     locks: &[LockEntry],
     deps_path: impl AsRef<Path>,
+    registries: &Registries,
     recursive_deps: bool,
+    options: &InstallOptions,
     progress: InstallProgress,
 ) -> Result<Vec<LockEntry>> {
     let mut set = JoinSet::new();
@@ -44,7 +76,20 @@ pub async fn update_dependencies(
 
             let lock = locks.iter().find(|l| l.name() == dep.name()).cloned();
             let paths = deps_path.as_ref().to_path_buf();
-            async move { update_dependency(&d, lock.as_ref(), &paths, recursive_deps, p).await }
+            let registries = registries.clone();
+            let options = options.clone();
+            async move {
+                update_dependency(
+                    &d,
+                    lock.as_ref(),
+                    &paths,
+                    &registries,
+                    recursive_deps,
+                    &options,
+                    p,
+                )
+                .await
+            }
         });
     }
 
@@ -56,11 +101,190 @@ pub async fn update_dependencies(
     Ok(results)
 }
 
+/// Check which entries of the lockfile have newer versions available.
+///
+/// For dependencies from the registry, the newest version allowed by the version requirement and
+/// the newest version overall are retrieved from the registry.
+///
+/// For git dependencies which track a branch (or the default branch), the locked commit is
+/// compared to the remote head of the branch. For git dependencies pinned to a rev or a tag, the
+/// locked commit is compared to the newest tag of the remote repository.
+///
+/// Dependencies with a custom URL and path dependencies cannot be checked and are omitted from the
+/// result. In offline mode, the versions of registry dependencies are read from the global cache.
+pub async fn check_outdated(
+    dependencies: &[Dependency],
+    locks: &[LockEntry],
+    registries: &Registries,
+    offline: bool,
+) -> Result<Vec<OutdatedDependency>> {
+    let mut set = JoinSet::new();
+    for lock in locks {
+        let dep = dependencies.iter().find(|d| d.name() == lock.name()).cloned();
+        let lock = lock.clone();
+        let registries = registries.clone();
+        debug!(dep = lock.name(); "spawning task to check if dependency is outdated");
+        set.spawn(async move {
+            check_outdated_dependency(dep.as_ref(), &lock, &registries, offline).await
+        });
+    }
+    let mut results = Vec::new();
+    while let Some(res) = set.join_next().await {
+        if let Some(res) = res?? {
+            results.push(res);
+        }
+    }
+    results.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    Ok(results)
+}
+
+/// Check whether a lockfile entry has a newer version available.
+///
+/// The dependency from the config file is `None` for transitive dependencies.
+async fn check_outdated_dependency(
+    dependency: Option<&Dependency>,
+    lock: &LockEntry,
+    registries: &Registries,
+    offline: bool,
+) -> Result<Option<OutdatedDependency>> {
+    match lock {
+        LockEntry::Git(lock) => {
+            let identifier = dependency.and_then(|d| d.as_git()).and_then(|d| d.identifier.clone());
+            match identifier {
+                Some(GitIdentifier::Rev(_) | GitIdentifier::Tag(_)) => {
+                    let latest = get_newest_tag(&lock.git).await?;
+                    Ok(Some(
+                        OutdatedDependency::builder()
+                            .name(&lock.name)
+                            .current(&lock.rev)
+                            .maybe_latest(
+                                latest.as_ref().map(|(tag, rev)| format!("{tag} ({rev})")),
+                            )
+                            .outdated(latest.is_some_and(|(_, rev)| rev != lock.rev))
+                            .build(),
+                    ))
+                }
+                identifier => {
+                    let reference = match identifier {
+                        Some(GitIdentifier::Branch(branch)) => format!("refs/heads/{branch}"),
+                        _ => "HEAD".to_string(),
+                    };
+                    let output =
+                        run_git_command(&["ls-remote", &lock.git, &reference], None).await?;
+                    let head = output.split_whitespace().next().map(ToString::to_string);
+                    Ok(Some(
+                        OutdatedDependency::builder()
+                            .name(&lock.name)
+                            .current(&lock.rev)
+                            .maybe_wanted(head.clone())
+                            .maybe_latest(head.clone())
+                            .outdated(head.is_some_and(|h| h != lock.rev))
+                            .build(),
+                    ))
+                }
+            }
+        }
+        LockEntry::Path(_) => {
+            debug!(dep = lock.name(); "path dependency cannot be checked for new versions");
+            Ok(None)
+        }
+        LockEntry::Http(_) | LockEntry::Private(_) => {
+            if dependency.is_some_and(|d| d.url().is_some()) {
+                debug!(dep = lock.name(); "dependency has a custom URL, cannot check for new versions");
+                return Ok(None);
+            }
+            let registry = match dependency {
+                Some(dep) => registries.for_dependency(dep)?,
+                None => registries.get(lock.registry())?,
+            };
+            let versions = match get_all_versions_descending(registry, lock.name(), offline).await {
+                Ok(versions) => versions,
+                Err(err) if dependency.is_none() => {
+                    // transitive dependencies might not come from the registry
+                    warn!(dep = lock.name(), err:%; "could not retrieve versions of transitive dependency");
+                    return Ok(None);
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let latest = match &versions {
+                Versions::Semver(versions) => versions.first().map(ToString::to_string),
+                Versions::NonSemver(versions) => versions.first().cloned(),
+            };
+            let wanted = match dependency {
+                Some(dep) => Some(get_latest_supported_version(registry, dep, offline).await?),
+                None => None,
+            };
+            // the locked version can be newer than the listed ones, e.g. if it was yanked
+            let outdated = [&wanted, &latest]
+                .into_iter()
+                .flatten()
+                .any(|v| is_newer_version(v, lock.version(), &versions));
+            Ok(Some(
+                OutdatedDependency::builder()
+                    .name(lock.name())
+                    .current(lock.version())
+                    .maybe_wanted(wanted)
+                    .maybe_latest(latest)
+                    .outdated(outdated)
+                    .build(),
+            ))
+        }
+    }
+}
+
+/// Check whether a version is newer than the current version.
+///
+/// Semver-compliant versions are compared according to semver. Otherwise, the versions are
+/// compared according to their position in the list of versions from the registry, which is sorted
+/// by descending creation date. A version which is not in the list is never considered newer.
+fn is_newer_version(version: &str, current: &str, versions: &Versions) -> bool {
+    if let (Ok(version), Ok(current)) = (Version::parse(version), Version::parse(current)) {
+        return version > current;
+    }
+    let Versions::NonSemver(versions) = versions else {
+        return false;
+    };
+    let position = |v: &str| versions.iter().position(|x| x == v);
+    matches!((position(version), position(current)), (Some(a), Some(b)) if a < b)
+}
+
+/// Retrieve the newest tag of a remote git repository, along with the commit it points to.
+///
+/// Tags are sorted by version, so that `v1.10.0` is newer than `v1.9.0`.
+async fn get_newest_tag(git: &str) -> Result<Option<(String, String)>> {
+    let output = run_git_command(&["ls-remote", "--tags", "--sort=-v:refname", git], None).await?;
+    Ok(parse_newest_tag(&output))
+}
+
+/// Parse the output of `git ls-remote --tags --sort=-v:refname` to find the newest tag.
+///
+/// Annotated tags are listed twice: the tag object, and the commit it points to with a `^{}`
+/// suffix (the "peeled" tag), which comes first. The commit is returned for those tags.
+fn parse_newest_tag(output: &str) -> Option<(String, String)> {
+    let refs: Vec<(&str, &str)> = output
+        .lines()
+        .filter_map(|line| {
+            let (rev, reference) = line.split_once('\t')?;
+            Some((rev, reference.strip_prefix("refs/tags/")?))
+        })
+        .collect();
+    let (_, newest) = refs.first()?;
+    let newest = newest.strip_suffix("^{}").unwrap_or(newest);
+    let peeled = format!("{newest}^{{}}");
+    let (rev, _) = refs
+        .iter()
+        .find(|(_, tag)| *tag == peeled)
+        .or_else(|| refs.iter().find(|(_, tag)| *tag == newest))?;
+    Some((newest.to_string(), rev.to_string()))
+}
+
 /// Update a single dependency to a new version.
 ///
 /// For Git dependencies without a ref or with a
 /// [`GitIdentifier::Branch`] ref, the function will update
-/// the dependency to the latest commit with `git pull`.
+/// the dependency to the latest commit with `git pull`. If the dependency is linked to the
+/// [`crate::store`] (see [`LinkMode`]), the latest commit is cloned into the store instead, so that
+/// the store entry of the previous commit is left untouched.
 ///
 /// For Git dependencies with a [`GitIdentifier::Rev`] or [`GitIdentifier::Tag`] ref, the function
 /// will reset the repo to the ref if the integrity check fails. An update is not really possible in
@@ -73,10 +297,42 @@ pub async fn update_dependency(
     dependency: &Dependency,
     lock: Option<&LockEntry>,
     deps: impl AsRef<Path>,
+    registries: &Registries,
     recursive_deps: bool,
+    options: &InstallOptions,
     progress: InstallProgress,
 ) -> Result<LockEntry> {
+    // a folder linked to the store must not be modified in place, even if the link mode changed
+    let linked = match lock {
+        Some(lock) => is_linked(lock.install_path(&deps)).await,
+        None => false,
+    };
     match dependency {
+        Dependency::Git(dep)
+            if (options.link_mode != LinkMode::Copy || linked) &&
+                matches!(dep.identifier, None | Some(GitIdentifier::Branch(_))) =>
+        {
+            debug!(dep:% = dependency; "updating linked git dependency based on a branch");
+            let new_lock = install_dependency(
+                dependency,
+                None,
+                &deps,
+                None,
+                registries,
+                recursive_deps,
+                options,
+                progress.clone(),
+            )
+            .await?;
+            if let (Some(LockEntry::Git(old)), LockEntry::Git(new)) = (lock, &new_lock) &&
+                old.rev != new.rev
+            {
+                debug!(dep:% = dependency, old_commit = old.rev, new_commit = new.rev; "updated dependency");
+                progress
+                    .log(format!("Updating {dependency} from {:.7} to {:.7}", old.rev, new.rev));
+            }
+            Ok(new_lock)
+        }
         Dependency::Git(dep) if matches!(dep.identifier, None | Some(GitIdentifier::Branch(_))) => {
             // we handle the git case in a special way because we don't need to re-clone the repo
             // update to the latest commit (git pull)
@@ -95,6 +351,16 @@ pub async fn update_dependency(
                 .to_string();
             debug!(dep:% = dependency; "old commit was {old_commit}");
 
+            // make sure the checkout matches the required subdirectory
+            match &dep.subdir {
+                Some(subdir) => {
+                    run_git_command(&["sparse-checkout", "set", subdir], Some(&path)).await?;
+                }
+                None => {
+                    run_git_command(&["sparse-checkout", "disable"], Some(&path)).await?;
+                }
+            }
+
             if let Some(GitIdentifier::Branch(ref branch)) = dep.identifier {
                 // checkout the desired branch
                 debug!(dep:% = dependency, branch; "checking out required branch");
@@ -133,6 +399,7 @@ pub async fn update_dependency(
                 .version(&dep.version_req)
                 .git(&dep.git)
                 .rev(commit)
+                .maybe_subdir(dep.subdir.clone())
                 .build()
                 .into();
             progress.update_all(dependency.into());
@@ -149,12 +416,37 @@ pub async fn update_dependency(
                     .version(&dep.version_req)
                     .git(&dep.git)
                     .rev(dep.identifier.as_ref().expect("identifier should be present").to_string())
+                    .maybe_subdir(dep.subdir.clone())
                     .build()
                     .into(),
             };
-            let new_lock =
-                install_dependency(dependency, Some(lock), &deps, None, recursive_deps, progress)
-                    .await?;
+            let new_lock = install_dependency(
+                dependency,
+                Some(lock),
+                &deps,
+                None,
+                registries,
+                recursive_deps,
+                options,
+                progress,
+            )
+            .await?;
+            Ok(new_lock)
+        }
+        Dependency::Path(_) => {
+            // the folder is linked again, there is no version to update to
+            debug!(dep:% = dependency; "updating path dependency");
+            let new_lock = install_dependency(
+                dependency,
+                None,
+                &deps,
+                None,
+                registries,
+                recursive_deps,
+                options,
+                progress,
+            )
+            .await?;
             Ok(new_lock)
         }
         _ => {
@@ -166,7 +458,12 @@ pub async fn update_dependency(
             // registry
             let force_version = match (dependency.url(), lock) {
                 (None, Some(lock)) => {
-                    let new_version = get_latest_supported_version(dependency).await?;
+                    let new_version = get_latest_supported_version(
+                        registries.for_dependency(dependency)?,
+                        dependency,
+                        options.offline,
+                    )
+                    .await?;
                     if lock.version() != new_version {
                         debug!(dep:% = dependency, old_version = lock.version(), new_version; "dependency has a new version available");
                         progress.log(format!(
@@ -184,7 +481,9 @@ pub async fn update_dependency(
                 None,
                 &deps,
                 force_version,
+                registries,
                 recursive_deps,
+                options,
                 progress,
             )
             .await?;
@@ -192,3 +491,114 @@ pub async fn update_dependency(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::HttpDependency,
+        lock::HttpLockEntry,
+        registry::{DEFAULT_REGISTRY, Registry, RegistryAuth},
+    };
+    use mockito::{Matcher, Server};
+
+    #[test]
+    fn test_parse_newest_tag() {
+        // output of `git ls-remote --tags --sort=-v:refname` with annotated and lightweight tags
+        let output = "d76f6bf5a47d9b2f942dab92924b4bfb1b1f900a\trefs/tags/v1.10.0^{}
+cc391724bd0412a35121de4fc1a54885f1c12a15\trefs/tags/v1.10.0
+865cce2d83303b46b651248eaf6f2835e8c7e8ab\trefs/tags/v1.9.0
+865cce2d83303b46b651248eaf6f2835e8c7e8ab\trefs/tags/v1.2.0^{}
+17632bd9a2d4937bb89d7fa797a5723e269b3acf\trefs/tags/v1.2.0
+";
+        assert_eq!(
+            parse_newest_tag(output),
+            Some(("v1.10.0".to_string(), "d76f6bf5a47d9b2f942dab92924b4bfb1b1f900a".to_string()))
+        );
+        let output = "865cce2d83303b46b651248eaf6f2835e8c7e8ab\trefs/tags/v1.9.0
+865cce2d83303b46b651248eaf6f2835e8c7e8ab\trefs/tags/v1.2.0^{}
+17632bd9a2d4937bb89d7fa797a5723e269b3acf\trefs/tags/v1.2.0
+";
+        assert_eq!(
+            parse_newest_tag(output),
+            Some(("v1.9.0".to_string(), "865cce2d83303b46b651248eaf6f2835e8c7e8ab".to_string()))
+        );
+        assert_eq!(parse_newest_tag(""), None);
+    }
+
+    #[test]
+    fn test_is_newer_version() {
+        let semver = Versions::Semver(vec![Version::new(2, 0, 0), Version::new(1, 0, 0)]);
+        assert!(is_newer_version("2.0.0", "1.0.0", &semver));
+        assert!(!is_newer_version("2.0.0", "2.0.0", &semver));
+        // yanked version newer than the listed ones
+        assert!(!is_newer_version("2.0.0", "2.1.0", &semver));
+        let non_semver = Versions::NonSemver(vec!["v2".to_string(), "v1".to_string()]);
+        assert!(is_newer_version("v2", "v1", &non_semver));
+        assert!(!is_newer_version("v1", "v2", &non_semver));
+        assert!(!is_newer_version("v2", "v3", &non_semver));
+    }
+
+    #[tokio::test]
+    async fn test_check_outdated_registry() {
+        let mut server = Server::new_async().await;
+        let data = r#"{"data":[{"created_at":"2024-08-06T17:31:25.751079Z","deleted":false,"downloads":3389,"id":"660132e6-4902-4804-8c4b-7cae0a648054","internal_name":"forge-std/2_0_0.zip","project_id":"37adefe5-9bc6-4777-aaf2-e56277d1f30b","url":"https://example.com/2_0_0.zip","version":"2.0.0"},{"created_at":"2024-07-03T14:44:59.729623Z","deleted":false,"downloads":5290,"id":"fa5160fc-ba7b-40fd-8e99-8becd6dadbe4","internal_name":"forge-std/1_9_2.zip","project_id":"37adefe5-9bc6-4777-aaf2-e56277d1f30b","url":"https://example.com/1_9_2.zip","version":"1.9.2"},{"created_at":"2024-07-03T14:44:58.148723Z","deleted":false,"downloads":21,"id":"b463683a-c4b4-40bf-b707-1c4eb343c4d2","internal_name":"forge-std/1_9_0.zip","project_id":"37adefe5-9bc6-4777-aaf2-e56277d1f30b","url":"https://example.com/1_9_0.zip","version":"1.9.0"}],"status":"success"}"#;
+        server
+            .mock("GET", "/api/v1/revision")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(data)
+            .create_async()
+            .await;
+        let dependencies: Vec<Dependency> = vec![
+            HttpDependency::builder().name("forge-std").version_req("^1.9.0").build().into(),
+            HttpDependency::builder().name("yanked").version_req("^2.0").build().into(),
+            HttpDependency::builder()
+                .name("custom")
+                .version_req("1.0.0")
+                .url("https://example.com/custom.zip")
+                .build()
+                .into(),
+        ];
+        let lock = |name: &str, version: &str| -> LockEntry {
+            HttpLockEntry::builder()
+                .name(name)
+                .version(version)
+                .url("https://example.com/zip.zip")
+                .checksum("123")
+                .integrity("456")
+                .build()
+                .into()
+        };
+        // the locked version of `yanked` is newer than the listed ones
+        let locks =
+            vec![lock("forge-std", "1.9.0"), lock("yanked", "2.1.0"), lock("custom", "1.0.0")];
+
+        let registry =
+            Registry::new(DEFAULT_REGISTRY, server.url().parse().unwrap(), RegistryAuth::None);
+        let res = check_outdated(&dependencies, &locks, &Registries::new([registry]), false).await;
+        assert!(res.is_ok(), "{res:?}");
+        let res = res.unwrap();
+        assert_eq!(res.len(), 2);
+        assert_eq!(
+            res[0],
+            OutdatedDependency::builder()
+                .name("forge-std")
+                .current("1.9.0")
+                .wanted("1.9.2")
+                .latest("2.0.0")
+                .outdated(true)
+                .build()
+        );
+        assert_eq!(
+            res[1],
+            OutdatedDependency::builder()
+                .name("yanked")
+                .current("2.1.0")
+                .wanted("2.0.0")
+                .latest("2.0.0")
+                .outdated(false)
+                .build()
+        );
+    }
+}
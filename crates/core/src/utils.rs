@@ -32,7 +32,7 @@ pub struct IntegrityChecksum(pub String);
 ///
 /// The token file is stored in the home directory of the user, or in the current directory
 /// if the home cannot be found, in a hidden folder called `.soldeer`. The token file is called
-/// `.soldeer_login`.
+/// `.soldeer_login`. The folder is created when a token is saved.
 ///
 /// The path can be overridden by setting the `SOLDEER_LOGIN_FILE` environment variable.
 pub fn login_file_path() -> Result<PathBuf, std::io::Error> {
@@ -45,12 +45,7 @@ pub fn login_file_path() -> Result<PathBuf, std::io::Error> {
 
     // if home dir cannot be found, use the current dir
     let dir = home::home_dir().unwrap_or(env::current_dir()?);
-    let security_directory = dir.join(".soldeer");
-    if !security_directory.exists() {
-        debug!(dir:?; ".soldeer folder does not exist, creating it");
-        fs::create_dir(&security_directory)?;
-    }
-    let login_file = security_directory.join(".soldeer_login");
+    let login_file = dir.join(".soldeer").join(".soldeer_login");
     debug!(login_file:?; "path to login file");
     Ok(login_file)
 }
@@ -97,17 +92,9 @@ pub fn hash_folder(folder_path: impl AsRef<Path>) -> Result<IntegrityChecksum, s
 
     let (tx, rx) = mpsc::channel::<[u8; 32]>();
 
-    // we use a parallel walker to speed things up
-    let walker = WalkBuilder::new(&folder_path)
-        .filter_entry(|entry| {
-            !(entry.path().is_dir() && entry.path().file_name().unwrap_or_default() == ".git")
-        })
-        .hidden(false)
-        .require_git(false)
-        .parents(false)
-        .git_global(false)
-        .git_exclude(false)
-        .build_parallel();
+    // we use a parallel walker to speed things up, starting from the canonical path so that paths
+    // can be made relative to the root even if the folder is a symlink
+    let walker = integrity_walker(root_path.as_ref()).build_parallel();
     walker.run(|| {
         let tx = tx.clone();
         let root_path = Arc::clone(&root_path);
@@ -117,48 +104,86 @@ pub fn hash_folder(folder_path: impl AsRef<Path>) -> Result<IntegrityChecksum, s
                 return WalkState::Continue;
             };
             let path = entry.path();
-            // first hash the filename/dirname to make sure it can't be renamed or removed
-            let mut hasher = Sha256::new();
-            hasher.update(
-                path.strip_prefix(root_path.as_ref())
-                    .expect("path should be a child of root")
-                    .to_slash_lossy()
-                    .as_bytes(),
-            );
+            let relative = path
+                .strip_prefix(root_path.as_ref())
+                .expect("path should be a child of root")
+                .to_slash_lossy();
             // for files, also hash the contents
-            if let Some(true) = entry.file_type().map(|t| t.is_file()) {
+            let content_hash = if let Some(true) = entry.file_type().map(|t| t.is_file()) {
                 if let Ok(file) = fs::File::open(path) {
                     let mut reader = std::io::BufReader::new(file);
-                    let hash = hash_content(&mut reader);
-                    hasher.update(hash);
+                    Some(hash_content(&mut reader))
                 } else {
                     warn!(path:?; "could not read file while hashing folder");
+                    None
                 }
-            }
+            } else {
+                None
+            };
+            let hash = integrity_entry_hash(&relative, content_hash.as_ref());
             // record the hash for that file/folder in the list
-            let hash: [u8; 32] = hasher.finalize().into();
             tx.send(hash)
                 .expect("Channel receiver should never be dropped before end of function scope");
             WalkState::Continue
         })
     });
     drop(tx);
-    let mut hasher = Sha256::new();
     // this cannot happen before tx is dropped safely
     let mut hashes = Vec::new();
     while let Ok(msg) = rx.recv() {
         hashes.push(msg);
     }
-    // sort hashes
+    let hash = combine_integrity_hashes(hashes);
+    debug!(path:? = folder_path.as_ref(), hash:% = hash; "folder hash was computed");
+    Ok(hash)
+}
+
+/// Compute the hash of an entry of a folder for its integrity checksum.
+///
+/// The relative path of the file or folder is hashed to make sure it can't be renamed or removed,
+/// followed by the hash of the contents for files.
+pub(crate) fn integrity_entry_hash(
+    relative_path: &str,
+    content_hash: Option<&[u8; 32]>,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(relative_path.as_bytes());
+    if let Some(content_hash) = content_hash {
+        hasher.update(content_hash);
+    }
+    hasher.finalize().into()
+}
+
+/// Combine the hashes of the entries of a folder into its integrity checksum.
+///
+/// The hashes are sorted first, so that the result doesn't depend on the walk order.
+pub(crate) fn combine_integrity_hashes(mut hashes: Vec<[u8; 32]>) -> IntegrityChecksum {
     hashes.par_sort_unstable();
     // hash the hashes (yo dawg...)
-    for hash in hashes.iter() {
+    let mut hasher = Sha256::new();
+    for hash in &hashes {
         hasher.update(hash);
     }
     let hash: [u8; 32] = hasher.finalize().into();
-    let hash = const_hex::encode(hash);
-    debug!(path:? = folder_path.as_ref(), hash; "folder hash was computed");
-    Ok(hash.into())
+    const_hex::encode(hash).into()
+}
+
+/// Configure a folder walker which visits the files covered by the integrity hash of a dependency.
+///
+/// Hidden files are included, but `.git` folders and files ignored by a `.gitignore` or `.ignore`
+/// file inside of the folder are skipped.
+pub(crate) fn integrity_walker(root: &Path) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .filter_entry(|entry| {
+            !(entry.path().is_dir() && entry.path().file_name().unwrap_or_default() == ".git")
+        })
+        .hidden(false)
+        .require_git(false)
+        .parents(false)
+        .git_global(false)
+        .git_exclude(false);
+    builder
 }
 
 /// Compute the SHA256 hash of the contents of a file
@@ -279,6 +304,24 @@ pub fn canonicalize_sync(path: impl AsRef<Path>) -> Result<PathBuf, std::io::Err
     dunce::canonicalize(path)
 }
 
+/// Compute the relative path from a base folder to a path.
+///
+/// Both paths should be absolute and canonicalized. If the path is not inside of the base folder,
+/// the result starts with the required number of `..` components.
+pub fn relative_path(base: impl AsRef<Path>, path: impl AsRef<Path>) -> PathBuf {
+    let base: Vec<_> = base.as_ref().components().collect();
+    let path: Vec<_> = path.as_ref().components().collect();
+    let common = base.iter().zip(&path).take_while(|(a, b)| a == b).count();
+    let mut res = PathBuf::new();
+    for _ in common..base.len() {
+        res.push("..");
+    }
+    for component in &path[common..] {
+        res.push(component);
+    }
+    res
+}
+
 /// Check if a path corresponds to the provided dependency.
 ///
 /// The folder does not need to exist. The folder name must start with the dependency name
@@ -401,4 +444,11 @@ mod tests {
         assert_ne!(hash2, hash3);
         assert_ne!(hash1, hash3);
     }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(relative_path("/a/b", "/a/b/c/d"), PathBuf::from("c/d"));
+        assert_eq!(relative_path("/a/b/c", "/a/dependencies"), PathBuf::from("../../dependencies"));
+        assert_eq!(relative_path("/a/b", "/a/b"), PathBuf::new());
+    }
 }
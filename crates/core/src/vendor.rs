@@ -0,0 +1,431 @@
+//! Vendoring of the locked dependencies, for installs without network access.
+//!
+//! [`vendor_dependencies`] copies every artifact referenced by the lockfile into a vendor folder:
+//! the archives of HTTP and private dependencies, and a git bundle for git dependencies. A
+//! static index (`index.json`) lists the artifacts along with the lockfile information they
+//! correspond to:
+//!
+//! ```text
+//! vendor
+//! ├── index.json
+//! ├── archives
+//! │   └── forge-std-1.9.2.zip
+//! └── git
+//!     └── my-lib-5d8f1e3c2b6a7f9e0d4c3b2a1f0e9d8c7b6a5f4e.bundle
+//! ```
+//!
+//! When a vendor folder is configured (see [`InstallOptions`][crate::install::InstallOptions]), the
+//! install process looks up archives by checksum and git repositories by URL and commit in the
+//! folder before using the cache or the network. Since the archives are verified against the
+//! checksums of the lockfile, the vendor folder doesn't need to be trusted.
+use crate::{
+    archive::ArchiveFormat,
+    cache::get_cached_archive,
+    download::download_file,
+    errors::{DownloadError, VendorError},
+    lock::LockEntry,
+    registry::{Registries, get_dependency_url_remote},
+    resolver::dependency_from_lock,
+    utils::{hash_file, run_git_command, sanitize_filename},
+};
+use log::{debug, warn};
+use path_slash::PathBufExt as _;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub type Result<T> = std::result::Result<T, VendorError>;
+
+/// The name of the index file in the vendor folder.
+pub const VENDOR_INDEX: &str = "index.json";
+
+/// The index of a vendor folder.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct VendorIndex {
+    /// The vendored artifacts, sorted by dependency name.
+    pub artifacts: Vec<VendoredArtifact>,
+}
+
+/// An artifact in the vendor folder.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct VendoredArtifact {
+    /// The name of the dependency.
+    pub name: String,
+
+    /// The locked version of the dependency.
+    pub version: String,
+
+    /// The path of the artifact, relative to the vendor folder and with forward slashes.
+    pub path: String,
+
+    /// Where the artifact comes from.
+    #[serde(flatten)]
+    pub source: VendoredSource,
+}
+
+/// The source of a vendored artifact, as recorded in the lockfile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum VendoredSource {
+    /// A zip archive downloaded from a URL.
+    Http { url: String, checksum: String },
+
+    /// A zip archive of a private package from a registry.
+    Private { registry: Option<String>, checksum: String },
+
+    /// A git bundle of a repository, containing the locked commit.
+    Git { git: String, rev: String },
+}
+
+impl VendorIndex {
+    /// Read the index of a vendor folder.
+    pub fn read(dir: impl AsRef<Path>) -> Result<Self> {
+        let path = dir.as_ref().join(VENDOR_INDEX);
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| VendorError::IOError { path: path.clone(), source: e })?;
+        serde_json::from_str(&contents).map_err(|e| VendorError::IndexError { path, source: e })
+    }
+
+    /// Write the index into a vendor folder.
+    pub fn write(&self, dir: impl AsRef<Path>) -> Result<()> {
+        let path = dir.as_ref().join(VENDOR_INDEX);
+        let contents = serde_json::to_string_pretty(self).expect("index should serialize");
+        fs::write(&path, contents).map_err(|e| VendorError::IOError { path, source: e })
+    }
+
+    /// Find the archive with the given checksum.
+    pub fn find_archive(&self, checksum: &str) -> Option<&VendoredArtifact> {
+        self.artifacts.iter().find(|a| match &a.source {
+            VendoredSource::Http { checksum: c, .. } |
+            VendoredSource::Private { checksum: c, .. } => c == checksum,
+            VendoredSource::Git { .. } => false,
+        })
+    }
+
+    /// Find the git bundle for the given repository URL and commit.
+    pub fn find_git(&self, git: &str, rev: &str) -> Option<&VendoredArtifact> {
+        self.artifacts.iter().find(
+            |a| matches!(&a.source, VendoredSource::Git { git: g, rev: r } if g == git && r == rev),
+        )
+    }
+}
+
+/// Copy the artifacts of all the lockfile entries into the vendor folder and write its index.
+///
+/// Archives are taken from the global cache if possible, and otherwise downloaded. Artifacts which
+/// are already present in the folder are kept. Path dependencies are not vendored. In offline mode,
+/// artifacts which are neither in the cache nor already vendored result in an error.
+pub async fn vendor_dependencies(
+    locks: &[LockEntry],
+    dir: impl AsRef<Path>,
+    registries: &Registries,
+    offline: bool,
+) -> Result<VendorIndex> {
+    let dir = dir.as_ref();
+    let previous = VendorIndex::read(dir).unwrap_or_default();
+    let mut index = VendorIndex::default();
+    for lock in locks {
+        if let Some(artifact) = vendor_dependency(lock, dir, &previous, registries, offline).await?
+        {
+            index.artifacts.push(artifact);
+        }
+    }
+    index.artifacts.sort_unstable_by(|a, b| a.name.cmp(&b.name).then(a.version.cmp(&b.version)));
+    index.write(dir)?;
+    debug!(dir:?, count = index.artifacts.len(); "vendor index written");
+    Ok(index)
+}
+
+/// Copy the artifact of a single lockfile entry into the vendor folder.
+///
+/// Returns `None` for path dependencies, which are not vendored.
+pub async fn vendor_dependency(
+    lock: &LockEntry,
+    dir: impl AsRef<Path>,
+    previous: &VendorIndex,
+    registries: &Registries,
+    offline: bool,
+) -> Result<Option<VendoredArtifact>> {
+    let dir = dir.as_ref();
+    let source = match lock {
+        LockEntry::Http(lock) => {
+            VendoredSource::Http { url: lock.url.clone(), checksum: lock.checksum.clone() }
+        }
+        LockEntry::Private(lock) => VendoredSource::Private {
+            registry: lock.registry.clone(),
+            checksum: lock.checksum.clone(),
+        },
+        LockEntry::Git(lock) => {
+            VendoredSource::Git { git: lock.git.clone(), rev: lock.rev.clone() }
+        }
+        LockEntry::Path(_) => {
+            debug!(dep = lock.name(); "path dependencies are not vendored");
+            return Ok(None);
+        }
+    };
+    let path = match &source {
+        VendoredSource::Http { checksum, .. } | VendoredSource::Private { checksum, .. } => {
+            vendor_archive(lock, checksum, dir, previous, registries, offline).await?
+        }
+        VendoredSource::Git { git, rev } => {
+            vendor_git(lock, git, rev, dir, previous, offline).await?
+        }
+    };
+    Ok(Some(VendoredArtifact {
+        name: lock.name().to_string(),
+        version: lock.version().to_string(),
+        path: path.to_slash_lossy().into_owned(),
+        source,
+    }))
+}
+
+/// Find the vendored archive with the given checksum in a vendor folder.
+pub fn vendored_archive(dir: &Path, checksum: &str) -> Option<PathBuf> {
+    let index = read_index_or_warn(dir)?;
+    let path = dir.join(&index.find_archive(checksum)?.path);
+    path.is_file().then_some(path)
+}
+
+/// Find the vendored git bundle for a repository and commit in a vendor folder.
+pub fn vendored_git(dir: &Path, git: &str, rev: &str) -> Option<PathBuf> {
+    let index = read_index_or_warn(dir)?;
+    let path = dir.join(&index.find_git(git, rev)?.path);
+    path.is_file().then_some(path)
+}
+
+fn read_index_or_warn(dir: &Path) -> Option<VendorIndex> {
+    match VendorIndex::read(dir) {
+        Ok(index) => Some(index),
+        Err(err) => {
+            warn!(dir:?, err:%; "could not read the vendor index");
+            None
+        }
+    }
+}
+
+/// Copy the archive of an HTTP or private dependency into the vendor folder.
+///
+/// Returns the path of the archive relative to the vendor folder.
+async fn vendor_archive(
+    lock: &LockEntry,
+    checksum: &str,
+    dir: &Path,
+    previous: &VendorIndex,
+    registries: &Registries,
+    offline: bool,
+) -> Result<PathBuf> {
+    if let Some(artifact) = previous.find_archive(checksum) &&
+        archive_matches(&dir.join(&artifact.path), checksum)
+    {
+        debug!(dep = lock.name(); "archive is already vendored");
+        return Ok(PathBuf::from(&artifact.path));
+    }
+    let folder = dir.join("archives");
+    fs::create_dir_all(&folder)
+        .map_err(|e| VendorError::IOError { path: folder.clone(), source: e })?;
+    let base_name = format!("{}-{}", lock.name(), lock.version());
+
+    let path = if let Some(cached) = get_cached_archive(checksum)? {
+        debug!(dep = lock.name(), cached:?; "copying archive from cache");
+        let format = ArchiveFormat::from_file_name(&cached.to_string_lossy()).unwrap_or_default();
+        let path = folder.join(sanitize_filename(&format!("{base_name}.{}", format.extension())));
+        fs::copy(&cached, &path)
+            .map_err(|e| VendorError::IOError { path: path.clone(), source: e })?;
+        path
+    } else if offline {
+        return Err(DownloadError::Offline(lock.name().to_string()).into());
+    } else {
+        let url = match lock {
+            LockEntry::Http(lock) => lock.url.clone(),
+            _ => {
+                let registry = registries.get(lock.registry())?;
+                get_dependency_url_remote(
+                    registry,
+                    &dependency_from_lock(lock),
+                    lock.version(),
+                    false,
+                )
+                .await?
+                .url
+            }
+        };
+        download_file(url, &folder, &base_name).await?
+    };
+    let relative =
+        Path::new("archives").join(path.file_name().expect("archive should have a name"));
+
+    let actual = tokio::task::spawn_blocking({
+        let path = path.clone();
+        move || hash_file(path)
+    })
+    .await?
+    .map_err(|e| VendorError::IOError { path: path.clone(), source: e })?;
+    if actual.0 != checksum {
+        let _ = fs::remove_file(&path);
+        return Err(VendorError::ArchiveChecksum {
+            dependency: lock.name().to_string(),
+            expected: checksum.to_string(),
+            actual: actual.0,
+        });
+    }
+    Ok(relative)
+}
+
+/// Create a git bundle containing the locked commit of a git dependency.
+///
+/// Returns the path of the bundle relative to the vendor folder.
+async fn vendor_git(
+    lock: &LockEntry,
+    git: &str,
+    rev: &str,
+    dir: &Path,
+    previous: &VendorIndex,
+    offline: bool,
+) -> Result<PathBuf> {
+    if let Some(artifact) = previous.find_git(git, rev) &&
+        dir.join(&artifact.path).is_file()
+    {
+        debug!(dep = lock.name(); "git bundle is already vendored");
+        return Ok(PathBuf::from(&artifact.path));
+    }
+    if offline {
+        return Err(DownloadError::Offline(git.to_string()).into());
+    }
+    let folder = dir.join("git");
+    fs::create_dir_all(&folder)
+        .map_err(|e| VendorError::IOError { path: folder.clone(), source: e })?;
+    let relative =
+        Path::new("git").join(sanitize_filename(&format!("{}-{rev}.bundle", lock.name())));
+    let path = dir.join(&relative);
+
+    // a full clone is needed to bundle all the objects reachable from the commit
+    let tmp_path = folder.join(format!("{}.tmp", uuid::Uuid::new_v4()));
+    let res = async {
+        run_git_command(["clone", git, tmp_path.to_string_lossy().as_ref()], None).await?;
+        run_git_command(["checkout", rev], Some(&tmp_path)).await?;
+        let path = dunce::canonicalize(&folder)
+            .map_err(|e| VendorError::IOError { path: folder.clone(), source: e })?
+            .join(path.file_name().expect("bundle path should have a file name"));
+        run_git_command(
+            ["bundle", "create", path.to_string_lossy().as_ref(), "HEAD"],
+            Some(&tmp_path),
+        )
+        .await?;
+        Ok::<_, VendorError>(())
+    }
+    .await;
+    let _ = fs::remove_dir_all(&tmp_path);
+    res?;
+    debug!(dep = lock.name(), path:?; "git bundle created");
+    Ok(relative)
+}
+
+/// Whether the file at the path is an archive with the given checksum.
+fn archive_matches(path: &Path, checksum: &str) -> bool {
+    hash_file(path).is_ok_and(|c| c.0 == checksum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock::{GitLockEntry, HttpLockEntry, PathLockEntry};
+    use testdir::testdir;
+
+    #[test]
+    fn test_index_lookup() {
+        let index = VendorIndex {
+            artifacts: vec![
+                VendoredArtifact {
+                    name: "lib1".to_string(),
+                    version: "1.0.0".to_string(),
+                    path: "archives/lib1-1.0.0.zip".to_string(),
+                    source: VendoredSource::Http {
+                        url: "https://example.com/lib1.zip".to_string(),
+                        checksum: "abc".to_string(),
+                    },
+                },
+                VendoredArtifact {
+                    name: "lib2".to_string(),
+                    version: "1.0.0".to_string(),
+                    path: "git/lib2-123.bundle".to_string(),
+                    source: VendoredSource::Git {
+                        git: "https://example.com/lib2.git".to_string(),
+                        rev: "123".to_string(),
+                    },
+                },
+            ],
+        };
+        assert_eq!(index.find_archive("abc").unwrap().name, "lib1");
+        assert!(index.find_archive("123").is_none());
+        assert_eq!(index.find_git("https://example.com/lib2.git", "123").unwrap().name, "lib2");
+        assert!(index.find_git("https://example.com/lib2.git", "456").is_none());
+
+        let dir = testdir!();
+        index.write(&dir).unwrap();
+        let contents = fs::read_to_string(dir.join(VENDOR_INDEX)).unwrap();
+        assert!(contents.contains(r#""type": "git""#), "{contents}");
+        assert_eq!(VendorIndex::read(&dir).unwrap(), index);
+    }
+
+    #[tokio::test]
+    async fn test_vendor_from_cache() {
+        let dir = testdir!();
+        let cache = dir.join("cache");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("archive.zip"), "zip contents").unwrap();
+        let checksum = hash_file(cache.join("archive.zip")).unwrap().0;
+        fs::rename(cache.join("archive.zip"), cache.join(format!("{checksum}.zip"))).unwrap();
+
+        let locks: Vec<LockEntry> = vec![
+            HttpLockEntry::builder()
+                .name("lib1")
+                .version("1.0.0")
+                .url("https://example.com/lib1.zip")
+                .checksum(&checksum)
+                .integrity("")
+                .build()
+                .into(),
+            PathLockEntry::builder().name("local").version("1.0.0").path("../local").build().into(),
+        ];
+        let vendor = dir.join("vendor");
+        fs::create_dir_all(&vendor).unwrap();
+        let index = temp_env::async_with_vars(
+            [("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().to_string()))],
+            vendor_dependencies(&locks, &vendor, &Registries::default(), false),
+        )
+        .await
+        .unwrap();
+        assert_eq!(index.artifacts.len(), 1);
+        assert_eq!(index.artifacts[0].path, "archives/lib1-1.0.0.zip");
+        assert_eq!(
+            fs::read_to_string(vendor.join(&index.artifacts[0].path)).unwrap(),
+            "zip contents"
+        );
+
+        let found = vendored_archive(&vendor, &checksum);
+        assert_eq!(found, Some(vendor.join("archives/lib1-1.0.0.zip")));
+    }
+
+    #[tokio::test]
+    async fn test_vendor_git_offline() {
+        let dir = testdir!();
+        let lock: LockEntry = GitLockEntry::builder()
+            .name("lib1")
+            .version("1.0.0")
+            .git("https://example.com/lib1.git")
+            .rev("123")
+            .build()
+            .into();
+        let res =
+            vendor_dependency(&lock, &dir, &VendorIndex::default(), &Registries::default(), true)
+                .await;
+        assert!(
+            matches!(res, Err(VendorError::DownloadError(DownloadError::Offline(_)))),
+            "{res:?}"
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! Read-only verification of the installed dependencies.
+//!
+//! Contrary to the install process, which re-installs dependencies that fail their integrity check,
+//! the functions of this module only report the differences between the installed files and the
+//! lockfile. Nothing is modified in the dependencies folder.
+use crate::{
+    archive,
+    cache::{cache_dir, find_archive},
+    errors::VerifyError,
+    install::{DependencyStatus, check_dependency_integrity, integrity_changes},
+    lock::LockEntry,
+    manifest::{ChangeKind, FileChange, IntegrityManifest},
+    registry::{Registries, get_dependency_url_remote},
+    resolver::dependency_from_lock,
+    utils::{IntegrityChecksum, hash_content, run_git_command},
+};
+use log::{debug, warn};
+use sha2::{Digest as _, Sha256};
+use std::{collections::BTreeMap, path::Path};
+
+pub type Result<T> = std::result::Result<T, VerifyError>;
+
+/// The result of the verification of a dependency.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct VerifyReport {
+    /// The name of the dependency.
+    pub name: String,
+
+    /// The locked version of the dependency.
+    pub version: String,
+
+    /// The status of the install folder.
+    pub status: DependencyStatus,
+
+    /// For HTTP dependencies which fail the integrity check, the files which differ from the
+    /// published archive.
+    ///
+    /// This is `None` if the archive could not be retrieved from the cache or downloaded.
+    pub changes: Option<Vec<FileChange>>,
+
+    /// For git dependencies which fail the integrity check, the output of `git diff --stat`
+    /// compared to the locked commit.
+    pub diff_stat: Option<String>,
+}
+
+/// Verify the integrity of all the dependencies of the lockfile.
+pub async fn verify_dependencies(
+    locks: &[LockEntry],
+    deps: impl AsRef<Path>,
+    registries: &Registries,
+    offline: bool,
+) -> Result<Vec<VerifyReport>> {
+    let mut reports = Vec::with_capacity(locks.len());
+    for lock in locks {
+        reports.push(verify_dependency(lock, deps.as_ref(), registries, offline).await?);
+    }
+    Ok(reports)
+}
+
+/// Verify the integrity of a single dependency.
+///
+/// When an HTTP dependency fails the integrity check, the changed files are listed from its
+/// integrity manifest (see [`crate::manifest`]). If it has no valid manifest, its archive is
+/// retrieved from the global cache, or downloaded in memory unless in offline mode, and compared
+/// file by file with the install folder.
+pub async fn verify_dependency(
+    lock: &LockEntry,
+    deps: impl AsRef<Path>,
+    registries: &Registries,
+    offline: bool,
+) -> Result<VerifyReport> {
+    let deps = deps.as_ref();
+    let status = check_dependency_integrity(lock, deps).await?;
+    let mut report = VerifyReport {
+        name: lock.name().to_string(),
+        version: lock.version().to_string(),
+        status,
+        changes: None,
+        diff_stat: None,
+    };
+    if status != DependencyStatus::FailedIntegrity {
+        return Ok(report);
+    }
+    let path = lock.install_path(deps);
+    match lock {
+        LockEntry::Http(_) | LockEntry::Private(_) => {
+            // the integrity manifest lists the changes without needing the archive
+            if let Some(changes) = integrity_changes(lock, deps).await? {
+                report.changes = Some(changes);
+                return Ok(report);
+            }
+            let Some(archive) = fetch_archive(lock, registries, offline).await? else {
+                warn!(dep:% = lock.name(); "archive is not available, cannot list modified files");
+                return Ok(report);
+            };
+            let expected = tokio::task::spawn_blocking(move || archive_files(&archive)).await??;
+            let actual = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || IntegrityManifest::generate(path)
+            })
+            .await?
+            .map_err(|e| VerifyError::IOError { path: path.clone(), source: e })?;
+            report.changes = Some(diff_files(&expected, &actual, &path));
+        }
+        LockEntry::Git(git) => {
+            let stat = run_git_command(&["diff", "--stat", &git.rev], Some(&path)).await;
+            report.diff_stat = stat.ok().map(|s| s.trim_end().to_string());
+        }
+        LockEntry::Path(_) => {}
+    }
+    Ok(report)
+}
+
+/// Retrieve the archive of an HTTP dependency.
+///
+/// The archive is read from the global cache if available, otherwise it's downloaded in memory. In
+/// both cases, its checksum must match the lockfile.
+async fn fetch_archive(
+    lock: &LockEntry,
+    registries: &Registries,
+    offline: bool,
+) -> Result<Option<Vec<u8>>> {
+    let checksum = match lock {
+        LockEntry::Http(lock) => &lock.checksum,
+        LockEntry::Private(lock) => &lock.checksum,
+        _ => return Ok(None),
+    };
+    if let Some(cached) = find_archive(&cache_dir()?, checksum) &&
+        let Ok(bytes) = tokio::fs::read(&cached).await &&
+        &archive_checksum(&bytes).0 == checksum
+    {
+        debug!(path:? = cached; "found archive in cache");
+        return Ok(Some(bytes));
+    }
+    if offline {
+        return Ok(None);
+    }
+    let url = match lock {
+        LockEntry::Http(lock) => lock.url.clone(),
+        _ => {
+            let registry = registries.get(lock.registry())?;
+            get_dependency_url_remote(registry, &dependency_from_lock(lock), lock.version(), false)
+                .await?
+                .url
+        }
+    };
+    debug!(url; "downloading archive");
+    let bytes = reqwest::get(&url).await?.error_for_status()?.bytes().await?.to_vec();
+    let actual = archive_checksum(&bytes);
+    if &actual.0 != checksum {
+        return Err(VerifyError::ArchiveChecksum {
+            dependency: lock.name().to_string(),
+            expected: checksum.clone(),
+            actual: actual.0,
+        });
+    }
+    Ok(Some(bytes))
+}
+
+/// Compute the checksum of an archive in memory.
+fn archive_checksum(bytes: &[u8]) -> IntegrityChecksum {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    const_hex::encode(hasher.finalize()).into()
+}
+
+/// Compute the SHA256 hash of each file of an archive.
+///
+/// Like during extraction, the top-level folder of the archive is stripped if all the entries are
+/// inside of it (and there are at least two entries).
+fn archive_files(bytes: &[u8]) -> Result<BTreeMap<String, IntegrityChecksum>> {
+    let mut files = BTreeMap::new();
+    archive::visit_files(bytes, |file| {
+        let mut contents = Vec::new();
+        file.reader.read_to_end(&mut contents)?;
+        let hash = hash_content(&mut contents.as_slice());
+        files.insert(file.path, const_hex::encode(hash).into());
+        Ok(())
+    })?;
+    Ok(files)
+}
+
+/// Compare the files of the archive with the files of the install folder.
+///
+/// Files of the archive which are still on disk but not covered by the integrity check (because
+/// they are ignored by a `.gitignore` file for instance) are not reported.
+fn diff_files(
+    expected: &BTreeMap<String, IntegrityChecksum>,
+    actual: &IntegrityManifest,
+    path: &Path,
+) -> Vec<FileChange> {
+    let mut changes = Vec::new();
+    for (file, hash) in expected {
+        match actual.files.get(file) {
+            Some(actual) if actual.sha256 != hash.0 => {
+                changes.push(FileChange { path: file.clone(), kind: ChangeKind::Modified });
+            }
+            Some(_) => {}
+            None if path.join(file).is_file() => {}
+            None => changes.push(FileChange { path: file.clone(), kind: ChangeKind::Deleted }),
+        }
+    }
+    for file in actual.files.keys().filter(|f| !expected.contains_key(*f)) {
+        changes.push(FileChange { path: file.clone(), kind: ChangeKind::Added });
+    }
+    changes.sort_unstable();
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        download::extract_archive,
+        lock::HttpLockEntry,
+        push::zip_file,
+        utils::{hash_file, hash_folder},
+    };
+    use std::fs;
+    use temp_env::async_with_vars;
+    use testdir::testdir;
+
+    #[tokio::test]
+    async fn test_verify_dependency_http() {
+        let dir = testdir!();
+        let pkg = dir.join("pkg");
+        fs::create_dir_all(pkg.join("src")).unwrap();
+        fs::write(pkg.join("src/A.sol"), "contract A {}").unwrap();
+        fs::write(pkg.join("src/B.sol"), "contract B {}").unwrap();
+        fs::write(pkg.join("README.md"), "# pkg").unwrap();
+        let files = [pkg.join("README.md"), pkg.join("src/A.sol"), pkg.join("src/B.sol")];
+        let zip_path = zip_file(&pkg, &files, "pkg").unwrap();
+        let checksum = hash_file(&zip_path).unwrap().to_string();
+        let cache = dir.join("cache");
+        fs::create_dir_all(&cache).unwrap();
+        fs::copy(&zip_path, cache.join(format!("{checksum}.zip"))).unwrap();
+        let deps = dir.join("dependencies");
+        let path = deps.join("pkg-1.0.0");
+        fs::create_dir_all(&path).unwrap();
+        extract_archive(&zip_path, &path).await.unwrap();
+        let lock: LockEntry = HttpLockEntry::builder()
+            .name("pkg")
+            .version("1.0.0")
+            .url("https://example.com/pkg.zip")
+            .checksum(&checksum)
+            .integrity(hash_folder(&path).unwrap().to_string())
+            .build()
+            .into();
+
+        let report = verify_dependency(&lock, &deps, &Registries::default(), false).await.unwrap();
+        assert_eq!(report.status, DependencyStatus::Installed);
+        assert_eq!(report.changes, None);
+
+        fs::write(path.join("src/A.sol"), "contract Evil {}").unwrap();
+        fs::remove_file(path.join("src/B.sol")).unwrap();
+        fs::write(path.join("src/C.sol"), "contract C {}").unwrap();
+        let report = async_with_vars(
+            [("SOLDEER_CACHE_DIR", Some(cache.to_string_lossy().as_ref()))],
+            verify_dependency(&lock, &deps, &Registries::default(), false),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.status, DependencyStatus::FailedIntegrity);
+        assert_eq!(
+            report.changes,
+            Some(vec![
+                FileChange { path: "src/A.sol".to_string(), kind: ChangeKind::Modified },
+                FileChange { path: "src/B.sol".to_string(), kind: ChangeKind::Deleted },
+                FileChange { path: "src/C.sol".to_string(), kind: ChangeKind::Added },
+            ])
+        );
+        // nothing was modified
+        assert_eq!(fs::read_to_string(path.join("src/A.sol")).unwrap(), "contract Evil {}");
+    }
+}
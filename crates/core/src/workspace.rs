@@ -0,0 +1,406 @@
+//! Workspaces of several projects which share their dependencies.
+//!
+//! A workspace is declared in the config file of its root folder with a `[workspace]` section
+//! listing the folders of its members, relative to the root. Glob patterns can be used:
+//!
+//! ```toml
+//! [workspace]
+//! members = ["packages/*"]
+//! ```
+//!
+//! Each member is a project with its own config file (`soldeer.toml` or `foundry.toml`). The
+//! dependencies of the root and of all members are installed together into the `dependencies`
+//! folder of the workspace root, and recorded in a single `soldeer.lock` lockfile. The remappings
+//! are generated for each member, according to its own config.
+//!
+//! Members can depend on each other with a path dependency (see [`crate::config::PathDependency`]):
+//!
+//! ```toml
+//! [dependencies]
+//! my-lib = { version = "1.0.0", path = "../my-lib" }
+//! ```
+use crate::{
+    config::{Dependency, ParsingWarning, Paths, read_config_deps, read_soldeer_config},
+    errors::{ConfigError, WorkspaceError},
+    remappings::{RemappingsAction, edit_remappings},
+    utils::{canonicalize_sync, relative_path},
+};
+use globset::Glob;
+use log::{debug, warn};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use toml_edit::DocumentMut;
+
+pub type Result<T> = std::result::Result<T, WorkspaceError>;
+
+/// The config files which can contain the `[workspace]` section.
+const CONFIG_FILES: [&str; 2] = ["soldeer.toml", "foundry.toml"];
+
+/// A workspace of several projects.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Workspace {
+    /// The paths of the workspace root project.
+    pub paths: Paths,
+
+    /// The members of the workspace, sorted by path.
+    pub members: Vec<WorkspaceMember>,
+}
+
+/// A member project of a workspace.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct WorkspaceMember {
+    /// The name of the member, which is the name of its folder.
+    pub name: String,
+
+    /// The paths of the member project.
+    ///
+    /// The `dependencies` and `lock` paths point to the workspace root.
+    pub paths: Paths,
+}
+
+impl Workspace {
+    /// Iterate over the names and paths of the projects of the workspace, starting with the root.
+    pub fn projects(&self) -> impl Iterator<Item = (&str, &Paths)> {
+        std::iter::once(("root", &self.paths))
+            .chain(self.members.iter().map(|m| (m.name.as_str(), &m.paths)))
+    }
+
+    /// Collect the dependencies of the workspace root and of all members.
+    ///
+    /// A dependency which is required by several projects must have the same definition in each of
+    /// them, otherwise a [`WorkspaceError::ConflictingRequirements`] error is returned. The paths
+    /// of path dependencies are made relative to the workspace root, which is where the
+    /// dependencies are installed.
+    pub fn dependencies(&self) -> Result<(Vec<Dependency>, Vec<ParsingWarning>)> {
+        let mut required: Vec<(Dependency, Vec<&str>)> = Vec::new();
+        let mut warnings = Vec::new();
+        for (name, paths) in self.projects() {
+            let (dependencies, mut project_warnings) = read_config_deps(&paths.config)?;
+            warnings.append(&mut project_warnings);
+            for mut dependency in dependencies {
+                if let Some(dep) = dependency.as_path_mut() {
+                    let path = paths.root.join(&dep.path);
+                    let path = canonicalize_sync(&path)
+                        .map_err(|e| WorkspaceError::IOError { path, source: e })?;
+                    dep.path = relative_path(&self.paths.root, path);
+                }
+                match required.iter_mut().find(|(d, _)| *d == dependency) {
+                    Some((_, projects)) => projects.push(name),
+                    None => required.push((dependency, vec![name])),
+                }
+            }
+        }
+        let mut dependencies: Vec<Dependency> = Vec::new();
+        for (dependency, _) in &required {
+            if dependencies.iter().any(|d| d.name() == dependency.name()) {
+                continue;
+            }
+            let conflicting: Vec<_> =
+                required.iter().filter(|(d, _)| d.name() == dependency.name()).collect();
+            if conflicting.len() > 1 {
+                return Err(WorkspaceError::ConflictingRequirements {
+                    dependency: dependency.name().to_string(),
+                    requirements: conflicting
+                        .iter()
+                        .map(|(d, projects)| format!("{} requires {d}", projects.join(", ")))
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                });
+            }
+            dependencies.push(dependency.clone());
+        }
+        debug!("found {} dependencies in workspace", dependencies.len());
+        Ok((dependencies, warnings))
+    }
+
+    /// Update the remappings of the workspace root and of each member, according to their own
+    /// config.
+    ///
+    /// The remappings of the root are only generated if it has dependencies of its own. If
+    /// `regenerate` is `true`, the existing remappings are discarded in all projects.
+    pub fn edit_remappings(&self, regenerate: bool) -> Result<()> {
+        for (name, paths) in self.projects() {
+            if paths.root == self.paths.root && read_config_deps(&paths.config)?.0.is_empty() {
+                debug!("workspace root has no dependencies, skipping its remappings");
+                continue;
+            }
+            let mut config = read_soldeer_config(&paths.config)?;
+            config.remappings_regenerate |= regenerate;
+            edit_remappings(&RemappingsAction::Update, &config, paths)?;
+            debug!(project = name; "updated remappings of workspace project");
+        }
+        Ok(())
+    }
+
+    /// Refresh the remappings of a dependency in each project which requires it.
+    pub fn refresh_remappings(&self, dependency: &Dependency) -> Result<()> {
+        for (name, paths) in self.projects() {
+            if !read_config_deps(&paths.config)?.0.iter().any(|d| d.name() == dependency.name()) {
+                continue;
+            }
+            let config = read_soldeer_config(&paths.config)?;
+            edit_remappings(&RemappingsAction::Refresh(dependency.clone()), &config, paths)?;
+            debug!(project = name, dep:% = dependency; "refreshed remappings of workspace project");
+        }
+        Ok(())
+    }
+
+    /// List the names of the projects which require a dependency.
+    pub fn projects_requiring(&self, dependency_name: &str) -> Result<Vec<String>> {
+        let mut projects = Vec::new();
+        for (name, paths) in self.projects() {
+            if read_config_deps(&paths.config)?.0.iter().any(|d| d.name() == dependency_name) {
+                projects.push(name.to_string());
+            }
+        }
+        Ok(projects)
+    }
+}
+
+/// Load the workspace which a project belongs to, if any.
+///
+/// The project can be the workspace root or one of its members.
+pub fn load_workspace(paths: &Paths) -> Result<Option<Workspace>> {
+    let Some(root) = &paths.workspace else {
+        return Ok(None);
+    };
+    let patterns = workspace_patterns(root)?.unwrap_or_default();
+    let root_paths = if &paths.root == root { paths.clone() } else { Paths::from_root(root)? };
+    let mut members = Vec::new();
+    for path in expand_members(root, &patterns)? {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        members.push(WorkspaceMember { name, paths: Paths::from_root(&path)? });
+    }
+    debug!(root:?; "loaded workspace with {} members", members.len());
+    Ok(Some(Workspace { paths: root_paths, members }))
+}
+
+/// Find the root of the workspace which a project belongs to, if any.
+///
+/// The project folder and its parents are searched for a config file with a `[workspace]`
+/// section. The search stops at the first workspace which is found: if the project is neither its
+/// root nor one of its members, `None` is returned.
+pub fn find_workspace_root(project_root: impl AsRef<Path>) -> Option<PathBuf> {
+    let project_root = canonicalize_sync(project_root.as_ref()).ok()?;
+    for dir in project_root.ancestors() {
+        let patterns = match workspace_patterns(dir) {
+            Ok(Some(patterns)) => patterns,
+            Ok(None) => continue,
+            Err(err) => {
+                warn!(dir:?, err:%; "could not read workspace config");
+                continue;
+            }
+        };
+        if dir == project_root {
+            debug!(dir:?; "project is a workspace root");
+            return Some(dir.to_path_buf());
+        }
+        return match expand_members(dir, &patterns) {
+            Ok(members) if members.contains(&project_root) => {
+                debug!(dir:?; "project is a workspace member");
+                Some(dir.to_path_buf())
+            }
+            Ok(_) => {
+                debug!(dir:?; "project is not a member of the enclosing workspace");
+                None
+            }
+            Err(err) => {
+                warn!(dir:?, err:%; "could not list workspace members");
+                None
+            }
+        };
+    }
+    None
+}
+
+/// Read the member patterns of the `[workspace]` section of a config file, if it has one.
+pub fn read_workspace_members(config_path: impl AsRef<Path>) -> Result<Option<Vec<String>>> {
+    let path = config_path.as_ref();
+    let contents = fs::read_to_string(path)
+        .map_err(|e| WorkspaceError::IOError { path: path.to_path_buf(), source: e })?;
+    let doc: DocumentMut = contents.parse::<DocumentMut>().map_err(ConfigError::from)?;
+    let Some(workspace) = doc.get("workspace") else {
+        return Ok(None);
+    };
+    let Some(members) = workspace.get("members") else {
+        return Ok(Some(Vec::new()));
+    };
+    let members = members.as_array().ok_or(WorkspaceError::InvalidMembers)?;
+    members
+        .iter()
+        .map(|m| m.as_str().map(ToString::to_string).ok_or(WorkspaceError::InvalidMembers))
+        .collect::<Result<Vec<_>>>()
+        .map(Some)
+}
+
+/// Expand the member patterns of a workspace into the list of member folders.
+///
+/// Each component of a pattern can be a glob (e.g. `packages/*`). Folders matched by a glob are
+/// only members if they contain a config file, whereas a folder given without a glob must contain
+/// one. The returned paths are canonicalized and sorted.
+pub fn expand_members(root: impl AsRef<Path>, patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let root = root.as_ref();
+    let mut members = Vec::new();
+    for pattern in patterns {
+        let mut candidates = vec![root.to_path_buf()];
+        let mut is_glob = false;
+        for component in Path::new(pattern).components() {
+            let component = component.as_os_str().to_string_lossy();
+            if !component.contains(['*', '?', '[', '{']) {
+                candidates = candidates.into_iter().map(|c| c.join(component.as_ref())).collect();
+                continue;
+            }
+            is_glob = true;
+            let matcher = Glob::new(&component)
+                .map_err(|e| WorkspaceError::InvalidPattern {
+                    pattern: pattern.clone(),
+                    source: e,
+                })?
+                .compile_matcher();
+            let mut matches = Vec::new();
+            for candidate in candidates {
+                let Ok(entries) = fs::read_dir(&candidate) else {
+                    continue;
+                };
+                for entry in entries.flatten() {
+                    let name = entry.file_name();
+                    // hidden folders are only matched explicitly
+                    if name.to_string_lossy().starts_with('.') && !component.starts_with('.') {
+                        continue;
+                    }
+                    if entry.path().is_dir() && matcher.is_match(&name) {
+                        matches.push(entry.path());
+                    }
+                }
+            }
+            candidates = matches;
+        }
+        for candidate in candidates {
+            if !CONFIG_FILES.iter().any(|f| candidate.join(f).is_file()) {
+                if is_glob {
+                    debug!(path:? = candidate; "folder matched by workspace pattern has no config file");
+                    continue;
+                }
+                return Err(WorkspaceError::MemberWithoutConfig(candidate));
+            }
+            members.push(
+                canonicalize_sync(&candidate)
+                    .map_err(|e| WorkspaceError::IOError { path: candidate, source: e })?,
+            );
+        }
+    }
+    members.sort_unstable();
+    members.dedup();
+    Ok(members)
+}
+
+/// Read the member patterns of the workspace defined in a folder, if any.
+fn workspace_patterns(dir: impl AsRef<Path>) -> Result<Option<Vec<String>>> {
+    for file in CONFIG_FILES {
+        let path = dir.as_ref().join(file);
+        if !path.is_file() {
+            continue;
+        }
+        if let Some(patterns) = read_workspace_members(&path)? {
+            return Ok(Some(patterns));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{HttpDependency, PathDependency};
+    use testdir::testdir;
+
+    fn create_workspace(dir: &Path, members: &[(&str, &str)]) {
+        fs::write(dir.join("soldeer.toml"), "[workspace]\nmembers = [\"packages/*\"]\n").unwrap();
+        for (name, dependencies) in members {
+            let path = dir.join("packages").join(name);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("soldeer.toml"), format!("[dependencies]\n{dependencies}\n"))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_expand_members() {
+        let dir = testdir!();
+        create_workspace(&dir, &[("a", ""), ("b", "")]);
+        fs::create_dir_all(dir.join("packages/no_config")).unwrap();
+        fs::create_dir_all(dir.join("packages/.hidden")).unwrap();
+        fs::write(dir.join("packages/.hidden/soldeer.toml"), "").unwrap();
+        let members = expand_members(&dir, &["packages/*".to_string()]).unwrap();
+        assert_eq!(members, vec![dir.join("packages/a"), dir.join("packages/b")]);
+
+        let res = expand_members(&dir, &["packages/no_config".to_string()]);
+        assert!(matches!(res, Err(WorkspaceError::MemberWithoutConfig(_))), "{res:?}");
+    }
+
+    #[test]
+    fn test_find_workspace_root() {
+        let dir = testdir!();
+        create_workspace(&dir, &[("a", "")]);
+        fs::create_dir_all(dir.join("other")).unwrap();
+        fs::write(dir.join("other/soldeer.toml"), "[dependencies]\n").unwrap();
+        assert_eq!(find_workspace_root(&dir), Some(dir.clone()));
+        assert_eq!(find_workspace_root(dir.join("packages/a")), Some(dir.clone()));
+        assert_eq!(find_workspace_root(dir.join("other")), None);
+
+        let paths = Paths::from_root(dir.join("packages/a")).unwrap();
+        assert_eq!(paths.workspace, Some(dir.clone()));
+        assert_eq!(paths.dependencies, dir.join("dependencies"));
+        assert_eq!(paths.remappings, dir.join("packages/a/remappings.txt"));
+    }
+
+    #[test]
+    fn test_workspace_dependencies() {
+        let dir = testdir!();
+        create_workspace(
+            &dir,
+            &[
+                ("a", "forge-std = \"1.9.2\"\nb = { version = \"1.0.0\", path = \"../b\" }"),
+                ("b", "forge-std = \"1.9.2\"\nsolady = \"0.1.0\""),
+            ],
+        );
+        let workspace = load_workspace(&Paths::from_root(&dir).unwrap()).unwrap().unwrap();
+        assert_eq!(workspace.members.len(), 2);
+        let (dependencies, _) = workspace.dependencies().unwrap();
+        assert_eq!(
+            dependencies,
+            vec![
+                HttpDependency::builder().name("forge-std").version_req("1.9.2").build().into(),
+                PathDependency::builder()
+                    .name("b")
+                    .version_req("1.0.0")
+                    .path("packages/b")
+                    .build()
+                    .into(),
+                HttpDependency::builder().name("solady").version_req("0.1.0").build().into(),
+            ]
+        );
+
+        fs::write(dir.join("packages/b/soldeer.toml"), "[dependencies]\nforge-std = \"1.9.3\"\n")
+            .unwrap();
+        let res = workspace.dependencies();
+        assert!(
+            matches!(res, Err(WorkspaceError::ConflictingRequirements { ref dependency, .. }) if dependency == "forge-std"),
+            "{res:?}"
+        );
+
+        fs::write(
+            dir.join("packages/b/soldeer.toml"),
+            "[dependencies]\nc = { version = \"1.0.0\", path = \"../c\" }\n",
+        )
+        .unwrap();
+        let res = workspace.dependencies();
+        assert!(matches!(res, Err(WorkspaceError::IOError { .. })), "{res:?}");
+    }
+}
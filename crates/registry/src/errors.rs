@@ -0,0 +1,33 @@
+use std::{io, path::PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum StorageError {
+    #[error("invalid project name {0:?}")]
+    InvalidName(String),
+
+    #[error("invalid version {0:?}")]
+    InvalidVersion(String),
+
+    #[error("project {0} already exists")]
+    ProjectExists(String),
+
+    #[error("project {0} not found")]
+    ProjectNotFound(String),
+
+    #[error("user {username} is not the owner of project {project}")]
+    NotOwner { project: String, username: String },
+
+    #[error("user {0} not found")]
+    UserNotFound(String),
+
+    #[error("version {version} of project {project} already exists")]
+    RevisionExists { project: String, version: String },
+
+    #[error("error during IO operation for {path:?}: {source}")]
+    IOError { path: PathBuf, source: io::Error },
+
+    #[error("error while parsing {path:?}: {source}")]
+    ParseError { path: PathBuf, source: serde_json::Error },
+}
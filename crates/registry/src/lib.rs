@@ -0,0 +1,15 @@
+//! A self-hostable registry for Soldeer packages.
+//!
+//! The server implements the subset of the <https://soldeer.xyz> API which is used by the Soldeer
+//! client, and stores projects and their zip archives on the local filesystem. It's intended for
+//! teams which can't reach the public registry, for instance in air-gapped environments.
+//!
+//! Clients can use it by pointing the `SOLDEER_API_URL` environment variable to the server, or by
+//! declaring it in the `[registries]` table of their config file.
+pub use errors::StorageError;
+pub use server::{ServerConfig, router, serve};
+pub use storage::Storage;
+
+pub mod errors;
+pub mod server;
+pub mod storage;
@@ -0,0 +1,114 @@
+//! Self-hostable registry server for Soldeer
+use clap::{Parser, Subcommand};
+use soldeer_registry::{
+    ServerConfig, Storage, StorageError, serve, server::DEFAULT_MAX_UPLOAD_SIZE,
+};
+use std::{net::SocketAddr, path::PathBuf};
+use tokio::net::TcpListener;
+
+/// A self-hostable registry for Soldeer packages
+#[derive(Debug, Clone, Parser)]
+#[command(name = "soldeer-registry", version)]
+struct Args {
+    /// The folder where projects, archives and tokens are stored
+    #[arg(long, default_value = ".")]
+    root: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum Command {
+    /// Start the registry server
+    Serve {
+        /// The address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        bind: SocketAddr,
+
+        /// The URL at which clients reach the server, used for download links
+        ///
+        /// Defaults to `http://<BIND>`.
+        #[arg(long)]
+        public_url: Option<String>,
+
+        /// The maximum size of an uploaded archive, in bytes
+        #[arg(long, default_value_t = DEFAULT_MAX_UPLOAD_SIZE)]
+        max_upload_size: usize,
+    },
+
+    /// Create a new project, so that revisions can be pushed to it
+    AddProject {
+        /// The name of the project
+        name: String,
+
+        /// A short description of the project
+        #[arg(long, default_value = "")]
+        description: String,
+
+        /// The URL of the project's repository
+        #[arg(long, default_value = "")]
+        github_url: String,
+
+        /// The SPDX license expression of the project
+        #[arg(long)]
+        license: Option<String>,
+
+        /// The user allowed to push revisions, which must have a token already
+        ///
+        /// Defaults to the first user who pushes a revision.
+        #[arg(long, value_name = "USERNAME")]
+        owner: Option<String>,
+    },
+
+    /// Create a CLI token for a user, to be used with `soldeer login --token`
+    AddToken {
+        /// The name of the user
+        username: String,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let args = Args::parse();
+    if let Err(err) = run(args).await {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let storage = Storage::open(args.root)?;
+    match args.command {
+        Command::Serve { bind, public_url, max_upload_size } => {
+            let config = ServerConfig::builder()
+                .public_url(public_url.unwrap_or_else(|| format!("http://{bind}")))
+                .max_upload_size(max_upload_size)
+                .build();
+            let listener = TcpListener::bind(bind).await?;
+            serve(listener, storage, config).await?;
+        }
+        Command::AddProject { name, description, github_url, license, owner } => {
+            let owner = match owner {
+                Some(username) => {
+                    Some(storage.user(&username)?.ok_or(StorageError::UserNotFound(username))?)
+                }
+                None => None,
+            };
+            let project = storage.create_project(
+                &name,
+                &description,
+                &github_url,
+                license.as_deref(),
+                owner.as_ref(),
+            )?;
+            println!("Created project {} with ID {}", project.name, project.id);
+        }
+        Command::AddToken { username } => {
+            let token = storage.add_token(&username)?;
+            println!("{token}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,308 @@
+//! HTTP server implementing the registry API.
+//!
+//! The following endpoints are served, which are the ones used by the Soldeer client:
+//!
+//! - `GET /api/v1/revision?project_name=&offset=&limit=`: list the revisions of a project
+//! - `GET /api/v1/revision-cli?project_name=&revision=`: get a single revision
+//! - `GET /api/v1/project?project_name=` (and `/api/v2/project`): get a project
+//! - `POST /api/v1/revision/upload`: upload a new revision (multipart form)
+//! - `GET /api/v1/auth/validate-cli-token`: check a CLI token
+//! - `GET /files/{project}/{file}`: download the zip archive of a revision
+//!
+//! Read endpoints are public. Uploading requires a CLI token (see
+//! [`Storage::add_token`][crate::storage::Storage::add_token]), which is sent in the
+//! `Authorization: Bearer` header. Logging in with an email and password is not supported. Only
+//! the owner of a project can upload revisions to it, other users get a `403 Forbidden` response.
+//!
+//! An upload can include the `public_key` and `signature` fields of a signed package, in which case
+//! the signature is checked against the archive before the revision is stored.
+use crate::{
+    errors::StorageError,
+    storage::{Storage, User},
+};
+use axum::{
+    Json, Router,
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use bon::Builder;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use soldeer_core::{
+    registry::{ProjectResponse, Revision, RevisionResponse},
+    signing::PackageSignature,
+    utils::hash_content,
+};
+use std::{io, sync::Arc};
+use tokio::net::TcpListener;
+use uuid::Uuid;
+
+/// The default maximum size for uploaded archives (50 MiB).
+pub const DEFAULT_MAX_UPLOAD_SIZE: usize = 50 * 1024 * 1024;
+
+/// Configuration for the registry server.
+#[derive(Debug, Clone, Builder)]
+#[non_exhaustive]
+pub struct ServerConfig {
+    /// The base URL at which clients reach the server.
+    ///
+    /// It's used to build the download URL of the archives, e.g. `http://registry.internal:8080`.
+    #[builder(into)]
+    pub public_url: String,
+
+    /// The maximum size of an upload request, in bytes.
+    #[builder(default = DEFAULT_MAX_UPLOAD_SIZE)]
+    pub max_upload_size: usize,
+}
+
+#[derive(Debug)]
+struct AppState {
+    storage: Storage,
+    config: ServerConfig,
+}
+
+/// Build the router for the registry API.
+pub fn router(storage: Storage, config: ServerConfig) -> Router {
+    let max_upload_size = config.max_upload_size;
+    let state = Arc::new(AppState { storage, config });
+    Router::new()
+        .route("/api/v1/revision", get(list_revisions))
+        .route("/api/v1/revision-cli", get(get_revision))
+        .route("/api/v1/project", get(list_projects))
+        .route("/api/v2/project", get(list_projects))
+        .route(
+            "/api/v1/revision/upload",
+            post(upload_revision).layer(DefaultBodyLimit::max(max_upload_size)),
+        )
+        .route("/api/v1/auth/validate-cli-token", get(validate_token))
+        .route("/files/{project}/{file}", get(download_archive))
+        .with_state(state)
+}
+
+/// Serve the registry API on the given listener until the process is stopped.
+pub async fn serve(
+    listener: TcpListener,
+    storage: Storage,
+    config: ServerConfig,
+) -> io::Result<()> {
+    info!(addr:? = listener.local_addr()?, root:? = storage.root(); "registry server listening");
+    axum::serve(listener, router(storage, config)).await
+}
+
+/// An error response from the API.
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self { status, message: message.into() }
+    }
+}
+
+impl From<StorageError> for ApiError {
+    fn from(err: StorageError) -> Self {
+        let status = match &err {
+            StorageError::InvalidName(_) | StorageError::InvalidVersion(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            StorageError::ProjectExists(_) => StatusCode::CONFLICT,
+            StorageError::NotOwner { .. } => StatusCode::FORBIDDEN,
+            // the client expects these status codes when uploading
+            StorageError::ProjectNotFound(_) => StatusCode::NO_CONTENT,
+            StorageError::RevisionExists { .. } => StatusCode::ALREADY_REPORTED,
+            _ => {
+                warn!(err:%; "storage error");
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+        Self::new(status, err.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if self.status == StatusCode::NO_CONTENT {
+            return self.status.into_response();
+        }
+        let body = serde_json::json!({ "status": "fail", "message": self.message });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+type ApiResult<T> = Result<T, ApiError>;
+
+#[derive(Debug, Deserialize)]
+struct RevisionsQuery {
+    project_name: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RevisionQuery {
+    project_name: String,
+    revision: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectQuery {
+    project_name: Option<String>,
+}
+
+async fn list_revisions(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RevisionsQuery>,
+) -> ApiResult<Json<RevisionResponse>> {
+    debug!(query:?; "listing revisions");
+    let revisions = state
+        .storage
+        .revisions(&query.project_name)?
+        .into_iter()
+        .skip(query.offset.unwrap_or_default())
+        .take(query.limit.unwrap_or(usize::MAX))
+        .map(|r| with_download_url(r, &state.config))
+        .collect();
+    Ok(Json(RevisionResponse::new(revisions)))
+}
+
+async fn get_revision(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RevisionQuery>,
+) -> ApiResult<Json<RevisionResponse>> {
+    debug!(query:?; "retrieving revision");
+    let revisions = state
+        .storage
+        .revisions(&query.project_name)?
+        .into_iter()
+        .filter(|r| r.version == query.revision)
+        .map(|r| with_download_url(r, &state.config))
+        .collect();
+    Ok(Json(RevisionResponse::new(revisions)))
+}
+
+async fn list_projects(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ProjectQuery>,
+) -> ApiResult<Json<ProjectResponse>> {
+    debug!(query:?; "listing projects");
+    let projects = match query.project_name {
+        Some(name) => state.storage.project(&name)?.into_iter().collect(),
+        None => state.storage.projects()?,
+    };
+    Ok(Json(ProjectResponse::new(projects)))
+}
+
+async fn upload_revision(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> ApiResult<Json<serde_json::Value>> {
+    let user = authenticate(&state, &headers)?;
+    let mut project_id = None;
+    let mut version = None;
+    let mut archive = None;
+    let mut public_key = None;
+    let mut signature = None;
+    while let Some(field) =
+        multipart.next_field().await.map_err(|e| ApiError::new(e.status(), e.body_text()))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "project_id" => project_id = Some(field_text(field).await?),
+            "revision" => version = Some(field_text(field).await?),
+            "public_key" => public_key = Some(field_text(field).await?),
+            "signature" => signature = Some(field_text(field).await?),
+            "zip_name" => {
+                archive = Some(
+                    field.bytes().await.map_err(|e| ApiError::new(e.status(), e.body_text()))?,
+                );
+            }
+            _ => debug!(name; "ignoring unknown form field"),
+        }
+    }
+    let (Some(project_id), Some(version), Some(archive)) = (project_id, version, archive) else {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "the form must contain `project_id`, `revision` and `zip_name`",
+        ));
+    };
+    let project_id: Uuid = project_id
+        .parse()
+        .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, "invalid project ID"))?;
+    let project = state
+        .storage
+        .project_by_id(project_id)?
+        .ok_or_else(|| StorageError::ProjectNotFound(project_id.to_string()))?;
+    let signature = match (public_key, signature) {
+        (Some(public_key), Some(signature)) => {
+            let signature =
+                PackageSignature::builder().public_key(public_key).signature(signature).build();
+            let checksum = const_hex::encode(hash_content(&mut archive.as_ref()));
+            signature
+                .verify(&checksum)
+                .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+            Some(signature)
+        }
+        (None, None) => None,
+        _ => {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "the form must contain both `public_key` and `signature`, or neither",
+            ));
+        }
+    };
+    state.storage.add_revision(&project, &user, &version, &archive, signature)?;
+    info!(project = project.name, version, user = user.username; "new revision uploaded");
+    Ok(Json(serde_json::json!({ "status": "success" })))
+}
+
+async fn validate_token(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> ApiResult<Json<serde_json::Value>> {
+    let user = authenticate(&state, &headers)?;
+    Ok(Json(serde_json::json!({ "status": "success", "data": user })))
+}
+
+async fn download_archive(
+    State(state): State<Arc<AppState>>,
+    Path((project, file)): Path<(String, String)>,
+) -> ApiResult<Response> {
+    let Some(path) = state.storage.archive_path(&format!("{project}/{file}")) else {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "archive not found"));
+    };
+    debug!(path:?; "serving archive");
+    let contents = tokio::fs::read(&path)
+        .await
+        .map_err(|e| StorageError::IOError { path: path.clone(), source: e })?;
+    Ok(([(header::CONTENT_TYPE, "application/zip")], contents).into_response())
+}
+
+/// Get the user for the bearer token in the request headers.
+fn authenticate(state: &AppState, headers: &HeaderMap) -> ApiResult<User> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| ApiError::new(StatusCode::UNAUTHORIZED, "missing token"))?;
+    state
+        .storage
+        .user_for_token(token)?
+        .ok_or_else(|| ApiError::new(StatusCode::UNAUTHORIZED, "invalid token"))
+}
+
+async fn field_text(field: axum::extract::multipart::Field<'_>) -> ApiResult<String> {
+    field.text().await.map_err(|e| ApiError::new(e.status(), e.body_text()))
+}
+
+/// Fill in the download URL of a revision.
+fn with_download_url(mut revision: Revision, config: &ServerConfig) -> Revision {
+    revision.url =
+        format!("{}/files/{}", config.public_url.trim_end_matches('/'), revision.internal_name);
+    revision
+}
@@ -0,0 +1,404 @@
+//! Filesystem storage for the registry.
+//!
+//! All the data is kept below a root folder with the following layout:
+//!
+//! ```text
+//! root
+//! ├── tokens.json              # CLI tokens and the user they belong to
+//! └── projects
+//!     └── my-project
+//!         ├── project.json     # project metadata
+//!         ├── revisions.json   # all revisions, most recent first
+//!         └── 1.0.0.zip        # the archive for version 1.0.0
+//! ```
+//!
+//! The files are plain JSON and can be inspected or backed up with usual tools.
+use crate::errors::StorageError;
+use chrono::Utc;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use soldeer_core::{
+    push::validate_name,
+    registry::{Project, Revision},
+    signing::PackageSignature,
+};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use uuid::Uuid;
+
+pub type Result<T> = std::result::Result<T, StorageError>;
+
+const TOKENS_FILE: &str = "tokens.json";
+const PROJECTS_DIR: &str = "projects";
+const PROJECT_FILE: &str = "project.json";
+const REVISIONS_FILE: &str = "revisions.json";
+
+/// A user which can push new revisions to the registry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct User {
+    /// The unique ID for the user.
+    pub id: Uuid,
+
+    /// The name of the user.
+    pub username: String,
+}
+
+/// The registry storage on the local filesystem.
+///
+/// Operations which modify the storage are serialized, so that a single instance can be shared
+/// between concurrent requests.
+#[derive(Debug)]
+pub struct Storage {
+    root: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl Storage {
+    /// Open the storage at the given root folder, creating it if needed.
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        let projects = root.join(PROJECTS_DIR);
+        fs::create_dir_all(&projects)
+            .map_err(|e| StorageError::IOError { path: projects, source: e })?;
+        debug!(root:?; "opened registry storage");
+        Ok(Self { root, write_lock: Mutex::new(()) })
+    }
+
+    /// The root folder of the storage.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Create a new project.
+    ///
+    /// The name must be a valid package name (see [`validate_name`]). The license is an SPDX
+    /// expression, which is exposed to the clients (e.g. for `soldeer sbom`).
+    ///
+    /// Only the owner of a project can add revisions to it. If no owner is provided, the first user
+    /// who adds a revision becomes the owner.
+    pub fn create_project(
+        &self,
+        name: &str,
+        description: &str,
+        github_url: &str,
+        license: Option<&str>,
+        owner: Option<&User>,
+    ) -> Result<Project> {
+        validate_name(name).map_err(|_| StorageError::InvalidName(name.to_string()))?;
+        let _guard = self.write_lock.lock().expect("lock should not be poisoned");
+        let dir = self.project_dir(name);
+        if dir.exists() {
+            return Err(StorageError::ProjectExists(name.to_string()));
+        }
+        fs::create_dir_all(&dir).map_err(|e| StorageError::IOError { path: dir, source: e })?;
+        let now = Utc::now();
+        let project = Project {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: description.to_string(),
+            github_url: github_url.to_string(),
+            created_by: owner.map(|u| u.id).unwrap_or_default(),
+            deleted: Some(false),
+            private: Some(false),
+            downloads: Some(0),
+            image: None,
+            long_description: None,
+            created_at: Some(now),
+            updated_at: Some(now),
+            organization_id: None,
+            latest_version: None,
+            deprecated: Some(false),
+            organization_name: None,
+            organization_verified: None,
+            license: license.map(ToString::to_string),
+        };
+        write_json(&self.project_dir(name).join(PROJECT_FILE), &project)?;
+        write_json(&self.project_dir(name).join(REVISIONS_FILE), &Vec::<Revision>::new())?;
+        debug!(name, id:% = project.id; "project created");
+        Ok(project)
+    }
+
+    /// Get a project by name.
+    pub fn project(&self, name: &str) -> Result<Option<Project>> {
+        if validate_name(name).is_err() {
+            return Ok(None);
+        }
+        let path = self.project_dir(name).join(PROJECT_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        read_json(&path).map(Some)
+    }
+
+    /// Get a project by its unique ID.
+    pub fn project_by_id(&self, id: Uuid) -> Result<Option<Project>> {
+        Ok(self.projects()?.into_iter().find(|p| p.id == id))
+    }
+
+    /// Get all projects, sorted by name.
+    pub fn projects(&self) -> Result<Vec<Project>> {
+        let dir = self.root.join(PROJECTS_DIR);
+        let entries = fs::read_dir(&dir)
+            .map_err(|e| StorageError::IOError { path: dir.clone(), source: e })?;
+        let mut projects = Vec::new();
+        for entry in entries {
+            let entry =
+                entry.map_err(|e| StorageError::IOError { path: dir.clone(), source: e })?;
+            let path = entry.path().join(PROJECT_FILE);
+            if path.exists() {
+                projects.push(read_json::<Project>(&path)?);
+            }
+        }
+        projects.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        Ok(projects)
+    }
+
+    /// Get all revisions of a project, most recent first.
+    ///
+    /// An unknown project has no revisions. The `url` of the returned revisions is left empty, it's
+    /// up to the server to fill it in with the download URL of the archive.
+    pub fn revisions(&self, name: &str) -> Result<Vec<Revision>> {
+        if validate_name(name).is_err() {
+            return Ok(Vec::new());
+        }
+        let path = self.project_dir(name).join(REVISIONS_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        read_json(&path)
+    }
+
+    /// Store a new revision of a project, along with its zip archive and optional signature.
+    ///
+    /// The user must be the owner of the project. A project without owner is claimed by the user.
+    pub fn add_revision(
+        &self,
+        project: &Project,
+        user: &User,
+        version: &str,
+        archive: &[u8],
+        signature: Option<PackageSignature>,
+    ) -> Result<Revision> {
+        let file_name = archive_file_name(version)
+            .ok_or_else(|| StorageError::InvalidVersion(version.to_string()))?;
+        let _guard = self.write_lock.lock().expect("lock should not be poisoned");
+        // read the project again while holding the lock, in case it was claimed in the meantime
+        let mut project = self
+            .project(&project.name)?
+            .ok_or_else(|| StorageError::ProjectNotFound(project.name.clone()))?;
+        if project.created_by.is_nil() {
+            debug!(name = project.name, username = user.username; "project claimed by user");
+            project.created_by = user.id;
+        } else if project.created_by != user.id {
+            return Err(StorageError::NotOwner {
+                project: project.name,
+                username: user.username.clone(),
+            });
+        }
+        let mut revisions = self.revisions(&project.name)?;
+        if revisions.iter().any(|r| r.version == version) {
+            return Err(StorageError::RevisionExists {
+                project: project.name,
+                version: version.to_string(),
+            });
+        }
+        let dir = self.project_dir(&project.name);
+        let archive_path = dir.join(&file_name);
+        fs::write(&archive_path, archive)
+            .map_err(|e| StorageError::IOError { path: archive_path, source: e })?;
+
+        let now = Utc::now();
+        let revision = Revision {
+            id: Uuid::new_v4(),
+            version: version.to_string(),
+            internal_name: format!("{}/{file_name}", project.name),
+            url: String::new(),
+            project_id: project.id,
+            deleted: false,
+            created_at: Some(now),
+            private: Some(false),
+            public_key: signature.as_ref().map(|s| s.public_key.clone()),
+            signature: signature.map(|s| s.signature),
+        };
+        revisions.insert(0, revision.clone());
+        write_json(&dir.join(REVISIONS_FILE), &revisions)?;
+
+        project.latest_version = Some(version.to_string());
+        project.updated_at = Some(now);
+        write_json(&dir.join(PROJECT_FILE), &project)?;
+        debug!(name = project.name, version; "revision added");
+        Ok(revision)
+    }
+
+    /// Get the path to the zip archive with the given internal name.
+    ///
+    /// Returns `None` if the internal name does not refer to an existing archive.
+    pub fn archive_path(&self, internal_name: &str) -> Option<PathBuf> {
+        let (name, file_name) = internal_name.split_once('/')?;
+        if validate_name(name).is_err() ||
+            !file_name.ends_with(".zip") ||
+            sanitize_filename::sanitize(file_name) != file_name
+        {
+            return None;
+        }
+        let path = self.project_dir(name).join(file_name);
+        path.is_file().then_some(path)
+    }
+
+    /// Create a new CLI token for the user with the given name.
+    ///
+    /// The user is created if it doesn't exist yet. A user can have several tokens.
+    pub fn add_token(&self, username: &str) -> Result<String> {
+        let _guard = self.write_lock.lock().expect("lock should not be poisoned");
+        let mut tokens = self.tokens()?;
+        let id =
+            tokens.values().find(|u| u.username == username).map_or_else(Uuid::new_v4, |u| u.id);
+        let token = Uuid::new_v4().simple().to_string();
+        tokens.insert(token.clone(), User { id, username: username.to_string() });
+        write_json(&self.root.join(TOKENS_FILE), &tokens)?;
+        debug!(username; "token created");
+        Ok(token)
+    }
+
+    /// Get the user which owns a token.
+    pub fn user_for_token(&self, token: &str) -> Result<Option<User>> {
+        Ok(self.tokens()?.remove(token))
+    }
+
+    /// Get a user by name.
+    ///
+    /// Users are created along with their first token (see [`Storage::add_token`]).
+    pub fn user(&self, username: &str) -> Result<Option<User>> {
+        Ok(self.tokens()?.into_values().find(|u| u.username == username))
+    }
+
+    fn tokens(&self) -> Result<BTreeMap<String, User>> {
+        let path = self.root.join(TOKENS_FILE);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        read_json(&path)
+    }
+
+    fn project_dir(&self, name: &str) -> PathBuf {
+        self.root.join(PROJECTS_DIR).join(name)
+    }
+}
+
+/// The name of the archive file for a version.
+///
+/// Returns `None` if the version can't be used as a file name.
+fn archive_file_name(version: &str) -> Option<String> {
+    let file_name = format!("{version}.zip");
+    (!version.is_empty() && sanitize_filename::sanitize(&file_name) == file_name)
+        .then_some(file_name)
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| StorageError::IOError { path: path.to_path_buf(), source: e })?;
+    serde_json::from_str(&contents)
+        .map_err(|e| StorageError::ParseError { path: path.to_path_buf(), source: e })
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let contents = serde_json::to_string_pretty(value).expect("value should serialize");
+    fs::write(path, contents)
+        .map_err(|e| StorageError::IOError { path: path.to_path_buf(), source: e })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testdir::testdir;
+
+    #[test]
+    fn test_create_project() {
+        let storage = Storage::open(testdir!()).unwrap();
+        let project = storage.create_project("my-lib", "A library", "", Some("MIT"), None).unwrap();
+        assert_eq!(storage.project("my-lib").unwrap(), Some(project.clone()));
+        assert_eq!(storage.project_by_id(project.id).unwrap(), Some(project));
+        assert!(storage.project("other").unwrap().is_none());
+        assert!(matches!(
+            storage.create_project("my-lib", "", "", None, None),
+            Err(StorageError::ProjectExists(_))
+        ));
+        assert!(matches!(
+            storage.create_project("../foo", "", "", None, None),
+            Err(StorageError::InvalidName(_))
+        ));
+    }
+
+    #[test]
+    fn test_add_revision() {
+        let storage = Storage::open(testdir!()).unwrap();
+        storage.add_token("alice").unwrap();
+        let alice = storage.user("alice").unwrap().unwrap();
+        let project = storage.create_project("my-lib", "", "", None, Some(&alice)).unwrap();
+        assert_eq!(project.created_by, alice.id);
+        storage.add_revision(&project, &alice, "1.0.0", b"first", None).unwrap();
+        let signature = PackageSignature::builder().public_key("abcd").signature("ef01").build();
+        let revision = storage
+            .add_revision(&project, &alice, "1.1.0", b"second", Some(signature.clone()))
+            .unwrap();
+        assert_eq!(revision.internal_name, "my-lib/1.1.0.zip");
+        assert_eq!(storage.revisions("my-lib").unwrap()[0].package_signature(), Some(signature));
+
+        let versions: Vec<_> =
+            storage.revisions("my-lib").unwrap().into_iter().map(|r| r.version).collect();
+        assert_eq!(versions, vec!["1.1.0", "1.0.0"]);
+        assert_eq!(
+            storage.project("my-lib").unwrap().unwrap().latest_version,
+            Some("1.1.0".to_string())
+        );
+
+        let path = storage.archive_path(&revision.internal_name).unwrap();
+        assert_eq!(fs::read(path).unwrap(), b"second");
+        assert!(storage.archive_path("my-lib/2.0.0.zip").is_none());
+        assert!(storage.archive_path("my-lib/../tokens.json").is_none());
+
+        assert!(matches!(
+            storage.add_revision(&project, &alice, "1.0.0", b"again", None),
+            Err(StorageError::RevisionExists { .. })
+        ));
+        assert!(matches!(
+            storage.add_revision(&project, &alice, "../1.0.0", b"bad", None),
+            Err(StorageError::InvalidVersion(_))
+        ));
+    }
+
+    #[test]
+    fn test_add_revision_owner() {
+        let storage = Storage::open(testdir!()).unwrap();
+        storage.add_token("alice").unwrap();
+        storage.add_token("bob").unwrap();
+        let alice = storage.user("alice").unwrap().unwrap();
+        let bob = storage.user("bob").unwrap().unwrap();
+        // the first user who pushes to a project without owner claims it
+        let project = storage.create_project("my-lib", "", "", None, None).unwrap();
+        assert!(project.created_by.is_nil());
+        storage.add_revision(&project, &alice, "1.0.0", b"first", None).unwrap();
+        assert_eq!(storage.project("my-lib").unwrap().unwrap().created_by, alice.id);
+        assert!(matches!(
+            storage.add_revision(&project, &bob, "1.1.0", b"second", None),
+            Err(StorageError::NotOwner { .. })
+        ));
+        assert_eq!(storage.revisions("my-lib").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_tokens() {
+        let storage = Storage::open(testdir!()).unwrap();
+        let token = storage.add_token("alice").unwrap();
+        let token2 = storage.add_token("alice").unwrap();
+        assert_ne!(token, token2);
+        let user = storage.user_for_token(&token).unwrap().unwrap();
+        assert_eq!(user.username, "alice");
+        assert_eq!(storage.user_for_token(&token2).unwrap(), Some(user));
+        assert!(storage.user_for_token("foo").unwrap().is_none());
+    }
+}
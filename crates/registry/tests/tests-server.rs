@@ -0,0 +1,150 @@
+use reqwest::Url;
+use soldeer_core::{
+    auth::check_token,
+    download::{download_file, extract_archive},
+    errors::{PublishError, RegistryError},
+    push::{filter_ignored_files, push_version},
+    registry::{
+        Registry, RegistryAuth, Versions, get_all_versions_descending, get_dependency_url_remote,
+        get_latest_version,
+    },
+    signing::{SigningKey, TrustPolicy},
+    utils::hash_file,
+};
+use soldeer_registry::{ServerConfig, Storage, serve};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use temp_env::async_with_vars;
+use testdir::testdir;
+use tokio::net::TcpListener;
+
+/// Start a registry server with a single project and user, returning a client handle for it.
+#[allow(clippy::unwrap_used)]
+async fn start_registry(dir: &Path) -> (Registry, String) {
+    let storage = Storage::open(dir.join("registry")).unwrap();
+    let token = storage.add_token("alice").unwrap();
+    let alice = storage.user("alice").unwrap();
+    storage.create_project("my-lib", "A library", "", None, alice.as_ref()).unwrap();
+    // another user, which doesn't own the project
+    fs::write(dir.join("token-bob"), storage.add_token("bob").unwrap()).unwrap();
+    let token_file = dir.join("token");
+    fs::write(&token_file, &token).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let config = ServerConfig::builder().public_url(&url).build();
+    tokio::spawn(serve(listener, storage, config));
+
+    let registry =
+        Registry::new("internal", Url::parse(&url).unwrap(), RegistryAuth::File(token_file));
+    (registry, token)
+}
+
+#[allow(clippy::unwrap_used)]
+fn setup_package(dir: &Path, contents: &str) -> (PathBuf, Vec<PathBuf>) {
+    let package = dir.join("my-lib");
+    fs::create_dir_all(&package).unwrap();
+    fs::write(package.join("Lib.sol"), contents).unwrap();
+    let files = filter_ignored_files(&package);
+    (package, files)
+}
+
+#[tokio::test]
+async fn test_check_token() {
+    let dir = testdir!();
+    let (registry, token) = start_registry(&dir).await;
+    assert_eq!(check_token(&registry, &token).await.unwrap(), "alice");
+    assert!(check_token(&registry, "invalid").await.is_err());
+}
+
+#[tokio::test]
+async fn test_push_and_download() {
+    let dir = testdir!();
+    let (registry, _) = start_registry(&dir).await;
+    let (package, files) = setup_package(&dir, "contract Lib {}");
+    push_version(&registry, "my-lib", "1.0.0", &package, &files, false, None).await.unwrap();
+    let (package, files) = setup_package(&dir, "contract LibV2 {}");
+    push_version(&registry, "my-lib", "1.1.0", &package, &files, false, None).await.unwrap();
+
+    let res = async_with_vars([("SOLDEER_CACHE_DIR", Some(dir.join("cache")))], async {
+        let latest = get_latest_version(&registry, "my-lib", false).await.unwrap();
+        assert_eq!(latest.version_req(), "1.1.0");
+        assert_eq!(latest.registry(), Some("internal"));
+        get_all_versions_descending(&registry, "my-lib", false).await
+    })
+    .await;
+    assert_eq!(
+        res.unwrap(),
+        Versions::Semver(vec!["1.1.0".parse().unwrap(), "1.0.0".parse().unwrap()])
+    );
+
+    let latest = get_latest_version(&registry, "my-lib", false).await.unwrap();
+    let url = get_dependency_url_remote(&registry, &latest, "1.0.0", false).await.unwrap();
+    let download = dir.join("download");
+    fs::create_dir_all(&download).unwrap();
+    let zip = download_file(url.url, &download, "my-lib").await.unwrap();
+    extract_archive(&zip, download.join("my-lib")).await.unwrap();
+    assert_eq!(
+        fs::read_to_string(download.join("my-lib").join("Lib.sol")).unwrap(),
+        "contract Lib {}"
+    );
+}
+
+#[tokio::test]
+async fn test_push_signed() {
+    let dir = testdir!();
+    let (registry, _) = start_registry(&dir).await;
+    let (key, _) = SigningKey::load_or_generate(dir.join("signing_key")).unwrap();
+    let (package, files) = setup_package(&dir, "contract Lib {}");
+    push_version(&registry, "my-lib", "1.0.0", &package, &files, false, Some(&key)).await.unwrap();
+
+    let latest = get_latest_version(&registry, "my-lib", false).await.unwrap();
+    let url = get_dependency_url_remote(&registry, &latest, "1.0.0", false).await.unwrap();
+    let signature = url.signature.unwrap();
+    assert_eq!(signature.public_key, key.public_key());
+
+    let download = dir.join("download");
+    fs::create_dir_all(&download).unwrap();
+    let zip = download_file(url.url, &download, "my-lib").await.unwrap();
+    let checksum = hash_file(&zip).unwrap();
+    let policy =
+        TrustPolicy::builder().require_signatures(true).keys(vec![key.public_key()]).build();
+    assert!(policy.check("my-lib~1.0.0", &checksum.0, Some(&signature)).is_ok());
+}
+
+#[tokio::test]
+async fn test_push_errors() {
+    let dir = testdir!();
+    let (registry, _) = start_registry(&dir).await;
+    let (package, files) = setup_package(&dir, "contract Lib {}");
+    push_version(&registry, "my-lib", "1.0.0", &package, &files, false, None).await.unwrap();
+
+    let res = push_version(&registry, "my-lib", "1.0.0", &package, &files, false, None).await;
+    assert!(matches!(res, Err(PublishError::AlreadyExists)), "{res:?}");
+
+    let res = push_version(&registry, "other-lib", "1.0.0", &package, &files, false, None).await;
+    assert!(
+        matches!(res, Err(PublishError::DownloadError(RegistryError::ProjectNotFound(_)))),
+        "{res:?}"
+    );
+
+    fs::write(dir.join("token"), "invalid").unwrap();
+    let res = push_version(&registry, "my-lib", "2.0.0", &package, &files, false, None).await;
+    assert!(matches!(res, Err(PublishError::AuthError(_))), "{res:?}");
+
+    // only the owner of the project can push to it
+    fs::copy(dir.join("token-bob"), dir.join("token")).unwrap();
+    let res = push_version(&registry, "my-lib", "2.0.0", &package, &files, false, None).await;
+    assert!(
+        matches!(res, Err(PublishError::HttpError(ref err)) if err.status() == Some(reqwest::StatusCode::FORBIDDEN)),
+        "{res:?}"
+    );
+    let res = async_with_vars(
+        [("SOLDEER_CACHE_DIR", Some(dir.join("cache")))],
+        get_all_versions_descending(&registry, "my-lib", false),
+    )
+    .await;
+    assert_eq!(res.unwrap(), Versions::Semver(vec!["1.0.0".parse().unwrap()]));
+}